@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pythnet_sdk::wire::v1::{AccumulatorUpdateData, Proof};
+use zklink_oracle::{
+    pyth::{decode::decode_accumulator_update, AccumulatorUpdateWitness, PriceUpdate},
+    testing::create_constraint_system,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_accumulator_update(data);
+    let Ok(update) = AccumulatorUpdateData::try_from_slice(data) else {
+        return;
+    };
+    // Re-encoding must always produce parsable bytes.
+    let witness = AccumulatorUpdateWitness::from_accumulator_update_data(&update);
+    witness.to_accumulator_update_data().unwrap();
+
+    let Proof::WormholeMerkle { updates, .. } = update.proof;
+    let cs = &mut create_constraint_system().unwrap();
+    for update in updates {
+        let _ = PriceUpdate::<_>::from_price_update_witness(cs, update);
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zklink_oracle::chainlink::witness::{FullReport, ReportV3};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ReportV3::from_bytes(data);
+    let Ok(report) = FullReport::from_bytes(data) else {
+        return;
+    };
+    let _ = report.signatures();
+    if let Ok(report) = report.report() {
+        let _ = report.packed_price();
+    }
+});
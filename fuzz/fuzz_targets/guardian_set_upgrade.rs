@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zklink_oracle::pyth::rotation::GuardianSetUpgradeVaa;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = GuardianSetUpgradeVaa::parse(data);
+});
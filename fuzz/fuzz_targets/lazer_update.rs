@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zklink_oracle::pyth::lazer::{LazerPayload, LazerUpdate};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = LazerPayload::from_bytes(data);
+    let Ok(update) = LazerUpdate::from_evm_bytes(data) else {
+        return;
+    };
+    // The EVM format has a single encoding.
+    assert_eq!(update.to_evm_bytes(), data);
+    let _ = update.decode_payload();
+});
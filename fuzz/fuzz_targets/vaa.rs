@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zklink_oracle::{
+    pyth::{decode::decode_vaa, Vaa},
+    testing::create_constraint_system,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_vaa(data);
+    let Ok(vaa) =
+        serde_wormhole::from_slice::<wormhole_sdk::Vaa<&serde_wormhole::RawMessage>>(data)
+    else {
+        return;
+    };
+    let num_signatures = vaa.signatures.len();
+    let cs = &mut create_constraint_system().unwrap();
+    let _ = Vaa::from_vaa_witness(cs, vaa, num_signatures);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zklink_oracle::{pyth::VaaPayload, testing::create_constraint_system};
+
+fuzz_target!(|data: &[u8]| {
+    let cs = &mut create_constraint_system().unwrap();
+    let _ = VaaPayload::new_from_slice(cs, data);
+});
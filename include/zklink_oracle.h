@@ -0,0 +1,62 @@
+/* C ABI of zklink-oracle, built with `cargo rustc --release --lib --crate-type cdylib --features ffi`. */
+#ifndef ZKLINK_ORACLE_H
+#define ZKLINK_ORACLE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Witness of a pyth oracle circuit. */
+typedef struct ZkLinkOracleWitness ZkLinkOracleWitness;
+
+/* Proving setup of a circuit shape along with its CRS. */
+typedef struct ZkLinkOracleSetup ZkLinkOracleSetup;
+
+/* Bytes allocated by the library, released by zklink_oracle_buffer_free. */
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} ZkLinkOracleBuffer;
+
+/* Message of the last failure on the calling thread, valid until the next call. NULL if none. */
+const char *zklink_oracle_last_error(void);
+
+/* Create a witness from `num_updates` PNAU accumulator updates, verifying `num_signatures`
+ * signatures of each against the `num_guardians` 20-byte addresses of `guardian_set`.
+ * Returns NULL on failure. */
+ZkLinkOracleWitness *zklink_oracle_witness_new(const uint8_t *const *updates, const size_t *lens,
+                                               size_t num_updates, const uint8_t *guardian_set,
+                                               size_t num_guardians, size_t num_signatures);
+
+/* Synthesize the circuit and check it is satisfied, `num_gates` may be NULL.
+ * Returns 0 on success, -1 on failure. */
+int32_t zklink_oracle_synthesize(const ZkLinkOracleWitness *witness, uint64_t *num_gates);
+
+/* Generate the proving setup of the circuits of `num_vaas` accumulator updates of `num_prices`
+ * prices, verifying `num_signatures` signatures against `guardian_set_size` guardians, with a
+ * monomial form CRS. The verification key is written to `verification_key` if not NULL.
+ * Returns NULL on failure. */
+ZkLinkOracleSetup *zklink_oracle_setup_new(size_t num_vaas, size_t num_signatures,
+                                           size_t num_prices, size_t guardian_set_size,
+                                           const uint8_t *crs, size_t crs_len,
+                                           ZkLinkOracleBuffer *verification_key);
+
+/* Prove `witness` with a setup of the same shape, writing the proof.
+ * Returns 0 on success, -1 on failure. */
+int32_t zklink_oracle_prove(const ZkLinkOracleWitness *witness, const ZkLinkOracleSetup *setup,
+                            ZkLinkOracleBuffer *proof);
+
+void zklink_oracle_witness_free(ZkLinkOracleWitness *witness);
+
+void zklink_oracle_setup_free(ZkLinkOracleSetup *setup);
+
+void zklink_oracle_buffer_free(ZkLinkOracleBuffer buffer);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ZKLINK_ORACLE_H */
@@ -0,0 +1,398 @@
+//! Prices attested by any oracle, proven by one circuit.
+//!
+//! An [`OracleAttestation`] is allocated from the witness of its transport, verified against the
+//! authorized signers and yields its prices as `(feed_id, price, publish_time)` bytes. Whatever the
+//! oracle, [`AttestationCircuit`] inputizes the same commitment to them, the keccak256 of
+//! [`crate::pyth::commitment`], so that consumers do not care which oracle the prices come from,
+//! along with the poseidon commitment of the signers that the verifier checks.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            pairing::{ff::Field, Engine},
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    utils::u64_to_fe,
+};
+
+use crate::{
+    error::OracleError,
+    gadgets::{
+        ethereum::Address,
+        rescue::circuit_rescue_hash,
+        staleness::{self, ReferenceTimestamp},
+    },
+    pyth::{
+        commitment::{
+            compute_packed_price_commitment_with_hash, packed_price_commitment_with_hash,
+            PriceCommitmentHash, LEN_PACKED_PRICE,
+        },
+        compute_guardian_set_commitment, GuardianSet,
+    },
+    report::section,
+};
+
+/// Price of a verified attestation, in the big-endian layout of pyth price feeds.
+#[derive(Debug, Clone, Copy)]
+pub struct AttestedPrice<E: Engine> {
+    pub feed_id: [Byte<E>; 32],
+    /// `i64` price.
+    pub price: [Byte<E>; 8],
+    /// Seconds since the unix epoch.
+    pub publish_time: [Byte<E>; 8],
+}
+
+impl<E: Engine> AttestedPrice<E> {
+    pub fn from_packed(packed: &[Byte<E>; LEN_PACKED_PRICE]) -> Self {
+        Self {
+            feed_id: packed[..32].try_into().unwrap(),
+            price: packed[32..40].try_into().unwrap(),
+            publish_time: packed[40..].try_into().unwrap(),
+        }
+    }
+
+    /// Bytes committed by [`packed_price_commitment_with_hash`].
+    pub fn pack(&self) -> [Byte<E>; LEN_PACKED_PRICE] {
+        let mut bytes = [Byte::zero(); LEN_PACKED_PRICE];
+        bytes[..32].copy_from_slice(&self.feed_id);
+        bytes[32..40].copy_from_slice(&self.price);
+        bytes[40..].copy_from_slice(&self.publish_time);
+        bytes
+    }
+}
+
+/// Signed prices of an oracle, as allocated in a circuit.
+pub trait OracleAttestation<E: Engine>: Sized {
+    /// Attestation as served by the oracle.
+    type Witness: Clone + std::fmt::Debug;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError>;
+
+    /// Whether the attestation is signed by `signers`.
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError>;
+
+    /// Attested prices, and whether all of them can be committed.
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError>;
+
+    /// Native packed [`OracleAttestation::prices`] of `witness`.
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError>;
+}
+
+/// Enforce that every attestation is signed by `signers` and that its prices can be committed, and
+/// return the prices of all of them, in order, with their commitment under `hash`.
+pub fn verify_attestations<E, CS, A>(
+    cs: &mut CS,
+    attestations: &[A],
+    signers: &[Address<E>],
+    hash: PriceCommitmentHash,
+) -> Result<(Vec<AttestedPrice<E>>, [Num<E>; 2]), SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+    A: OracleAttestation<E>,
+{
+    let mut attested = vec![];
+    for attestation in attestations.iter() {
+        section(cs, "verify_attestation", |cs| {
+            let is_valid = attestation.verify(cs, signers)?;
+            Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))
+        })?;
+
+        let (is_ok, prices) = attestation.prices(cs)?;
+        Boolean::enforce_equal(cs, &is_ok, &Boolean::constant(true))?;
+        attested.extend(prices);
+    }
+    let commitment = section(cs, "commit_prices", |cs| {
+        let packed = attested.iter().map(AttestedPrice::pack).collect::<Vec<_>>();
+        packed_price_commitment_with_hash(cs, &packed, hash)
+    })?;
+    Ok((attested, commitment))
+}
+
+/// Circuit proving the prices of `attestations` of one oracle, signed by `signers`. Its public
+/// input is [`attestation_public_input`]: the two halves of [`packed_price_commitment_with_hash`]
+/// under `commitment_hash`, keccak256 by default, high first, the poseidon commitment of the
+/// signers, then the reference timestamp of [`AttestationCircuit::with_staleness_check`] if any.
+///
+/// The signers are witnesses, so a verifier must check the signer commitment against the signers
+/// it trusts: otherwise any key could sign the prices and be listed as the signer.
+#[derive(Debug, Clone)]
+pub struct AttestationCircuit<E: Engine, A: OracleAttestation<E>> {
+    pub attestations: Vec<A::Witness>,
+    pub signers: Vec<[u8; 20]>,
+    pub commitment: [E::Fr; 2],
+    /// [`compute_guardian_set_commitment`] of the signers.
+    pub signers_commitment: E::Fr,
+    pub commitment_hash: PriceCommitmentHash,
+    /// Reference timestamp and maximum age, in seconds, of the publish times.
+    pub staleness: Option<(u64, u64)>,
+}
+
+impl<E: Engine, A: OracleAttestation<E>> AttestationCircuit<E, A> {
+    pub fn new(attestations: Vec<A::Witness>, signers: Vec<[u8; 20]>) -> Result<Self, OracleError> {
+        let commitment =
+            compute_attestation_commitment::<E, A>(&attestations, PriceCommitmentHash::default())?;
+        let signers_commitment = compute_guardian_set_commitment::<E>(&signers)?;
+        Ok(Self {
+            attestations,
+            signers,
+            commitment,
+            signers_commitment,
+            commitment_hash: PriceCommitmentHash::default(),
+            staleness: None,
+        })
+    }
+
+    /// Commit to the prices with `hash` instead of keccak256, such as an algebraic hash for a
+    /// verifier inside another SNARK.
+    pub fn with_commitment_hash(mut self, hash: PriceCommitmentHash) -> Result<Self, OracleError> {
+        self.commitment = compute_attestation_commitment::<E, A>(&self.attestations, hash)?;
+        self.commitment_hash = hash;
+        Ok(self)
+    }
+
+    /// Inputize `reference_timestamp` after the commitment and enforce that every price was
+    /// published at most `max_age` seconds before it, see [`ReferenceTimestamp::is_fresh`].
+    pub fn with_staleness_check(
+        mut self,
+        reference_timestamp: u64,
+        max_age: u64,
+    ) -> Result<Self, OracleError> {
+        for witness in self.attestations.iter() {
+            for packed in A::packed_prices(witness)? {
+                let publish_time = i64::from_be_bytes(packed[40..].try_into().unwrap());
+                if !staleness::is_fresh(reference_timestamp, publish_time, max_age) {
+                    return Err(OracleError::StalePrice {
+                        publish_time,
+                        reference_timestamp,
+                        max_age,
+                    });
+                }
+            }
+        }
+        self.staleness = Some((reference_timestamp, max_age));
+        Ok(self)
+    }
+
+    /// Public input of the proof, see [`attestation_public_input`].
+    pub fn public_input(&self) -> Result<Vec<E::Fr>, SynthesisError> {
+        attestation_public_input::<E>(
+            self.commitment,
+            &self.signers,
+            self.staleness
+                .map(|(reference_timestamp, _)| reference_timestamp),
+        )
+    }
+}
+
+/// Public input expected by the verifier of an [`AttestationCircuit`] committing to `commitment`
+/// and signed by `signers`, the signers the verifier trusts.
+pub fn attestation_public_input<E: Engine>(
+    commitment: [E::Fr; 2],
+    signers: &[[u8; 20]],
+    reference_timestamp: Option<u64>,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    let mut input = commitment.to_vec();
+    input.push(compute_guardian_set_commitment::<E>(signers)?);
+    input.extend(reference_timestamp.map(u64_to_fe::<E::Fr>));
+    Ok(input)
+}
+
+impl<E: Engine, A: OracleAttestation<E>> Circuit<E> for AttestationCircuit<E, A> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span =
+            tracing::info_span!("attestation_synthesize", num = self.attestations.len()).entered();
+        crate::utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let signers = GuardianSet::from_witness(cs, &self.signers)?;
+        let attestations = self
+            .attestations
+            .iter()
+            .map(|witness| A::from_witness(cs, witness))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (prices, commitment) =
+            verify_attestations(cs, &attestations, &signers.addresses, self.commitment_hash)?;
+        for half in commitment.iter() {
+            half.get_variable().inputize(cs)?;
+        }
+        // Without it, the prover picks the keys the attestations are checked against.
+        signers.commitment(cs)?.get_variable().inputize(cs)?;
+        if let Some((reference_timestamp, max_age)) = self.staleness {
+            let reference = ReferenceTimestamp::inputize(cs, Some(reference_timestamp))?;
+            let publish_times = prices
+                .iter()
+                .map(|price| price.publish_time)
+                .collect::<Vec<_>>();
+            reference.enforce_fresh(cs, &publish_times, max_age)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(),
+        ])
+    }
+}
+
+/// Native commitment of [`verify_attestations`], the public input of [`AttestationCircuit`].
+pub fn compute_attestation_commitment<E: Engine, A: OracleAttestation<E>>(
+    attestations: &[A::Witness],
+    hash: PriceCommitmentHash,
+) -> Result<[E::Fr; 2], OracleError> {
+    let mut packed = vec![];
+    for witness in attestations.iter() {
+        packed.extend(A::packed_prices(witness)?);
+    }
+    Ok(compute_packed_price_commitment_with_hash::<E>(
+        &packed, hash,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, plonk::better_better_cs::cs::Circuit},
+        testing::create_test_artifacts_with_optimized_gate,
+    };
+
+    use super::{attestation_public_input, AttestationCircuit, OracleAttestation};
+    use crate::{
+        error::OracleError,
+        pyth::commitment::{
+            compute_packed_price_commitment, compute_packed_price_commitment_with_hash,
+            PriceCommitmentHash,
+        },
+        stork::circuit::AllocatedSignedPriceUpdate,
+        testing::fixtures::{guardian_address, guardian_key, StorkFixture},
+    };
+
+    type StorkCircuit = AttestationCircuit<Bn256, AllocatedSignedPriceUpdate<Bn256>>;
+
+    #[test]
+    fn test_attestation_circuit() -> anyhow::Result<()> {
+        let fixture = StorkFixture::default();
+        let updates = vec![
+            fixture.update(),
+            StorkFixture {
+                asset_id: [3u8; 32],
+                ..fixture.clone()
+            }
+            .update(),
+        ];
+        let circuit = StorkCircuit::new(updates.clone(), vec![fixture.publisher_address()])?;
+        let packed = updates
+            .iter()
+            .map(|update| update.packed_price().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            circuit.commitment,
+            compute_packed_price_commitment::<Bn256>(&packed)?
+        );
+        assert_eq!(
+            AllocatedSignedPriceUpdate::<Bn256>::packed_prices(&updates[1])?,
+            vec![packed[1]]
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        assert_eq!(
+            circuit.public_input()?,
+            attestation_public_input::<Bn256>(
+                circuit.commitment,
+                &[fixture.publisher_address()],
+                None
+            )?
+        );
+
+        // Not signed by an authorized publisher.
+        let circuit = StorkCircuit::new(updates.clone(), vec![guardian_address(&guardian_key(1))])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Signed by a foreign key listed as the signer: the proof holds but commits to that signer,
+        // so a verifier expecting the authorized publisher rejects the public input.
+        let foreign = StorkFixture {
+            publisher: 1,
+            ..fixture.clone()
+        };
+        let circuit = StorkCircuit::new(vec![foreign.update()], vec![foreign.publisher_address()])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert_ne!(
+            circuit.public_input()?,
+            attestation_public_input::<Bn256>(
+                circuit.commitment,
+                &[fixture.publisher_address()],
+                None
+            )?
+        );
+
+        // Fresh for a minute after the publish time of the fixture.
+        let reference_timestamp = fixture.timestamp / 1_000_000_000 + 60;
+        let circuit = StorkCircuit::new(updates.clone(), vec![fixture.publisher_address()])?
+            .with_staleness_check(reference_timestamp, 60)?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        let err = StorkCircuit::new(updates.clone(), vec![fixture.publisher_address()])?
+            .with_staleness_check(reference_timestamp + 1, 60)
+            .unwrap_err();
+        assert!(matches!(err, OracleError::StalePrice { .. }));
+
+        // Committed with poseidon for a recursive verifier.
+        let circuit = StorkCircuit::new(updates.clone(), vec![fixture.publisher_address()])?
+            .with_commitment_hash(PriceCommitmentHash::Poseidon)?;
+        assert_eq!(
+            circuit.commitment,
+            compute_packed_price_commitment_with_hash::<Bn256>(
+                &packed,
+                PriceCommitmentHash::Poseidon
+            )?
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        // Without the native check, the circuit is not satisfied.
+        let mut circuit = StorkCircuit::new(updates.clone(), vec![fixture.publisher_address()])?;
+        circuit.staleness = Some((reference_timestamp + 1, 60));
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // A negative price cannot be committed.
+        let negative = StorkFixture {
+            quantized_value: -1,
+            ..fixture
+        };
+        assert!(StorkCircuit::new(vec![negative.update()], vec![]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::{io::Read as _, path::PathBuf};
+
+use clap::{Parser, Subcommand};
+use zklink_oracle::{config::OracleConfig, estimate, pyth::decode};
+
+#[derive(Parser)]
+#[command(name = "zklink-oracle", about = "Tooling for zklink oracle circuits")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decode and pretty-print a hex/base64 VAA or `PNAU` accumulator update.
+    Decode {
+        /// Hex or base64 encoded input, `-` to read it from stdin.
+        input: String,
+        /// Print as JSON instead of human-readable text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validate an oracle configuration file and print the size of its circuit.
+    Config {
+        /// TOML configuration file.
+        path: PathBuf,
+    },
+    /// Fetch historical updates of the configured feeds from Benchmarks and write validated witness
+    /// bundles, one JSON file per interval and bundle, to `out`.
+    #[cfg(feature = "hermes-client")]
+    Backfill {
+        /// TOML configuration file.
+        #[arg(long)]
+        config: PathBuf,
+        /// First unix timestamp of the range.
+        #[arg(long)]
+        from: i64,
+        /// Last unix timestamp of the range, inclusive.
+        #[arg(long)]
+        to: i64,
+        /// Seconds between two backfilled timestamps.
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Output directory.
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, default_value = zklink_oracle::hermes::BENCHMARKS_ENDPOINT)]
+        endpoint: String,
+    },
+}
+
+fn read_input(input: String) -> anyhow::Result<String> {
+    if input == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf)?;
+        Ok(buf)
+    } else {
+        Ok(input)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Decode { input, json } => {
+            let bytes = decode::decode_input(&read_input(input)?)?;
+            let decoded = decode::decode(&bytes)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&decoded)?);
+            } else {
+                print!("{}", decoded);
+            }
+        }
+        Command::Config { path } => {
+            let config = OracleConfig::load(&path)?;
+            let geometry = config.geometry()?;
+            println!("feeds:              {}", config.feeds.len());
+            println!("VAAs per proof:     {}", geometry.num_vaas);
+            println!("prices per VAA:     {}", geometry.num_prices);
+            println!("signatures per VAA: {}", geometry.num_signatures);
+            println!("guardians:          {}", geometry.guardian_set_size);
+            println!(
+                "estimated gates:    {}",
+                estimate::estimate_gates(&geometry)
+            );
+            println!(
+                "power of tau:       {}",
+                estimate::required_power_of_tau(&geometry)
+            );
+        }
+        #[cfg(feature = "hermes-client")]
+        Command::Backfill {
+            config,
+            from,
+            to,
+            interval,
+            out,
+            endpoint,
+        } => {
+            use zklink_oracle::hermes::{backfill, HermesClient, RetryPolicy};
+
+            let config = OracleConfig::load(&config)?;
+            let client = HermesClient::new(vec![endpoint], RetryPolicy::default())?;
+            std::fs::create_dir_all(&out)?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(backfill(&client, &config, from, to, interval, |interval| {
+                for (i, witness) in interval.witnesses.iter().enumerate() {
+                    let path = out.join(format!("{}-{}.json", interval.timestamp, i));
+                    witness.write_json(std::fs::File::create(&path)?)?;
+                }
+                println!(
+                    "{}: {} witnesses",
+                    interval.timestamp,
+                    interval.witnesses.len()
+                );
+                Ok(())
+            }))?;
+        }
+    }
+    Ok(())
+}
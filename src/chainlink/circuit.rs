@@ -0,0 +1,296 @@
+use advanced_circuit_component::{
+    circuit_structures::byte::{Byte, IntoBytes as _},
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    traits::CSAllocatable,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
+};
+
+use super::{witness::FullReport, LEN_REPORT_CONTEXT, LEN_REPORT_V3, LEN_WORD, REPORT_DECIMALS};
+use crate::{
+    attestation::{AttestedPrice, OracleAttestation},
+    error::OracleError,
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+    },
+    pyth::commitment::{COMMITTED_DECIMALS, LEN_PACKED_PRICE},
+    utils::{div_to_i64, to_array},
+};
+
+/// Bytes of the prices of a report, the low 24 bytes of their words.
+const LEN_INT192: usize = 24;
+
+/// Report of schema v3 with `NUM_SIGS` of its signatures.
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedReport<E: Engine, const NUM_SIGS: usize> {
+    pub report_context: [Byte<E>; LEN_REPORT_CONTEXT],
+    pub report: [Byte<E>; LEN_REPORT_V3],
+    pub signatures: [Signature<E>; NUM_SIGS],
+}
+
+impl<E: Engine, const NUM_SIGS: usize> AllocatedSignedReport<E, NUM_SIGS> {
+    /// Allocate the report and its first `NUM_SIGS` signatures.
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &FullReport,
+    ) -> Result<Self, OracleError> {
+        // Fail early on reports the circuit cannot parse.
+        witness.report()?;
+        let signatures = witness.signatures();
+        if signatures.len() < NUM_SIGS {
+            return Err(OracleError::InsufficientSignatures {
+                got: signatures.len(),
+                need: NUM_SIGS,
+            });
+        }
+        let report_context =
+            CSAllocatable::alloc_from_witness(cs, Some(witness.report_context_bytes()))?;
+        let report = {
+            let bytes = to_array("report", &witness.report_blob)?;
+            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+        };
+        let signatures = signatures
+            .iter()
+            .take(NUM_SIGS)
+            .map(|signature| Signature::from_bytes_witness(cs, signature))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            report_context,
+            report,
+            signatures: signatures.try_into().unwrap(),
+        })
+    }
+
+    fn word(&self, index: usize) -> &[Byte<E>] {
+        &self.report[index * LEN_WORD..(index + 1) * LEN_WORD]
+    }
+
+    pub fn feed_id(&self) -> [Byte<E>; 32] {
+        self.word(0).try_into().unwrap()
+    }
+
+    /// Observations timestamp as the 8 bytes of a pyth publish time, the uint32 being zero-padded
+    /// in its word.
+    pub fn observations_timestamp(&self) -> [Byte<E>; 8] {
+        self.word(2)[LEN_WORD - 8..].try_into().unwrap()
+    }
+
+    pub fn benchmark_price(&self) -> [Byte<E>; LEN_INT192] {
+        self.word(6)[LEN_WORD - LEN_INT192..].try_into().unwrap()
+    }
+
+    pub fn bid(&self) -> [Byte<E>; LEN_INT192] {
+        self.word(7)[LEN_WORD - LEN_INT192..].try_into().unwrap()
+    }
+
+    pub fn ask(&self) -> [Byte<E>; LEN_INT192] {
+        self.word(8)[LEN_WORD - LEN_INT192..].try_into().unwrap()
+    }
+
+    /// `keccak256(keccak256(report) || report_context)`, the hash signed by the DON.
+    pub fn signed_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let params = Keccak256Params::new(cs)?;
+        self.signed_hash_with_params(cs, &params)
+    }
+
+    /// See [`Self::signed_hash`].
+    pub fn signed_hash_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = keccak256::digest_with_params(cs, params, &self.report)?.to_vec();
+        bytes.extend(self.report_context);
+        keccak256::digest_with_params(cs, params, &bytes)
+    }
+
+    /// Whether the signatures recover to distinct members of `signers`.
+    pub fn check_by_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let keccak = Keccak256Params::new(cs)?;
+        let msg_hash = {
+            let hash = self.signed_hash_with_params(cs, &keccak)?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let params = Secp256k1Params::new(cs);
+        let mut is_ok = vec![Boolean::constant(true)];
+        let mut recovered = vec![];
+        for signature in self.signatures.iter() {
+            let (successful, address) =
+                signature.ecrecover_address_with_params(cs, &params, &keccak, &msg_hash)?;
+            let address = Address::from_bytes(cs, &address)?;
+            let mut is_signer = vec![];
+            for signer in signers.iter() {
+                is_signer.push(signer.equals(cs, &address)?);
+            }
+            is_ok.push(successful);
+            is_ok.push(smart_or(cs, &is_signer)?);
+            for previous in recovered.iter() {
+                is_ok.push(address.equals(cs, previous)?.not());
+            }
+            recovered.push(address);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// The benchmark price packed as [`crate::pyth::commitment::pack_price`] packs pyth prices, and
+    /// whether it can be: the price is truncated to [`COMMITTED_DECIMALS`] and must be non-negative
+    /// and fit an `i64`.
+    pub fn packed_price<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, [Byte<E>; LEN_PACKED_PRICE]), SynthesisError> {
+        // a negative price reads as an unsigned of at least 2^191, whose quotient does not fit
+        let (is_ok, quotient) = div_to_i64(
+            cs,
+            &self.benchmark_price(),
+            10u64.pow(REPORT_DECIMALS - COMMITTED_DECIMALS),
+        )?;
+
+        let mut packed = [Byte::zero(); LEN_PACKED_PRICE];
+        packed[..32].copy_from_slice(&self.feed_id());
+        packed[32..40].copy_from_slice(&quotient.into_be_bytes(cs)?);
+        packed[40..].copy_from_slice(&self.observations_timestamp());
+        Ok((is_ok, packed))
+    }
+}
+
+impl<E: Engine, const NUM_SIGS: usize> OracleAttestation<E> for AllocatedSignedReport<E, NUM_SIGS> {
+    type Witness = FullReport;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError> {
+        AllocatedSignedReport::from_witness(cs, witness)
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        self.check_by_signers(cs, signers)
+    }
+
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError> {
+        let (is_ok, packed) = self.packed_price(cs)?;
+        Ok((is_ok, vec![AttestedPrice::from_packed(&packed)]))
+    }
+
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        let packed = witness.report()?.packed_price().ok_or_else(|| {
+            OracleError::ReportDecode("benchmark price cannot be committed".to_string())
+        })?;
+        Ok(vec![packed])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::AllocatedSignedReport;
+    use crate::{
+        error::OracleError,
+        gadgets::ethereum::Address,
+        pyth::commitment::{compute_packed_price_commitment, packed_price_commitment},
+        testing::fixtures::ChainlinkFixture,
+        utils::testing::create_test_constraint_system,
+    };
+
+    #[test]
+    fn test_signed_report() -> Result<(), SynthesisError> {
+        let fixture = ChainlinkFixture {
+            num_signatures: 2,
+            ..Default::default()
+        };
+        let full_report = fixture.full_report();
+        let cs = &mut create_test_constraint_system()?;
+        let signers = fixture
+            .signers()
+            .iter()
+            .map(|signer| Address::from_address_witness(cs, signer))
+            .collect::<Result<Vec<_>, _>>()?;
+        let report = AllocatedSignedReport::<Bn256, 2>::from_witness(cs, &full_report)?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&report.signed_hash(cs)?),
+            Some(full_report.signed_hash().to_vec())
+        );
+        assert_eq!(
+            report.check_by_signers(cs, &signers)?.get_value(),
+            Some(true)
+        );
+        // Without the second signer.
+        assert_eq!(
+            report.check_by_signers(cs, &signers[..1])?.get_value(),
+            Some(false)
+        );
+
+        let (is_ok, packed) = report.packed_price(cs)?;
+        assert_eq!(is_ok.get_value(), Some(true));
+        let expected = full_report.report().unwrap().packed_price().unwrap();
+        assert_eq!(
+            Byte::get_byte_value_multiple(&packed),
+            Some(expected.to_vec())
+        );
+        let commitment = packed_price_commitment(cs, &[packed])?;
+        assert_eq!(
+            commitment.map(|half| half.get_value()),
+            compute_packed_price_commitment::<Bn256>(&[expected])?.map(Some)
+        );
+        assert!(cs.is_satisfied());
+
+        // The same signature twice.
+        let mut repeated = full_report.clone();
+        repeated.raw_rs[1] = repeated.raw_rs[0];
+        repeated.raw_ss[1] = repeated.raw_ss[0];
+        repeated.raw_vs[1] = repeated.raw_vs[0];
+        let report = AllocatedSignedReport::<Bn256, 2>::from_witness(cs, &repeated)?;
+        assert_eq!(
+            report.check_by_signers(cs, &signers)?.get_value(),
+            Some(false)
+        );
+
+        let err = AllocatedSignedReport::<Bn256, 3>::from_witness(cs, &full_report).unwrap_err();
+        assert!(matches!(
+            err,
+            OracleError::InsufficientSignatures { got: 2, need: 3 }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative_price_is_not_packed() -> Result<(), SynthesisError> {
+        let fixture = ChainlinkFixture {
+            num_signatures: 1,
+            benchmark_price: -1,
+            ..Default::default()
+        };
+        let cs = &mut create_test_constraint_system()?;
+        let report = AllocatedSignedReport::<Bn256, 1>::from_witness(cs, &fixture.full_report())?;
+        let (is_ok, _) = report.packed_price(cs)?;
+        assert_eq!(is_ok.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
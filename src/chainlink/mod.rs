@@ -0,0 +1,24 @@
+//! Chainlink Data Streams reports.
+//!
+//! A report of schema v3 is signed by the nodes of a DON over
+//! `keccak256(keccak256(report) || report_context)`. The circuit checks the signatures against the
+//! authorized signers and packs the benchmark price as the pyth prices of
+//! [`crate::pyth::commitment`], so that both oracles share one commitment layout. Benchmark prices
+//! have 18 decimals and are committed with
+//! [`COMMITTED_DECIMALS`](crate::pyth::commitment::COMMITTED_DECIMALS), to fit the `i64` of pyth
+//! prices.
+
+pub mod circuit;
+pub mod witness;
+
+/// Bytes of an ABI word.
+pub const LEN_WORD: usize = 32;
+/// Bytes of the report context: config digest, epoch and round, extra hash.
+pub const LEN_REPORT_CONTEXT: usize = 3 * LEN_WORD;
+/// Bytes of a v3 report: feed id, valid from and observations timestamps, native and link fees,
+/// expiry, benchmark, bid and ask prices, one word each.
+pub const LEN_REPORT_V3: usize = 9 * LEN_WORD;
+/// Report schema version, in the first two bytes of the feed id.
+pub const REPORT_SCHEMA_V3: u16 = 3;
+/// Decimals of the prices in a report.
+pub const REPORT_DECIMALS: u32 = 18;
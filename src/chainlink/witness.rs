@@ -0,0 +1,278 @@
+use num_bigint::{BigInt, BigUint, Sign};
+
+use super::{LEN_REPORT_CONTEXT, LEN_REPORT_V3, LEN_WORD, REPORT_DECIMALS, REPORT_SCHEMA_V3};
+use crate::{
+    error::OracleError,
+    pyth::commitment::{COMMITTED_DECIMALS, LEN_PACKED_PRICE},
+};
+
+/// Signed report as served by the Data Streams API, the ABI encoding of
+/// `(bytes32[3] reportContext, bytes reportBlob, bytes32[] rawRs, bytes32[] rawSs, bytes32 rawVs)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullReport {
+    pub report_context: [[u8; 32]; 3],
+    pub report_blob: Vec<u8>,
+    pub raw_rs: Vec<[u8; 32]>,
+    pub raw_ss: Vec<[u8; 32]>,
+    /// Recovery id of the i-th signature at byte i.
+    pub raw_vs: [u8; 32],
+}
+
+impl FullReport {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        let report_context = [word(bytes, 0)?, word(bytes, 1)?, word(bytes, 2)?];
+        let report_blob = {
+            let offset = word_as_usize(bytes, 3)?;
+            let len = word_as_usize(&bytes[offset.min(bytes.len())..], 0)?;
+            let start = offset.saturating_add(LEN_WORD);
+            bytes
+                .get(start..start.saturating_add(len))
+                .ok_or(OracleError::InvalidLength {
+                    what: "report blob",
+                    got: bytes.len().saturating_sub(start),
+                    expected: len,
+                })?
+                .to_vec()
+        };
+        let words_at = |index: usize| -> Result<Vec<[u8; 32]>, OracleError> {
+            let offset = word_as_usize(bytes, index)?;
+            let tail = &bytes[offset.min(bytes.len())..];
+            let len = word_as_usize(tail, 0)?;
+            (1..=len).map(|i| word(tail, i)).collect()
+        };
+        let raw_rs = words_at(4)?;
+        let raw_ss = words_at(5)?;
+        if raw_rs.len() != raw_ss.len() {
+            return Err(OracleError::InvalidLength {
+                what: "report signature s values",
+                got: raw_ss.len(),
+                expected: raw_rs.len(),
+            });
+        }
+        // `raw_vs` holds one recovery id per byte.
+        if raw_rs.len() > LEN_WORD {
+            return Err(OracleError::ReportDecode(format!(
+                "{} signatures, at most {}",
+                raw_rs.len(),
+                LEN_WORD
+            )));
+        }
+        Ok(Self {
+            report_context,
+            report_blob,
+            raw_rs,
+            raw_ss,
+            raw_vs: word(bytes, 6)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let blob_words = self.report_blob.len().div_ceil(LEN_WORD);
+        let blob_offset = 7 * LEN_WORD;
+        let rs_offset = blob_offset + (1 + blob_words) * LEN_WORD;
+        let ss_offset = rs_offset + (1 + self.raw_rs.len()) * LEN_WORD;
+
+        let mut bytes = self.report_context.concat();
+        for offset in [blob_offset, rs_offset, ss_offset] {
+            bytes.extend(usize_to_word(offset));
+        }
+        bytes.extend(self.raw_vs);
+        bytes.extend(usize_to_word(self.report_blob.len()));
+        bytes.extend(&self.report_blob);
+        bytes.resize(rs_offset, 0);
+        for values in [&self.raw_rs, &self.raw_ss] {
+            bytes.extend(usize_to_word(values.len()));
+            bytes.extend(values.concat());
+        }
+        bytes
+    }
+
+    /// Hash signed by the DON, `keccak256(keccak256(report) || report_context)`.
+    pub fn signed_hash(&self) -> [u8; 32] {
+        let mut bytes = crate::keccak::keccak256(&self.report_blob).to_vec();
+        bytes.extend(self.report_context.concat());
+        crate::keccak::keccak256(&bytes)
+    }
+
+    /// Signatures in format `32-byte r || 32-byte s || 1-byte recid`.
+    pub fn signatures(&self) -> Vec<[u8; 65]> {
+        self.raw_rs
+            .iter()
+            .zip(self.raw_ss.iter())
+            .enumerate()
+            .map(|(i, (r, s))| {
+                let mut signature = [0u8; 65];
+                signature[..32].copy_from_slice(r);
+                signature[32..64].copy_from_slice(s);
+                signature[64] = self.raw_vs[i];
+                signature
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> Result<ReportV3, OracleError> {
+        ReportV3::from_bytes(&self.report_blob)
+    }
+
+    pub fn report_context_bytes(&self) -> [u8; LEN_REPORT_CONTEXT] {
+        self.report_context.concat().try_into().unwrap()
+    }
+}
+
+/// Report of schema v3, for crypto assets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportV3 {
+    pub feed_id: [u8; 32],
+    pub valid_from_timestamp: u32,
+    pub observations_timestamp: u32,
+    pub native_fee: BigUint,
+    pub link_fee: BigUint,
+    pub expires_at: u32,
+    pub benchmark_price: BigInt,
+    pub bid: BigInt,
+    pub ask: BigInt,
+}
+
+impl ReportV3 {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        if bytes.len() != LEN_REPORT_V3 {
+            return Err(OracleError::InvalidLength {
+                what: "report",
+                got: bytes.len(),
+                expected: LEN_REPORT_V3,
+            });
+        }
+        let feed_id = word(bytes, 0)?;
+        let version = u16::from_be_bytes([feed_id[0], feed_id[1]]);
+        if version != REPORT_SCHEMA_V3 {
+            return Err(OracleError::ReportDecode(format!(
+                "unsupported report schema version {}",
+                version
+            )));
+        }
+        let as_u32 = |index: usize| -> Result<u32, OracleError> {
+            u32::try_from(word_as_usize(bytes, index)?).map_err(|_| {
+                OracleError::ReportDecode(format!("word {} overflows a uint32", index))
+            })
+        };
+        let as_uint = |index: usize| word(bytes, index).map(|w| BigUint::from_bytes_be(&w));
+        let as_int = |index: usize| word(bytes, index).map(|w| BigInt::from_signed_bytes_be(&w));
+        Ok(Self {
+            feed_id,
+            valid_from_timestamp: as_u32(1)?,
+            observations_timestamp: as_u32(2)?,
+            native_fee: as_uint(3)?,
+            link_fee: as_uint(4)?,
+            expires_at: as_u32(5)?,
+            benchmark_price: as_int(6)?,
+            bid: as_int(7)?,
+            ask: as_int(8)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.feed_id.to_vec();
+        for timestamp in [self.valid_from_timestamp, self.observations_timestamp] {
+            bytes.extend(usize_to_word(timestamp as usize));
+        }
+        for fee in [&self.native_fee, &self.link_fee] {
+            let fee = fee.to_bytes_be();
+            bytes.extend(vec![0u8; LEN_WORD - fee.len()]);
+            bytes.extend(fee);
+        }
+        bytes.extend(usize_to_word(self.expires_at as usize));
+        for price in [&self.benchmark_price, &self.bid, &self.ask] {
+            let padding = if price.sign() == Sign::Minus { 0xff } else { 0 };
+            let price = price.to_signed_bytes_be();
+            bytes.extend(vec![padding; LEN_WORD - price.len()]);
+            bytes.extend(price);
+        }
+        bytes
+    }
+
+    /// Benchmark price with [`COMMITTED_DECIMALS`], rounded down, if it is not negative and fits an
+    /// `i64`.
+    pub fn committed_price(&self) -> Option<i64> {
+        let divisor = BigInt::from(10u64.pow(REPORT_DECIMALS - COMMITTED_DECIMALS));
+        if self.benchmark_price.sign() == Sign::Minus {
+            return None;
+        }
+        i64::try_from(&self.benchmark_price / divisor).ok()
+    }
+
+    /// `feed_id || price || observations_timestamp` as packed by
+    /// [`crate::pyth::commitment::pack_price`], if the price can be committed.
+    pub fn packed_price(&self) -> Option<[u8; LEN_PACKED_PRICE]> {
+        let price = self.committed_price()?;
+        let mut bytes = [0u8; LEN_PACKED_PRICE];
+        bytes[..32].copy_from_slice(&self.feed_id);
+        bytes[32..40].copy_from_slice(&price.to_be_bytes());
+        bytes[40..].copy_from_slice(&(self.observations_timestamp as u64).to_be_bytes());
+        Some(bytes)
+    }
+}
+
+fn word(bytes: &[u8], index: usize) -> Result<[u8; 32], OracleError> {
+    let start = index * LEN_WORD;
+    bytes
+        .get(start..start + LEN_WORD)
+        .map(|word| word.try_into().unwrap())
+        .ok_or(OracleError::InvalidLength {
+            what: "ABI encoded report",
+            got: bytes.len(),
+            expected: start + LEN_WORD,
+        })
+}
+
+fn word_as_usize(bytes: &[u8], index: usize) -> Result<usize, OracleError> {
+    let word = word(bytes, index)?;
+    if word[..LEN_WORD - 8].iter().any(|b| *b != 0) {
+        return Err(OracleError::ReportDecode(format!(
+            "word {} overflows a length",
+            index
+        )));
+    }
+    Ok(u64::from_be_bytes(word[LEN_WORD - 8..].try_into().unwrap()) as usize)
+}
+
+fn usize_to_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[LEN_WORD - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FullReport, ReportV3};
+    use crate::error::OracleError;
+
+    #[test]
+    fn test_full_report_round_trip() -> anyhow::Result<()> {
+        let full_report = crate::testing::fixtures::ChainlinkFixture::default().full_report();
+        let bytes = full_report.to_bytes();
+        assert_eq!(bytes.len() % 32, 0);
+        assert_eq!(FullReport::from_bytes(&bytes)?, full_report);
+
+        let report = full_report.report()?;
+        assert_eq!(ReportV3::from_bytes(&report.to_bytes())?, report);
+        assert_eq!(report.committed_price(), Some(301_234_567_890));
+
+        let mut unsupported = full_report.report_blob.clone();
+        unsupported[1] = 2;
+        assert!(matches!(
+            ReportV3::from_bytes(&unsupported),
+            Err(OracleError::ReportDecode(_))
+        ));
+        assert!(matches!(
+            FullReport::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(OracleError::InvalidLength { .. })
+        ));
+
+        // Length of the report blob at the end of the address space.
+        let mut overflowing = bytes.clone();
+        let offset = u64::from_be_bytes(bytes[4 * 32 - 8..4 * 32].try_into()?) as usize;
+        overflowing[offset + 24..offset + 32].fill(0xff);
+        assert!(FullReport::from_bytes(&overflowing).is_err());
+        Ok(())
+    }
+}
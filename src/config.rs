@@ -0,0 +1,314 @@
+//! Oracle configuration shared by the CLI and the prover service.
+//!
+//! A single TOML file describes the circuit geometry, the guardian set and the feeds to prove:
+//!
+//! ```toml
+//! [circuit]
+//! num_vaas = 1
+//! num_prices = 3
+//! num_signatures = 13 # optional, the guardian set quorum by default
+//! environment = "mainnet" # optional, or "testnet" to accept the VAAs of pythtest
+//!
+//! [guardian_set]
+//! source = "mainnet" # or "file" with `path = "guardians.toml"`, or "inline" with the fields
+//!                    # of a guardian set configuration
+//!
+//! [[feeds]]
+//! id = "0xe62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43"
+//! max_staleness = 60 # optional, maximum age of a price in seconds
+//! max_conf_bps = 50  # optional, maximum confidence interval in basis points of the price
+//! ```
+//!
+//! A relative guardian set `path` is resolved against the directory of the configuration file.
+
+use std::path::{Path, PathBuf};
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use anyhow::Context as _;
+use pythnet_sdk::{messages::Message, wire::from_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    estimate::CircuitGeometry,
+    pyth::{
+        AccumulatorUpdateWitness, GuardianSetConfig, GuardianSetSource, OracleCircuitBuilder,
+        PythEnvironment,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleConfig {
+    pub circuit: CircuitConfig,
+    #[serde(default)]
+    pub guardian_set: GuardianSetSourceConfig,
+    pub feeds: Vec<FeedConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitConfig {
+    pub num_vaas: usize,
+    pub num_prices: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_signatures: Option<usize>,
+    #[serde(default)]
+    pub environment: PythEnvironment,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum GuardianSetSourceConfig {
+    #[default]
+    Mainnet,
+    File {
+        path: PathBuf,
+    },
+    Inline(GuardianSetConfig),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// Hex feed id, with or without `0x`.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_staleness: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_conf_bps: Option<u64>,
+}
+
+impl FeedConfig {
+    pub fn feed_id(&self) -> Result<[u8; 32], anyhow::Error> {
+        hex::decode(self.id.strip_prefix("0x").unwrap_or(&self.id))
+            .with_context(|| format!("invalid feed id {}", self.id))?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("invalid feed id length {}", self.id))
+    }
+
+    /// Check a price of this feed against the thresholds at time `now`.
+    pub fn check(
+        &self,
+        price: i64,
+        conf: u64,
+        publish_time: i64,
+        now: i64,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(max_staleness) = self.max_staleness {
+            anyhow::ensure!(
+                crate::pyth::timestamp::is_fresh(now, publish_time, max_staleness),
+                "price of {} published at {} is older than {} seconds",
+                self.id,
+                publish_time,
+                max_staleness
+            );
+        }
+        if let Some(max_conf_bps) = self.max_conf_bps {
+            anyhow::ensure!(
+                conf as u128 * 10_000 <= price.unsigned_abs() as u128 * max_conf_bps as u128,
+                "confidence {} of {} exceeds {} bps of price {}",
+                conf,
+                self.id,
+                max_conf_bps,
+                price
+            );
+        }
+        Ok(())
+    }
+}
+
+impl OracleConfig {
+    pub fn from_toml(s: &str) -> Result<Self, anyhow::Error> {
+        toml::from_str::<Self>(s)?.validate()
+    }
+
+    /// Load from a TOML file, resolving a relative guardian set path against its directory.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut config = Self::from_toml(&content)
+            .with_context(|| format!("invalid configuration {}", path.display()))?;
+        if let GuardianSetSourceConfig::File { path: file } = &mut config.guardian_set {
+            if file.is_relative() {
+                if let Some(dir) = path.parent() {
+                    *file = dir.join(&*file);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    fn validate(self) -> Result<Self, anyhow::Error> {
+        anyhow::ensure!(self.circuit.num_vaas > 0, "at least one VAA is required");
+        anyhow::ensure!(
+            self.circuit.num_prices > 0,
+            "at least one price per update is required"
+        );
+        anyhow::ensure!(
+            self.circuit.num_signatures != Some(0),
+            "no signature to verify"
+        );
+        anyhow::ensure!(!self.feeds.is_empty(), "no feed configured");
+        let ids = self
+            .feeds
+            .iter()
+            .map(|feed| feed.feed_id())
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, id) in ids.iter().enumerate() {
+            if let Some(j) = ids[..i].iter().position(|other| other == id) {
+                anyhow::bail!("feed {} duplicates feed {}", i, j);
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn guardian_set_source(&self) -> GuardianSetSource {
+        match &self.guardian_set {
+            GuardianSetSourceConfig::Mainnet => GuardianSetSource::Mainnet,
+            GuardianSetSourceConfig::File { path } => GuardianSetSource::File(path.clone()),
+            GuardianSetSourceConfig::Inline(config) => GuardianSetSource::Config(config.clone()),
+        }
+    }
+
+    /// Geometry of the configured circuit, loading the guardian set for its size and quorum.
+    pub fn geometry(&self) -> Result<CircuitGeometry, anyhow::Error> {
+        let guardian_set = self.guardian_set_source().load()?;
+        Ok(CircuitGeometry {
+            guardian_set_size: guardian_set.addresses.len(),
+            ..CircuitGeometry::new(
+                self.circuit.num_vaas,
+                self.circuit
+                    .num_signatures
+                    .unwrap_or_else(|| guardian_set.quorum()),
+                self.circuit.num_prices,
+            )
+        })
+    }
+
+    /// Hex feed ids of all configured feeds.
+    pub fn feed_ids(&self) -> Vec<String> {
+        self.feeds
+            .iter()
+            .map(|feed| {
+                feed.id
+                    .strip_prefix("0x")
+                    .unwrap_or(&feed.id)
+                    .to_lowercase()
+            })
+            .collect()
+    }
+
+    /// Circuit builder of the configured geometry and guardian set, `NUM_PRICES` must match.
+    pub fn circuit_builder<E: Engine, const NUM_PRICES: usize>(
+        &self,
+    ) -> Result<OracleCircuitBuilder<E, NUM_PRICES>, anyhow::Error> {
+        anyhow::ensure!(
+            NUM_PRICES == self.circuit.num_prices,
+            "configured for {} prices, got {}",
+            self.circuit.num_prices,
+            NUM_PRICES
+        );
+        let mut builder = OracleCircuitBuilder::new()
+            .num_vaas(self.circuit.num_vaas)
+            .environment(self.circuit.environment)
+            .guardian_set(self.guardian_set_source());
+        if let Some(num_signatures) = self.circuit.num_signatures {
+            builder = builder.num_signatures(num_signatures);
+        }
+        Ok(builder)
+    }
+
+    /// Check every price of `update` belongs to a configured feed and meets its thresholds.
+    pub fn check_update(
+        &self,
+        update: &AccumulatorUpdateWitness,
+        now: i64,
+    ) -> Result<(), anyhow::Error> {
+        for price_update in update.updates.iter() {
+            let Message::PriceFeedMessage(price_feed) =
+                from_slice::<byteorder::BE, Message>(&price_update.message)?
+            else {
+                anyhow::bail!("invalid price feed message");
+            };
+            let feed_id = hex::encode(price_feed.feed_id);
+            let feed = self
+                .feeds
+                .iter()
+                .zip(self.feed_ids())
+                .find_map(|(feed, id)| (id == feed_id).then_some(feed))
+                .ok_or_else(|| anyhow::anyhow!("feed {} is not configured", feed_id))?;
+            feed.check(
+                price_feed.price,
+                price_feed.conf,
+                price_feed.publish_time,
+                now,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GuardianSetSourceConfig, OracleConfig};
+    use crate::testing::fixtures::{AccumulatorFixture, FixturePrice};
+
+    const CONFIG: &str = r#"
+[circuit]
+num_vaas = 2
+num_prices = 1
+
+[guardian_set]
+source = "file"
+path = "guardians.toml"
+
+[[feeds]]
+id = "0x0101010101010101010101010101010101010101010101010101010101010101"
+max_staleness = 60
+max_conf_bps = 100
+"#;
+
+    #[test]
+    fn test_from_toml() -> anyhow::Result<()> {
+        let config = OracleConfig::from_toml(CONFIG)?;
+        assert_eq!(config.circuit.num_vaas, 2);
+        assert_eq!(
+            config.guardian_set,
+            GuardianSetSourceConfig::File {
+                path: "guardians.toml".into()
+            }
+        );
+        assert_eq!(config.feed_ids(), vec![hex::encode([1u8; 32])]);
+
+        let duplicated = format!(
+            "{}\n[[feeds]]\nid = \"{}\"\n",
+            CONFIG,
+            hex::encode([1u8; 32])
+        );
+        assert!(OracleConfig::from_toml(&duplicated).is_err());
+
+        let mainnet = OracleConfig::from_toml(&CONFIG.replace(
+            "source = \"file\"\npath = \"guardians.toml\"",
+            "source = \"mainnet\"",
+        ))?;
+        let geometry = mainnet.geometry()?;
+        assert_eq!(geometry.num_signatures, 13);
+        assert_eq!(geometry.guardian_set_size, 19);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_update() -> anyhow::Result<()> {
+        let config = OracleConfig::from_toml(CONFIG)?;
+        let price = |feed_id, conf| FixturePrice {
+            conf,
+            ..FixturePrice::new(feed_id, 1000, -8, 100)
+        };
+        let update = AccumulatorFixture::with_prices(vec![price([1; 32], 10)]).witness();
+        config.check_update(&update, 160)?;
+        assert!(config.check_update(&update, 161).is_err());
+
+        let wide = AccumulatorFixture::with_prices(vec![price([1; 32], 11)]).witness();
+        assert!(config.check_update(&wide, 100).is_err());
+        let unknown = AccumulatorFixture::with_prices(vec![price([2; 32], 10)]).witness();
+        assert!(config.check_update(&unknown, 100).is_err());
+        Ok(())
+    }
+}
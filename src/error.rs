@@ -0,0 +1,86 @@
+use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
+
+/// Errors of witness construction.
+///
+/// Witness constructors return `OracleError` so that callers can tell the causes apart, it is only
+/// converted into [`SynthesisError`] at the circuit boundary. The original error is kept inside
+/// `SynthesisError::IoError` and can be recovered with [`OracleError::from_synthesis_error`].
+#[derive(Debug, thiserror::Error)]
+pub enum OracleError {
+    #[error("invalid {what} length {got}, expect {expected}")]
+    InvalidLength {
+        what: &'static str,
+        got: usize,
+        expected: usize,
+    },
+    #[error("got {got} signatures, need at least {need}")]
+    InsufficientSignatures { got: usize, need: usize },
+    #[error("expected {expected} prices, got {got}")]
+    InvalidPriceCount { got: usize, expected: usize },
+    #[error("failed to decode VAA: {0}")]
+    VaaDecode(String),
+    #[error("failed to decode wormhole payload: {0}")]
+    PayloadDecode(String),
+    #[error("failed to decode price message: {0}")]
+    MessageDecode(String),
+    #[error("invalid message type {got}, expect {expected}")]
+    InvalidMessageType { got: u8, expected: u8 },
+    #[error("unsupported message, only price feed messages are supported")]
+    UnsupportedMessage,
+    #[error("failed to decode report: {0}")]
+    ReportDecode(String),
+    #[error("price published at {publish_time} is older than {max_age}s at {reference_timestamp}")]
+    StalePrice {
+        publish_time: i64,
+        reference_timestamp: u64,
+        max_age: u64,
+    },
+    #[error("price {price}e{exponent} cannot be committed with {decimals} decimals")]
+    UncommittablePrice {
+        price: i64,
+        exponent: i32,
+        decimals: u32,
+    },
+    #[error("invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error(transparent)]
+    Synthesis(#[from] SynthesisError),
+}
+
+impl OracleError {
+    /// Recover an `OracleError` converted into a `SynthesisError`.
+    pub fn from_synthesis_error(err: &SynthesisError) -> Option<&Self> {
+        match err {
+            SynthesisError::IoError(e) => e.get_ref()?.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl From<OracleError> for SynthesisError {
+    fn from(err: OracleError) -> Self {
+        match err {
+            OracleError::Synthesis(e) => e,
+            err => SynthesisError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
+
+    use super::OracleError;
+
+    #[test]
+    fn test_synthesis_error_round_trip() {
+        let err: SynthesisError = OracleError::InsufficientSignatures { got: 1, need: 13 }.into();
+        assert!(matches!(
+            OracleError::from_synthesis_error(&err),
+            Some(OracleError::InsufficientSignatures { got: 1, need: 13 })
+        ));
+
+        let err: SynthesisError = OracleError::Synthesis(SynthesisError::Unsatisfiable).into();
+        assert!(matches!(err, SynthesisError::Unsatisfiable));
+    }
+}
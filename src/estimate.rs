@@ -0,0 +1,178 @@
+//! Constraint-count estimation for the pyth [`PriceOracle`](crate::pyth::PriceOracle) circuit.
+//!
+//! Synthesizing the oracle circuit just to learn its size takes minutes for realistic geometries.
+//! The functions here compute the size from per-gadget cost models instead, so a service can pick
+//! the CRS size or reject an over-budget request immediately. Only the dominant costs,
+//! [`KECCAK_BLOCK_GATES`] and [`ECRECOVER_GATES`], are calibrated by the tests at the bottom of this
+//! file, which synthesize each gadget and compare the result with the model, along with the total of
+//! the default geometry. The other costs are rough guesses, a fraction of a percent of the total.
+//!
+//! There is no constraint-free synthesis mode running the gadgets without a real assembly: the
+//! gadgets read lookup tables, explicit constants and variable values back from the constraint
+//! system while computing their witnesses, so counting them would take a second implementation of
+//! `TrivialAssembly`. For exact numbers, [`crate::report::ConstraintReport`] synthesizes the circuit.
+
+/// Shape of a pyth [`PriceOracle`](crate::pyth::PriceOracle) circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitGeometry {
+    /// Number of accumulator updates (one VAA each) verified by the circuit.
+    pub num_vaas: usize,
+    /// Number of VAA signatures verified for each accumulator update.
+    pub num_signatures: usize,
+    /// Number of price updates contained in each accumulator update.
+    pub num_prices: usize,
+    /// Depth of the pyth merkle tree, which is `10` by now.
+    pub merkle_depth: usize,
+    /// Number of guardians in the wormhole guardian set, which is `19` by now.
+    pub guardian_set_size: usize,
+}
+
+impl CircuitGeometry {
+    pub fn new(num_vaas: usize, num_signatures: usize, num_prices: usize) -> Self {
+        Self {
+            num_vaas,
+            num_signatures,
+            num_prices,
+            merkle_depth: DEFAULT_MERKLE_DEPTH,
+            guardian_set_size: DEFAULT_GUARDIAN_SET_SIZE,
+        }
+    }
+}
+
+pub const DEFAULT_MERKLE_DEPTH: usize = 10;
+pub const DEFAULT_GUARDIAN_SET_SIZE: usize = 19;
+
+/// Gates of one keccak256 permutation (136 bytes of input).
+pub const KECCAK_BLOCK_GATES: usize = 26_723;
+/// Gates of one ECDSA public key recovery.
+pub const ECRECOVER_GATES: usize = 897_803;
+/// Gates of matching one recovered address against one guardian. Rough, not measured.
+pub const GUARDIAN_MATCH_GATES: usize = 60;
+/// Gates of sorting the two children of a merkle node, hashing excluded. Rough, not measured.
+pub const MERKLE_NODE_GATES: usize = 150;
+/// Gates of normalizing one price to 18 decimals. Rough, not measured.
+pub const PRICE_NORMALIZATION_GATES: usize = 400;
+/// Gates of one poseidon permutation. Rough, not measured.
+pub const POSEIDON_PERMUTATION_GATES: usize = 300;
+/// Gates that do not depend on the geometry: lookup tables, rescue hash and the final commitment.
+/// Rough, not measured.
+pub const FIXED_GATES: usize = 1_000;
+
+const KECCAK_RATE_BYTES: usize = 136;
+const POSEIDON_RATE: usize = 2;
+const LEN_VAA_BODY: usize = 88;
+const LEN_PRICE_FEED_LEAF: usize = 1 + crate::pyth::WIDTH_PRICE_FEED_BYTES;
+const LEN_MERKLE_NODE: usize = 1 + 2 * crate::gadgets::keccak160::WIDTH_HASH_BYTES;
+
+/// Number of keccak permutations needed to hash `len` bytes.
+pub fn keccak_blocks(len: usize) -> usize {
+    // At least one byte of padding is always appended.
+    len / KECCAK_RATE_BYTES + 1
+}
+
+/// Number of poseidon permutations needed to hash `len` field elements.
+pub fn poseidon_permutations(len: usize) -> usize {
+    len.div_ceil(POSEIDON_RATE).max(1)
+}
+
+/// Estimated gates of verifying the signatures of one VAA.
+pub fn vaa_gates(geometry: &CircuitGeometry) -> usize {
+    let body_digest = keccak_blocks(LEN_VAA_BODY) + keccak_blocks(32);
+    let per_signature = ECRECOVER_GATES
+        + keccak_blocks(64) * KECCAK_BLOCK_GATES
+        + geometry.guardian_set_size * GUARDIAN_MATCH_GATES;
+    body_digest * KECCAK_BLOCK_GATES + geometry.num_signatures * per_signature
+}
+
+/// Estimated gates of verifying one price update against the merkle root and committing to it.
+pub fn price_update_gates(geometry: &CircuitGeometry) -> usize {
+    let leaf = keccak_blocks(LEN_PRICE_FEED_LEAF) * KECCAK_BLOCK_GATES;
+    let node = keccak_blocks(LEN_MERKLE_NODE) * KECCAK_BLOCK_GATES + MERKLE_NODE_GATES;
+    leaf + geometry.merkle_depth * node + PRICE_NORMALIZATION_GATES
+}
+
+/// Estimated gates of the whole circuit.
+pub fn estimate_gates(geometry: &CircuitGeometry) -> usize {
+    let prices_commitment =
+        poseidon_permutations(2 * geometry.num_prices) * POSEIDON_PERMUTATION_GATES;
+    let per_vaa = vaa_gates(geometry)
+        + geometry.num_prices * price_update_gates(geometry)
+        + prices_commitment;
+    let guardian_set_hash =
+        poseidon_permutations(geometry.guardian_set_size) * POSEIDON_PERMUTATION_GATES;
+    FIXED_GATES + guardian_set_hash + geometry.num_vaas * per_vaa
+}
+
+/// Returns the smallest power of tau whose CRS can hold the circuit.
+pub fn required_power_of_tau(geometry: &CircuitGeometry) -> u32 {
+    let gates = estimate_gates(geometry);
+    // The domain must be strictly larger than the number of gates.
+    (gates + 1).next_power_of_two().trailing_zeros()
+}
+
+/// Returns true if the circuit fits into a CRS of size `2^power_of_tau`.
+pub fn fits(geometry: &CircuitGeometry, power_of_tau: u32) -> bool {
+    required_power_of_tau(geometry) <= power_of_tau
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::SynthesisError,
+        traits::CSAllocatable,
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::CircuitGeometry;
+    use crate::{gadgets::ecdsa::Signature, utils::testing::create_test_constraint_system};
+
+    fn assert_close(estimated: usize, measured: usize) {
+        let diff = estimated.abs_diff(measured);
+        assert!(
+            diff * 100 <= measured * 15,
+            "estimated {} gates, measured {}",
+            estimated,
+            measured
+        );
+    }
+
+    #[test]
+    fn test_keccak_block_cost() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let bytes = (0..100u8)
+            .map(|b| Byte::from_u8_witness(cs, Some(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = cs.n();
+        crate::gadgets::keccak256::digest(cs, &bytes)?;
+        assert_close(super::KECCAK_BLOCK_GATES, cs.n() - n);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecrecover_cost() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let signature = hex::decode("0c0422df7d6f26a8d6250236060b8acd514fa4e8d260ff3c32c3aad4b6b470376e0f5a27e14e47ad328d01c3d8a4b969febab06ea26c84caa1fbe1779d62a78500").unwrap();
+        let message_hash =
+            hex::decode("c74d460340f9fea30c254d133303361e67246c40a52e6b5ddbbd813e0d211762")
+                .unwrap();
+        let message_hash =
+            UInt256::alloc_from_witness(cs, Some(BigUint::from_bytes_be(&message_hash)))?;
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        let n = cs.n();
+        signature.ecrecover(cs, &message_hash)?;
+        assert_close(super::ECRECOVER_GATES, cs.n() - n);
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_geometry() {
+        // `GATES` was measured for 13 signatures and 4 prices.
+        let geometry = CircuitGeometry::new(1, 13, 4);
+        assert_close(super::estimate_gates(&geometry), crate::pyth::GATES);
+        assert_eq!(super::required_power_of_tau(&geometry), 24);
+        assert!(super::fits(&geometry, 24));
+        assert!(!super::fits(&CircuitGeometry::new(2, 13, 4), 24));
+    }
+}
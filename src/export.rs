@@ -0,0 +1,240 @@
+//! Export of a synthesized constraint system for third-party audit tooling.
+//!
+//! The exported document is JSON with the following layout (format `zklink-oracle-circuit/v1`):
+//!
+//! ```text
+//! {
+//!   "format": "zklink-oracle-circuit/v1",
+//!   "state_width": 4,
+//!   "num_inputs": 1,              // public inputs
+//!   "num_aux": 123,               // witness variables
+//!   "gates": [                    // one entry per row of the trace, public input rows first
+//!     {
+//!       "row": 0,
+//!       "gate": "main gate of width 4 with D_next and selector optimization",
+//!       "variables": ["i0", "a0", "a0", "a0"],
+//!       "coefficients": { "GateSetupPolynomial(\"main gate ...\", 0)": "0x0..01", ... }
+//!     }
+//!   ],
+//!   "copy_constraints": [         // positions sharing the same variable, as [column, row]
+//!     { "variable": "a12", "positions": [[0, 17], [2, 18]] }
+//!   ],
+//!   "lookup_tables": [            // rows where the table is applied over the first `width` columns
+//!     { "name": "Range check table", "width": 3, "size": 65536, "rows": [20, 21] }
+//!   ]
+//! }
+//! ```
+//!
+//! Variables are named `i<index>` for public inputs and `a<index>` for witnesses, field elements are
+//! big-endian hex. Copy constraints only list variables used at two positions or more.
+
+use std::{collections::BTreeMap, io::Write};
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::Engine,
+    plonk::better_better_cs::cs::{
+        Index, MainGate, PlonkConstraintSystemParams, PolyIdentifier, TrivialAssembly, Variable,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::fr_to_hex;
+
+pub const FORMAT: &str = "zklink-oracle-circuit/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitDescription {
+    pub format: String,
+    pub state_width: usize,
+    pub num_inputs: usize,
+    pub num_aux: usize,
+    pub gates: Vec<GateRow>,
+    pub copy_constraints: Vec<CopyConstraint>,
+    pub lookup_tables: Vec<LookupTableUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateRow {
+    pub row: usize,
+    pub gate: String,
+    pub variables: Vec<String>,
+    pub coefficients: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyConstraint {
+    pub variable: String,
+    pub positions: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupTableUsage {
+    pub name: String,
+    pub width: usize,
+    pub size: usize,
+    pub rows: Vec<usize>,
+}
+
+fn variable_name(variable: &Variable) -> String {
+    match variable.get_unchecked() {
+        Index::Input(i) => format!("i{}", i),
+        Index::Aux(i) => format!("a{}", i),
+    }
+}
+
+impl CircuitDescription {
+    /// Describe a synthesized constraint system.
+    pub fn from_assembly<E: Engine, P: PlonkConstraintSystemParams<E>, MG: MainGate<E>>(
+        cs: &TrivialAssembly<E, P, MG>,
+    ) -> Self {
+        let main_gate_name = cs.main_gate.name().to_string();
+        let mut gates = vec![];
+        let mut positions: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+
+        let storages = [
+            (&cs.inputs_storage, cs.num_input_gates, 0),
+            (&cs.aux_storage, cs.num_aux_gates, cs.num_input_gates),
+        ];
+        for (storage_index, (storage, num_gates, row_offset)) in storages.into_iter().enumerate() {
+            for i in 0..num_gates {
+                let row = row_offset + i;
+                let mut variables = vec![];
+                for column in 0..P::STATE_WIDTH {
+                    let id = PolyIdentifier::VariablesPolynomial(column);
+                    if let Some(variable) = storage.state_map.get(&id).and_then(|v| v.get(i)) {
+                        let name = variable_name(variable);
+                        positions
+                            .entry(name.clone())
+                            .or_default()
+                            .push((column, row));
+                        variables.push(name);
+                    }
+                }
+                let coefficients = storage
+                    .setup_map
+                    .iter()
+                    .filter_map(|(id, values)| {
+                        values
+                            .get(i)
+                            .map(|value| (format!("{:?}", id), fr_to_hex(value)))
+                    })
+                    .collect();
+                // Public input rows are always main gates, aux rows are tracked by gate density.
+                let gate = if storage_index == 0 {
+                    main_gate_name.clone()
+                } else {
+                    cs.aux_gate_density
+                        .0
+                        .iter()
+                        .find(|(_, density)| density.get(i).unwrap_or(false))
+                        .map(|(gate, _)| gate.name().to_string())
+                        .unwrap_or_else(|| main_gate_name.clone())
+                };
+                gates.push(GateRow {
+                    row,
+                    gate,
+                    variables,
+                    coefficients,
+                });
+            }
+        }
+
+        let copy_constraints = positions
+            .into_iter()
+            .filter(|(_, positions)| positions.len() > 1)
+            .map(|(variable, positions)| CopyConstraint {
+                variable,
+                positions,
+            })
+            .collect();
+
+        let lookup_tables = cs
+            .tables
+            .iter()
+            .map(|table| {
+                let name = table.functional_name();
+                let rows = cs
+                    .table_selectors
+                    .get(&name)
+                    .map(|selector| {
+                        selector
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, used)| *used)
+                            .map(|(i, _)| cs.num_input_gates + i)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                LookupTableUsage {
+                    name,
+                    width: table.width(),
+                    size: table.size(),
+                    rows,
+                }
+            })
+            .collect();
+
+        Self {
+            format: FORMAT.to_string(),
+            state_width: P::STATE_WIDTH,
+            num_inputs: cs.num_inputs,
+            num_aux: cs.num_aux,
+            gates,
+            copy_constraints,
+            lookup_tables,
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+/// Write the description of a synthesized constraint system as JSON.
+pub fn export_assembly<E, P, MG, W>(
+    cs: &TrivialAssembly<E, P, MG>,
+    writer: W,
+) -> Result<(), serde_json::Error>
+where
+    E: Engine,
+    P: PlonkConstraintSystemParams<E>,
+    MG: MainGate<E>,
+    W: Write,
+{
+    CircuitDescription::from_assembly(cs).write_json(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use super::CircuitDescription;
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_export_keccak256() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let input_bytes = b"hello world"
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        crate::gadgets::keccak256::digest(cs, &input_bytes)?;
+
+        let description = CircuitDescription::from_assembly(cs);
+        assert_eq!(description.gates.len(), cs.n());
+        assert!(!description.copy_constraints.is_empty());
+        assert!(description
+            .lookup_tables
+            .iter()
+            .any(|table| !table.rows.is_empty()));
+
+        let mut json = vec![];
+        description.write_json(&mut json).unwrap();
+        let decoded: CircuitDescription = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded.format, super::FORMAT);
+        assert_eq!(decoded.gates.len(), description.gates.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,463 @@
+//! C ABI for embedding the pyth oracle circuit in non-Rust provers, built with the `ffi` feature.
+//! The declarations are in `include/zklink_oracle.h`.
+//!
+//! Functions returning `int32_t` return `0` on success and `-1` on failure, in which case
+//! [`zklink_oracle_last_error`] describes the failure. Objects allocated by this library must be
+//! released with the matching `*_free` function.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    kate_commitment::{Crs, CrsForMonomialForm},
+    pairing::bn256::{Bn256, Fr},
+    plonk::{
+        better_better_cs::{
+            cs::{
+                Circuit, PlonkCsWidth4WithNextStepAndCustomGatesParams, ProvingAssembly,
+                TrivialAssembly,
+            },
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        commitments::transcript::keccak_transcript::RollingKeccakTranscript,
+    },
+    worker::Worker,
+};
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+
+use crate::pyth::{
+    setup::{generate_keys, OracleSetup},
+    OracleWitness, PriceOracle, PriceOracleSetup,
+};
+
+type Params = PlonkCsWidth4WithNextStepAndCustomGatesParams;
+type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+/// Largest number of prices per accumulator update supported through the C ABI.
+pub const MAX_NUM_PRICES: usize = 8;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Witness of a pyth oracle circuit, opaque to C.
+pub struct ZkLinkOracleWitness {
+    witness: OracleWitness,
+    num_prices: usize,
+}
+
+/// Proving setup of a circuit shape along with its CRS, opaque to C.
+pub struct ZkLinkOracleSetup {
+    num_vaas: usize,
+    num_signatures: usize,
+    guardian_set_size: usize,
+    num_prices: usize,
+    crs: Crs<Bn256, CrsForMonomialForm>,
+    /// [`OracleSetup`] of `num_prices` prices.
+    setup: Box<dyn Any>,
+}
+
+/// Bytes allocated by this library, released by [`zklink_oracle_buffer_free`].
+#[repr(C)]
+pub struct ZkLinkOracleBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl ZkLinkOracleBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let bytes = Box::leak(bytes.into_boxed_slice());
+        Self {
+            data: bytes.as_mut_ptr(),
+            len: bytes.len(),
+        }
+    }
+}
+
+fn set_last_error(msg: String) {
+    let msg = CString::new(msg.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// Run `f`, turning errors and panics into `-1` and the last error.
+fn ffi_call(f: impl FnOnce() -> Result<(), anyhow::Error>) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            set_last_error(format!("{:#}", e));
+            -1
+        }
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string());
+            set_last_error(msg);
+            -1
+        }
+    }
+}
+
+/// Call `$f::<N>($args)` with `N` the runtime number of prices.
+macro_rules! dispatch_num_prices {
+    ($num_prices:expr, $f:ident($($arg:expr),*)) => {
+        match $num_prices {
+            1 => $f::<1>($($arg),*),
+            2 => $f::<2>($($arg),*),
+            3 => $f::<3>($($arg),*),
+            4 => $f::<4>($($arg),*),
+            5 => $f::<5>($($arg),*),
+            6 => $f::<6>($($arg),*),
+            7 => $f::<7>($($arg),*),
+            8 => $f::<8>($($arg),*),
+            n => Err(anyhow::anyhow!(
+                "unsupported number of prices {}, expect 1 to {}",
+                n,
+                MAX_NUM_PRICES
+            )),
+        }
+    };
+}
+
+fn synthesize<const N: usize>(witness: &OracleWitness) -> Result<u64, anyhow::Error> {
+    let circuit = witness.to_price_oracle::<Bn256, N>()?;
+    let mut cs = TrivialAssembly::<Bn256, Params, MainGate>::new();
+    circuit.synthesize(&mut cs)?;
+    anyhow::ensure!(cs.is_satisfied(), "witness does not satisfy the circuit");
+    Ok(cs.n() as u64)
+}
+
+fn setup<const N: usize>(
+    num_vaas: usize,
+    num_signatures: usize,
+    guardian_set_size: usize,
+    crs: &Crs<Bn256, CrsForMonomialForm>,
+) -> Result<(Box<dyn Any>, Vec<u8>), anyhow::Error> {
+    let shape = PriceOracleSetup::<Bn256, N>::new(num_vaas, num_signatures, guardian_set_size);
+    let (setup, vk) = generate_keys(&shape, crs)?;
+    let mut vk_bytes = vec![];
+    vk.write(&mut vk_bytes)?;
+    Ok((Box::new(setup), vk_bytes))
+}
+
+fn prove<const N: usize>(
+    witness: &OracleWitness,
+    setup: &ZkLinkOracleSetup,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let circuit = witness.to_price_oracle::<Bn256, N>()?;
+    let shape = (
+        circuit.accumulator_update_data.len(),
+        circuit.num_signature_to_verify,
+        circuit.guardian_set.len(),
+    );
+    let expected = (
+        setup.num_vaas,
+        setup.num_signatures,
+        setup.guardian_set_size,
+    );
+    anyhow::ensure!(
+        shape == expected,
+        "witness of (VAAs, signatures, guardians) {:?} does not fit the setup of {:?}",
+        shape,
+        expected
+    );
+    let oracle_setup = setup
+        .setup
+        .downcast_ref::<OracleSetup<Bn256, N>>()
+        .ok_or_else(|| anyhow::anyhow!("setup of {} prices, expect {}", setup.num_prices, N))?;
+    let worker = Worker::new();
+
+    let mut assembly = ProvingAssembly::<Bn256, Params, MainGate>::new();
+    circuit.synthesize(&mut assembly)?;
+    assembly.finalize();
+    let proof = assembly.create_proof::<PriceOracle<Bn256, N>, RollingKeccakTranscript<Fr>>(
+        &worker,
+        oracle_setup,
+        &setup.crs,
+        None,
+    )?;
+
+    let mut proof_bytes = vec![];
+    proof.write(&mut proof_bytes)?;
+    Ok(proof_bytes)
+}
+
+/// Message of the last failure on the calling thread, valid until the next call. `NULL` if none.
+#[no_mangle]
+pub extern "C" fn zklink_oracle_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Create a witness from `num_updates` `PNAU` accumulator updates, verifying `num_signatures`
+/// signatures of each against the `num_guardians` 20-byte addresses of `guardian_set`. Returns
+/// `NULL` on failure.
+///
+/// # Safety
+///
+/// `updates` and `lens` must point to `num_updates` buffers and their lengths, `guardian_set` to
+/// `20 * num_guardians` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_witness_new(
+    updates: *const *const u8,
+    lens: *const usize,
+    num_updates: usize,
+    guardian_set: *const u8,
+    num_guardians: usize,
+    num_signatures: usize,
+) -> *mut ZkLinkOracleWitness {
+    let mut witness = ptr::null_mut();
+    ffi_call(|| {
+        anyhow::ensure!(
+            !updates.is_null() && !lens.is_null() && num_updates > 0,
+            "no accumulator update"
+        );
+        let updates = slice::from_raw_parts(updates, num_updates);
+        let lens = slice::from_raw_parts(lens, num_updates);
+        let data = updates
+            .iter()
+            .zip(lens)
+            .map(|(update, len)| {
+                anyhow::ensure!(!update.is_null(), "null accumulator update");
+                Ok(AccumulatorUpdateData::try_from_slice(
+                    slice::from_raw_parts(*update, *len),
+                )?)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let pythnet_sdk::wire::v1::Proof::WormholeMerkle { updates, .. } = &data[0].proof;
+        let num_prices = updates.len();
+        anyhow::ensure!(
+            !guardian_set.is_null() && num_guardians > 0,
+            "no guardian set"
+        );
+        let guardian_set = slice::from_raw_parts(guardian_set, 20 * num_guardians)
+            .chunks_exact(20)
+            .map(|address| address.try_into().unwrap())
+            .collect();
+        let oracle_witness = OracleWitness::new(&data, guardian_set, num_signatures);
+        // Fail early on data rejected by the native checks.
+        dispatch_num_prices!(num_prices, check(&oracle_witness))?;
+        witness = Box::into_raw(Box::new(ZkLinkOracleWitness {
+            witness: oracle_witness,
+            num_prices,
+        }));
+        Ok(())
+    });
+    witness
+}
+
+fn check<const N: usize>(witness: &OracleWitness) -> Result<(), anyhow::Error> {
+    witness.to_price_oracle::<Bn256, N>()?;
+    Ok(())
+}
+
+/// Synthesize the circuit of `witness` and check it is satisfied, writing the number of gates to
+/// `num_gates` (if not `NULL`).
+///
+/// # Safety
+///
+/// `witness` must come from [`zklink_oracle_witness_new`].
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_synthesize(
+    witness: *const ZkLinkOracleWitness,
+    num_gates: *mut u64,
+) -> i32 {
+    ffi_call(|| {
+        let witness = witness
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("null witness"))?;
+        let n = dispatch_num_prices!(witness.num_prices, synthesize(&witness.witness))?;
+        if let Some(num_gates) = num_gates.as_mut() {
+            *num_gates = n;
+        }
+        Ok(())
+    })
+}
+
+/// Generate the proving setup of the circuits of `num_vaas` accumulator updates of `num_prices`
+/// prices, verifying `num_signatures` signatures against `guardian_set_size` guardians, with a
+/// monomial form CRS (as written by `Crs::write`). The verification key is written to
+/// `verification_key` if not `NULL`. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `crs` must point to `crs_len` bytes, `verification_key` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_setup_new(
+    num_vaas: usize,
+    num_signatures: usize,
+    num_prices: usize,
+    guardian_set_size: usize,
+    crs: *const u8,
+    crs_len: usize,
+    verification_key: *mut ZkLinkOracleBuffer,
+) -> *mut ZkLinkOracleSetup {
+    let mut result = ptr::null_mut();
+    ffi_call(|| {
+        anyhow::ensure!(!crs.is_null(), "null CRS");
+        let crs = Crs::<Bn256, CrsForMonomialForm>::read(slice::from_raw_parts(crs, crs_len))?;
+        let (oracle_setup, vk_bytes) = dispatch_num_prices!(
+            num_prices,
+            setup(num_vaas, num_signatures, guardian_set_size, &crs)
+        )?;
+        if let Some(verification_key) = verification_key.as_mut() {
+            *verification_key = ZkLinkOracleBuffer::new(vk_bytes);
+        }
+        result = Box::into_raw(Box::new(ZkLinkOracleSetup {
+            num_vaas,
+            num_signatures,
+            guardian_set_size,
+            num_prices,
+            crs,
+            setup: oracle_setup,
+        }));
+        Ok(())
+    });
+    result
+}
+
+/// Prove `witness` with `setup`, which must be of the same shape, writing the proof to `proof`.
+///
+/// # Safety
+///
+/// `witness` must come from [`zklink_oracle_witness_new`], `setup` from
+/// [`zklink_oracle_setup_new`], `proof` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_prove(
+    witness: *const ZkLinkOracleWitness,
+    setup: *const ZkLinkOracleSetup,
+    proof: *mut ZkLinkOracleBuffer,
+) -> i32 {
+    ffi_call(|| {
+        let witness = witness
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("null witness"))?;
+        let setup = setup
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("null setup"))?;
+        anyhow::ensure!(!proof.is_null(), "null output buffer");
+        anyhow::ensure!(
+            witness.num_prices == setup.num_prices,
+            "witness of {} prices does not fit the setup of {} prices",
+            witness.num_prices,
+            setup.num_prices
+        );
+        let proof_bytes = dispatch_num_prices!(witness.num_prices, prove(&witness.witness, setup))?;
+        *proof = ZkLinkOracleBuffer::new(proof_bytes);
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `witness` must come from [`zklink_oracle_witness_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_witness_free(witness: *mut ZkLinkOracleWitness) {
+    if !witness.is_null() {
+        drop(Box::from_raw(witness));
+    }
+}
+
+/// # Safety
+///
+/// `setup` must come from [`zklink_oracle_setup_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_setup_free(setup: *mut ZkLinkOracleSetup) {
+    if !setup.is_null() {
+        drop(Box::from_raw(setup));
+    }
+}
+
+/// # Safety
+///
+/// `buffer` must have been written by this library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn zklink_oracle_buffer_free(buffer: ZkLinkOracleBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(slice::from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use base64::Engine as _;
+
+    use super::*;
+    use crate::pyth::{GuardianSetConfig, DEFAULT_ACCUMULATOR_UPDATE_DATA};
+
+    #[test]
+    fn test_witness_new() {
+        let update = base64::engine::general_purpose::STANDARD
+            .decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)
+            .unwrap();
+        let updates = [update.as_ptr()];
+        let lens = [update.len()];
+        let guardian_set = GuardianSetConfig::mainnet().addresses.concat();
+        let num_guardians = guardian_set.len() / 20;
+        unsafe {
+            let witness = zklink_oracle_witness_new(
+                updates.as_ptr(),
+                lens.as_ptr(),
+                1,
+                guardian_set.as_ptr(),
+                num_guardians,
+                1,
+            );
+            assert!(!witness.is_null());
+            assert_eq!((*witness).num_prices, 3);
+            assert_eq!((*witness).witness.guardian_set.len(), num_guardians);
+            zklink_oracle_witness_free(witness);
+
+            // Shifted guardians, the signers are not at their indices.
+            let witness = zklink_oracle_witness_new(
+                updates.as_ptr(),
+                lens.as_ptr(),
+                1,
+                guardian_set[20..].as_ptr(),
+                num_guardians - 1,
+                1,
+            );
+            assert!(witness.is_null());
+            let witness =
+                zklink_oracle_witness_new(updates.as_ptr(), lens.as_ptr(), 1, ptr::null(), 0, 1);
+            assert!(witness.is_null());
+
+            let lens = [update.len() - 1];
+            let witness = zklink_oracle_witness_new(
+                updates.as_ptr(),
+                lens.as_ptr(),
+                1,
+                guardian_set.as_ptr(),
+                num_guardians,
+                1,
+            );
+            assert!(witness.is_null());
+            assert!(!CStr::from_ptr(zklink_oracle_last_error())
+                .to_bytes()
+                .is_empty());
+
+            assert_eq!(zklink_oracle_synthesize(ptr::null(), ptr::null_mut()), -1);
+            let mut proof = ZkLinkOracleBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                zklink_oracle_prove(ptr::null(), ptr::null(), &mut proof),
+                -1
+            );
+            assert!(
+                zklink_oracle_setup_new(1, 1, 3, 19, ptr::null(), 0, ptr::null_mut()).is_null()
+            );
+        }
+    }
+}
@@ -17,7 +17,7 @@ use advanced_circuit_component::{
                 FieldElement, ReductionStatus, RnsParameters, BITWISE_LOGICAL_OPS_TABLE_NAME,
             },
             boolean::{AllocatedBit, Boolean},
-            curve_new::AffinePoint,
+            curve_new::{AffinePoint, ProjectivePoint},
             linear_combination::LinearCombination,
             Assignment,
         },
@@ -31,7 +31,8 @@ use advanced_circuit_component::{
 use num::traits::FromBytes;
 use num_bigint::BigUint;
 
-use crate::utils::new_synthesis_error;
+use super::keccak256::Keccak256Params;
+use crate::error::OracleError;
 
 // UInt256.inner is private so I have to use this hack
 fn uint256_inner<E: Engine, CS: ConstraintSystem<E>>(
@@ -67,7 +68,7 @@ fn convert_uint256_to_field_element<'a, E: Engine, F: PrimeField, CS: Constraint
 
 const CHUNK_BITLEN: usize = 64;
 const SECP_B_COEF: u64 = 7;
-const EXCEPTION_FLAGS_ARR_LEN: usize = 4;
+const EXCEPTION_FLAGS_ARR_LEN: usize = 12;
 const X_POWERS_ARR_LEN: usize = 256;
 
 /// Circuit implementation of ECDSA signature.
@@ -88,6 +89,71 @@ impl<E: Engine> Signature<E> {
         ecrecover(cs, &self.recid, &self.r, &self.s, message_hash)
     }
 
+    /// Like [`Signature::ecrecover`], with curve parameters shared by several signatures.
+    ///
+    /// If `params` requires low-s, a signature with `s > n / 2` fails and recovers the zero key.
+    pub fn ecrecover_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        message_hash: &UInt256<E>,
+    ) -> Result<EcRecoverRes<E>, SynthesisError> {
+        let (success, (x, y)) =
+            ecrecover_with_params(cs, params, &self.recid, &self.r, &self.s, message_hash)?;
+        if !params.require_low_s {
+            return Ok((success, (x, y)));
+        }
+        let is_low_s = self.is_low_s(cs)?;
+        let success = Boolean::and(cs, &success, &is_low_s)?;
+        Ok((success, (x.mask(cs, &success)?, y.mask(cs, &success)?)))
+    }
+
+    /// Recover the ethereum address of the signer: the low 20 bytes of the keccak256 of the
+    /// public key, big-endian, zero if the recovery fails.
+    pub fn ecrecover_address<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        message_hash: &UInt256<E>,
+    ) -> Result<(Boolean, [Byte<E>; 20]), SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        self.ecrecover_address_with_params(cs, &params, &keccak, message_hash)
+    }
+
+    /// Like [`Signature::ecrecover_address`], with curve parameters and the keccak gadget shared by
+    /// several signatures.
+    pub fn ecrecover_address_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        message_hash: &UInt256<E>,
+    ) -> Result<(Boolean, [Byte<E>; 20]), SynthesisError> {
+        let (success, (x, y)) = self.ecrecover_with_params(cs, params, message_hash)?;
+        let mut pubkey = x.into_be_bytes(cs)?;
+        pubkey.extend(y.into_be_bytes(cs)?);
+        let hash = super::keccak256::digest_with_params(cs, keccak, &pubkey)?;
+        let mut address = [Byte::zero(); 20];
+        for (byte, hashed) in address.iter_mut().zip(hash[12..].iter()) {
+            // a byte times a boolean is still a byte
+            let masked = hashed.inner.mask(cs, &success)?;
+            *byte = Byte::from_num_unconstrained(cs, masked);
+        }
+        Ok((success, address))
+    }
+
+    /// Whether `s <= n / 2`, the canonical one of the two valid signatures `(r, s)` and
+    /// `(r, n - s)` of a message.
+    pub fn is_low_s<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let half_n = UInt256::constant(Secp256k1Params::<E>::half_n());
+        // half_n - s borrows iff s > half_n
+        let (_, is_high_s) = half_n.sub(cs, &self.s)?;
+        Ok(is_high_s.not())
+    }
+
     /// Verify the signature against the message hash and the public key.
     pub fn verify<CS: ConstraintSystem<E>>(
         &self,
@@ -106,19 +172,20 @@ impl<E: Engine> Signature<E> {
     pub fn from_bytes_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: &[u8],
-    ) -> Result<Self, SynthesisError> {
+    ) -> Result<Self, OracleError> {
         if witness.len() != 65 {
-            return Err(new_synthesis_error(format!(
-                "expected 65 bytes, got {}",
-                witness.len()
-            )));
+            return Err(OracleError::InvalidLength {
+                what: "signature",
+                got: witness.len(),
+                expected: 65,
+            });
         };
         let witness = (
             BigUint::from_be_bytes(witness[..32].try_into().unwrap()),
             BigUint::from_be_bytes(witness[32..64].try_into().unwrap()),
             witness[64] as u32,
         );
-        Self::alloc_from_witness(cs, Some(witness))
+        Ok(Self::alloc_from_witness(cs, Some(witness))?)
     }
 }
 
@@ -162,6 +229,52 @@ impl<E: Engine> CSAllocatable<E> for Signature<E> {
 
 pub type EcRecoverRes<E> = (Boolean, (UInt256<E>, UInt256<E>));
 
+type G = advanced_circuit_component::secp256k1::PointAffine;
+type Base = <G as GenericCurveAffine>::Base;
+type Scalar = <G as GenericCurveAffine>::Scalar;
+
+/// secp256k1 constants of a constraint system, built once and shared by all its signature gadgets.
+#[derive(Debug, Clone)]
+pub struct Secp256k1Params<E: Engine> {
+    /// Modulus of the base field.
+    pub p: UInt256<E>,
+    /// Order of the group.
+    pub n: UInt256<E>,
+    pub base_field: RnsParameters<E, Base>,
+    pub scalar_field: RnsParameters<E, Scalar>,
+    /// Whether recovery fails for signatures with `s > n / 2`, see [`Signature::is_low_s`].
+    pub require_low_s: bool,
+    /// `j * 16^i * G` at `[i][j]`, the multiples of the generator added by each window of a
+    /// scalar, see [`add_generator_mul`].
+    pub generator_table: Vec<[G; 1 << GENERATOR_WINDOW_BITLEN]>,
+}
+
+impl<E: Engine> Secp256k1Params<E> {
+    pub fn new<CS: ConstraintSystem<E>>(cs: &mut CS) -> Self {
+        use franklin_crypto::plonk::circuit::bigint_new::bigint::repr_to_biguint;
+        Self {
+            p: UInt256::constant(repr_to_biguint::<Secp256Fq>(&Secp256Fq::char())),
+            n: UInt256::constant(repr_to_biguint::<Secp256Fr>(&Secp256Fr::char())),
+            base_field: RnsParameters::new_optimal(cs, CHUNK_BITLEN),
+            scalar_field: RnsParameters::new_optimal(cs, CHUNK_BITLEN),
+            require_low_s: false,
+            generator_table: generator_table(),
+        }
+    }
+
+    /// Reject the malleable high-s form of signatures, as ethereum does since EIP-2.
+    pub fn with_low_s(mut self) -> Self {
+        self.require_low_s = true;
+        self
+    }
+
+    /// Half the group order, rounded down.
+    pub fn half_n() -> BigUint {
+        use franklin_crypto::plonk::circuit::bigint_new::bigint::repr_to_biguint;
+        repr_to_biguint::<Secp256Fr>(&Secp256Fr::char()) >> 1
+    }
+}
+
 /// Recover the public key from the signature and the message hash.
 pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
@@ -169,22 +282,34 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     r_as_u64x4: &UInt256<E>,
     s_as_u64x4: &UInt256<E>,
     message_hash_as_u64x4: &UInt256<E>,
+) -> Result<EcRecoverRes<E>, SynthesisError> {
+    let params = Secp256k1Params::new(cs);
+    ecrecover_with_params(
+        cs,
+        &params,
+        recid,
+        r_as_u64x4,
+        s_as_u64x4,
+        message_hash_as_u64x4,
+    )
+}
+
+/// Like [`ecrecover`], with curve parameters shared by several signatures.
+pub fn ecrecover_with_params<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Secp256k1Params<E>,
+    recid: &UInt32<E>,
+    r_as_u64x4: &UInt256<E>,
+    s_as_u64x4: &UInt256<E>,
+    message_hash_as_u64x4: &UInt256<E>,
 ) -> Result<EcRecoverRes<E>, SynthesisError> {
     // Init parameters
-    type G = advanced_circuit_component::secp256k1::PointAffine;
-    type Base = <G as GenericCurveAffine>::Base;
-    type Scalar = <G as GenericCurveAffine>::Scalar;
-    use franklin_crypto::plonk::circuit::bigint_new::bigint::repr_to_biguint;
-    let secp_p_as_u64x4 = UInt256::<E>::constant(repr_to_biguint::<Secp256Fq>(&Secp256Fq::char()));
-    let secp_n_as_u64x4 = UInt256::<E>::constant(repr_to_biguint::<Secp256Fr>(&Secp256Fr::char()));
-    let rns_strategy_for_base_field = RnsParameters::<E, Base>::new_optimal(cs, CHUNK_BITLEN);
-    let rns_strategy_for_scalar_field = RnsParameters::<E, Scalar>::new_optimal(cs, CHUNK_BITLEN);
-    let mut minus_one_in_external_field = {
-        let one_in_external_field = FieldElement::<E, Base>::one(&rns_strategy_for_base_field);
-        one_in_external_field.negate(cs)?
-    };
+    let secp_p_as_u64x4 = &params.p;
+    let secp_n_as_u64x4 = &params.n;
+    let rns_strategy_for_base_field = &params.base_field;
+    let rns_strategy_for_scalar_field = &params.scalar_field;
     let (b_coef_in_external_field, valid_x_in_external_field, valid_t_in_external_field) = {
-        let f = |v: u64| FieldElement::constant(u64_to_fe::<Base>(v), &rns_strategy_for_base_field);
+        let f = |v: u64| FieldElement::constant(u64_to_fe::<Base>(v), rns_strategy_for_base_field);
         (f(SECP_B_COEF), f(9), f(9 + SECP_B_COEF))
     };
 
@@ -225,7 +350,7 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     lc.enforce_zero(cs)?;
 
     // x = r + n if x_overflow else r
-    let (r_plus_n_as_u64x4, of) = r_as_u64x4.add(cs, &secp_n_as_u64x4)?;
+    let (r_plus_n_as_u64x4, of) = r_as_u64x4.add(cs, secp_n_as_u64x4)?;
     let mut x_as_u64x4 =
         UInt256::conditionally_select(cs, &x_overflow, &r_plus_n_as_u64x4, r_as_u64x4)?;
     let error = Boolean::and(cs, &x_overflow, &of)?;
@@ -234,7 +359,7 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     // we handle x separately as it is the only element of base field of a curve (no a scalar field element!)
     // check that x < q - order of base point on Secp256 curve
     // if it is not actually the case - mask x to be zero
-    let (_res, is_in_range) = x_as_u64x4.sub(cs, &secp_p_as_u64x4)?;
+    let (_res, is_in_range) = x_as_u64x4.sub(cs, secp_p_as_u64x4)?;
     x_as_u64x4 = x_as_u64x4.mask(cs, &is_in_range)?;
     exception_flags.push(is_in_range.not());
     // let raw_x_limbs = x_as_u64x4
@@ -247,28 +372,35 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
         FieldElement::<E, Base>::alloc_from_limbs_unchecked(
             cs,
             &raw_x_limbs,
-            &rns_strategy_for_base_field,
+            rns_strategy_for_base_field,
             true,
         )?
     };
 
+    // r and s must be below the group order: the limbs below are reduced modulo n, so r + n or
+    // s + n would otherwise pass as a second encoding of the same signature
+    for scalar in [r_as_u64x4, s_as_u64x4] {
+        let (_res, is_in_range) = scalar.sub(cs, secp_n_as_u64x4)?;
+        exception_flags.push(is_in_range.not());
+    }
+
     let mut r_fe = convert_uint256_to_field_element::<E, Scalar, CS>(
         cs,
         r_as_u64x4,
-        &rns_strategy_for_scalar_field,
+        rns_strategy_for_scalar_field,
         &mut exception_flags,
     )?;
     let mut s_fe = convert_uint256_to_field_element::<E, Scalar, CS>(
         cs,
         s_as_u64x4,
-        &rns_strategy_for_scalar_field,
+        rns_strategy_for_scalar_field,
         &mut exception_flags,
     )?;
     // NB: although it is not strictly an exception we also assume that hash is never zero as field element
-    let mut message_hash_fe = convert_uint256_to_field_element::<E, Scalar, CS>(
+    let message_hash_fe = convert_uint256_to_field_element::<E, Scalar, CS>(
         cs,
         message_hash_as_u64x4,
-        &rns_strategy_for_scalar_field,
+        rns_strategy_for_scalar_field,
         &mut exception_flags,
     )?;
 
@@ -310,13 +442,11 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     for idx in [3, 5, 6, 7, 8, 31].into_iter() {
         acc = acc.mul(cs, &t_powers[idx])?;
     }
-    let mut legendre_symbol = t_powers[255].div(cs, &acc)?;
-
-    let t_is_nonresidue = FieldElement::<E, Base>::equals(
-        cs,
-        &mut legendre_symbol,
-        &mut minus_one_in_external_field,
-    )?;
+    // t is a nonresidue iff t^{2^255} / acc = -1, checked as t^{2^255} = -acc: a non-native
+    // division is a witnessed quotient checked by one multiplication, which the equality avoids
+    let mut minus_acc = acc.negate(cs)?;
+    let mut t_pow_2_255 = t_powers[255].clone();
+    let t_is_nonresidue = FieldElement::<E, Base>::equals(cs, &mut t_pow_2_255, &mut minus_acc)?;
     exception_flags.push(t_is_nonresidue);
     // unfortunately, if t is found to be a quadratic nonresidue, we can't simply let x to be zero,
     // because then t_new = 7 is again a quadratic nonresidue. So, in this case we let x to be 9, then
@@ -348,7 +478,7 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
         (_, _) => None,
     };
     let (y, y_decomposition) =
-        FieldElement::<E, Base>::alloc_ext(cs, y_wit, &rns_strategy_for_base_field)?;
+        FieldElement::<E, Base>::alloc_ext(cs, y_wit, rns_strategy_for_base_field)?;
     {
         // enforce that y^2 == t
         let mut y_squared = y.square(cs)?;
@@ -406,14 +536,27 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     // current implementation of point by scalar multiplications doesn't support multiplication by zero
     // so we check that all s, r, hash are not zero (as FieldElements):
     // if any of them is zero we reject the signature and in circuit itself replace all zero variables by ones
-    let mut x_point = unsafe { AffinePoint::<E, G>::from_xy_unchecked(x, y) };
-    let s_x = x_point.mul_by_scalar_for_prime_order_curve(cs, &mut s_fe)?;
+    let x_point = unsafe { AffinePoint::<E, G>::from_xy_unchecked(x, y) };
+    let generator = AffinePoint::<E, G>::constant(G::one(), rns_strategy_for_base_field);
 
-    let mut generator = AffinePoint::<E, G>::constant(G::one(), &rns_strategy_for_base_field);
-    let hash_g = generator.mul_by_scalar_for_prime_order_curve(cs, &mut message_hash_fe)?;
-
-    // rhs = s * X - hash * G
-    let mut rhs_proj = s_x.sub(cs, &hash_g)?;
+    // rhs = s * X + (-hash) * G, the fixed-base part added from the precomputed table
+    let mut minus_hash_fe = message_hash_fe.negate(cs)?;
+    let s_times_x = glv_scalar_mul(
+        cs,
+        &x_point,
+        &mut s_fe,
+        &generator,
+        rns_strategy_for_base_field,
+        rns_strategy_for_scalar_field,
+        &mut exception_flags,
+    )?;
+    let mut rhs_proj = add_generator_mul(
+        cs,
+        s_times_x,
+        &mut minus_hash_fe,
+        &params.generator_table,
+        rns_strategy_for_base_field,
+    )?;
     let (mut rhs_affine, is_point_at_infty) =
         rhs_proj.convert_to_affine_or_default(cs, &generator)?;
     exception_flags.push(is_point_at_infty);
@@ -430,11 +573,31 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
         _ => None,
     };
     let (mut q, q_x_chunks, q_y_chunks) =
-        AffinePoint::alloc_ext(cs, q_wit, &rns_strategy_for_base_field)?;
+        AffinePoint::alloc_ext(cs, q_wit, rns_strategy_for_base_field)?;
     q.enforce_if_normalized(cs)?;
+    {
+        // Q must satisfy y^2 = x^3 + b, which also rules out the point at infinity: it has no
+        // affine coordinates and its (0, 0) placeholder is not on the curve
+        let mut y_squared = q.y.square(cs)?;
+        let mut x_cubed_plus_b = q.x.square(cs)?.mul(cs, &q.x)?.add_with_reduction(
+            cs,
+            &b_coef_in_external_field,
+            ReductionStatus::Loose,
+        )?;
+        let is_on_curve = FieldElement::<E, Base>::equals(cs, &mut y_squared, &mut x_cubed_plus_b)?;
+        exception_flags.push(is_on_curve.not());
+    }
 
-    // lhs = r * Q
-    let lhs_proj = q.mul_by_scalar_for_prime_order_curve(cs, &mut r_fe)?;
+    // lhs = r * Q, over half of the bits of r thanks to the endomorphism of secp256k1
+    let lhs_proj = glv_scalar_mul(
+        cs,
+        &q,
+        &mut r_fe,
+        &generator,
+        rns_strategy_for_base_field,
+        rns_strategy_for_scalar_field,
+        &mut exception_flags,
+    )?;
     // NB: we assume that the difference is NEVER point at infinity
     // it is justified by the fact their difference must be a public key Q which is never point at infinity
     let mut lhs_affine = unsafe { lhs_proj.convert_to_affine(cs)? };
@@ -462,27 +625,232 @@ pub fn ecrecover<E: Engine, CS: ConstraintSystem<E>>(
     let x_uint256 = uint256_from_bytes_with_mask(cs, &q_x_chunks_be[..], &any_exception.not())?;
     let y_uint256 = uint256_from_bytes_with_mask(cs, &q_y_chunks_be[..], &any_exception.not())?;
 
-    // let pubkey_bytes = q_x_chunks_be
-    //     .into_iter()
-    //     .chain(q_y_chunks_be.into_iter())
-    //     .map(|b| b.get_byte_value().unwrap())
-    //     .collect::<Vec<_>>();
-    // println!("pubkey: {}", hex::encode(&pubkey_bytes[..]));
-
     Ok((any_exception.not(), (x_uint256, y_uint256)))
 }
 
+/// Compute `a * p + b * q` with Strauss-Shamir interleaving: a single double-and-add over the
+/// little-endian bits of both scalars, of equal length, adding `p`, `q` or the precomputed `p + q`
+/// at each step, so the doublings are shared instead of paid once per multiplication.
+///
+/// `p == -q` makes `p + q` the point at infinity and is pushed to `exceptions`, `default` standing
+/// in for it. The scalars must not be zero.
+fn interleaved_mul<'a, E, G, CS>(
+    cs: &mut CS,
+    p: &AffinePoint<'a, E, G>,
+    a_bits: &[Boolean],
+    q: &mut AffinePoint<'a, E, G>,
+    b_bits: &[Boolean],
+    default: &AffinePoint<'a, E, G>,
+    params: &'a RnsParameters<E, G::Base>,
+    exceptions: &mut Vec<Boolean>,
+) -> Result<ProjectivePoint<'a, E, G>, SynthesisError>
+where
+    E: Engine,
+    G: GenericCurveAffine,
+    G::Base: PrimeField,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(a_bits.len(), b_bits.len());
+    let mut p_plus_q = ProjectivePoint::from(p.clone()).add_mixed(cs, q)?;
+    let (p_plus_q, p_equals_minus_q) = p_plus_q.convert_to_affine_or_default(cs, default)?;
+    exceptions.push(p_equals_minus_q);
+
+    let mut acc = ProjectivePoint::<E, G>::zero(params);
+    for (a_bit, b_bit) in a_bits.iter().rev().zip(b_bits.iter().rev()) {
+        acc = acc.double(cs)?;
+        let p_or_p_plus_q = AffinePoint::conditionally_select(cs, b_bit, &p_plus_q, p)?;
+        let mut addend = AffinePoint::conditionally_select(cs, a_bit, &p_or_p_plus_q, q)?;
+        let sum = acc.add_mixed(cs, &mut addend)?;
+        let any_bit = Boolean::or(cs, a_bit, b_bit)?;
+        acc = ProjectivePoint::conditionally_select(cs, &any_bit, &sum, &acc)?;
+    }
+    Ok(acc)
+}
+
+/// Eigenvalue of the endomorphism `(x, y) -> (beta * x, y)` of secp256k1, in the scalar field.
+const GLV_LAMBDA: &str =
+    "37718080363155996902926221483475020450927657555482586988616620542887997980018";
+/// Cube root of unity of the base field defining the endomorphism.
+const GLV_BETA: &str =
+    "55594575648329892869085402983802832744385952214688224221778511981742606582254";
+/// Bound on the bit length of the halves of a decomposed scalar.
+const GLV_SCALAR_BITLEN: usize = 128;
+
+/// Split `k` into `k1 + lambda * k2 mod n`, each half as its sign and magnitude below
+/// `2^GLV_SCALAR_BITLEN`, with the lattice basis of libsecp256k1.
+fn glv_decompose(k: &BigUint) -> [(bool, BigUint); 2] {
+    use num_bigint::BigInt;
+    let hex = |s: &str| BigUint::parse_bytes(s.as_bytes(), 16).unwrap();
+    let n = hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+    // basis (a1, b1), (a2, b2) with b1 = -minus_b1 and b2 = a1
+    let a1 = hex("3086d221a7d46bcde86c90e49284eb15");
+    let minus_b1 = hex("e4437ed6010e88286f547fa90abfe4c3");
+    let a2 = hex("114ca50f7a8e2f3f657c1108d9d44cfd8");
+    let round_div = |a: BigUint| (a * 2u32 + &n) / (&n * 2u32);
+    let c1 = BigInt::from(round_div(&a1 * k));
+    let c2 = BigInt::from(round_div(&minus_b1 * k));
+    let (a1, minus_b1, a2) = (BigInt::from(a1), BigInt::from(minus_b1), BigInt::from(a2));
+    let k1 = BigInt::from(k.clone()) - &c1 * &a1 - &c2 * a2;
+    let k2 = c1 * minus_b1 - c2 * a1;
+    [k1, k2].map(|half| {
+        (
+            half.sign() == num_bigint::Sign::Minus,
+            half.magnitude().clone(),
+        )
+    })
+}
+
+/// Compute `k * p` with the GLV method: `k = k1 + lambda * k2` with halves of 128 bits and
+/// `lambda * p = (beta * x, y)`, so [`interleaved_mul`] runs over 128 bits instead of 256.
+///
+/// The decomposition is a witness, checked against `k` in the scalar field.
+fn glv_scalar_mul<'a, E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    p: &AffinePoint<'a, E, G>,
+    k: &mut FieldElement<'a, E, Scalar>,
+    default: &AffinePoint<'a, E, G>,
+    base_params: &'a RnsParameters<E, Base>,
+    scalar_params: &'a RnsParameters<E, Scalar>,
+    exceptions: &mut Vec<Boolean>,
+) -> Result<ProjectivePoint<'a, E, G>, SynthesisError> {
+    use franklin_crypto::plonk::circuit::bigint_new::bigint::repr_to_biguint;
+    let decomposition = k
+        .get_field_value()
+        .map(|k| glv_decompose(&repr_to_biguint::<Scalar>(&k.into_repr())));
+    let mut signs = vec![];
+    let mut bits = vec![];
+    let mut halves = vec![];
+    for i in 0..2 {
+        let witness = decomposition.as_ref().map(|d| d[i].clone());
+        let is_negative = Boolean::Is(AllocatedBit::alloc(
+            cs,
+            witness.as_ref().map(|(is_negative, _)| *is_negative),
+        )?);
+        let magnitude =
+            witness.map(|(_, magnitude)| Scalar::from_str(&magnitude.to_string()).unwrap());
+        let (mut magnitude, _) =
+            FieldElement::<E, Scalar>::alloc_ext(cs, magnitude, scalar_params)?;
+        let magnitude_bits = magnitude.decompose_into_binary_representation(cs)?;
+        for bit in magnitude_bits[GLV_SCALAR_BITLEN..].iter() {
+            Boolean::enforce_equal(cs, bit, &Boolean::constant(false))?;
+        }
+        let negated = magnitude.negate(cs)?;
+        halves.push(FieldElement::conditionally_select(
+            cs,
+            &is_negative,
+            &negated,
+            &magnitude,
+        )?);
+        signs.push(is_negative);
+        bits.push(magnitude_bits[..GLV_SCALAR_BITLEN].to_vec());
+    }
+
+    // k = k1 + lambda * k2
+    let lambda = FieldElement::constant(Scalar::from_str(GLV_LAMBDA).unwrap(), scalar_params);
+    let mut recomposed = halves[1].mul(cs, &lambda)?.add(cs, &halves[0])?;
+    FieldElement::enforce_equal(cs, &mut recomposed, k)?;
+
+    // k1 * p + k2 * (lambda * p), with the signs moved to the points
+    let beta = FieldElement::constant(Base::from_str(GLV_BETA).unwrap(), base_params);
+    let endomorphism = unsafe { AffinePoint::from_xy_unchecked(p.x.mul(cs, &beta)?, p.y.clone()) };
+    let negated = p.negate(cs)?;
+    let p1 = AffinePoint::conditionally_select(cs, &signs[0], &negated, p)?;
+    let negated = endomorphism.negate(cs)?;
+    let mut p2 = AffinePoint::conditionally_select(cs, &signs[1], &negated, &endomorphism)?;
+    interleaved_mul(
+        cs,
+        &p1,
+        &bits[0],
+        &mut p2,
+        &bits[1],
+        default,
+        base_params,
+        exceptions,
+    )
+}
+
+/// Bits of a scalar selecting one entry of the generator table.
+const GENERATOR_WINDOW_BITLEN: usize = 4;
+
+fn generator_table() -> Vec<[G; 1 << GENERATOR_WINDOW_BITLEN]> {
+    let num_windows = (Scalar::NUM_BITS as usize).div_ceil(GENERATOR_WINDOW_BITLEN);
+    let mut base = G::one().into_projective();
+    let mut table = Vec::with_capacity(num_windows);
+    for _ in 0..num_windows {
+        // the entry of digit 0 is never added and only keeps the coordinates valid
+        let mut window = [G::one(); 1 << GENERATOR_WINDOW_BITLEN];
+        let mut multiple = base;
+        for entry in window.iter_mut().skip(1) {
+            *entry = multiple.into_affine();
+            multiple.add_assign(&base);
+        }
+        table.push(window);
+        for _ in 0..GENERATOR_WINDOW_BITLEN {
+            base.double();
+        }
+    }
+    table
+}
+
+/// Add `k * G` to `acc` from the generator table, one addition per window of the scalar instead
+/// of a double-and-add over its bits. The entries are constants, only their selection by the
+/// window bits is synthesized.
+fn add_generator_mul<'a, E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    mut acc: ProjectivePoint<'a, E, G>,
+    k: &mut FieldElement<'a, E, Scalar>,
+    table: &[[G; 1 << GENERATOR_WINDOW_BITLEN]],
+    params: &'a RnsParameters<E, Base>,
+) -> Result<ProjectivePoint<'a, E, G>, SynthesisError> {
+    let bits = k.decompose_into_binary_representation(cs)?;
+    for (window_bits, window) in bits.chunks(GENERATOR_WINDOW_BITLEN).zip(table.iter()) {
+        // select the entry of the digit, halving the candidates with each bit from the lowest
+        let mut candidates = window
+            .iter()
+            .map(|point| AffinePoint::<E, G>::constant(*point, params))
+            .collect::<Vec<_>>();
+        for bit in window_bits.iter() {
+            candidates = candidates
+                .chunks(2)
+                .map(|pair| AffinePoint::conditionally_select(cs, bit, &pair[1], &pair[0]))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+        let mut entry = candidates.pop().unwrap();
+        let sum = acc.add_mixed(cs, &mut entry)?;
+        let is_nonzero = smart_or(cs, window_bits)?;
+        acc = ProjectivePoint::conditionally_select(cs, &is_nonzero, &sum, &acc)?;
+    }
+    Ok(acc)
+}
+
 #[cfg(test)]
 mod tests {
     use advanced_circuit_component::{
-        franklin_crypto::{bellman::SynthesisError, plonk::circuit::boolean::Boolean},
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, GenericCurveAffine, GenericCurveProjective, PrimeField,
+                SynthesisError,
+            },
+            plonk::circuit::{
+                bigint_new::FieldElement,
+                boolean::{AllocatedBit, Boolean},
+                curve_new::{AffinePoint, ProjectivePoint},
+            },
+        },
         traits::CSAllocatable,
         vm::primitives::uint256::UInt256,
     };
     use num::Num as _;
     use num_bigint::BigUint;
 
-    use crate::{gadgets::ecdsa::Signature, utils::testing::create_test_constraint_system};
+    use crate::{
+        gadgets::ecdsa::{
+            add_generator_mul, generator_table, glv_decompose, interleaved_mul, Scalar,
+            Secp256k1Params, Signature, G, GLV_LAMBDA, GLV_SCALAR_BITLEN,
+        },
+        utils::testing::{create_test_constraint_system, CountingConstraintSystem},
+    };
 
     #[test]
     fn test_ecrecover() -> Result<(), SynthesisError> {
@@ -520,4 +888,253 @@ mod tests {
         assert!(cs.is_satisfied());
         Ok(())
     }
+
+    #[test]
+    fn test_ecrecover_address() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let signature = hex::decode("0c0422df7d6f26a8d6250236060b8acd514fa4e8d260ff3c32c3aad4b6b470376e0f5a27e14e47ad328d01c3d8a4b969febab06ea26c84caa1fbe1779d62a78500").unwrap();
+        let message_hash = UInt256::alloc_from_witness(
+            cs,
+            Some(BigUint::from_bytes_be(
+                &hex::decode("c74d460340f9fea30c254d133303361e67246c40a52e6b5ddbbd813e0d211762")
+                    .unwrap(),
+            )),
+        )?;
+        let pubkey = hex::decode("1d152307c6b72b0ed0418b0e70cd80e7f5295b8d86f5722d3f5213fbd2394f36b7ce9c3e45905178455900b44abb308f3ef480481a4b2ee3f70aca157fde396a").unwrap();
+        let expected = crate::keccak::keccak256(&pubkey);
+
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        let (success, address) = signature.ecrecover_address(cs, &message_hash)?;
+        assert_eq!(success.get_value(), Some(true));
+        assert_eq!(
+            Byte::get_byte_value_multiple(&address).as_deref(),
+            Some(&expected[12..])
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_glv_decompose() {
+        let n = BigUint::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let lambda = BigUint::from_str_radix(GLV_LAMBDA, 10).unwrap();
+        for k in [
+            BigUint::from(1u32),
+            &n - 1u32,
+            &n >> 1,
+            lambda.clone(),
+            BigUint::from_str_radix(
+                "6e0f5a27e14e47ad328d01c3d8a4b969febab06ea26c84caa1fbe1779d62a785",
+                16,
+            )
+            .unwrap(),
+        ] {
+            let [(k1_is_negative, k1), (k2_is_negative, k2)] = glv_decompose(&k);
+            assert!(k1.bits() <= GLV_SCALAR_BITLEN as u64);
+            assert!(k2.bits() <= GLV_SCALAR_BITLEN as u64);
+            let signed = |is_negative: bool, half: BigUint| {
+                if is_negative {
+                    &n - half % &n
+                } else {
+                    half
+                }
+            };
+            let recomposed =
+                (signed(k1_is_negative, k1) + signed(k2_is_negative, k2) * &lambda) % &n;
+            assert_eq!(recomposed, k);
+        }
+    }
+
+    #[test]
+    fn test_generator_table() {
+        let table = generator_table();
+        assert_eq!(table.len(), 64);
+        let multiple = |k: u64| {
+            let mut point = G::one().into_projective();
+            GenericCurveProjective::mul_assign(
+                &mut point,
+                Scalar::from_str(&k.to_string()).unwrap().into_repr(),
+            );
+            point.into_affine()
+        };
+        assert_eq!(table[0][1], G::one());
+        assert_eq!(table[0][15], multiple(15));
+        assert_eq!(table[1][3], multiple(3 * 16));
+        assert_eq!(table[2][7], multiple(7 * 256));
+    }
+
+    #[test]
+    fn test_interleaved_mul_gates() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let params = Secp256k1Params::<Bn256>::new(&mut **cs);
+        let base = &params.base_field;
+        let multiple = |k: u64| {
+            let mut point = G::one().into_projective();
+            GenericCurveProjective::mul_assign(
+                &mut point,
+                Scalar::from_str(&k.to_string()).unwrap().into_repr(),
+            );
+            point.into_affine()
+        };
+        let (p, _, _) = AffinePoint::alloc_ext(&mut **cs, Some(multiple(3)), base)?;
+        let (mut q, _, _) = AffinePoint::alloc_ext(&mut **cs, Some(multiple(5)), base)?;
+        let default = AffinePoint::constant(G::one(), base);
+        let mut alloc_bits = |step: usize| {
+            (0..GLV_SCALAR_BITLEN)
+                .map(|i| {
+                    Ok(Boolean::Is(AllocatedBit::alloc(
+                        &mut **cs,
+                        Some(i % step == 0),
+                    )?))
+                })
+                .collect::<Result<Vec<_>, SynthesisError>>()
+        };
+        let a_bits = alloc_bits(3)?;
+        let b_bits = alloc_bits(5)?;
+
+        let mut exceptions = vec![];
+        cs.measure("interleaved", |cs| {
+            interleaved_mul(
+                cs,
+                &p,
+                &a_bits,
+                &mut q,
+                &b_bits,
+                &default,
+                base,
+                &mut exceptions,
+            )
+        })?;
+        assert_eq!(exceptions[0].get_value(), Some(false));
+        // Two double-and-adds, without even adding their results.
+        cs.measure("separate", |cs| {
+            for (point, bits) in [(&p, &a_bits), (&q, &b_bits)] {
+                let mut point = point.clone();
+                let mut acc = ProjectivePoint::zero(base);
+                for bit in bits.iter().rev() {
+                    acc = acc.double(cs)?;
+                    let sum = acc.add_mixed(cs, &mut point)?;
+                    acc = ProjectivePoint::conditionally_select(cs, bit, &sum, &acc)?;
+                }
+            }
+            Ok(())
+        })?;
+        assert!(cs.gates("interleaved") < cs.gates("separate"));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generator_mul_gates() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let params = Secp256k1Params::<Bn256>::new(&mut **cs);
+        let base = &params.base_field;
+        let k = Scalar::from_str(
+            "49784436325225209346498934520466563536271402394853221522428312553429016651653",
+        )
+        .unwrap();
+        let (mut k, _) = FieldElement::alloc_ext(&mut **cs, Some(k), &params.scalar_field)?;
+        cs.measure("windowed", |cs| {
+            add_generator_mul(
+                cs,
+                ProjectivePoint::zero(base),
+                &mut k,
+                &params.generator_table,
+                base,
+            )
+        })?;
+        // Adding G bit by bit, the doublings being shared with the other scalar as in
+        // `interleaved_mul`, so that only the additions are counted.
+        cs.measure("shared doubling", |cs| {
+            let bits = k.decompose_into_binary_representation(cs)?;
+            let mut generator = AffinePoint::constant(G::one(), base);
+            let mut acc = ProjectivePoint::zero(base);
+            for bit in bits.iter() {
+                let sum = acc.add_mixed(cs, &mut generator)?;
+                acc = ProjectivePoint::conditionally_select(cs, bit, &sum, &acc)?;
+            }
+            Ok(acc)
+        })?;
+        assert!(cs.gates("windowed") < cs.gates("shared doubling"));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecrecover_rejects_degenerate_signatures() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let signature = hex::decode("0c0422df7d6f26a8d6250236060b8acd514fa4e8d260ff3c32c3aad4b6b470376e0f5a27e14e47ad328d01c3d8a4b969febab06ea26c84caa1fbe1779d62a78500").unwrap();
+        let message_hash = UInt256::alloc_from_witness(
+            cs,
+            Some(BigUint::from_bytes_be(
+                &hex::decode("c74d460340f9fea30c254d133303361e67246c40a52e6b5ddbbd813e0d211762")
+                    .unwrap(),
+            )),
+        )?;
+        let order = BigUint::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let r = BigUint::from_bytes_be(&signature[..32]);
+        let s = BigUint::from_bytes_be(&signature[32..64]);
+        for (r, s) in [
+            (BigUint::from(0u32), s.clone()),
+            (r.clone(), BigUint::from(0u32)),
+            (r.clone(), &order + 1u32),
+            (&order + 1u32, s.clone()),
+        ] {
+            let signature = Signature::alloc_from_witness(cs, Some((r, s, signature[64] as u32)))?;
+            let (success, _) = signature.ecrecover(cs, &message_hash)?;
+            assert_eq!(success.get_value(), Some(false));
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecrecover_low_s() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let signature = hex::decode("0c0422df7d6f26a8d6250236060b8acd514fa4e8d260ff3c32c3aad4b6b470376e0f5a27e14e47ad328d01c3d8a4b969febab06ea26c84caa1fbe1779d62a78500").unwrap();
+        let message_hash = UInt256::alloc_from_witness(
+            cs,
+            Some(BigUint::from_bytes_be(
+                &hex::decode("c74d460340f9fea30c254d133303361e67246c40a52e6b5ddbbd813e0d211762")
+                    .unwrap(),
+            )),
+        )?;
+        let order = BigUint::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let r = BigUint::from_bytes_be(&signature[..32]);
+        let s = BigUint::from_bytes_be(&signature[32..64]);
+        let recid = signature[64] as u32;
+        let low = Signature::alloc_from_witness(cs, Some((r.clone(), s.clone(), recid)))?;
+        // (r, n - s) recovers the same key with the parity of the point flipped.
+        let high = Signature::alloc_from_witness(cs, Some((r, &order - &s, recid ^ 1)))?;
+        assert_eq!(low.is_low_s(cs)?.get_value(), Some(true));
+        assert_eq!(high.is_low_s(cs)?.get_value(), Some(false));
+
+        let params = Secp256k1Params::new(cs);
+        let (success, low_key) = low.ecrecover_with_params(cs, &params, &message_hash)?;
+        assert_eq!(success.get_value(), Some(true));
+        let (success, high_key) = high.ecrecover_with_params(cs, &params, &message_hash)?;
+        assert_eq!(success.get_value(), Some(true));
+        assert_eq!(high_key.0.get_value(), low_key.0.get_value());
+
+        let params = params.with_low_s();
+        let (success, _) = low.ecrecover_with_params(cs, &params, &message_hash)?;
+        assert_eq!(success.get_value(), Some(true));
+        let (success, (x, _)) = high.ecrecover_with_params(cs, &params, &message_hash)?;
+        assert_eq!(success.get_value(), Some(false));
+        assert_eq!(x.get_value(), Some(BigUint::from(0u32)));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 }
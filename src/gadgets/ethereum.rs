@@ -12,7 +12,7 @@ use advanced_circuit_component::{
 use num::traits::{FromBytes, ToBytes};
 use num_bigint::BigUint;
 
-use crate::utils::{self, new_synthesis_error};
+use crate::{error::OracleError, utils};
 
 /// Circuit representation of Ethereum address.
 #[derive(Debug, Clone)]
@@ -36,6 +36,10 @@ impl<E: Engine> Address<E> {
         self.0
     }
 
+    pub fn constant(address: &[u8; 20]) -> Self {
+        Self(UInt256::constant(BigUint::from_bytes_be(address)))
+    }
+
     /// Returns 0 address if condition is false, else self.
     pub fn mask<CS: ConstraintSystem<E>>(
         &self,
@@ -84,26 +88,41 @@ impl<E: Engine> Address<E> {
         cs: &mut CS,
         witness: &[u8; 20],
     ) -> Result<Self, SynthesisError> {
-        let mut chunks_be_arr = [0u8; 32];
-        chunks_be_arr[12..].copy_from_slice(&witness[..]);
-        let uint256 = BigUint::from_be_bytes(&chunks_be_arr);
-        let uint256 = UInt256::alloc_from_biguint(cs, Some(uint256))?;
-        Ok(Self(uint256))
+        Self::from_optional_witness(cs, Some(witness))
+    }
+
+    /// Allocate an address with absent witness, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        Self::from_optional_witness(cs, None)
+    }
+
+    /// Allocate an address, `None` for setup passes.
+    pub fn from_optional_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<&[u8; 20]>,
+    ) -> Result<Self, SynthesisError> {
+        let uint256 = witness.map(|witness| {
+            let mut chunks_be_arr = [0u8; 32];
+            chunks_be_arr[12..].copy_from_slice(&witness[..]);
+            BigUint::from_be_bytes(&chunks_be_arr)
+        });
+        Ok(Self(UInt256::alloc_from_biguint(cs, uint256)?))
     }
 
     /// Create address from public key witness in compressed / uncompressed format.
     pub fn from_pubkey_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: &[u8],
-    ) -> Result<Self, SynthesisError> {
-        let pubkey = secp256k1::PublicKey::from_slice(witness).map_err(new_synthesis_error)?;
+    ) -> Result<Self, OracleError> {
+        let pubkey = secp256k1::PublicKey::from_slice(witness)
+            .map_err(|e| OracleError::InvalidPublicKey(e.to_string()))?;
         let bytes = pubkey.serialize_uncompressed();
         use sha3::Digest as _;
         let address: [u8; 32] = sha3::Keccak256::new_with_prefix(&bytes[1..])
             .finalize()
             .into();
         let address: [u8; 20] = address[address.len() - 20..].try_into().unwrap();
-        Self::from_address_witness(cs, &address)
+        Ok(Self::from_address_witness(cs, &address)?)
     }
 
     pub fn equals<CS: ConstraintSystem<E>>(
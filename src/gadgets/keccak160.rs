@@ -1,16 +1,19 @@
 use std::usize;
 
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::{
     circuit_structures::byte::Byte,
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+        plonk::circuit::boolean::Boolean,
     },
-    glue::prepacked_long_comparison,
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 
-use crate::utils::{new_synthesis_error, num_from_be_bytes};
+use super::{
+    keccak256::Keccak256Params,
+    merkle::{self, bytes_equal, sort_pair, HashGadget},
+};
+use crate::utils::new_synthesis_error;
 
 pub const WIDTH_HASH_BYTES: usize = 20;
 pub type Hash<E> = [Byte<E>; WIDTH_HASH_BYTES];
@@ -30,126 +33,101 @@ pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     bytes: &[Byte<E>],
 ) -> Result<Hash<E>, SynthesisError> {
-    let digest256 = super::keccak256::digest(cs, bytes)?;
+    let params = Keccak256Params::new(cs)?;
+    digest_with_params(cs, &params, bytes)
+}
+
+/// Like [`digest`], with the gadget shared by several digests.
+pub fn digest_with_params<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: &[Byte<E>],
+) -> Result<Hash<E>, SynthesisError> {
+    let digest256 = super::keccak256::digest_with_params(cs, params, bytes)?;
     let mut digest160 = [Byte::<E>::zero(); WIDTH_HASH_BYTES];
     digest160[..].copy_from_slice(&digest256[..WIDTH_HASH_BYTES]);
     Ok(digest160)
 }
 
-/// Circuit implementation of pyth [`MerkleRoot`](https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L53-L66).
-#[derive(Debug, Clone)]
-pub struct MerkleRoot<E: Engine>(Hash<E>);
-/// Circuit implementation of pyth
-/// [`MerklePath`](https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L39-L51)
+/// Keccak256 truncated to its first 20 bytes, hashing merkle trees the way the Pyth accumulator
+/// does.
 #[derive(Debug, Clone, Copy)]
-pub struct MerklePath<E: Engine, const N: usize>(pub [Hash<E>; N]);
-
-impl<E: Engine, const N: usize> MerklePath<E, N> {
-    pub fn new(proof: [Hash<E>; N]) -> Self {
-        Self(proof)
-    }
-
-    pub fn new_from_slice(proof: &[Hash<E>]) -> Result<Self, SynthesisError> {
-        let proof = proof.try_into().map_err(|_| {
-            new_synthesis_error(format!(
-                "invalid proof length {}, expect {}",
-                proof.len(),
-                N
-            ))
-        })?;
-        Ok(Self(proof))
-    }
-
-    pub fn len(&self) -> usize {
-        N
-    }
-
-    pub fn is_empty(&self) -> bool {
-        N == 0
-    }
-}
+pub struct Keccak160;
 
-impl<E: Engine> MerkleRoot<E> {
-    pub fn new(hash: Hash<E>) -> Self {
-        Self(hash)
-    }
+impl<E: Engine> HashGadget<E> for Keccak160 {
+    type Hash = Hash<E>;
+    type Params = Keccak256Params<E>;
 
-    pub fn inner(&self) -> Hash<E> {
-        self.0
+    fn params<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Keccak256Params<E>, SynthesisError> {
+        Keccak256Params::new(cs)
     }
 
-    /// Compute hash of a leaf node.
-    pub fn hash_leaf<CS: ConstraintSystem<E>>(
+    fn hash_leaf<CS: ConstraintSystem<E>>(
         cs: &mut CS,
+        params: &Keccak256Params<E>,
         item: &[Byte<E>],
     ) -> Result<Hash<E>, SynthesisError> {
-        let mut bytes = vec![Byte::zero()];
-        bytes.extend_from_slice(item);
-        digest(cs, &bytes)
+        let digest256 = super::keccak256::digest_iter_with_params(
+            cs,
+            params,
+            std::iter::once(Byte::zero()).chain(item.iter().copied()),
+        )?;
+        hash_from_slice(&digest256[..WIDTH_HASH_BYTES])
     }
 
-    /// Compute hash of a node.
-    pub fn hash_node<CS: ConstraintSystem<E>>(
+    fn hash_node<CS: ConstraintSystem<E>>(
         cs: &mut CS,
+        params: &Keccak256Params<E>,
         l: Hash<E>,
         r: Hash<E>,
     ) -> Result<Hash<E>, SynthesisError> {
-        let ln = num_from_be_bytes(cs, &l)?;
-        let rn = num_from_be_bytes(cs, &r)?;
-        let (_, l_is_greater) =
-            prepacked_long_comparison(cs, &[ln], &[rn], &[WIDTH_HASH_BYTES * 8])?;
-        let (l, r): (Hash<_>, Hash<_>) = {
-            let zipped = (0..WIDTH_HASH_BYTES)
-                .map(|i| {
-                    let li = l[i].inner;
-                    let ri = r[i].inner;
-                    // reverse if l_is_greater is true
-                    let (li, ri) = Num::conditionally_reverse(cs, &li, &ri, &l_is_greater)?;
-                    Ok((
-                        Byte::from_num_unconstrained(cs, li),
-                        Byte::from_num_unconstrained(cs, ri),
-                    ))
-                    // (1u8, 1u8)
-                })
-                .collect::<Result<Vec<_>, SynthesisError>>()?;
-            let (l, r): (Vec<_>, Vec<_>) = zipped.into_iter().unzip();
-
-            (hash_from_slice(&l)?, hash_from_slice(&r)?)
-        };
+        let (l, r) = sort_pair(cs, l, r)?;
         // https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L201-L207
         let mut bytes = [Byte::zero(); 1 + WIDTH_HASH_BYTES * 2];
         bytes[0] = Byte::<E>::constant(1);
         bytes[1..WIDTH_HASH_BYTES + 1].copy_from_slice(&l[..]);
         bytes[WIDTH_HASH_BYTES + 1..].copy_from_slice(&r[..]);
-        digest(cs, &bytes)
+        digest_with_params(cs, params, &bytes)
     }
 
-    /// Check if the given item is in the merkle tree.
-    pub fn check<CS: ConstraintSystem<E>, const N: usize>(
-        &self,
+    fn equals<CS: ConstraintSystem<E>>(
         cs: &mut CS,
-        path: &MerklePath<E, N>,
-        item: &[Byte<E>],
+        a: &Hash<E>,
+        b: &Hash<E>,
     ) -> Result<Boolean, SynthesisError> {
-        let mut current = Self::hash_leaf(cs, item)?;
-        for hash in &path.0 {
-            let (l, r) = (current, *hash);
-            current = Self::hash_node(cs, l, r)?;
+        bytes_equal(cs, a, b)
+    }
+
+    fn enforce_equal<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Hash<E>,
+        b: &Hash<E>,
+    ) -> Result<(), SynthesisError> {
+        for (a, b) in a.iter().zip(b.iter()) {
+            a.inner.enforce_equal(cs, &b.inner)?;
         }
-        let current = num_from_be_bytes(cs, &current)?;
-        let root = num_from_be_bytes(cs, &self.0)?;
-        Num::equals(cs, &current, &root)
+        Ok(())
     }
 }
 
+/// Circuit implementation of pyth [`MerkleRoot`](https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L53-L66).
+pub type MerkleRoot<E> = merkle::MerkleRoot<E, Keccak160>;
+/// Circuit implementation of pyth
+/// [`MerklePath`](https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L39-L51)
+pub type MerklePath<E, const N: usize> = merkle::MerklePath<E, Keccak160, N>;
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        gadgets::keccak160::{MerklePath, MerkleRoot},
-        utils::testing::create_test_constraint_system,
+        gadgets::{
+            keccak160::{MerklePath, MerkleRoot},
+            keccak256::Keccak256Params,
+        },
+        utils::testing::{create_test_constraint_system, CountingConstraintSystem},
     };
 
     use super::Hash;
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
     use advanced_circuit_component::{
         circuit_structures::byte::Byte,
         franklin_crypto::{
@@ -157,7 +135,6 @@ mod tests {
             plonk::circuit::boolean::Boolean,
         },
     };
-    use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 
     #[test]
     fn test_keccak160() -> Result<(), SynthesisError> {
@@ -222,11 +199,36 @@ mod tests {
             MerklePath::new(nodes)
         };
         let item = hex_to_bytes(cs, "0007ad7b4a7662d19a6bc675f6b467172d2f3947fa653ca97555a9b2023640662800000000152f9dbf00000000000796fafffffff800000000655ccff700000000655ccff70000000015718f26000000000008745c");
+        let params = Keccak256Params::new(cs)?;
         let n = cs.n();
-        let valid = merkle_root.check(cs, &merkle_path, &item)?;
+        let valid = merkle_root.check(cs, &params, &merkle_path, &item)?;
         Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
         let n = cs.n() - n;
         println!("Roughly {} gates", n);
+        let root = merkle_path.root(cs, &params, &item)?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&root.inner()),
+            Byte::get_byte_value_multiple(&merkle_root.inner())
+        );
+        merkle_path.enforce_inclusion(cs, &params, &item, &merkle_root)?;
+        assert!(cs.is_satisfied());
+
+        let mut tampered = item.clone();
+        tampered[1] = Byte::constant(0);
+        merkle_path.enforce_inclusion(cs, &params, &tampered, &merkle_root)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merkle_node_cost() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let l = hex_to_hash(&mut **cs, "c7073cf69695359c52329409390f17b8f27770c8");
+        let r = hex_to_hash(&mut **cs, "210eb6077a92151e6057fa3dab51814634d5fe67");
+        let params = Keccak256Params::new(&mut **cs)?;
+        cs.measure("hash_node", |cs| MerkleRoot::hash_node(cs, &params, l, r))?;
+        // One keccak block plus sorting the children.
+        cs.assert_within_budget("hash_node", 27_500);
         assert!(cs.is_satisfied());
         Ok(())
     }
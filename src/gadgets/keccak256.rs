@@ -1,42 +1,417 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::{
     circuit_structures::byte::Byte,
     franklin_crypto::{
-        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
         plonk::circuit::{
+            allocated_num::Num, boolean::Boolean,
             hashes_with_tables::keccak::gadgets::Keccak256Gadget,
+            linear_combination::LinearCombination,
             tables::RANGE_CHECK_SINGLE_APPLICATION_TABLE_NAME,
         },
     },
+    glue::prepacked_long_comparison,
     scheduler::block_header::keccak_output_into_bytes,
+    utils::u64_to_fe,
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+use super::merkle::{bytes_equal, sort_pair, HashGadget};
+
+/// keccak256 gadget of a constraint system, its round constants and lookup tables set up once and
+/// shared by the `*_with_params` digests, like [`Secp256k1Params`](super::ecdsa::Secp256k1Params).
+pub struct Keccak256Params<E: Engine> {
+    gadget: Keccak256Gadget<E>,
+}
+
+impl<E: Engine> Keccak256Params<E> {
+    pub fn new<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        let gadget = Keccak256Gadget::new(
+            cs,
+            None,
+            None,
+            None,
+            None,
+            true,
+            RANGE_CHECK_SINGLE_APPLICATION_TABLE_NAME,
+        )?;
+        Ok(Self { gadget })
+    }
+
+    /// Output lanes of keccak256 over the padded `lanes`.
+    fn permute<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        lanes: &[Num<E>],
+    ) -> Result<Vec<Num<E>>, SynthesisError> {
+        self.gadget.digest(cs, lanes)
+    }
+}
 
 // cost about 26000 gates for each block
+//
+// The gadget of `hashes_with_tables` already evaluates theta, rho and chi through lookup tables
+// over base-13/base-9 sparse representations rather than boolean constraints, so every keccak
+// here, including keccak160 and the merkle gadgets built on it, is table-backed.
 pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     bytes: &[Byte<E>],
 ) -> Result<[Byte<E>; 32], SynthesisError> {
-    let keccak_gadget = Keccak256Gadget::new(
-        cs,
-        None,
-        None,
-        None,
-        None,
-        true,
-        RANGE_CHECK_SINGLE_APPLICATION_TABLE_NAME,
-    )?;
-    let result = keccak_gadget.digest_from_bytes(cs, bytes)?;
+    let params = Keccak256Params::new(cs)?;
+    digest_with_params(cs, &params, bytes)
+}
+
+/// Like [`digest`], with the gadget shared by several digests.
+pub fn digest_with_params<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let result = params.gadget.digest_from_bytes(cs, bytes)?;
     let digest = keccak_output_into_bytes(cs, result)?;
     Ok(digest)
 }
 
+/// Keccak256 over sorted pairs, hashing merkle trees the way Ethereum contracts such as
+/// OpenZeppelin's `MerkleProof` do: leaves are the digest of the item, nodes the digest of their
+/// children smaller first.
+#[derive(Debug, Clone, Copy)]
+pub struct Keccak256;
+
+impl<E: Engine> HashGadget<E> for Keccak256 {
+    type Hash = [Byte<E>; 32];
+    type Params = Keccak256Params<E>;
+
+    fn params<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Keccak256Params<E>, SynthesisError> {
+        Keccak256Params::new(cs)
+    }
+
+    fn hash_leaf<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
+        item: &[Byte<E>],
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        digest_with_params(cs, params, item)
+    }
+
+    fn hash_node<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
+        l: [Byte<E>; 32],
+        r: [Byte<E>; 32],
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let (l, r) = sort_pair(cs, l, r)?;
+        digest_iter_with_params(cs, params, l.into_iter().chain(r))
+    }
+
+    fn equals<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &[Byte<E>; 32],
+        b: &[Byte<E>; 32],
+    ) -> Result<Boolean, SynthesisError> {
+        bytes_equal(cs, a, b)
+    }
+}
+
+/// Bytes of a keccak lane.
+const LANE_BYTES: usize = 8;
+const RATE_LANES: usize = 17;
+
+/// Digest of the bytes yielded by `bytes`, packed into lanes as they come so that no intermediate
+/// byte array has to be built by the caller.
+pub fn digest_iter<E: Engine, CS: ConstraintSystem<E>, I: IntoIterator<Item = Byte<E>>>(
+    cs: &mut CS,
+    bytes: I,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let params = Keccak256Params::new(cs)?;
+    digest_iter_with_params(cs, &params, bytes)
+}
+
+/// Like [`digest_iter`], with the gadget shared by several digests.
+pub fn digest_iter_with_params<
+    E: Engine,
+    CS: ConstraintSystem<E>,
+    I: IntoIterator<Item = Byte<E>>,
+>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: I,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let lanes = pack_padded_lanes(cs, bytes)?;
+    let result = params.permute(cs, &lanes)?;
+    let digest = keccak_output_into_bytes(cs, result)?;
+    Ok(digest)
+}
+
+/// keccak256(keccak256(bytes)), the intermediate digest staying packed in lanes between the two.
+pub fn double_digest<E: Engine, CS: ConstraintSystem<E>, I: IntoIterator<Item = Byte<E>>>(
+    cs: &mut CS,
+    bytes: I,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let params = Keccak256Params::new(cs)?;
+    double_digest_with_params(cs, &params, bytes)
+}
+
+/// Like [`double_digest`], with the gadget shared by several digests.
+pub fn double_digest_with_params<
+    E: Engine,
+    CS: ConstraintSystem<E>,
+    I: IntoIterator<Item = Byte<E>>,
+>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: I,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let lanes = pack_padded_lanes(cs, bytes)?;
+    let first = params.permute(cs, &lanes)?;
+    let result = permute_lanes(cs, params, &first)?;
+    keccak_output_into_bytes(cs, result)
+}
+
+/// Digest of the first `len` bytes of `bytes`, for inputs whose length is only known in-circuit.
+/// `len` must be below 2^32 and is enforced to be at most `bytes.len()`.
+///
+/// The gadget does not expose its intermediate states, so every number of blocks the input may
+/// span is hashed, its last block padded at `len`, and the digest of the number of blocks of `len`
+/// is selected: up to `k` blocks cost `k(k+1)/2` permutations.
+pub fn digest_var_len<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    len: &Num<E>,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let params = Keccak256Params::new(cs)?;
+    let result = permute_var_len(cs, &params, bytes, len)?;
+    keccak_output_into_bytes(cs, result)
+}
+
+/// keccak256(keccak256(bytes)) of the first `len` bytes of `bytes`, see [`digest_var_len`].
+pub fn double_digest_var_len<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    len: &Num<E>,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let params = Keccak256Params::new(cs)?;
+    double_digest_var_len_with_params(cs, &params, bytes, len)
+}
+
+/// Like [`double_digest_var_len`], with the gadget shared by several digests.
+pub fn double_digest_var_len_with_params<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: &[Byte<E>],
+    len: &Num<E>,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let first = permute_var_len(cs, params, bytes, len)?;
+    let result = permute_lanes(cs, params, &first)?;
+    keccak_output_into_bytes(cs, result)
+}
+
+/// Output lanes of keccak256 over the first `len` bytes of `bytes`.
+fn permute_var_len<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    bytes: &[Byte<E>],
+    len: &Num<E>,
+) -> Result<Vec<Num<E>>, SynthesisError> {
+    let rate_bytes = RATE_LANES * LANE_BYTES;
+    let num_blocks = bytes.len() / rate_bytes + 1;
+    let len = *len;
+    let max_len = Num::Constant(u64_to_fe(bytes.len() as u64));
+    let (_, is_too_long) = prepacked_long_comparison(cs, &[len], &[max_len], &[32])?;
+    Boolean::enforce_equal(cs, &is_too_long, &Boolean::constant(false))?;
+
+    // Every block before the last one of a candidate is made of input bytes only.
+    let mut full_lanes = vec![];
+    for lane in bytes[..(num_blocks - 1) * rate_bytes].chunks(LANE_BYTES) {
+        let mut packed = LinearCombination::zero();
+        for (i, byte) in lane.iter().enumerate() {
+            packed.add_assign_number_with_coeff(&byte.inner, u64_to_fe(1u64 << (8 * i)));
+        }
+        full_lanes.push(packed.into_num(cs)?);
+    }
+
+    let mut result: Option<Vec<Num<E>>> = None;
+    for block in 0..num_blocks {
+        // Input bytes before `len`, the first padding byte at `len`, the last one at the end.
+        let mut lanes = full_lanes[..block * RATE_LANES].to_vec();
+        let mut lane = LinearCombination::zero();
+        for position in block * rate_bytes..(block + 1) * rate_bytes {
+            let coeff = u64_to_fe(1u64 << (8 * (position % LANE_BYTES)));
+            let position_num = Num::Constant(u64_to_fe(position as u64));
+            let (is_end, is_input) = prepacked_long_comparison(cs, &[len], &[position_num], &[32])?;
+            if let Some(byte) = bytes.get(position) {
+                let byte = Num::conditionally_select(cs, &is_input, &byte.inner, &Num::zero())?;
+                lane.add_assign_number_with_coeff(&byte, coeff);
+            }
+            lane.add_assign_boolean_with_coeff(&is_end, coeff);
+            if position == (block + 1) * rate_bytes - 1 {
+                lane.add_assign_constant(u64_to_fe(0x80u64 << 56));
+            }
+            if (position + 1) % LANE_BYTES == 0 {
+                lanes.push(std::mem::replace(&mut lane, LinearCombination::zero()).into_num(cs)?);
+            }
+        }
+        let output = params.permute(cs, &lanes)?;
+        result = Some(match result {
+            None => output,
+            Some(previous) => {
+                // `len` spans this block if it reaches its start.
+                let start = Num::Constant(u64_to_fe((block * rate_bytes) as u64));
+                let (is_equal, is_greater) =
+                    prepacked_long_comparison(cs, &[len], &[start], &[32])?;
+                let is_spanned = Boolean::or(cs, &is_equal, &is_greater)?;
+                output
+                    .iter()
+                    .zip(previous.iter())
+                    .map(|(output, previous)| {
+                        Num::conditionally_select(cs, &is_spanned, output, previous)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        });
+    }
+    Ok(result.expect("at least one block"))
+}
+
+/// Keccak256 over input absorbed chunk by chunk, for messages concatenated from several parts.
+///
+/// Absorbed bytes are packed into lanes right away, so only the lanes are kept. The gadget does
+/// not expose its intermediate states, so the permutations all run in [`Keccak256Sponge::finalize`].
+pub struct Keccak256Sponge<E: Engine> {
+    lanes: Vec<Num<E>>,
+    /// Lane of the bytes absorbed past the last full lane.
+    lane: LinearCombination<E>,
+    len: usize,
+}
+
+impl<E: Engine> Default for Keccak256Sponge<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Engine> Keccak256Sponge<E> {
+    pub fn new() -> Self {
+        Self {
+            lanes: vec![],
+            lane: LinearCombination::zero(),
+            len: 0,
+        }
+    }
+
+    /// Number of bytes absorbed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn absorb<CS: ConstraintSystem<E>, I: IntoIterator<Item = Byte<E>>>(
+        &mut self,
+        cs: &mut CS,
+        bytes: I,
+    ) -> Result<(), SynthesisError> {
+        for byte in bytes {
+            self.lane.add_assign_number_with_coeff(
+                &byte.inner,
+                u64_to_fe(1u64 << (8 * (self.len % LANE_BYTES))),
+            );
+            self.len += 1;
+            if self.len % LANE_BYTES == 0 {
+                self.push_lane(cs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Digest of all of the absorbed bytes.
+    pub fn finalize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let params = Keccak256Params::new(cs)?;
+        self.finalize_with_params(cs, &params)
+    }
+
+    /// Like [`Keccak256Sponge::finalize`], with the gadget shared by several digests.
+    pub fn finalize_with_params<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let lanes = self.into_padded_lanes(cs)?;
+        let result = params.permute(cs, &lanes)?;
+        keccak_output_into_bytes(cs, result)
+    }
+
+    fn push_lane<CS: ConstraintSystem<E>>(&mut self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let lane = std::mem::replace(&mut self.lane, LinearCombination::zero());
+        self.lanes.push(lane.into_num(cs)?);
+        Ok(())
+    }
+
+    /// Absorbed lanes, pad10*1 padding included.
+    fn into_padded_lanes<CS: ConstraintSystem<E>>(
+        mut self,
+        cs: &mut CS,
+    ) -> Result<Vec<Num<E>>, SynthesisError> {
+        let rate_bytes = RATE_LANES * LANE_BYTES;
+        let len = self.len;
+        let padded_len = (len / rate_bytes + 1) * rate_bytes;
+        for position in len..padded_len {
+            let mut padding = 0u64;
+            if position == len {
+                padding |= 0x01;
+            }
+            if position == padded_len - 1 {
+                padding |= 0x80;
+            }
+            self.lane
+                .add_assign_constant(u64_to_fe(padding << (8 * (position % LANE_BYTES))));
+            if (position + 1) % LANE_BYTES == 0 {
+                self.push_lane(cs)?;
+            }
+        }
+        Ok(self.lanes)
+    }
+}
+
+/// Pack `bytes` little-endian into lanes, pad10*1 padding included.
+fn pack_padded_lanes<E: Engine, CS: ConstraintSystem<E>, I: IntoIterator<Item = Byte<E>>>(
+    cs: &mut CS,
+    bytes: I,
+) -> Result<Vec<Num<E>>, SynthesisError> {
+    let mut sponge = Keccak256Sponge::new();
+    sponge.absorb(cs, bytes)?;
+    sponge.into_padded_lanes(cs)
+}
+
+/// Output lanes of keccak256 over the packed `lanes`.
+fn permute_lanes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    params: &Keccak256Params<E>,
+    lanes: &[Num<E>],
+) -> Result<Vec<Num<E>>, SynthesisError> {
+    // pad10*1 with the keccak domain byte, both padding bytes falling on lane boundaries
+    let num_padding = RATE_LANES - lanes.len() % RATE_LANES;
+    let mut padding = vec![E::Fr::zero(); num_padding];
+    padding[0] = u64_to_fe(0x01);
+    padding[num_padding - 1].add_assign(&u64_to_fe(0x80u64 << 56));
+    let mut padded = lanes.to_vec();
+    padded.extend(padding.into_iter().map(Num::Constant));
+    params.permute(cs, &padded)
+}
+
 #[cfg(test)]
 mod tests {
     use advanced_circuit_component::{
-        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+        circuit_structures::byte::Byte,
+        franklin_crypto::{bellman::SynthesisError, plonk::circuit::allocated_num::Num},
+        utils::u64_to_fe,
     };
 
-    use crate::utils::testing::create_test_constraint_system;
+    use crate::utils::testing::{create_test_constraint_system, CountingConstraintSystem};
 
     #[test]
     fn test_keccak256() -> Result<(), SynthesisError> {
@@ -54,8 +429,157 @@ mod tests {
             hex::encode(digest),
             "47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad"
         );
-        let n = cs.n() - n;
-        println!("Roughly {} gates", n);
+        assert!(cs.n() - n <= 27_000);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keccak256_shared_params() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let input_bytes = b"hello world"
+            .iter()
+            .map(|b| Byte::from_u8_witness(&mut **cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let fresh = cs.measure("fresh", |cs| {
+            (0..3)
+                .map(|_| super::digest(cs, &input_bytes))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        let shared = cs.measure("shared", |cs| {
+            let params = super::Keccak256Params::new(cs)?;
+            (0..3)
+                .map(|_| super::digest_with_params(cs, &params, &input_bytes))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        for (fresh, shared) in fresh.iter().zip(shared.iter()) {
+            assert_eq!(
+                Byte::get_byte_value_multiple(fresh),
+                Byte::get_byte_value_multiple(shared)
+            );
+        }
+        // The round constants are allocated once instead of once per digest.
+        assert!(cs.gates("shared") < cs.gates("fresh"), "{}", cs.report());
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_iter() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        // Partial last lane, a single padding byte, a whole padding block.
+        for len in [11usize, 135, 136] {
+            let bytes = (0..len)
+                .map(|b| Byte::from_u8_witness(cs, Some(b as u8)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let expected = super::digest(cs, &bytes)?;
+            let digest = super::digest_iter(cs, bytes.iter().copied())?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected)
+            );
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keccak256_sponge() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let bytes = (0..300u32)
+            .map(|b| Byte::from_u8_witness(cs, Some(b as u8)))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Chunks splitting lanes and blocks, an empty chunk, and a block-aligned total.
+        for chunks in [vec![0usize, 3, 3, 130, 300], vec![0, 136, 136, 272]] {
+            let mut sponge = super::Keccak256Sponge::new();
+            for range in chunks.windows(2) {
+                sponge.absorb(cs, bytes[range[0]..range[1]].iter().copied())?;
+            }
+            let len = *chunks.last().unwrap();
+            assert_eq!(sponge.len(), len);
+            let expected = super::digest(cs, &bytes[..len])?;
+            let digest = sponge.finalize(cs)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected)
+            );
+        }
+        let empty = super::Keccak256Sponge::new().finalize(cs)?;
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&empty).unwrap()),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_double_digest() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let input = (0..88u8)
+            .map(|b| Byte::from_u8_witness(&mut **cs, Some(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let expected = cs.measure("separate", |cs| {
+            let first = super::digest(cs, &input)?;
+            super::digest(cs, &first)
+        })?;
+        let fused = cs.measure("fused", |cs| {
+            super::double_digest(cs, input.iter().copied())
+        })?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&fused),
+            Byte::get_byte_value_multiple(&expected)
+        );
+        assert!(cs.gates("fused") < cs.gates("separate"));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_var_len() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let bytes = (0..150u8)
+            .map(|b| Byte::from_u8_witness(cs, Some(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Within the first block, a single padding byte, a whole padding block, all of the bytes.
+        for len in [0usize, 37, 135, 136, 150] {
+            let len_witness = Num::alloc(cs, Some(u64_to_fe(len as u64)))?;
+            let expected = super::digest(cs, &bytes[..len])?;
+            let digest = super::digest_var_len(cs, &bytes, &len_witness)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected),
+                "length {}",
+                len
+            );
+            let expected = super::digest(cs, &expected)?;
+            let digest = super::double_digest_var_len(cs, &bytes, &len_witness)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected)
+            );
+        }
+        assert!(cs.is_satisfied());
+
+        let too_long = Num::alloc(cs, Some(u64_to_fe(151)))?;
+        super::digest_var_len(cs, &bytes, &too_long)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keccak256_cost() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let one_block = (0..135u8)
+            .map(|b| Byte::from_u8_witness(&mut **cs, Some(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let two_blocks = (0..136u8)
+            .map(|b| Byte::from_u8_witness(&mut **cs, Some(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        cs.measure("one block", |cs| super::digest(cs, &one_block))?;
+        cs.measure("two blocks", |cs| super::digest(cs, &two_blocks))?;
+        cs.assert_within_budget("one block", 27_000);
+        cs.assert_within_budget("two blocks", 2 * 27_000);
         assert!(cs.is_satisfied());
         Ok(())
     }
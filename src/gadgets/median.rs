@@ -0,0 +1,157 @@
+//! Median of the prices of one asset from several oracles.
+//!
+//! The prices are sorted out of circuit. The circuit checks that the sorted witness is ordered and
+//! is a permutation of the prices, by comparing `prod(gamma - price)` over both lists at a challenge
+//! `gamma` hashed from all of them, so that a single compromised oracle can not move the median out
+//! of the range of the honest ones.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::primitives::UInt64,
+};
+use num_bigint::BigUint;
+
+use super::poseidon::circuit_poseidon_hash;
+use crate::utils::{fr_from_biguint, new_synthesis_error};
+
+/// `price + 2^63`, in `[0, 2^64)`, which orders the big-endian `i64` prices as unsigned integers.
+fn order_key<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &[Byte<E>; 8],
+) -> Result<Num<E>, SynthesisError> {
+    let mut bytes = *price;
+    bytes.reverse();
+    let unsigned = UInt64::from_bytes_le(cs, &bytes)?.into_num();
+    let (_, is_negative) = prepacked_long_comparison(
+        cs,
+        &[unsigned],
+        &[Num::Constant(u64_to_fe(i64::MAX as u64))],
+        &[64],
+    )?;
+    // Two's complement: a negative price reads as itself plus 2^64.
+    let wrap = Num::conditionally_select(
+        cs,
+        &is_negative,
+        &Num::Constant(fr_from_biguint::<E>(&(BigUint::from(1u8) << 64))?),
+        &Num::zero(),
+    )?;
+    unsigned
+        .add(cs, &Num::Constant(u64_to_fe(1 << 63)))?
+        .sub(cs, &wrap)
+}
+
+/// Median of the big-endian `i64` `prices`, the lower one of the two middle prices for an even
+/// number of prices.
+pub fn median<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    prices: &[[Byte<E>; 8]],
+) -> Result<[Byte<E>; 8], SynthesisError> {
+    if prices.is_empty() {
+        return Err(new_synthesis_error("median of no prices"));
+    }
+    let sorted_witness = prices
+        .iter()
+        .map(|price| {
+            Byte::get_byte_value_multiple(price)
+                .map(|bytes| i64::from_be_bytes(bytes.try_into().unwrap()))
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|mut prices| {
+            prices.sort();
+            prices
+        });
+    let sorted = (0..prices.len())
+        .map(|i| {
+            let witness = sorted_witness
+                .as_ref()
+                .map(|prices| prices[i].to_be_bytes());
+            CSAllocatable::alloc_from_witness(cs, witness)
+        })
+        .collect::<Result<Vec<[Byte<E>; 8]>, _>>()?;
+
+    let keys = prices
+        .iter()
+        .map(|price| order_key(cs, price))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sorted_keys = sorted
+        .iter()
+        .map(|price| order_key(cs, price))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for pair in sorted_keys.windows(2) {
+        let (_, is_greater) = prepacked_long_comparison(cs, &[pair[0]], &[pair[1]], &[64])?;
+        Boolean::enforce_equal(cs, &is_greater, &Boolean::constant(false))?;
+    }
+
+    let gamma = {
+        let mut input = keys.clone();
+        input.extend(sorted_keys.iter().copied());
+        circuit_poseidon_hash(cs, &input)?
+    };
+    let mut products = [Num::one(), Num::one()];
+    for (product, keys) in products.iter_mut().zip([&keys, &sorted_keys]) {
+        for key in keys.iter() {
+            let term = gamma.sub(cs, key)?;
+            *product = product.mul(cs, &term)?;
+        }
+    }
+    products[0].enforce_equal(cs, &products[1])?;
+
+    Ok(sorted[(prices.len() - 1) / 2])
+}
+
+/// Native [`median`].
+pub fn compute_median(prices: &[i64]) -> Option<i64> {
+    let mut prices = prices.to_vec();
+    prices.sort();
+    prices.get(prices.len().checked_sub(1)? / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        traits::CSAllocatable,
+    };
+
+    use super::{compute_median, median};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_median() -> Result<(), SynthesisError> {
+        let cases: [&[i64]; 5] = [
+            &[300, 100, 200],
+            &[100, 100, 5_000_000],
+            &[-5, 7, -1, 3],
+            &[i64::MIN, i64::MAX, 0],
+            &[42],
+        ];
+        assert_eq!(compute_median(&[]), None);
+        assert_eq!(compute_median(cases[0]), Some(200));
+        assert_eq!(compute_median(cases[2]), Some(-1));
+
+        let cs = &mut create_test_constraint_system()?;
+        for prices in cases {
+            let allocated = prices
+                .iter()
+                .map(|price| CSAllocatable::alloc_from_witness(cs, Some(price.to_be_bytes())))
+                .collect::<Result<Vec<[Byte<Bn256>; 8]>, _>>()?;
+            let result = median(cs, &allocated)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&result),
+                compute_median(prices).map(|price| price.to_be_bytes().to_vec())
+            );
+        }
+        assert!(cs.is_satisfied());
+        assert!(median::<Bn256, _>(cs, &[]).is_err());
+        Ok(())
+    }
+}
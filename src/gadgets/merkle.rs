@@ -0,0 +1,374 @@
+use std::fmt::Debug;
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+};
+
+use crate::utils::new_synthesis_error;
+
+/// Hash of a merkle tree, deciding how leaves and nodes are hashed and how hashes compare.
+///
+/// Implemented by [`Keccak160`](super::keccak160::Keccak160) for the Pyth accumulator,
+/// [`Keccak256`](super::keccak256::Keccak256) for Ethereum-style sorted-pair trees and
+/// [`Poseidon`](super::poseidon::Poseidon) for cheap in-circuit commitments. There is no SHA-256
+/// implementation: no oracle of this crate commits to a SHA-256 tree.
+pub trait HashGadget<E: Engine> {
+    type Hash: Copy + Debug;
+    /// Tables and constants of the hash, set up once per constraint system and shared by every
+    /// hash of a tree.
+    type Params;
+
+    fn params<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self::Params, SynthesisError>;
+
+    /// Hash of a leaf holding `item`.
+    fn hash_leaf<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &Self::Params,
+        item: &[Byte<E>],
+    ) -> Result<Self::Hash, SynthesisError>;
+
+    /// Hash of a node over its children.
+    fn hash_node<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &Self::Params,
+        l: Self::Hash,
+        r: Self::Hash,
+    ) -> Result<Self::Hash, SynthesisError>;
+
+    fn equals<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Self::Hash,
+        b: &Self::Hash,
+    ) -> Result<Boolean, SynthesisError>;
+
+    fn enforce_equal<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Self::Hash,
+        b: &Self::Hash,
+    ) -> Result<(), SynthesisError> {
+        let is_equal = Self::equals(cs, a, b)?;
+        Boolean::enforce_equal(cs, &is_equal, &Boolean::constant(true))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleRoot<E: Engine, H: HashGadget<E>>(H::Hash);
+
+#[derive(Debug, Clone, Copy)]
+pub struct MerklePath<E: Engine, H: HashGadget<E>, const N: usize>(pub [H::Hash; N]);
+
+impl<E: Engine, H: HashGadget<E>, const N: usize> MerklePath<E, H, N> {
+    pub fn new(proof: [H::Hash; N]) -> Self {
+        Self(proof)
+    }
+
+    pub fn new_from_slice(proof: &[H::Hash]) -> Result<Self, SynthesisError> {
+        let proof = proof.try_into().map_err(|_| {
+            new_synthesis_error(format!(
+                "invalid proof length {}, expect {}",
+                proof.len(),
+                N
+            ))
+        })?;
+        Ok(Self(proof))
+    }
+
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Root of the tree holding `item` at the leaf this path starts from.
+    pub fn root<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &H::Params,
+        item: &[Byte<E>],
+    ) -> Result<MerkleRoot<E, H>, SynthesisError> {
+        let mut current = H::hash_leaf(cs, params, item)?;
+        for hash in &self.0 {
+            current = H::hash_node(cs, params, current, *hash)?;
+        }
+        Ok(MerkleRoot::new(current))
+    }
+
+    /// Enforce that `item` is in the tree of `root`, such as the root of a
+    /// [`VaaPayload`](crate::pyth::VaaPayload).
+    pub fn enforce_inclusion<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &H::Params,
+        item: &[Byte<E>],
+        root: &MerkleRoot<E, H>,
+    ) -> Result<(), SynthesisError> {
+        let computed = self.root(cs, params, item)?;
+        H::enforce_equal(cs, &computed.0, &root.0)
+    }
+}
+
+impl<E: Engine, H: HashGadget<E>> MerkleRoot<E, H> {
+    pub fn new(hash: H::Hash) -> Self {
+        Self(hash)
+    }
+
+    pub fn inner(&self) -> H::Hash {
+        self.0
+    }
+
+    /// Compute hash of a leaf node.
+    pub fn hash_leaf<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &H::Params,
+        item: &[Byte<E>],
+    ) -> Result<H::Hash, SynthesisError> {
+        H::hash_leaf(cs, params, item)
+    }
+
+    /// Compute hash of a node.
+    pub fn hash_node<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        params: &H::Params,
+        l: H::Hash,
+        r: H::Hash,
+    ) -> Result<H::Hash, SynthesisError> {
+        H::hash_node(cs, params, l, r)
+    }
+
+    /// Check if the given item is in the merkle tree.
+    pub fn check<CS: ConstraintSystem<E>, const N: usize>(
+        &self,
+        cs: &mut CS,
+        params: &H::Params,
+        path: &MerklePath<E, H, N>,
+        item: &[Byte<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let current = path.root(cs, params, item)?.0;
+        H::equals(cs, &current, &self.0)
+    }
+}
+
+/// Bytes of the largest hash packed into a single field element by [`pack_limbs`].
+const MAX_LIMB_BYTES: usize = 31;
+
+/// Pack big-endian `bytes` into limbs, least significant first as in
+/// [`prepacked_long_comparison`], with their width in bits: a single limb if they fit a field
+/// element, as a 20-byte hash does, else their low and high halves. The bytes are already range
+/// checked so the limbs are too.
+pub(crate) fn pack_limbs<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<(Vec<Num<E>>, Vec<usize>), SynthesisError> {
+    let mut pack = |limb: &[Byte<E>]| {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for byte in limb.iter().rev() {
+            lc.add_assign_number_with_coeff(&byte.inner, coeff);
+            coeff.mul_assign(&u64_to_fe(256));
+        }
+        lc.into_num(cs)
+    };
+    if bytes.len() <= MAX_LIMB_BYTES {
+        return Ok((vec![pack(bytes)?], vec![bytes.len() * 8]));
+    }
+    let (hi, lo) = bytes.split_at(bytes.len() / 2);
+    Ok((vec![pack(lo)?, pack(hi)?], vec![lo.len() * 8, hi.len() * 8]))
+}
+
+/// Whether the big-endian byte hashes `a` and `b` are equal, compared by packed limbs.
+pub(crate) fn bytes_equal<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &[Byte<E>],
+    b: &[Byte<E>],
+) -> Result<Boolean, SynthesisError> {
+    let (a, _) = pack_limbs(cs, a)?;
+    let (b, _) = pack_limbs(cs, b)?;
+    let mut is_equal = Boolean::constant(true);
+    for (a, b) in a.iter().zip(b.iter()) {
+        let is_limb_equal = Num::equals(cs, a, b)?;
+        is_equal = Boolean::and(cs, &is_equal, &is_limb_equal)?;
+    }
+    Ok(is_equal)
+}
+
+/// The big-endian byte hashes `l` and `r`, smaller first.
+pub(crate) fn sort_pair<E: Engine, CS: ConstraintSystem<E>, const W: usize>(
+    cs: &mut CS,
+    l: [Byte<E>; W],
+    r: [Byte<E>; W],
+) -> Result<([Byte<E>; W], [Byte<E>; W]), SynthesisError> {
+    let (ln, widths) = pack_limbs(cs, &l)?;
+    let (rn, _) = pack_limbs(cs, &r)?;
+    let (_, l_is_greater) = prepacked_long_comparison(cs, &ln, &rn, &widths)?;
+    let mut sorted_l = [Byte::zero(); W];
+    let mut sorted_r = [Byte::zero(); W];
+    for i in 0..W {
+        // reverse if l_is_greater is true
+        let (li, ri) = Num::conditionally_reverse(cs, &l[i].inner, &r[i].inner, &l_is_greater)?;
+        sorted_l[i] = Byte::from_num_unconstrained(cs, li);
+        sorted_r[i] = Byte::from_num_unconstrained(cs, ri);
+    }
+    Ok((sorted_l, sorted_r))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::{Bn256, Fr},
+        plonk::better_better_cs::cs::ConstraintSystem,
+        Field, SynthesisError,
+    };
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    };
+    use num_bigint::BigUint;
+
+    use super::{bytes_equal, HashGadget, MerklePath, MerkleRoot};
+    use crate::{
+        gadgets::{
+            keccak256::Keccak256,
+            poseidon::{poseidon_hash, Poseidon},
+        },
+        keccak::keccak256,
+        utils::{
+            fr_from_biguint,
+            testing::{create_test_constraint_system, CountingConstraintSystem},
+        },
+    };
+
+    /// Items of a four-leaf tree, the second one being proven.
+    const ITEMS: [&[u8]; 4] = [
+        b"first",
+        b"second leaf",
+        b"third",
+        b"a fourth leaf over 31 bytes long",
+    ];
+
+    fn sorted_keccak_node(l: [u8; 32], r: [u8; 32]) -> [u8; 32] {
+        let (l, r) = if l <= r { (l, r) } else { (r, l) };
+        keccak256(&[l, r].concat())
+    }
+
+    fn poseidon_leaf(item: &[u8]) -> Fr {
+        let mut input = vec![
+            Fr::zero(),
+            fr_from_biguint::<Bn256>(&item.len().into()).unwrap(),
+        ];
+        input.extend(
+            item.chunks(31)
+                .map(|chunk| fr_from_biguint::<Bn256>(&BigUint::from_bytes_be(chunk)).unwrap()),
+        );
+        poseidon_hash::<Bn256>(&input)
+    }
+
+    fn poseidon_node(l: Fr, r: Fr) -> Fr {
+        let mut sum = l;
+        sum.add_assign(&r);
+        let mut product = l;
+        product.mul_assign(&r);
+        poseidon_hash::<Bn256>(&[Fr::one(), sum, product])
+    }
+
+    fn alloc_bytes<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Vec<Byte<Bn256>>, SynthesisError> {
+        bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect()
+    }
+
+    #[test]
+    fn test_keccak256_merkle() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let leaves = ITEMS.map(keccak256);
+        let root = sorted_keccak_node(
+            sorted_keccak_node(leaves[0], leaves[1]),
+            sorted_keccak_node(leaves[2], leaves[3]),
+        );
+        let mut alloc_hash = |hash: [u8; 32]| -> Result<[Byte<Bn256>; 32], SynthesisError> {
+            Ok(alloc_bytes(cs, &hash)?.try_into().unwrap())
+        };
+        let path = MerklePath::<Bn256, Keccak256, 2>::new([
+            alloc_hash(leaves[0])?,
+            alloc_hash(sorted_keccak_node(leaves[2], leaves[3]))?,
+        ]);
+        let root = MerkleRoot::<Bn256, Keccak256>::new(alloc_hash(root)?);
+        let item = alloc_bytes(cs, ITEMS[1])?;
+        let params = Keccak256::params(cs)?;
+        let valid = root.check(cs, &params, &path, &item)?;
+        Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
+        path.enforce_inclusion(cs, &params, &item, &root)?;
+        assert!(cs.is_satisfied());
+
+        let other = alloc_bytes(cs, ITEMS[2])?;
+        assert_eq!(
+            root.check(cs, &params, &path, &other)?.get_value(),
+            Some(false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_poseidon_merkle() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let leaves = ITEMS.map(poseidon_leaf);
+        let root = poseidon_node(
+            poseidon_node(leaves[0], leaves[1]),
+            poseidon_node(leaves[2], leaves[3]),
+        );
+        let path = MerklePath::<Bn256, Poseidon, 2>::new([
+            Num::alloc(cs, Some(leaves[0]))?,
+            Num::alloc(cs, Some(poseidon_node(leaves[2], leaves[3])))?,
+        ]);
+        let root = MerkleRoot::<Bn256, Poseidon>::new(Num::alloc(cs, Some(root))?);
+        let item = alloc_bytes(cs, ITEMS[1])?;
+        let valid = root.check(cs, &(), &path, &item)?;
+        Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
+        assert!(cs.is_satisfied());
+
+        let other = alloc_bytes(cs, ITEMS[3])?;
+        let leaf = Poseidon::hash_leaf(cs, &(), &other)?;
+        assert_eq!(leaf.get_value(), Some(leaves[3]));
+        assert_eq!(root.check(cs, &(), &path, &other)?.get_value(), Some(false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes_equal_gates() -> Result<(), SynthesisError> {
+        let cs = &mut CountingConstraintSystem::new()?;
+        let hash = keccak256(b"item");
+        let a = alloc_bytes(&mut **cs, &hash)?;
+        let b = alloc_bytes(&mut **cs, &hash)?;
+        // A 20-byte hash fits one limb, a 32-byte hash takes two.
+        let short = cs.measure("20 bytes", |cs| bytes_equal(cs, &a[12..], &b[12..]))?;
+        let long = cs.measure("32 bytes", |cs| bytes_equal(cs, &a, &b))?;
+        assert_eq!(short.get_value(), Some(true));
+        assert_eq!(long.get_value(), Some(true));
+        assert!(cs.gates("20 bytes") < cs.gates("32 bytes"));
+
+        let mut other = hash;
+        other[0] ^= 1;
+        let other = alloc_bytes(&mut **cs, &other)?;
+        assert_eq!(bytes_equal(&mut **cs, &a, &other)?.get_value(), Some(false));
+        assert_eq!(
+            bytes_equal(&mut **cs, &a[12..], &other[12..])?.get_value(),
+            Some(true)
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -2,5 +2,9 @@ pub mod ecdsa;
 pub mod ethereum;
 pub mod keccak160;
 pub mod keccak256;
+pub mod median;
+pub mod merkle;
 pub mod poseidon;
+pub mod price_math;
 pub mod rescue;
+pub mod staleness;
@@ -1,23 +1,100 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::allocated_num::Num,
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
     },
     rescue_poseidon::{CircuitGenericSponge, GenericSponge, PoseidonParams},
+    utils::u64_to_fe,
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+use super::merkle::HashGadget;
+use crate::utils::num_from_be_bytes;
+
 const WIDTH: usize = 3;
-const RATE: usize = 2;
+pub const RATE: usize = 2;
 
 pub fn circuit_poseidon_hash<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     input: &[Num<E>],
 ) -> Result<Num<E>, SynthesisError> {
-    let params = PoseidonParams::<E, RATE, WIDTH>::default();
-    Ok(CircuitGenericSponge::hash_num(cs, input, &params, None)?[0])
+    Ok(circuit_poseidon_sponge(cs, input)?[0])
 }
 
 pub fn poseidon_hash<E: Engine>(input: &[E::Fr]) -> E::Fr {
+    poseidon_sponge::<E>(input)[0]
+}
+
+/// Whole squeezed rate of the sponge, of which [`circuit_poseidon_hash`] keeps the first element.
+pub fn circuit_poseidon_sponge<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    input: &[Num<E>],
+) -> Result<[Num<E>; RATE], SynthesisError> {
     let params = PoseidonParams::<E, RATE, WIDTH>::default();
-    GenericSponge::hash(input, &params, None)[0]
+    CircuitGenericSponge::hash_num(cs, input, &params, None)
+}
+
+/// Native [`circuit_poseidon_sponge`].
+pub fn poseidon_sponge<E: Engine>(input: &[E::Fr]) -> [E::Fr; RATE] {
+    let params = PoseidonParams::<E, RATE, WIDTH>::default();
+    GenericSponge::hash(input, &params, None)
+}
+
+/// Bytes of the big-endian chunks a merkle leaf is packed into, fitting a field element.
+const LEAF_CHUNK_BYTES: usize = 31;
+
+/// Poseidon over field elements, hashing merkle trees in a few hundred gates per node instead of
+/// a keccak block. Leaves hash `[0, item length, item in 31-byte big-endian chunks..]`, nodes
+/// `[1, l + r, l * r]`, which does not depend on the order of the children so that paths need no
+/// direction bits.
+#[derive(Debug, Clone, Copy)]
+pub struct Poseidon;
+
+impl<E: Engine> HashGadget<E> for Poseidon {
+    type Hash = Num<E>;
+    type Params = ();
+
+    fn params<CS: ConstraintSystem<E>>(_cs: &mut CS) -> Result<(), SynthesisError> {
+        Ok(())
+    }
+
+    fn hash_leaf<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        _params: &(),
+        item: &[Byte<E>],
+    ) -> Result<Num<E>, SynthesisError> {
+        let mut input = vec![Num::zero(), Num::Constant(u64_to_fe(item.len() as u64))];
+        for chunk in item.chunks(LEAF_CHUNK_BYTES) {
+            input.push(num_from_be_bytes(cs, chunk)?);
+        }
+        circuit_poseidon_hash(cs, &input)
+    }
+
+    fn hash_node<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        _params: &(),
+        l: Num<E>,
+        r: Num<E>,
+    ) -> Result<Num<E>, SynthesisError> {
+        let sum = l.add(cs, &r)?;
+        let product = l.mul(cs, &r)?;
+        circuit_poseidon_hash(cs, &[Num::Constant(u64_to_fe(1)), sum, product])
+    }
+
+    fn equals<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Num<E>,
+        b: &Num<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        Num::equals(cs, a, b)
+    }
+
+    fn enforce_equal<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Num<E>,
+        b: &Num<E>,
+    ) -> Result<(), SynthesisError> {
+        a.enforce_equal(cs, b)
+    }
 }
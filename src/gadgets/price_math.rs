@@ -0,0 +1,621 @@
+//! Arithmetic on prices: normalization, cross rates and deviations.
+//!
+//! A cross rate derives a pair no oracle publishes from two pairs sharing their quote currency, e.g.
+//! `ETH/BTC = (ETH/USD) / (BTC/USD)`. For prices `p_a * 10^e_a` and `p_b * 10^e_b`, the rate with
+//! exponent `e` is `p_a * 10^(e_a - e_b - e) / p_b`. The power of ten multiplies the numerator or the
+//! divisor depending on its sign, so that the division, rounding down, is the only rounding.
+//!
+//! Normalization rescales a price to the 18 decimals of zkLink settlement in the same way, its only
+//! division rounding in the direction chosen by the caller.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            pairing::{
+                ff::{Field, PrimeField},
+                Engine,
+            },
+            plonk::better_better_cs::cs::ConstraintSystem,
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::{AllocatedNum, Num},
+            bigint_new::bigint::repr_to_biguint,
+            boolean::Boolean,
+        },
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::smart_and,
+        primitives::{uint256::UInt256, UInt32, UInt64},
+    },
+};
+use num_bigint::BigUint;
+
+use crate::{
+    pyth::PriceFeed,
+    utils::{num_from_be_bytes, uint256_from_bytes_with_mask},
+};
+
+/// Bytes of the remainder of [`div_floor`], bounding its operands.
+pub const LEN_OPERAND: usize = 22;
+/// Bits of the shifts of [`cross_rate`] and [`normalize`], which may move a price by at most `10^31`.
+const EXPONENT_SHIFT_BITS: usize = 5;
+pub const MAX_EXPONENT_SHIFT: u32 = (1 << EXPONENT_SHIFT_BITS) - 1;
+/// Decimals of the prices of [`normalize`].
+pub const NORMALIZED_DECIMALS: u32 = 18;
+/// Denominator of the tolerances of [`is_within_tolerance`].
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Price `price * 10^exponent`, as the big-endian `i64` and `i32` of pyth price feeds.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaledPrice<E: Engine> {
+    pub price: [Byte<E>; 8],
+    pub exponent: [Byte<E>; 4],
+}
+
+impl<E: Engine> From<&PriceFeed<E>> for ScaledPrice<E> {
+    fn from(price_feed: &PriceFeed<E>) -> Self {
+        Self {
+            price: price_feed.price,
+            exponent: price_feed.exponent,
+        }
+    }
+}
+
+/// The big-endian `i64` `price`, and whether it is not negative.
+fn unsigned_price<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &[Byte<E>; 8],
+) -> Result<(Boolean, Num<E>), SynthesisError> {
+    let mut bytes = *price;
+    bytes.reverse();
+    let price = UInt64::from_bytes_le(cs, &bytes)?.into_num();
+    let (_, is_negative) = prepacked_long_comparison(
+        cs,
+        &[price],
+        &[Num::Constant(u64_to_fe(i64::MAX as u64))],
+        &[64],
+    )?;
+    Ok((is_negative.not(), price))
+}
+
+impl<E: Engine> ScaledPrice<E> {
+    /// The signed exponent as a field element, a negative exponent being the negation of its absolute
+    /// value.
+    pub fn exponent<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Num<E>, SynthesisError> {
+        let mut bytes = self.exponent;
+        bytes.reverse();
+        let exponent = UInt32::from_bytes_le(cs, &bytes)?.into_num();
+        let (_, is_negative) = prepacked_long_comparison(
+            cs,
+            &[exponent],
+            &[Num::Constant(u64_to_fe(i32::MAX as u64))],
+            &[32],
+        )?;
+        // Two's complement: a negative exponent reads as itself plus 2^32.
+        let offset = Num::conditionally_select(
+            cs,
+            &is_negative,
+            &Num::Constant(u64_to_fe(1 << 32)),
+            &Num::zero(),
+        )?;
+        exponent.sub(cs, &offset)
+    }
+
+    fn exponent_witness(&self) -> Option<i32> {
+        Byte::get_byte_value_multiple(&self.exponent)
+            .map(|bytes| i32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+/// `numerator / divisor` rounded down, and whether it fits an `i64`.
+///
+/// The quotient and the remainder are witnessed and checked by `numerator = quotient * divisor +
+/// remainder` and `remainder < divisor`. Both operands must be below `2^(8 * LEN_OPERAND)`, so that
+/// nothing wraps around the field. When the quotient does not fit or the divisor is zero, the flag
+/// is false and the quotient zero.
+pub fn div_floor<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    numerator: &Num<E>,
+    divisor: &Num<E>,
+) -> Result<(Boolean, UInt64<E>), SynthesisError> {
+    let witness = match (numerator.get_value(), divisor.get_value()) {
+        (Some(numerator), Some(divisor)) => {
+            let numerator = repr_to_biguint::<E::Fr>(&numerator.into_repr());
+            let divisor = repr_to_biguint::<E::Fr>(&divisor.into_repr());
+            let quotient = if divisor == BigUint::default() {
+                None
+            } else {
+                u64::try_from(&numerator / &divisor)
+                    .ok()
+                    .filter(|quotient| *quotient <= i64::MAX as u64)
+            };
+            Some(match quotient {
+                Some(quotient) => (quotient, numerator % divisor),
+                None => (0, BigUint::default()),
+            })
+        }
+        _ => None,
+    };
+    let quotient = UInt64::alloc_from_witness(cs, witness.as_ref().map(|(q, _)| *q))?;
+    let remainder = {
+        let bytes = witness.map(|(_, remainder)| {
+            let remainder = remainder.to_bytes_be();
+            let mut bytes = [0u8; LEN_OPERAND];
+            bytes[LEN_OPERAND - remainder.len()..].copy_from_slice(&remainder);
+            bytes
+        });
+        let bytes: [Byte<E>; LEN_OPERAND] = CSAllocatable::alloc_from_witness(cs, bytes)?;
+        num_from_be_bytes(cs, &bytes)?
+    };
+
+    let (_, is_above_max) = prepacked_long_comparison(
+        cs,
+        &[quotient.inner],
+        &[Num::Constant(u64_to_fe(i64::MAX as u64))],
+        &[64],
+    )?;
+    let (_, is_above_remainder) =
+        prepacked_long_comparison(cs, &[*divisor], &[remainder], &[8 * LEN_OPERAND])?;
+    let recomposed = quotient.inner.mul(cs, divisor)?.add(cs, &remainder)?;
+    let is_recomposed = Num::equals(cs, &recomposed, numerator)?;
+    let is_ok = smart_and(cs, &[is_above_max.not(), is_above_remainder, is_recomposed])?;
+    Ok((is_ok, quotient))
+}
+
+/// `10^shift`, with `shift` below `2^EXPONENT_SHIFT_BITS`.
+fn pow10<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    shift: Option<u32>,
+) -> Result<(Num<E>, Num<E>), SynthesisError> {
+    let shift = Num::alloc(cs, shift.map(|shift| u64_to_fe(shift as u64)))?;
+    let mut bits = shift.into_bits_le(cs, Some(EXPONENT_SHIFT_BITS))?;
+    bits.reverse();
+    let ten = AllocatedNum::alloc_cnst(cs, u64_to_fe(10))?;
+    Ok((shift, Num::Variable(AllocatedNum::pow(cs, &ten, &bits)?)))
+}
+
+/// `10^up` and `10^down` for `shift = up - down`, one of them being zero and the other at most
+/// [`MAX_EXPONENT_SHIFT`], and whether `shift` can be split so.
+fn split_shift<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    shift: &Num<E>,
+    witness: Option<i64>,
+) -> Result<(Boolean, Num<E>, Num<E>), SynthesisError> {
+    let witness = witness.map(|shift| match u32::try_from(shift.unsigned_abs()) {
+        Ok(abs) if abs <= MAX_EXPONENT_SHIFT && shift >= 0 => (abs, 0),
+        Ok(abs) if abs <= MAX_EXPONENT_SHIFT => (0, abs),
+        _ => (0, 0),
+    });
+    let (up, up_scale) = pow10(cs, witness.map(|(up, _)| up))?;
+    let (down, down_scale) = pow10(cs, witness.map(|(_, down)| down))?;
+    let is_split = {
+        let split = up.sub(cs, &down)?;
+        Num::equals(cs, &split, shift)?
+    };
+    let is_one_zero = {
+        let product = up.mul(cs, &down)?;
+        Num::equals(cs, &product, &Num::zero())?
+    };
+    let is_ok = Boolean::and(cs, &is_split, &is_one_zero)?;
+    Ok((is_ok, up_scale, down_scale))
+}
+
+/// Price of `base` in units of `quote` with `exponent`, rounded down, and whether it can be
+/// computed: both prices are not negative, `quote` is not zero, the exponents are at most
+/// [`MAX_EXPONENT_SHIFT`] apart from the result and the result fits an `i64`.
+pub fn cross_rate<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    base: &ScaledPrice<E>,
+    quote: &ScaledPrice<E>,
+    exponent: i32,
+) -> Result<(Boolean, UInt64<E>), SynthesisError> {
+    let (is_base_ok, base_price) = unsigned_price(cs, &base.price)?;
+    let (is_quote_ok, quote_price) = unsigned_price(cs, &quote.price)?;
+
+    // shift = e_a - e_b - e, split into the shifts of the numerator and of the divisor.
+    let shift = base
+        .exponent(cs)?
+        .sub(cs, &quote.exponent(cs)?)?
+        .sub(cs, &Num::Constant(i64_to_fe::<E>(exponent as i64)))?;
+    let shift_witness = base
+        .exponent_witness()
+        .zip(quote.exponent_witness())
+        .map(|(e_a, e_b)| e_a as i64 - e_b as i64 - exponent as i64);
+    let (is_shift_ok, numerator_scale, divisor_scale) = split_shift(cs, &shift, shift_witness)?;
+
+    let numerator = base_price.mul(cs, &numerator_scale)?;
+    let divisor = quote_price.mul(cs, &divisor_scale)?;
+    let (is_quotient_ok, quotient) = div_floor(cs, &numerator, &divisor)?;
+    let is_ok = smart_and(cs, &[is_base_ok, is_quote_ok, is_shift_ok, is_quotient_ok])?;
+    Ok((is_ok, quotient))
+}
+
+/// Direction in which [`normalize`] rounds prices with more decimals than
+/// [`NORMALIZED_DECIMALS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `price` with [`NORMALIZED_DECIMALS`], as expected by zkLink settlement, and whether it can be
+/// normalized: the price is not negative and its exponent at most [`MAX_EXPONENT_SHIFT`] away
+/// from `-NORMALIZED_DECIMALS`. A normalized price is below `2^63 * 10^MAX_EXPONENT_SHIFT`, so it
+/// never overflows its `UInt256`, which is zero when the price can not be normalized.
+pub fn normalize<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &ScaledPrice<E>,
+    rounding: Rounding,
+) -> Result<(Boolean, UInt256<E>), SynthesisError> {
+    let (is_ok, bytes) = rescale(cs, price, NORMALIZED_DECIMALS, rounding)?;
+    let mut padded = [Byte::zero(); 32];
+    padded[32 - LEN_OPERAND..].copy_from_slice(&bytes);
+    let normalized = uint256_from_bytes_with_mask(cs, &padded, &is_ok)?;
+    Ok((is_ok, normalized))
+}
+
+/// `price` with `decimals` rounded down, as the big-endian bytes of an `i64`, and whether it can be
+/// rescaled: as for [`normalize`], and the result fits an `i64`. Used to commit prices of any
+/// exponent with the same number of decimals.
+pub fn rescale_to_i64<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &ScaledPrice<E>,
+    decimals: u32,
+) -> Result<(Boolean, [Byte<E>; 8]), SynthesisError> {
+    let (is_rescaled, bytes) = rescale(cs, price, decimals, Rounding::Down)?;
+    let (high, low) = bytes.split_at(LEN_OPERAND - 8);
+    let high = num_from_be_bytes(cs, high)?;
+    let is_high_zero = Num::equals(cs, &high, &Num::zero())?;
+    let low: [Byte<E>; 8] = low.try_into().expect("8 low bytes");
+    let (is_not_negative, _) = unsigned_price(cs, &low)?;
+    let is_ok = smart_and(cs, &[is_rescaled, is_high_zero, is_not_negative])?;
+    Ok((is_ok, low))
+}
+
+/// `price` with `decimals` as [`LEN_OPERAND`] big-endian bytes, and whether it can be rescaled.
+fn rescale<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &ScaledPrice<E>,
+    decimals: u32,
+    rounding: Rounding,
+) -> Result<(Boolean, [Byte<E>; LEN_OPERAND]), SynthesisError> {
+    let (is_price_ok, unsigned) = unsigned_price(cs, &price.price)?;
+    let shift = price
+        .exponent(cs)?
+        .add(cs, &Num::Constant(u64_to_fe(decimals as u64)))?;
+    let shift_witness = price
+        .exponent_witness()
+        .map(|exponent| exponent as i64 + decimals as i64);
+    let (is_shift_ok, up_scale, down_scale) = split_shift(cs, &shift, shift_witness)?;
+
+    // The quotient is at most the price, so it always fits.
+    let (is_quotient_ok, quotient) = div_floor(cs, &unsigned, &down_scale)?;
+    let quotient = match rounding {
+        Rounding::Down => quotient.inner,
+        Rounding::Up => {
+            let truncated = quotient.inner.mul(cs, &down_scale)?;
+            let is_exact = Num::equals(cs, &truncated, &unsigned)?;
+            let carry = Num::conditionally_select(cs, &is_exact, &Num::zero(), &Num::one())?;
+            quotient.inner.add(cs, &carry)?
+        }
+    };
+    let normalized = quotient.mul(cs, &up_scale)?;
+
+    let bytes: [Byte<E>; LEN_OPERAND] = {
+        let witness = normalized.get_value().map(|value| {
+            let value = repr_to_biguint::<E::Fr>(&value.into_repr()).to_bytes_be();
+            let mut bytes = [0u8; LEN_OPERAND];
+            if value.len() <= LEN_OPERAND {
+                bytes[LEN_OPERAND - value.len()..].copy_from_slice(&value);
+            }
+            bytes
+        });
+        CSAllocatable::alloc_from_witness(cs, witness)?
+    };
+    let is_recomposed = {
+        let recomposed = num_from_be_bytes(cs, &bytes)?;
+        Num::equals(cs, &recomposed, &normalized)?
+    };
+    let is_ok = smart_and(
+        cs,
+        &[is_price_ok, is_shift_ok, is_quotient_ok, is_recomposed],
+    )?;
+    Ok((is_ok, bytes))
+}
+
+/// Native [`normalize`].
+pub fn compute_normalized_price(price: i64, exponent: i32, rounding: Rounding) -> Option<BigUint> {
+    compute_rescaled_price(price, exponent, NORMALIZED_DECIMALS, rounding)
+}
+
+/// Native [`rescale_to_i64`].
+pub fn compute_rescaled_i64(price: i64, exponent: i32, decimals: u32) -> Option<i64> {
+    i64::try_from(compute_rescaled_price(
+        price,
+        exponent,
+        decimals,
+        Rounding::Down,
+    )?)
+    .ok()
+}
+
+fn compute_rescaled_price(
+    price: i64,
+    exponent: i32,
+    decimals: u32,
+    rounding: Rounding,
+) -> Option<BigUint> {
+    let shift = exponent as i64 + decimals as i64;
+    if price < 0 || shift.unsigned_abs() > MAX_EXPONENT_SHIFT as u64 {
+        return None;
+    }
+    let scale = BigUint::from(10u32).pow(shift.unsigned_abs() as u32);
+    let price = BigUint::from(price as u64);
+    if shift >= 0 {
+        return Some(price * scale);
+    }
+    let quotient = &price / &scale;
+    match rounding {
+        Rounding::Up if quotient.clone() * &scale != price => Some(quotient + 1u32),
+        _ => Some(quotient),
+    }
+}
+
+/// Whether `price` deviates from `reference` by at most `tolerance_bps` basis points of
+/// `reference`, `|reference - price| * BASIS_POINTS <= tolerance_bps * reference`. Both prices must
+/// have the same exponent and not be negative. The tolerance is a constant, or a witness inputized
+/// by the caller.
+pub fn is_within_tolerance<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    reference: &[Byte<E>; 8],
+    price: &[Byte<E>; 8],
+    tolerance_bps: &UInt32<E>,
+) -> Result<Boolean, SynthesisError> {
+    let (is_reference_ok, reference) = unsigned_price(cs, reference)?;
+    let (is_price_ok, price) = unsigned_price(cs, price)?;
+    let (_, is_above) = prepacked_long_comparison(cs, &[price], &[reference], &[64])?;
+    let deviation = {
+        let above = price.sub(cs, &reference)?;
+        let below = reference.sub(cs, &price)?;
+        Num::conditionally_select(cs, &is_above, &above, &below)?
+    };
+    // Below 2^63 * 2^14 and 2^32 * 2^63.
+    let lhs = deviation.mul(cs, &Num::Constant(u64_to_fe(BASIS_POINTS)))?;
+    let rhs = tolerance_bps.inner.mul(cs, &reference)?;
+    let (_, is_beyond) = prepacked_long_comparison(cs, &[lhs], &[rhs], &[96])?;
+    smart_and(cs, &[is_reference_ok, is_price_ok, is_beyond.not()])
+}
+
+/// Native [`is_within_tolerance`].
+pub fn compute_is_within_tolerance(reference: i64, price: i64, tolerance_bps: u32) -> bool {
+    reference >= 0
+        && price >= 0
+        && reference.abs_diff(price) as u128 * BASIS_POINTS as u128
+            <= tolerance_bps as u128 * reference as u128
+}
+
+fn i64_to_fe<E: Engine>(value: i64) -> E::Fr {
+    let mut fe = u64_to_fe::<E::Fr>(value.unsigned_abs());
+    if value < 0 {
+        fe.negate();
+    }
+    fe
+}
+
+/// Native [`cross_rate`].
+pub fn compute_cross_rate(
+    (base_price, base_exponent): (i64, i32),
+    (quote_price, quote_exponent): (i64, i32),
+    exponent: i32,
+) -> Option<i64> {
+    if base_price < 0 || quote_price <= 0 {
+        return None;
+    }
+    let shift = base_exponent as i64 - quote_exponent as i64 - exponent as i64;
+    if shift.unsigned_abs() > MAX_EXPONENT_SHIFT as u64 {
+        return None;
+    }
+    let scale = BigUint::from(10u32).pow(shift.unsigned_abs() as u32);
+    let (mut numerator, mut divisor) = (
+        BigUint::from(base_price as u64),
+        BigUint::from(quote_price as u64),
+    );
+    if shift >= 0 {
+        numerator *= scale;
+    } else {
+        divisor *= scale;
+    }
+    i64::try_from(numerator / divisor).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        traits::CSAllocatable,
+        vm::primitives::UInt32,
+    };
+
+    use num_bigint::BigUint;
+
+    use super::{
+        compute_cross_rate, compute_is_within_tolerance, compute_normalized_price,
+        compute_rescaled_i64, cross_rate, is_within_tolerance, normalize, rescale_to_i64, Rounding,
+        ScaledPrice,
+    };
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_cross_rate() -> Result<(), SynthesisError> {
+        let eth = (300_012_345_678, -8);
+        let btc = (6_000_000_000_000, -8);
+        assert_eq!(compute_cross_rate(eth, btc, -8), Some(5_000_205));
+        // The same rate from a quote with another exponent.
+        assert_eq!(
+            compute_cross_rate(eth, (60_000_000_000, -6), -8),
+            Some(5_000_205)
+        );
+        assert_eq!(compute_cross_rate(eth, btc, -2), Some(5));
+        assert_eq!(compute_cross_rate(eth, btc, 2), Some(0));
+        assert_eq!(compute_cross_rate(eth, (0, -8), -8), None);
+        assert_eq!(compute_cross_rate((-1, -8), btc, -8), None);
+        assert_eq!(compute_cross_rate(eth, btc, -40), None);
+        assert_eq!(compute_cross_rate(btc, (1, -8), -18), None);
+
+        let cs = &mut create_test_constraint_system()?;
+        let cases = [
+            (eth, btc, -8),
+            (eth, (60_000_000_000, -6), -8),
+            (eth, btc, -2),
+            (eth, btc, 2),
+            (btc, eth, -12),
+            (eth, (0, -8), -8),
+            ((-1, -8), btc, -8),
+            (eth, btc, -40),
+            (btc, (1, -8), -18),
+        ];
+        for (base, quote, exponent) in cases {
+            let [base_alloc, quote_alloc] =
+                [base, quote].map(|(price, expo): (i64, i32)| ScaledPrice::<Bn256> {
+                    price: CSAllocatable::alloc_from_witness(cs, Some(price.to_be_bytes()))
+                        .unwrap(),
+                    exponent: CSAllocatable::alloc_from_witness(cs, Some(expo.to_be_bytes()))
+                        .unwrap(),
+                });
+            let (is_ok, rate) = cross_rate(cs, &base_alloc, &quote_alloc, exponent)?;
+            let expected = compute_cross_rate(base, quote, exponent);
+            assert_eq!(is_ok.get_value(), Some(expected.is_some()));
+            if let Some(expected) = expected {
+                assert_eq!(rate.get_value(), Some(expected as u64));
+            }
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize() -> Result<(), SynthesisError> {
+        let cases = [
+            (123_456_789, -8, Rounding::Down),
+            (5, -20, Rounding::Down),
+            (5, -20, Rounding::Up),
+            (150, -20, Rounding::Down),
+            (150, -20, Rounding::Up),
+            (200, -20, Rounding::Up),
+            (7, 3, Rounding::Down),
+            (i64::MAX, 13, Rounding::Up),
+            (i64::MAX, -49, Rounding::Up),
+            (7, 14, Rounding::Down),
+            (7, -50, Rounding::Up),
+            (-1, -8, Rounding::Down),
+        ];
+        assert_eq!(
+            compute_normalized_price(123_456_789, -8, Rounding::Down),
+            Some(BigUint::from(1_234_567_890_000_000_000u64))
+        );
+        assert_eq!(
+            compute_normalized_price(150, -20, Rounding::Up),
+            Some(BigUint::from(2u32))
+        );
+        assert_eq!(compute_normalized_price(7, 14, Rounding::Down), None);
+
+        let cs = &mut create_test_constraint_system()?;
+        for (price, exponent, rounding) in cases {
+            let scaled = ScaledPrice::<Bn256> {
+                price: CSAllocatable::alloc_from_witness(cs, Some(price.to_be_bytes()))?,
+                exponent: CSAllocatable::alloc_from_witness(cs, Some(exponent.to_be_bytes()))?,
+            };
+            let (is_ok, normalized) = normalize(cs, &scaled, rounding)?;
+            let expected = compute_normalized_price(price, exponent, rounding);
+            assert_eq!(is_ok.get_value(), Some(expected.is_some()));
+            assert_eq!(normalized.get_value(), Some(expected.unwrap_or_default()));
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescale_to_i64() -> Result<(), SynthesisError> {
+        let cases = [
+            (123_456_789, -8),
+            (123_456_789, -10),
+            (123_456_789, -5),
+            (7, 3),
+            // Overflows the i64 once scaled.
+            (i64::MAX, -7),
+            (-1, -8),
+        ];
+        assert_eq!(compute_rescaled_i64(123_456_789, -10, 8), Some(1_234_567));
+        assert_eq!(
+            compute_rescaled_i64(123_456_789, -5, 8),
+            Some(123_456_789_000)
+        );
+        assert_eq!(compute_rescaled_i64(i64::MAX, -7, 8), None);
+
+        let cs = &mut create_test_constraint_system()?;
+        for (price, exponent) in cases {
+            let scaled = ScaledPrice::<Bn256> {
+                price: CSAllocatable::alloc_from_witness(cs, Some(price.to_be_bytes()))?,
+                exponent: CSAllocatable::alloc_from_witness(cs, Some(exponent.to_be_bytes()))?,
+            };
+            let (is_ok, rescaled) = rescale_to_i64(cs, &scaled, 8)?;
+            let expected = compute_rescaled_i64(price, exponent, 8);
+            assert_eq!(is_ok.get_value(), Some(expected.is_some()));
+            if let Some(expected) = expected {
+                assert_eq!(
+                    Byte::get_byte_value_multiple(&rescaled),
+                    Some(expected.to_be_bytes().to_vec())
+                );
+            }
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_within_tolerance() -> Result<(), SynthesisError> {
+        // 1% of a reference of 2000.
+        assert!(compute_is_within_tolerance(2_000, 2_020, 100));
+        assert!(compute_is_within_tolerance(2_000, 1_980, 100));
+        assert!(!compute_is_within_tolerance(2_000, 2_021, 100));
+        assert!(!compute_is_within_tolerance(2_000, 1_979, 100));
+        assert!(!compute_is_within_tolerance(-2_000, -2_000, 100));
+
+        let cs = &mut create_test_constraint_system()?;
+        let tolerance = UInt32::alloc_from_witness(cs, Some(100))?;
+        let cases = [
+            (2_000, 2_020),
+            (2_000, 1_980),
+            (2_000, 2_021),
+            (2_000, 1_979),
+            (0, 0),
+            (0, 1),
+            (i64::MAX, 0),
+            (i64::MAX, i64::MAX - 1),
+            (-2_000, -2_000),
+            (2_000, -2_000),
+        ];
+        for (reference, price) in cases {
+            let [reference_alloc, price_alloc] = [reference, price].map(|price: i64| {
+                CSAllocatable::alloc_from_witness(cs, Some(price.to_be_bytes())).unwrap()
+            });
+            let is_ok =
+                is_within_tolerance::<Bn256, _>(cs, &reference_alloc, &price_alloc, &tolerance)?;
+            assert_eq!(
+                is_ok.get_value(),
+                Some(compute_is_within_tolerance(reference, price, 100)),
+                "{} and {}",
+                reference,
+                price
+            );
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
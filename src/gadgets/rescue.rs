@@ -1,16 +1,36 @@
 use crate::franklin_crypto::bellman::plonk::better_better_cs::cs::ConstraintSystem;
 use crate::franklin_crypto::bellman::SynthesisError;
 use crate::franklin_crypto::plonk::circuit::allocated_num::Num;
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::rescue_poseidon::{
-    CircuitGenericSponge, CustomGate, HashParams, RescueParams,
+    CircuitGenericSponge, CustomGate, GenericSponge, HashParams, RescueParams,
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+pub const RATE: usize = 2;
+const WIDTH: usize = 3;
+
+fn rescue_params<E: Engine>() -> RescueParams<E, RATE, WIDTH> {
+    let mut params = RescueParams::specialized_for_num_rounds(5, 100); // Alignment with bn254_rescue_params function
+    params.use_custom_gate(CustomGate::QuinticWidth4); // Above
+    params
+}
 
 pub fn circuit_rescue_hash<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     input: &[Num<E>],
 ) -> Result<Num<E>, SynthesisError> {
-    let mut params = RescueParams::specialized_for_num_rounds(5, 100); // Alignment with bn254_rescue_params function
-    params.use_custom_gate(CustomGate::QuinticWidth4); // Above
-    Ok(CircuitGenericSponge::<E, 2, 3>::hash_num(cs, input, &params, None)?[0])
+    Ok(circuit_rescue_sponge(cs, input)?[0])
+}
+
+/// Whole squeezed rate of the sponge, of which [`circuit_rescue_hash`] keeps the first element.
+pub fn circuit_rescue_sponge<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    input: &[Num<E>],
+) -> Result<[Num<E>; RATE], SynthesisError> {
+    CircuitGenericSponge::<E, RATE, WIDTH>::hash_num(cs, input, &rescue_params(), None)
+}
+
+/// Native [`circuit_rescue_sponge`].
+pub fn rescue_sponge<E: Engine>(input: &[E::Fr]) -> [E::Fr; RATE] {
+    GenericSponge::hash(input, &rescue_params::<E>(), None)
 }
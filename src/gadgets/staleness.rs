@@ -0,0 +1,132 @@
+//! Freshness of publish times against a reference timestamp.
+//!
+//! The reference, typically the time at which the proof is consumed, is a public input: the consumer
+//! only compares it with its own clock, while the proof guarantees that every price was published at
+//! most `max_age` seconds before it. As in [`crate::pyth::timestamp::is_fresh`], a price published
+//! after the reference is fresh.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::primitives::UInt64,
+};
+
+use crate::utils::new_synthesis_error;
+
+/// Reference timestamp, in seconds, inputized by the circuit.
+#[derive(Debug, Clone, Copy)]
+pub struct ReferenceTimestamp<E: Engine> {
+    pub timestamp: UInt64<E>,
+}
+
+impl<E: Engine> ReferenceTimestamp<E> {
+    /// Allocate the reference timestamp and inputize it, `None` for setup passes.
+    pub fn inputize<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<u64>,
+    ) -> Result<Self, SynthesisError> {
+        let timestamp = UInt64::alloc_from_witness(cs, witness)?;
+        timestamp.inner.get_variable().inputize(cs)?;
+        Ok(Self { timestamp })
+    }
+
+    /// Whether the big-endian `i64` `publish_time` is at least `reference - max_age`, checked as
+    /// `publish_time + max_age >= reference` so that nothing underflows. Negative publish times are
+    /// never fresh.
+    pub fn is_fresh<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        publish_time: &[Byte<E>; 8],
+        max_age: u64,
+    ) -> Result<Boolean, SynthesisError> {
+        if max_age > i64::MAX as u64 {
+            return Err(new_synthesis_error(format!(
+                "max age {} overflows an i64",
+                max_age
+            )));
+        }
+        let publish_time = {
+            let mut bytes = *publish_time;
+            bytes.reverse();
+            UInt64::from_bytes_le(cs, &bytes)?.into_num()
+        };
+        let (_, is_negative) = prepacked_long_comparison(
+            cs,
+            &[publish_time],
+            &[Num::Constant(u64_to_fe(i64::MAX as u64))],
+            &[64],
+        )?;
+        // A negative publish time is replaced by zero so that the sum of two terms below 2^63 fits
+        // the 64-bit comparison, the result being false anyway.
+        let publish_time =
+            Num::conditionally_select(cs, &is_negative, &Num::zero(), &publish_time)?;
+        let deadline = publish_time.add(cs, &Num::Constant(u64_to_fe(max_age)))?;
+        let (_, is_stale) =
+            prepacked_long_comparison(cs, &[self.timestamp.inner], &[deadline], &[64])?;
+        Boolean::and(cs, &is_negative.not(), &is_stale.not())
+    }
+
+    /// Enforce [`ReferenceTimestamp::is_fresh`] for every publish time.
+    pub fn enforce_fresh<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        publish_times: &[[Byte<E>; 8]],
+        max_age: u64,
+    ) -> Result<(), SynthesisError> {
+        for publish_time in publish_times.iter() {
+            let is_fresh = self.is_fresh(cs, publish_time, max_age)?;
+            Boolean::enforce_equal(cs, &is_fresh, &Boolean::constant(true))?;
+        }
+        Ok(())
+    }
+}
+
+/// Native [`ReferenceTimestamp::is_fresh`].
+pub fn is_fresh(reference: u64, publish_time: i64, max_age: u64) -> bool {
+    publish_time >= 0 && (publish_time as u64).saturating_add(max_age) >= reference
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        traits::CSAllocatable,
+    };
+
+    use super::{is_fresh, ReferenceTimestamp};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_is_fresh() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let reference = ReferenceTimestamp::<Bn256>::inputize(cs, Some(1_000))?;
+        for publish_time in [940i64, 939, 1_000, 2_000, 0, -1, i64::MIN] {
+            let bytes: [Byte<Bn256>; 8] =
+                CSAllocatable::alloc_from_witness(cs, Some(publish_time.to_be_bytes()))?;
+            assert_eq!(
+                reference.is_fresh(cs, &bytes, 60)?.get_value(),
+                Some(is_fresh(1_000, publish_time, 60)),
+                "publish time {}",
+                publish_time
+            );
+        }
+        assert!(is_fresh(1_000, 940, 60));
+        assert!(!is_fresh(1_000, 939, 60));
+        assert!(!is_fresh(0, -1, 60));
+        assert!(cs.is_satisfied());
+
+        let stale: [Byte<Bn256>; 8] =
+            CSAllocatable::alloc_from_witness(cs, Some(939i64.to_be_bytes()))?;
+        reference.enforce_fresh(cs, &[stale], 60)?;
+        assert!(!cs.is_satisfied());
+        assert!(reference.is_fresh(cs, &stale, u64::MAX).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! gRPC interface of the prover service (`proto/prover.proto`), built with the `grpc` feature.
+//!
+//! The generated code requires `protoc` at build time.
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+use crate::{
+    pyth::{OracleWitness, GUARDIAN_SET},
+    utils::fr_to_be_bytes,
+    witness::PublicInputData,
+};
+
+pub mod proto {
+    tonic::include_proto!("zklink.oracle.v1");
+}
+
+pub use proto::{
+    prover_client::ProverClient,
+    prover_server::{Prover, ProverServer},
+    GetProofRequest, ProofResponse, ProofStatus, PublicInput, StreamProofsRequest,
+    SubmitUpdateRequest, SubmitUpdateResponse,
+};
+
+impl TryFrom<&OracleWitness> for SubmitUpdateRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(witness: &OracleWitness) -> Result<Self, Self::Error> {
+        Ok(Self {
+            accumulator_updates: witness
+                .accumulator_updates
+                .iter()
+                .map(|update| update.to_bytes())
+                .collect::<Result<_, _>>()?,
+            guardian_set: witness.guardian_set.iter().map(|g| g.to_vec()).collect(),
+            num_signatures: witness.num_signature_to_verify as u32,
+        })
+    }
+}
+
+impl TryFrom<&SubmitUpdateRequest> for OracleWitness {
+    type Error = anyhow::Error;
+
+    fn try_from(request: &SubmitUpdateRequest) -> Result<Self, Self::Error> {
+        let data = request
+            .accumulator_updates
+            .iter()
+            .map(|bytes| pythnet_sdk::wire::v1::AccumulatorUpdateData::try_from_slice(bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set = if request.guardian_set.is_empty() {
+            GUARDIAN_SET.to_vec()
+        } else {
+            request
+                .guardian_set
+                .iter()
+                .map(|g| {
+                    g.as_slice()
+                        .try_into()
+                        .map_err(|_| anyhow::anyhow!("invalid guardian address length {}", g.len()))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok(OracleWitness::new(
+            &data,
+            guardian_set,
+            request.num_signatures as usize,
+        ))
+    }
+}
+
+impl PublicInput {
+    pub fn new<E: Engine>(public_input_data: &PublicInputData<E>, commitment: &E::Fr) -> Self {
+        let prices_summarize = &public_input_data.prices_summarize;
+        Self {
+            guardian_set_hash: fr_to_be_bytes(&public_input_data.guardian_set_hash),
+            earliest_publish_time: fr_to_be_bytes(&public_input_data.earliest_publish_time),
+            prices_commitment: fr_to_be_bytes(&prices_summarize.commitment),
+            prices_num: fr_to_be_bytes(&prices_summarize.num),
+            prices_commitment_base_sum: fr_to_be_bytes(&prices_summarize.commitment_base_sum),
+            commitment: fr_to_be_bytes(commitment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::{PublicInput, SubmitUpdateRequest};
+    use crate::pyth::{OracleWitness, PriceOracle};
+
+    #[test]
+    fn test_submit_update_request() -> anyhow::Result<()> {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(2, 1);
+        let witness = OracleWitness::from(&oracle);
+        let request = SubmitUpdateRequest::try_from(&witness)?;
+        assert_eq!(request.accumulator_updates.len(), 2);
+        assert_eq!(OracleWitness::try_from(&request)?, witness);
+
+        let public_input = PublicInput::new(&oracle.public_input_data, &oracle.commitment);
+        assert_eq!(public_input.commitment.len(), 32);
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+use super::{batch, HermesClient};
+use crate::{
+    config::OracleConfig,
+    pyth::{compute_public_input, AccumulatorUpdateWitness, OracleWitness},
+};
+
+pub const BENCHMARKS_ENDPOINT: &str = "https://benchmarks.pyth.network";
+
+/// Validated witness bundles of the prices at one point of a backfilled range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillInterval {
+    pub timestamp: i64,
+    pub witnesses: Vec<OracleWitness>,
+}
+
+/// Fetch the configured feeds every `interval` seconds from `from` to `to` (inclusive) from a
+/// [Benchmarks](https://benchmarks.pyth.network/docs) `client`, batching them into witnesses of the
+/// configured geometry.
+///
+/// Each witness runs through the native checks of the circuit, so any interval that returns can be
+/// proven. `on_interval` is called as soon as an interval is ready, e.g. to write it out.
+pub async fn backfill<F: FnMut(BackfillInterval) -> Result<(), anyhow::Error>>(
+    client: &HermesClient,
+    config: &OracleConfig,
+    from: i64,
+    to: i64,
+    interval: u64,
+    mut on_interval: F,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(interval > 0, "interval must be positive");
+    anyhow::ensure!(from <= to, "empty range {} to {}", from, to);
+    let geometry = config.geometry()?;
+    let guardian_set = config.guardian_set_source().load()?.addresses;
+    let groups = config
+        .feed_ids()
+        .chunks(geometry.num_prices)
+        .map(|c| c.to_vec())
+        .collect::<Vec<_>>();
+    for timestamp in (from..=to).step_by(interval as usize) {
+        let mut updates = vec![];
+        for group in groups.iter() {
+            for data in client.benchmarks_updates_at(group, timestamp).await? {
+                let update = AccumulatorUpdateWitness::from_accumulator_update_data(&data);
+                config.check_update(&update, timestamp)?;
+                updates.push(update);
+            }
+        }
+        let witnesses = batch(updates, &geometry, &guardian_set)?;
+        for witness in witnesses.iter() {
+            compute_public_input::<Bn256>(
+                &witness.accumulator_update_data()?,
+                &witness.guardian_set,
+                witness.num_signature_to_verify,
+                geometry.num_prices,
+            )?;
+        }
+        tracing::info!(
+            timestamp,
+            witnesses = witnesses.len(),
+            "backfill interval ready"
+        );
+        on_interval(BackfillInterval {
+            timestamp,
+            witnesses,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backfill;
+    use crate::{
+        config::OracleConfig,
+        hermes::{HermesClient, RetryPolicy},
+    };
+
+    #[tokio::test]
+    async fn test_backfill_requests() -> anyhow::Result<()> {
+        let config = OracleConfig::from_toml(
+            "[circuit]\nnum_vaas = 1\nnum_prices = 1\n\n[[feeds]]\nid = \"e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43\"\n",
+        )
+        .unwrap();
+        // Nothing listens on port 9 of localhost.
+        let client =
+            HermesClient::new(vec!["http://127.0.0.1:9".to_string()], RetryPolicy::none())?;
+        let err = backfill(&client, &config, 100, 200, 0, |_| Ok(()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("interval"));
+        let err = backfill(&client, &config, 100, 200, 60, |_| Ok(()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/v1/updates/price/100"));
+        Ok(())
+    }
+}
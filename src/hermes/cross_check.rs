@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use pythnet_sdk::{messages::Message, wire::from_slice};
+
+use super::HermesClient;
+use crate::pyth::AccumulatorUpdateWitness;
+
+/// Re-fetch the prices of `updates` from `reference` at the same publish times and check both
+/// sources agree, before spending prover time on data of a single source.
+pub async fn cross_check(
+    reference: &HermesClient,
+    updates: &[AccumulatorUpdateWitness],
+) -> Result<(), anyhow::Error> {
+    for (i, update) in updates.iter().enumerate() {
+        // Feeds of an update may have been published at different times.
+        let mut ids_by_publish_time = BTreeMap::<i64, Vec<String>>::new();
+        for (feed_id, (publish_time, _)) in price_messages(update)? {
+            ids_by_publish_time
+                .entry(publish_time)
+                .or_default()
+                .push(hex::encode(feed_id));
+        }
+        let mut reference_updates = vec![];
+        for (publish_time, ids) in ids_by_publish_time {
+            for data in reference.updates_at(&ids, publish_time).await? {
+                reference_updates.push(AccumulatorUpdateWitness::from_accumulator_update_data(
+                    &data,
+                ));
+            }
+        }
+        compare(update, &reference_updates)
+            .map_err(|e| anyhow::anyhow!("accumulator update {}: {}", i, e))?;
+    }
+    Ok(())
+}
+
+/// Check every price of `update` is found unchanged in `reference`.
+pub fn compare(
+    update: &AccumulatorUpdateWitness,
+    reference: &[AccumulatorUpdateWitness],
+) -> Result<(), anyhow::Error> {
+    let mut reference_messages = BTreeMap::new();
+    for reference in reference {
+        reference_messages.extend(price_messages(reference)?);
+    }
+    for (feed_id, (publish_time, message)) in price_messages(update)? {
+        match reference_messages.get(&feed_id) {
+            Some((_, reference)) if reference == &message => {}
+            Some((reference_publish_time, _)) => anyhow::bail!(
+                "price of {} published at {} differs from the reference (published at {})",
+                hex::encode(feed_id),
+                publish_time,
+                reference_publish_time
+            ),
+            None => anyhow::bail!(
+                "price of {} published at {} is missing from the reference",
+                hex::encode(feed_id),
+                publish_time
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Price feed messages of an update by feed id, along with their publish time.
+fn price_messages(
+    update: &AccumulatorUpdateWitness,
+) -> Result<BTreeMap<[u8; 32], (i64, Vec<u8>)>, anyhow::Error> {
+    update
+        .updates
+        .iter()
+        .map(
+            |price_update| match from_slice::<byteorder::BE, Message>(&price_update.message)? {
+                Message::PriceFeedMessage(p) => {
+                    Ok((p.feed_id, (p.publish_time, price_update.message.clone())))
+                }
+                _ => anyhow::bail!("invalid price feed message"),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare;
+    use crate::testing::fixtures::{AccumulatorFixture, FixturePrice};
+
+    #[test]
+    fn test_compare() {
+        let prices = vec![
+            FixturePrice::new([1; 32], 100, -8, 10),
+            FixturePrice::new([2; 32], 200, -8, 10),
+        ];
+        let update = AccumulatorFixture::with_prices(prices.clone()).witness();
+        // The reference may split feeds across updates of another slot.
+        let reference = prices
+            .iter()
+            .map(|p| {
+                AccumulatorFixture {
+                    slot: 7,
+                    ..AccumulatorFixture::with_prices(vec![*p])
+                }
+                .witness()
+            })
+            .collect::<Vec<_>>();
+        compare(&update, &reference).unwrap();
+
+        let mut tampered = prices.clone();
+        tampered[1].price += 1;
+        let reference = vec![AccumulatorFixture::with_prices(tampered).witness()];
+        assert!(compare(&update, &reference)
+            .unwrap_err()
+            .to_string()
+            .contains("differs"));
+        assert!(compare(&update, &reference[..0])
+            .unwrap_err()
+            .to_string()
+            .contains("missing"));
+    }
+}
@@ -0,0 +1,272 @@
+//! Client of the pyth [Hermes](https://hermes.pyth.network/docs) price service.
+//!
+//! Requests go to a prioritized list of endpoints. A transient failure (connection error, timeout,
+//! `429` or `5xx`) moves on to the next endpoint, and once every endpoint failed the client waits
+//! according to its [`RetryPolicy`] before starting another round. Other failures are returned
+//! immediately since another instance would answer the same.
+//!
+//! Responses are matched against the schemas of every known API version (see
+//! [`PriceUpdatesResponse`]), and a route removed from an endpoint falls back to its successor, so
+//! schema changes surface as a [`ResponseError`] instead of an opaque decoding failure.
+//!
+//! [`WormholescanClient`] fetches signed VAAs by sequence, as a fallback when Hermes serves the VAA
+//! of a specific sequence with too few signatures, see [`Scheduler::with_vaa_fallback`].
+
+mod backfill;
+mod cross_check;
+mod response;
+mod retry;
+mod scheduler;
+mod wormholescan;
+
+pub use backfill::*;
+pub use cross_check::*;
+pub use response::*;
+pub use retry::RetryPolicy;
+pub use scheduler::*;
+pub use wormholescan::*;
+
+use anyhow::Context as _;
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::pyth::{AccumulatorUpdateWitness, OracleWitness};
+
+pub const MAINNET_ENDPOINT: &str = "https://hermes.pyth.network";
+
+#[derive(Debug, Clone)]
+pub struct HermesClient {
+    endpoints: Vec<String>,
+    retry: RetryPolicy,
+    http: reqwest::Client,
+}
+
+impl HermesClient {
+    /// `endpoints` are tried in order, the first one is preferred.
+    pub fn new(endpoints: Vec<String>, retry: RetryPolicy) -> Result<Self, anyhow::Error> {
+        if endpoints.is_empty() {
+            anyhow::bail!("at least one endpoint is required")
+        }
+        let endpoints = endpoints
+            .into_iter()
+            .map(|e| e.trim_end_matches('/').to_string())
+            .collect();
+        let http = reqwest::Client::builder()
+            .timeout(retry.request_timeout)
+            .build()
+            .context("failed to build http client")?;
+        Ok(Self {
+            endpoints,
+            retry,
+            http,
+        })
+    }
+
+    /// Client of the public mainnet endpoint with the default retry policy.
+    pub fn mainnet() -> Result<Self, anyhow::Error> {
+        Self::new(vec![MAINNET_ENDPOINT.to_string()], RetryPolicy::default())
+    }
+
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// `GET` `path` with `query` and decode the JSON response.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, anyhow::Error> {
+        let mut errors = vec![];
+        for round in 0..self.retry.max_rounds.max(1) {
+            tokio::time::sleep(self.retry.backoff(round)).await;
+            for endpoint in self.endpoints.iter() {
+                let url = format!("{}{}", endpoint, path);
+                match self.http.get(&url).query(query).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        return response
+                            .json()
+                            .await
+                            .with_context(|| format!("invalid response from {}", url));
+                    }
+                    Ok(response) if is_transient_status(response.status()) => {
+                        errors.push(format!("{}: {}", url, response.status()));
+                    }
+                    Ok(response) => {
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        return Err(ResponseError::Status { url, status, body }.into());
+                    }
+                    Err(e) if is_transient_error(&e) => errors.push(format!("{}: {}", url, e)),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        anyhow::bail!("all hermes endpoints failed: {}", errors.join("; "))
+    }
+
+    /// Latest accumulator update containing `ids` (hex feed ids, with or without `0x`), from
+    /// [`/api/latest_vaas`](https://hermes.pyth.network/docs/#/rest/latest_vaas), or from
+    /// `/v2/updates/price/latest` on endpoints which dropped the former.
+    pub async fn latest_vaas(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AccumulatorUpdateData>, anyhow::Error> {
+        let query = ids
+            .iter()
+            .map(|id| ("ids[]", id.clone()))
+            .collect::<Vec<_>>();
+        match self.get::<Value>("/api/latest_vaas", &query).await {
+            Ok(value) => Ok(PriceUpdatesResponse::from_value(value)?.decode()?),
+            Err(e)
+                if e.downcast_ref::<ResponseError>()
+                    .is_some_and(ResponseError::is_route_gone) =>
+            {
+                tracing::warn!("{}, falling back to /v2/updates/price/latest", e);
+                self.price_updates("/v2/updates/price/latest", ids).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Latest accumulator updates containing `ids`, from
+    /// [`/v2/updates/price/latest`](https://hermes.pyth.network/docs/#/rest/latest_price_updates),
+    /// split into witnesses ready for the circuit.
+    pub async fn latest_price_updates(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AccumulatorUpdateWitness>, anyhow::Error> {
+        let updates = self.price_updates("/v2/updates/price/latest", ids).await?;
+        Ok(updates
+            .iter()
+            .map(AccumulatorUpdateWitness::from_accumulator_update_data)
+            .collect())
+    }
+
+    /// Witness of the circuit verifying the latest updates of `ids`.
+    pub async fn latest_witness(
+        &self,
+        ids: &[String],
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+    ) -> Result<OracleWitness, anyhow::Error> {
+        Ok(OracleWitness {
+            accumulator_updates: self.latest_price_updates(ids).await?,
+            guardian_set,
+            num_signature_to_verify,
+        })
+    }
+
+    /// Accumulator updates containing `ids` at `publish_time`, from
+    /// [`/v2/updates/price/{publish_time}`](https://hermes.pyth.network/docs/#/rest/timestamp_price_updates).
+    pub async fn updates_at(
+        &self,
+        ids: &[String],
+        publish_time: i64,
+    ) -> Result<Vec<AccumulatorUpdateData>, anyhow::Error> {
+        self.price_updates(&format!("/v2/updates/price/{}", publish_time), ids)
+            .await
+    }
+
+    /// Accumulator updates containing `ids` at `timestamp`, from the
+    /// [`/v1/updates/price/{timestamp}`](https://benchmarks.pyth.network/docs#/Updates/price_updates_timestamp_route_v1_updates_price__timestamp__get)
+    /// route of a client pointing to [Benchmarks](https://benchmarks.pyth.network), which serves
+    /// historical data.
+    pub async fn benchmarks_updates_at(
+        &self,
+        ids: &[String],
+        timestamp: i64,
+    ) -> Result<Vec<AccumulatorUpdateData>, anyhow::Error> {
+        self.price_updates(&format!("/v1/updates/price/{}", timestamp), ids)
+            .await
+    }
+
+    async fn price_updates(
+        &self,
+        path: &str,
+        ids: &[String],
+    ) -> Result<Vec<AccumulatorUpdateData>, anyhow::Error> {
+        let mut query = ids
+            .iter()
+            .map(|id| ("ids[]", id.clone()))
+            .collect::<Vec<_>>();
+        query.push(("encoding", "base64".to_string()));
+        query.push(("parsed", "false".to_string()));
+        let response: Value = self.get(path, &query).await?;
+        Ok(PriceUpdatesResponse::from_value(response)?.decode()?)
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn is_transient_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_request()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{HermesClient, RetryPolicy};
+
+    #[test]
+    fn test_new_requires_endpoint() {
+        assert!(HermesClient::new(vec![], RetryPolicy::none()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failover_exhausts_endpoints() -> anyhow::Result<()> {
+        // Nothing listens on port 9 of localhost.
+        let client = HermesClient::new(
+            vec![
+                "http://127.0.0.1:9".to_string(),
+                "http://127.0.0.1:9/".to_string(),
+            ],
+            RetryPolicy {
+                max_rounds: 2,
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(client.endpoints()[1], "http://127.0.0.1:9");
+        let err = client
+            .latest_vaas(&[
+                "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43".to_string(),
+            ])
+            .await
+            .unwrap_err();
+        // Two rounds over two endpoints.
+        assert_eq!(err.to_string().matches("/api/latest_vaas: ").count(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_latest_witness() -> anyhow::Result<()> {
+        use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+        use crate::testing::{fixtures::AccumulatorFixture, mock_server::MockServer};
+
+        let server = MockServer::start()?;
+        let fixture = AccumulatorFixture::default();
+        server.add_update(fixture.witness());
+        let client = HermesClient::new(vec![server.url()], RetryPolicy::none())?;
+        let ids = [hex::encode(fixture.prices[0].feed_id)];
+
+        let updates = client.latest_price_updates(&ids).await?;
+        assert_eq!(updates, vec![fixture.witness()]);
+        let witness = client
+            .latest_witness(&ids, fixture.guardian_set(), fixture.num_signatures)
+            .await?;
+        witness.to_price_oracle::<Bn256, 1>()?;
+        assert!(server.requests()[0].starts_with("/v2/updates/price/latest"));
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use base64::Engine as _;
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Failure of a Hermes request that another attempt would not fix.
+#[derive(Debug, thiserror::Error)]
+pub enum ResponseError {
+    #[error("{url}: {status} {body}")]
+    Status {
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("unknown response schema: {0}")]
+    UnknownSchema(String),
+    #[error("unsupported encoding {0}")]
+    UnsupportedEncoding(String),
+    #[error("invalid accumulator update {index}: {reason}")]
+    InvalidUpdate { index: usize, reason: String },
+}
+
+impl ResponseError {
+    /// Whether the route is gone from the endpoint, so a route of a newer API should be tried.
+    pub fn is_route_gone(&self) -> bool {
+        matches!(
+            self,
+            ResponseError::Status { status, .. }
+                if *status == StatusCode::NOT_FOUND || *status == StatusCode::GONE
+        )
+    }
+}
+
+/// Accumulator updates of a price update response, by API version.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum PriceUpdatesResponse {
+    /// `/v2/updates/price/*`: `{"binary": {"encoding": "base64", "data": [..]}, "parsed": [..]}`.
+    /// Benchmarks `/v1/updates/price/*` answers in the same schema.
+    V2 { binary: BinaryPriceUpdate },
+    /// `/api/latest_vaas`: `["<base64>", ..]`.
+    V1(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BinaryPriceUpdate {
+    /// Missing from early v2 responses, which were always base64.
+    #[serde(default)]
+    pub encoding: Option<String>,
+    pub data: Vec<String>,
+}
+
+impl PriceUpdatesResponse {
+    /// Recognize the schema of `value`, describing it if it matches no known version.
+    pub fn from_value(value: Value) -> Result<Self, ResponseError> {
+        let description = match &value {
+            Value::Object(fields) => format!(
+                "object with fields {}",
+                fields.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+            Value::Array(_) => "array of non-strings".to_string(),
+            other => other.to_string(),
+        };
+        serde_json::from_value(value).map_err(|_| ResponseError::UnknownSchema(description))
+    }
+
+    pub fn decode(&self) -> Result<Vec<AccumulatorUpdateData>, ResponseError> {
+        let (encoding, data) = match self {
+            PriceUpdatesResponse::V2 { binary } => (binary.encoding.as_deref(), &binary.data),
+            PriceUpdatesResponse::V1(data) => (Some("base64"), data),
+        };
+        data.iter()
+            .enumerate()
+            .map(|(index, data)| {
+                let invalid = |reason: String| ResponseError::InvalidUpdate { index, reason };
+                let bytes = match encoding {
+                    Some("base64") => base64::engine::general_purpose::STANDARD
+                        .decode(data)
+                        .map_err(|e| invalid(e.to_string()))?,
+                    Some("hex") => decode_hex(data).map_err(|e| invalid(e.to_string()))?,
+                    // Hex strings are also valid base64, so try hex first.
+                    None => decode_hex(data)
+                        .ok()
+                        .or_else(|| base64::engine::general_purpose::STANDARD.decode(data).ok())
+                        .ok_or_else(|| invalid("neither hex nor base64".to_string()))?,
+                    Some(other) => {
+                        return Err(ResponseError::UnsupportedEncoding(other.to_string()))
+                    }
+                };
+                AccumulatorUpdateData::try_from_slice(&bytes).map_err(|e| invalid(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(data.strip_prefix("0x").unwrap_or(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine as _;
+    use serde_json::json;
+
+    use super::{PriceUpdatesResponse, ResponseError};
+    use crate::pyth::DEFAULT_ACCUMULATOR_UPDATE_DATA;
+
+    #[test]
+    fn test_decode_versions() -> anyhow::Result<()> {
+        let bytes =
+            base64::engine::general_purpose::STANDARD.decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let hex = hex::encode(&bytes);
+        for value in [
+            json!([DEFAULT_ACCUMULATOR_UPDATE_DATA]),
+            json!({"binary": {"encoding": "base64", "data": [DEFAULT_ACCUMULATOR_UPDATE_DATA]}}),
+            json!({"binary": {"encoding": "hex", "data": [hex]}, "parsed": []}),
+            json!({"binary": {"data": [format!("0x{}", hex)]}}),
+            json!({"binary": {"data": [DEFAULT_ACCUMULATOR_UPDATE_DATA]}}),
+        ] {
+            let updates = PriceUpdatesResponse::from_value(value)?.decode()?;
+            assert_eq!(updates.len(), 1);
+        }
+
+        let err = PriceUpdatesResponse::from_value(json!({"updates": []})).unwrap_err();
+        assert!(matches!(err, ResponseError::UnknownSchema(s) if s.contains("updates")));
+        let err = PriceUpdatesResponse::from_value(
+            json!({"binary": {"encoding": "base58", "data": [hex]}}),
+        )?
+        .decode()
+        .unwrap_err();
+        assert!(matches!(err, ResponseError::UnsupportedEncoding(_)));
+        let err = PriceUpdatesResponse::from_value(json!(["AAAA"]))?
+            .decode()
+            .unwrap_err();
+        assert!(matches!(err, ResponseError::InvalidUpdate { index: 0, .. }));
+        Ok(())
+    }
+}
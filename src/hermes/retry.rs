@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Exponential backoff between rounds of requests over all endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Number of rounds over the endpoint list before giving up, at least 1.
+    pub max_rounds: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Timeout of a single request.
+    pub request_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retry, each endpoint is tried once.
+    pub fn none() -> Self {
+        Self {
+            max_rounds: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before round `round` (0-based), the first round starts immediately.
+    pub fn backoff(&self, round: usize) -> Duration {
+        if round == 0 {
+            return Duration::ZERO;
+        }
+        let factor = self.multiplier.powi(round as i32 - 1);
+        self.initial_backoff.mul_f64(factor).min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_rounds: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            multiplier: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::ZERO);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(10), Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::BTreeSet,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::time::Instant;
+
+use super::{cross_check, HermesClient, WormholescanClient};
+use crate::{
+    config::OracleConfig,
+    estimate::CircuitGeometry,
+    pyth::{decode::decode_vaa, AccumulatorUpdateWitness, OracleWitness},
+};
+
+/// Collects requested feeds and turns them into witness bundles of a fixed circuit geometry.
+///
+/// Feeds are split into groups of `geometry.num_prices`, one accumulator update is fetched per group
+/// and the fetch is repeated until all updates come from the same pythnet slot. The updates are then
+/// padded to the geometry and grouped by `geometry.num_vaas` into [`OracleWitness`]es.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    client: HermesClient,
+    geometry: CircuitGeometry,
+    guardian_set: Vec<[u8; 20]>,
+    poll_interval: Duration,
+    max_wait: Duration,
+    cross_check: Option<HermesClient>,
+    vaa_fallback: Option<WormholescanClient>,
+    config: Option<OracleConfig>,
+    pending: BTreeSet<String>,
+}
+
+impl Scheduler {
+    pub fn new(
+        client: HermesClient,
+        geometry: CircuitGeometry,
+        guardian_set: Vec<[u8; 20]>,
+    ) -> Self {
+        Self {
+            client,
+            geometry,
+            guardian_set,
+            poll_interval: Duration::from_millis(400),
+            max_wait: Duration::from_secs(10),
+            cross_check: None,
+            vaa_fallback: None,
+            config: None,
+            pending: BTreeSet::new(),
+        }
+    }
+
+    /// Scheduler of the configured geometry and guardian set, with all configured feeds requested.
+    /// Fetched prices are checked against the per-feed thresholds of `config`.
+    pub fn from_config(client: HermesClient, config: OracleConfig) -> Result<Self, anyhow::Error> {
+        let geometry = config.geometry()?;
+        let guardian_set = config.guardian_set_source().load()?.addresses;
+        let mut scheduler = Self::new(client, geometry, guardian_set);
+        scheduler.request(config.feed_ids());
+        scheduler.config = Some(config);
+        Ok(scheduler)
+    }
+
+    /// Interval between fetches while waiting for a common slot, and how long to wait at most.
+    pub fn with_polling(mut self, poll_interval: Duration, max_wait: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Dry-run every fetched update against `reference`, which should point to other endpoints
+    /// than the main client, and fail the run if they disagree.
+    pub fn with_cross_check(mut self, reference: HermesClient) -> Self {
+        self.cross_check = Some(reference);
+        self
+    }
+
+    /// Take the VAA of an update from `fallback` when Hermes serves it with fewer signatures than
+    /// the geometry verifies.
+    pub fn with_vaa_fallback(mut self, fallback: WormholescanClient) -> Self {
+        self.vaa_fallback = Some(fallback);
+        self
+    }
+
+    /// Request hex feed ids (with or without `0x`) to be included in the next batches.
+    pub fn request<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, feed_ids: I) {
+        for id in feed_ids {
+            let id = id.as_ref();
+            self.pending
+                .insert(id.strip_prefix("0x").unwrap_or(id).to_lowercase());
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Fetch all pending feeds and emit witness bundles, pending feeds are cleared on success.
+    pub async fn run(&mut self) -> Result<Vec<OracleWitness>, anyhow::Error> {
+        if self.pending.is_empty() {
+            return Ok(vec![]);
+        }
+        let groups = self
+            .pending
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .chunks(self.geometry.num_prices)
+            .map(|c| c.to_vec())
+            .collect::<Vec<_>>();
+        let mut updates = self.fetch_common_slot(&groups).await?;
+        if let Some(fallback) = &self.vaa_fallback {
+            for update in updates.iter_mut() {
+                complete_signatures(fallback, update, self.geometry.num_signatures).await?;
+            }
+        }
+        if let Some(config) = &self.config {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            for update in updates.iter() {
+                config.check_update(update, now)?;
+            }
+        }
+        if let Some(reference) = &self.cross_check {
+            cross_check(reference, &updates).await?;
+        }
+        let witnesses = batch(updates, &self.geometry, &self.guardian_set)?;
+        self.pending.clear();
+        Ok(witnesses)
+    }
+
+    async fn fetch_common_slot(
+        &self,
+        groups: &[Vec<String>],
+    ) -> Result<Vec<AccumulatorUpdateWitness>, anyhow::Error> {
+        let start = Instant::now();
+        loop {
+            let mut updates = vec![];
+            for group in groups {
+                for data in self.client.latest_vaas(group).await? {
+                    updates.push(AccumulatorUpdateWitness::from_accumulator_update_data(
+                        &data,
+                    ));
+                }
+            }
+            let slots = updates
+                .iter()
+                .map(slot)
+                .collect::<Result<BTreeSet<_>, _>>()?;
+            if slots.len() == 1 && updates.len() == groups.len() {
+                return Ok(updates);
+            }
+            if start.elapsed() >= self.max_wait {
+                anyhow::bail!(
+                    "no common slot after {:?}, got slots {:?}",
+                    self.max_wait,
+                    slots
+                );
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+/// Replace the VAA of `update` by the one of `fallback` if it carries fewer than `num_signatures`
+/// signatures. The replacement must sign the same body, so that the merkle proofs still hold.
+async fn complete_signatures(
+    fallback: &WormholescanClient,
+    update: &mut AccumulatorUpdateWitness,
+    num_signatures: usize,
+) -> Result<(), anyhow::Error> {
+    let vaa = decode_vaa(&update.vaa)?;
+    if vaa.signatures.len() >= num_signatures {
+        return Ok(());
+    }
+    let emitter: [u8; 32] = hex::decode(&vaa.emitter_address)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid emitter address"))?;
+    let full = fallback
+        .vaa((vaa.emitter_chain, emitter, vaa.sequence))
+        .await?;
+    let decoded = decode_vaa(&full)?;
+    anyhow::ensure!(
+        decoded.digest == vaa.digest,
+        "fallback VAA of sequence {} signs another body",
+        vaa.sequence
+    );
+    anyhow::ensure!(
+        decoded.signatures.len() >= num_signatures,
+        "fallback VAA of sequence {} carries {} signatures, expect {}",
+        vaa.sequence,
+        decoded.signatures.len(),
+        num_signatures
+    );
+    update.vaa = full;
+    Ok(())
+}
+
+/// Pythnet slot of an accumulator update.
+pub fn slot(update: &AccumulatorUpdateWitness) -> Result<u64, anyhow::Error> {
+    let vaa = decode_vaa(&update.vaa)?;
+    let message = vaa
+        .wormhole_message
+        .ok_or_else(|| anyhow::anyhow!("VAA does not carry an accumulator message"))?;
+    Ok(message.slot)
+}
+
+/// Pad accumulator updates to the geometry and group them into witness bundles.
+///
+/// Missing price updates repeat the last price update of the same accumulator update, and missing
+/// accumulator updates repeat the last one, which keeps the publish time ordering of the circuit.
+pub fn batch(
+    updates: Vec<AccumulatorUpdateWitness>,
+    geometry: &CircuitGeometry,
+    guardian_set: &[[u8; 20]],
+) -> Result<Vec<OracleWitness>, anyhow::Error> {
+    let updates = updates
+        .into_iter()
+        .map(|mut update| {
+            let num_prices = update.updates.len();
+            anyhow::ensure!(
+                num_prices > 0 && num_prices <= geometry.num_prices,
+                "got {} prices, expect 1 to {}",
+                num_prices,
+                geometry.num_prices
+            );
+            let last = update.updates[num_prices - 1].clone();
+            update.updates.resize(geometry.num_prices, last);
+            Ok(update)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(updates
+        .chunks(geometry.num_vaas)
+        .map(|chunk| {
+            let mut accumulator_updates = chunk.to_vec();
+            accumulator_updates.resize(geometry.num_vaas, chunk[chunk.len() - 1].clone());
+            OracleWitness {
+                accumulator_updates,
+                guardian_set: guardian_set.to_vec(),
+                num_signature_to_verify: geometry.num_signatures,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::{batch, slot};
+    use crate::{
+        estimate::CircuitGeometry,
+        testing::fixtures::{AccumulatorFixture, FixturePrice},
+    };
+
+    #[test]
+    fn test_batch() -> anyhow::Result<()> {
+        let fixtures = (0..3u8)
+            .map(|i| AccumulatorFixture {
+                slot: 42,
+                ..AccumulatorFixture::with_prices(vec![FixturePrice::new([i; 32], 1, -8, 1)])
+            })
+            .collect::<Vec<_>>();
+        let updates = fixtures.iter().map(|f| f.witness()).collect::<Vec<_>>();
+        assert_eq!(slot(&updates[0])?, 42);
+
+        let geometry = CircuitGeometry::new(2, 1, 2);
+        let witnesses = batch(updates, &geometry, &fixtures[0].guardian_set())?;
+        assert_eq!(witnesses.len(), 2);
+        for witness in witnesses.iter() {
+            assert_eq!(witness.accumulator_updates.len(), 2);
+            // Padded witnesses are still accepted by the circuit.
+            witness.to_price_oracle::<Bn256, 2>()?;
+        }
+        assert_eq!(
+            witnesses[1].accumulator_updates[0],
+            witnesses[1].accumulator_updates[1]
+        );
+        Ok(())
+    }
+}
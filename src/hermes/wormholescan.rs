@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use base64::Engine as _;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use super::{is_transient_error, is_transient_status, RetryPolicy};
+use crate::pyth::decode::decode_vaa;
+
+pub const WORMHOLESCAN_ENDPOINT: &str = "https://api.wormholescan.io";
+pub use crate::pyth::{PYTHNET_ACCUMULATOR_EMITTER, PYTHNET_CHAIN_ID};
+
+/// `(emitter chain, emitter address, sequence)` of a VAA.
+pub type VaaId = (u16, [u8; 32], u64);
+
+/// Fallback source of signed VAAs by sequence, for when Hermes omits or delays the VAA of a
+/// specific sequence, from the [Wormholescan](https://api.wormholescan.io/swagger/index.html) API.
+///
+/// The public API is rate limited, so requests are spaced by at least `min_interval` and a `429`
+/// waits for its `Retry-After` before the next round. Signed VAAs never change, they are cached by id
+/// for the lifetime of the client and its clones, once checked to carry the requested id.
+#[derive(Debug, Clone)]
+pub struct WormholescanClient {
+    endpoint: String,
+    retry: RetryPolicy,
+    min_interval: Duration,
+    http: reqwest::Client,
+    next_request: Arc<tokio::sync::Mutex<Instant>>,
+    cache: Arc<Mutex<HashMap<VaaId, Vec<u8>>>>,
+}
+
+impl WormholescanClient {
+    pub fn new(endpoint: String, retry: RetryPolicy) -> Result<Self, anyhow::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(retry.request_timeout)
+            .build()
+            .context("failed to build http client")?;
+        Ok(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            retry,
+            min_interval: Duration::from_millis(500),
+            http,
+            next_request: Arc::new(tokio::sync::Mutex::new(Instant::now())),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Client of the public endpoint with the default retry policy.
+    pub fn mainnet() -> Result<Self, anyhow::Error> {
+        Self::new(WORMHOLESCAN_ENDPOINT.to_string(), RetryPolicy::default())
+    }
+
+    /// Minimum delay between two requests, shared by the clones of this client.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Signed VAA `id`, from the cache or
+    /// [`/api/v1/vaas/{chain}/{emitter}/{seq}`](https://api.wormholescan.io/swagger/index.html).
+    pub async fn vaa(&self, id: VaaId) -> Result<Vec<u8>, anyhow::Error> {
+        if let Some(vaa) = self.cache.lock().unwrap().get(&id) {
+            return Ok(vaa.clone());
+        }
+        let (chain, emitter, sequence) = id;
+        let url = format!(
+            "{}/api/v1/vaas/{}/{}/{}",
+            self.endpoint,
+            chain,
+            hex::encode(emitter),
+            sequence
+        );
+        let response: VaaResponse = self.get(&url).await?;
+        let vaa = base64::engine::general_purpose::STANDARD
+            .decode(&response.data.vaa)
+            .map_err(anyhow::Error::from)
+            .and_then(|vaa| check_vaa_id(&vaa, id).map(|_| vaa))
+            .with_context(|| format!("invalid VAA from {}", url))?;
+        self.cache.lock().unwrap().insert(id, vaa.clone());
+        Ok(vaa)
+    }
+
+    /// Signed pythnet accumulator VAA of `sequence`.
+    pub async fn accumulator_vaa(&self, sequence: u64) -> Result<Vec<u8>, anyhow::Error> {
+        self.vaa((PYTHNET_CHAIN_ID, PYTHNET_ACCUMULATOR_EMITTER, sequence))
+            .await
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, anyhow::Error> {
+        let mut errors = vec![];
+        let mut retry_after = Duration::ZERO;
+        for round in 0..self.retry.max_rounds.max(1) {
+            tokio::time::sleep(self.retry.backoff(round).max(retry_after)).await;
+            self.pace().await;
+            match self.http.get(url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    return response
+                        .json()
+                        .await
+                        .with_context(|| format!("invalid response from {}", url));
+                }
+                Ok(response) if is_transient_status(response.status()) => {
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        retry_after = response
+                            .headers()
+                            .get(RETRY_AFTER)
+                            .and_then(|v| v.to_str().ok())
+                            .and_then(|v| v.parse().ok())
+                            .map(Duration::from_secs)
+                            .unwrap_or_default()
+                            .min(self.retry.max_backoff);
+                    }
+                    errors.push(format!("{}: {}", url, response.status()));
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    anyhow::bail!("{}: {} {}", url, status, body);
+                }
+                Err(e) if is_transient_error(&e) => errors.push(format!("{}: {}", url, e)),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        anyhow::bail!("wormholescan request failed: {}", errors.join("; "))
+    }
+
+    /// Wait until `min_interval` elapsed since the previous request.
+    async fn pace(&self) {
+        let mut next_request = self.next_request.lock().await;
+        tokio::time::sleep_until(*next_request).await;
+        *next_request = Instant::now() + self.min_interval;
+    }
+}
+
+/// Fail unless `vaa` parses and is the VAA `id`.
+fn check_vaa_id(vaa: &[u8], id: VaaId) -> Result<(), anyhow::Error> {
+    let vaa = decode_vaa(vaa)?;
+    let emitter: [u8; 32] = hex::decode(&vaa.emitter_address)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid emitter address"))?;
+    let actual = (vaa.emitter_chain, emitter, vaa.sequence);
+    if actual != id {
+        anyhow::bail!(
+            "got VAA {}/{}/{}, expect {}/{}/{}",
+            actual.0,
+            hex::encode(actual.1),
+            actual.2,
+            id.0,
+            hex::encode(id.1),
+            id.2
+        )
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct VaaResponse {
+    data: VaaData,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaaData {
+    vaa: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{WormholescanClient, PYTHNET_ACCUMULATOR_EMITTER, PYTHNET_CHAIN_ID};
+    use crate::hermes::RetryPolicy;
+
+    #[tokio::test]
+    async fn test_cache_and_pacing() -> anyhow::Result<()> {
+        // Nothing listens on port 9 of localhost.
+        let client =
+            WormholescanClient::new("http://127.0.0.1:9/".to_string(), RetryPolicy::none())?
+                .with_min_interval(Duration::from_millis(50));
+        assert_eq!(client.endpoint(), "http://127.0.0.1:9");
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.pace().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let err = client.accumulator_vaa(1).await.unwrap_err();
+        assert!(err.to_string().contains(&format!(
+            "/api/v1/vaas/26/{}/1",
+            hex::encode(PYTHNET_ACCUMULATOR_EMITTER)
+        )));
+
+        let id = (PYTHNET_CHAIN_ID, PYTHNET_ACCUMULATOR_EMITTER, 2);
+        // Clones share the cache.
+        client
+            .clone()
+            .cache
+            .lock()
+            .unwrap()
+            .insert(id, vec![1, 2, 3]);
+        assert_eq!(client.vaa(id).await?, vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vaa_checked_before_caching() -> anyhow::Result<()> {
+        use crate::{
+            pyth::decode::decode_vaa,
+            testing::{fixtures::AccumulatorFixture, mock_server::MockServer},
+        };
+
+        let server = MockServer::start()?;
+        let vaa = AccumulatorFixture::default().vaa();
+        let decoded = decode_vaa(&vaa)?;
+        let emitter: [u8; 32] = hex::decode(&decoded.emitter_address)?.try_into().unwrap();
+        let id = (decoded.emitter_chain, emitter, decoded.sequence);
+        server.add_vaa(id.0, id.1, id.2, vaa.clone());
+        // Served under the next sequence as well, which it does not carry.
+        server.add_vaa(id.0, id.1, id.2 + 1, vaa.clone());
+
+        let client = WormholescanClient::new(server.url(), RetryPolicy::none())?
+            .with_min_interval(Duration::ZERO);
+        assert_eq!(client.vaa(id).await?, vaa);
+        let err = client.vaa((id.0, id.1, id.2 + 1)).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("expect"));
+        assert!(!client
+            .cache
+            .lock()
+            .unwrap()
+            .contains_key(&(id.0, id.1, id.2 + 1)));
+        Ok(())
+    }
+}
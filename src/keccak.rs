@@ -0,0 +1,117 @@
+//! Native keccak256 for witness computation.
+//!
+//! Batches hash several messages in lockstep, `LANES` at a time, with the group spread across the
+//! rayon thread pool. With the `simd` feature (nightly only) the permutations of a group run as one
+//! 4-way vectorized permutation of `keccak`, otherwise one after the other.
+
+use sha3::{Digest, Keccak256};
+
+/// Bytes absorbed per permutation.
+const RATE: usize = 136;
+/// Messages hashed in lockstep.
+const LANES: usize = 4;
+
+type State = [u64; 25];
+
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+/// Keccak256 of each of `inputs`, in order.
+pub fn keccak256_batch<T: AsRef<[u8]> + Sync>(inputs: &[T]) -> Vec<[u8; 32]> {
+    let groups = inputs.chunks(LANES).collect::<Vec<_>>();
+    crate::utils::par_map(&groups, |_, group| hash_group(group)).concat()
+}
+
+fn hash_group<T: AsRef<[u8]>>(group: &[T]) -> Vec<[u8; 32]> {
+    let num_blocks = group
+        .iter()
+        .map(|input| input.as_ref().len() / RATE + 1)
+        .collect::<Vec<_>>();
+    let mut states = [[0u64; 25]; LANES];
+    let mut digests = vec![[0u8; 32]; group.len()];
+    for block in 0..num_blocks.iter().copied().max().unwrap_or(0) {
+        let mut active = [false; LANES];
+        for (i, input) in group.iter().enumerate() {
+            if block < num_blocks[i] {
+                absorb(&mut states[i], input.as_ref(), block);
+                active[i] = true;
+            }
+        }
+        permute(&mut states, &active);
+        for (i, digest) in digests.iter_mut().enumerate() {
+            // A finished state may be permuted again afterwards, so squeeze it right away.
+            if block + 1 == num_blocks[i] {
+                *digest = squeeze(&states[i]);
+            }
+        }
+    }
+    digests
+}
+
+/// XOR the `index`-th block of `input` padded with pad10*1 into `state`.
+fn absorb(state: &mut State, input: &[u8], index: usize) {
+    let start = index * RATE;
+    let chunk = &input[start.min(input.len())..(start + RATE).min(input.len())];
+    let mut block = [0u8; RATE];
+    block[..chunk.len()].copy_from_slice(chunk);
+    if chunk.len() < RATE {
+        block[chunk.len()] ^= 0x01;
+        block[RATE - 1] ^= 0x80;
+    }
+    for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+        *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+}
+
+fn squeeze(state: &State) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    for (bytes, lane) in digest.chunks_exact_mut(8).zip(state.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(not(feature = "simd"))]
+fn permute(states: &mut [State; LANES], active: &[bool; LANES]) {
+    for (state, active) in states.iter_mut().zip(active.iter()) {
+        if *active {
+            keccak::f1600(state);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+fn permute(states: &mut [State; LANES], _active: &[bool; LANES]) {
+    use keccak::simd::{f1600x4, u64x4};
+
+    let mut interleaved =
+        std::array::from_fn(|i| u64x4::from_array(std::array::from_fn(|j| states[j][i])));
+    f1600x4(&mut interleaved);
+    for (i, lanes) in interleaved.iter().enumerate() {
+        for (j, lane) in lanes.to_array().into_iter().enumerate() {
+            states[j][i] = lane;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keccak256, keccak256_batch, RATE};
+
+    #[test]
+    fn test_keccak256_batch() {
+        let inputs = [0, 1, RATE - 1, RATE, RATE + 1, 3 * RATE + 7, 20, 64, 65]
+            .into_iter()
+            .map(|len| (0..len).map(|i| i as u8).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for n in 0..=inputs.len() {
+            let expected = inputs[..n].iter().map(|i| keccak256(i)).collect::<Vec<_>>();
+            assert_eq!(keccak256_batch(&inputs[..n]), expected);
+        }
+        assert_eq!(
+            hex::encode(keccak256(&[])),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+}
@@ -1,8 +1,34 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
 pub use advanced_circuit_component::franklin_crypto;
 pub use pythnet_sdk;
 
+pub mod attestation;
+pub mod chainlink;
+pub mod config;
+pub mod error;
+pub mod estimate;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod gadgets;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "hermes-client")]
+pub mod hermes;
+pub mod keccak;
+#[cfg(feature = "onchain")]
+pub mod onchain;
 pub mod pyth;
+#[cfg(feature = "python")]
+mod python;
 pub mod redstone;
+pub mod report;
+pub mod store;
+pub mod stork;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod witness;
@@ -0,0 +1,210 @@
+//! Calldata of the zkLink oracle verifier contract, built with the `onchain` feature.
+//!
+//! The contract is expected to expose
+//!
+//! ```solidity
+//! struct OracleSummary {
+//!     uint256 guardianSetHash;
+//!     uint256 earliestPublishTime;
+//!     uint256 pricesCommitment;
+//!     uint256 pricesNum;
+//!     uint256 pricesCommitmentBaseSum;
+//! }
+//!
+//! function verify(uint256[] calldata publicInputs, uint256[] calldata proof, OracleSummary calldata summary)
+//! ```
+//!
+//! where `publicInputs` holds the commitment inputized by the circuit, which the contract recomputes
+//! from `summary`, and `proof` is serialized in the layout of the generated PLONK verifier.
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::{
+        bn256::Bn256,
+        ff::PrimeField,
+        CurveAffine, Engine,
+    },
+    plonk::better_better_cs::{cs::Circuit, proof::Proof},
+};
+use ethers::{
+    abi::{self, Token, Tokenizable},
+    contract::{EthAbiCodec, EthAbiType},
+    types::{Bytes, U256},
+    utils::keccak256,
+};
+
+use crate::{utils::fr_to_be_bytes, witness::PublicInputData};
+
+pub const VERIFY_SIGNATURE: &str =
+    "verify(uint256[],uint256[],(uint256,uint256,uint256,uint256,uint256))";
+
+/// Public input data of a proof, as passed to the verifier contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq, EthAbiType, EthAbiCodec)]
+pub struct OracleSummary {
+    pub guardian_set_hash: U256,
+    pub earliest_publish_time: U256,
+    pub prices_commitment: U256,
+    pub prices_num: U256,
+    pub prices_commitment_base_sum: U256,
+}
+
+impl OracleSummary {
+    pub fn new<E: Engine>(public_input_data: &PublicInputData<E>) -> Self {
+        let prices_summarize = &public_input_data.prices_summarize;
+        Self {
+            guardian_set_hash: fr_to_u256(&public_input_data.guardian_set_hash),
+            earliest_publish_time: fr_to_u256(&public_input_data.earliest_publish_time),
+            prices_commitment: fr_to_u256(&prices_summarize.commitment),
+            prices_num: fr_to_u256(&prices_summarize.num),
+            prices_commitment_base_sum: fr_to_u256(&prices_summarize.commitment_base_sum),
+        }
+    }
+}
+
+/// Arguments of the `verify` call of the verifier contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyCall {
+    pub public_inputs: Vec<U256>,
+    pub proof: Vec<U256>,
+    pub summary: OracleSummary,
+}
+
+impl VerifyCall {
+    pub fn new<C: Circuit<Bn256>>(
+        proof: &Proof<Bn256, C>,
+        public_input_data: &PublicInputData<Bn256>,
+    ) -> Self {
+        let (public_inputs, proof) = serialize_proof(proof);
+        Self {
+            public_inputs,
+            proof,
+            summary: OracleSummary::new(public_input_data),
+        }
+    }
+
+    pub fn selector() -> [u8; 4] {
+        keccak256(VERIFY_SIGNATURE)[..4].try_into().unwrap()
+    }
+
+    /// ABI encoded calldata, selector included.
+    pub fn calldata(&self) -> Bytes {
+        let tokens = [
+            Token::Array(self.public_inputs.iter().map(|v| Token::Uint(*v)).collect()),
+            Token::Array(self.proof.iter().map(|v| Token::Uint(*v)).collect()),
+            self.summary.clone().into_token(),
+        ];
+        [&Self::selector()[..], &abi::encode(&tokens)]
+            .concat()
+            .into()
+    }
+}
+
+fn fr_to_u256<F: PrimeField>(fr: &F) -> U256 {
+    U256::from_big_endian(&fr_to_be_bytes(fr))
+}
+
+fn point_to_u256<G: CurveAffine>(point: &G) -> [U256; 2]
+where
+    G::Base: PrimeField,
+{
+    let (x, y) = point.into_xy_unchecked();
+    [fr_to_u256(&x), fr_to_u256(&y)]
+}
+
+/// Serialize a proof into its public inputs and the words expected by the PLONK verifier contract.
+pub fn serialize_proof<C: Circuit<Bn256>>(proof: &Proof<Bn256, C>) -> (Vec<U256>, Vec<U256>) {
+    let inputs = proof.inputs.iter().map(fr_to_u256).collect();
+
+    let mut words = vec![];
+    for point in proof.state_polys_commitments.iter() {
+        words.extend(point_to_u256(point));
+    }
+    words.extend(point_to_u256(
+        &proof.copy_permutation_grand_product_commitment,
+    ));
+    for point in [
+        &proof.lookup_s_poly_commitment,
+        &proof.lookup_grand_product_commitment,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        words.extend(point_to_u256(point));
+    }
+    for point in proof.quotient_poly_parts_commitments.iter() {
+        words.extend(point_to_u256(point));
+    }
+
+    words.extend(proof.state_polys_openings_at_z.iter().map(fr_to_u256));
+    words.extend(
+        proof
+            .state_polys_openings_at_dilations
+            .iter()
+            .map(|(_, _, v)| fr_to_u256(v)),
+    );
+    words.extend(
+        proof
+            .gate_setup_openings_at_z
+            .iter()
+            .map(|(_, _, v)| fr_to_u256(v)),
+    );
+    words.extend(
+        proof
+            .gate_selectors_openings_at_z
+            .iter()
+            .map(|(_, v)| fr_to_u256(v)),
+    );
+    words.extend(
+        proof
+            .copy_permutation_polys_openings_at_z
+            .iter()
+            .map(fr_to_u256),
+    );
+    words.push(fr_to_u256(
+        &proof.copy_permutation_grand_product_opening_at_z_omega,
+    ));
+    words.extend(
+        [
+            &proof.lookup_s_poly_opening_at_z_omega,
+            &proof.lookup_grand_product_opening_at_z_omega,
+            &proof.lookup_t_poly_opening_at_z,
+            &proof.lookup_t_poly_opening_at_z_omega,
+            &proof.lookup_selector_poly_opening_at_z,
+            &proof.lookup_table_type_poly_opening_at_z,
+        ]
+        .into_iter()
+        .flatten()
+        .map(fr_to_u256),
+    );
+    words.push(fr_to_u256(&proof.quotient_poly_opening_at_z));
+    words.push(fr_to_u256(&proof.linearization_poly_opening_at_z));
+    words.extend(point_to_u256(&proof.opening_proof_at_z));
+    words.extend(point_to_u256(&proof.opening_proof_at_z_omega));
+
+    (inputs, words)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+    use ethers::types::U256;
+
+    use super::{OracleSummary, VerifyCall};
+    use crate::pyth::PriceOracle;
+
+    #[test]
+    fn test_calldata() {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let summary = OracleSummary::new(&oracle.public_input_data);
+        assert_eq!(summary.prices_num, U256::one());
+
+        let call = VerifyCall {
+            public_inputs: vec![U256::from(1)],
+            proof: vec![U256::from(2), U256::from(3)],
+            summary,
+        };
+        let calldata = call.calldata();
+        assert_eq!(calldata[..4], VerifyCall::selector());
+        // 2 array offsets and 5 summary words, then the length and elements of each array.
+        assert_eq!(calldata.len(), 4 + 32 * (2 + 5 + 2 + 1 + 2));
+    }
+}
@@ -0,0 +1,61 @@
+//! Combined commitment of several [`PriceOracle`](super::PriceOracle) proofs.
+//!
+//! An aggregation of `K` oracle proofs exposes the poseidon hash of their commitments, in order,
+//! so that a block carries one commitment whatever the number of proofs.
+//!
+//! This is only the commitment: there is no `OracleAggregationCircuit` recursively verifying the
+//! inner proofs, so nothing here reduces the number of proofs a block posts. The verifier checks
+//! each proof against its commitment and recomputes the combined one with
+//! [`aggregate_commitments`].
+
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+    plonk::circuit::allocated_num::Num,
+};
+
+use crate::gadgets::poseidon::{circuit_poseidon_hash, poseidon_hash};
+
+/// Combined commitment of the inner proofs of `commitments`.
+pub fn aggregate_commitments<E: Engine>(commitments: &[E::Fr]) -> E::Fr {
+    poseidon_hash::<E>(commitments)
+}
+
+/// Circuit counterpart of [`aggregate_commitments`].
+pub fn circuit_aggregate_commitments<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    commitments: &[Num<E>],
+) -> Result<Num<E>, SynthesisError> {
+    circuit_poseidon_hash(cs, commitments)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::{
+        bellman::{pairing::bn256::Bn256, SynthesisError},
+        plonk::circuit::allocated_num::Num,
+    };
+
+    use super::{aggregate_commitments, circuit_aggregate_commitments};
+    use crate::{pyth::PriceOracle, utils::testing::create_test_constraint_system};
+
+    #[test]
+    fn test_aggregate_commitments() -> Result<(), SynthesisError> {
+        let commitments =
+            [1, 2].map(|num_vaas| PriceOracle::<Bn256, 3>::circuit_default(num_vaas, 1).commitment);
+        let expected = aggregate_commitments::<Bn256>(&commitments);
+
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = commitments
+            .iter()
+            .map(|c| Num::alloc(cs, Some(*c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let aggregated = circuit_aggregate_commitments(cs, &allocated)?;
+        assert_eq!(aggregated.get_value(), Some(expected));
+        assert!(cs.is_satisfied());
+
+        let mut swapped = commitments;
+        swapped.swap(0, 1);
+        assert_ne!(aggregate_commitments::<Bn256>(&swapped), expected);
+        Ok(())
+    }
+}
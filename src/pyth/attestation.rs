@@ -0,0 +1,192 @@
+use std::marker::PhantomData;
+
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    vm::partitioner::smart_and,
+};
+use pythnet_sdk::{
+    messages::Message,
+    wire::v1::{AccumulatorUpdateData, Proof},
+};
+
+use super::{
+    commitment::{pack_price, pack_price_message, LEN_PACKED_PRICE},
+    PriceUpdates, PythEnvironment,
+};
+use crate::{
+    attestation::{AttestedPrice, OracleAttestation},
+    error::OracleError,
+    gadgets::ethereum::Address,
+};
+
+/// Pyth network of a [`PythAttestation`], part of the circuit type so that the verification key
+/// fixes the accepted emitter.
+pub trait PythNetwork: Clone + std::fmt::Debug {
+    const ENVIRONMENT: PythEnvironment;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PythMainnet;
+
+impl PythNetwork for PythMainnet {
+    const ENVIRONMENT: PythEnvironment = PythEnvironment::Mainnet;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PythTestnet;
+
+impl PythNetwork for PythTestnet {
+    const ENVIRONMENT: PythEnvironment = PythEnvironment::Testnet;
+}
+
+/// Accumulator update, the witness of a [`PythAttestation`].
+#[derive(Debug, Clone)]
+pub struct AccumulatorAttestation {
+    pub accumulator_update_data: AccumulatorUpdateData,
+}
+
+/// Accumulator update of `NUM_PRICES` prices as an [`OracleAttestation`], with `NUM_SIGNATURES`
+/// signatures of the guardians verified and emitted by the accumulator of the network `N`. Both are
+/// fixed by the circuit rather than read from the witness, which could otherwise lower the quorum
+/// or pick another network.
+#[derive(Debug, Clone)]
+pub struct PythAttestation<
+    E: Engine,
+    const NUM_PRICES: usize,
+    const NUM_SIGNATURES: usize,
+    N: PythNetwork = PythMainnet,
+> {
+    pub updates: PriceUpdates<E, NUM_PRICES, 10>,
+    _network: PhantomData<N>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize, const NUM_SIGNATURES: usize, N: PythNetwork>
+    OracleAttestation<E> for PythAttestation<E, NUM_PRICES, NUM_SIGNATURES, N>
+{
+    type Witness = AccumulatorAttestation;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError> {
+        let updates = PriceUpdates::from_optional_witness(
+            cs,
+            Some(witness.accumulator_update_data.clone()),
+            NUM_SIGNATURES,
+        )?;
+        Ok(Self {
+            updates,
+            _network: PhantomData,
+        })
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let is_valid = self.updates.check_by_address(cs, signers)?;
+        let is_from_emitter = self.updates.vaa.check_emitter(cs, N::ENVIRONMENT)?;
+        let is_accumulator_message = self.updates.vaa.body.payload.check_format(cs)?;
+        let is_supported_version = self.updates.vaa.check_version(cs)?;
+        smart_and(
+            cs,
+            &[
+                is_valid,
+                is_from_emitter,
+                is_accumulator_message,
+                is_supported_version,
+            ],
+        )
+    }
+
+    /// Prices rescaled to [`COMMITTED_DECIMALS`](super::commitment::COMMITTED_DECIMALS), which can be committed if not negative and
+    /// fitting an `i64` once rescaled.
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError> {
+        let mut is_ok = vec![];
+        let mut prices = vec![];
+        for update in self.updates.price_updates.iter() {
+            let (is_committable, packed) = pack_price(cs, &update.message)?;
+            is_ok.push(is_committable);
+            prices.push(AttestedPrice::from_packed(&packed));
+        }
+        Ok((smart_and(cs, &is_ok)?, prices))
+    }
+
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        let Proof::WormholeMerkle { updates, .. } = &witness.accumulator_update_data.proof;
+        if updates.len() != NUM_PRICES {
+            return Err(OracleError::InvalidPriceCount {
+                got: updates.len(),
+                expected: NUM_PRICES,
+            });
+        }
+        updates
+            .iter()
+            .map(|update| {
+                let message: Vec<u8> = update.message.clone().into();
+                match pythnet_sdk::wire::from_slice::<byteorder::BE, Message>(&message) {
+                    Ok(Message::PriceFeedMessage(price)) => pack_price_message(&price),
+                    Ok(_) => Err(OracleError::UnsupportedMessage),
+                    Err(e) => Err(OracleError::MessageDecode(e.to_string())),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, plonk::better_better_cs::cs::Circuit},
+        testing::create_test_artifacts_with_optimized_gate,
+    };
+
+    use super::{AccumulatorAttestation, PythAttestation, PythTestnet};
+    use crate::{
+        attestation::{AttestationCircuit, OracleAttestation},
+        testing::fixtures::{AccumulatorFixture, FixturePrice},
+    };
+
+    #[test]
+    fn test_pyth_attestation() -> anyhow::Result<()> {
+        let fixture = AccumulatorFixture::with_prices(vec![
+            FixturePrice::new([1u8; 32], 100, -8, 1_700_000_000),
+            FixturePrice::new([2u8; 32], 200, -6, 1_700_000_001),
+        ]);
+        let witness = AccumulatorAttestation {
+            accumulator_update_data: fixture.build(),
+        };
+        let packed = PythAttestation::<Bn256, 2, 2>::packed_prices(&witness)?;
+        assert_eq!(packed.len(), 2);
+        assert_eq!(packed[1][..32], [2u8; 32]);
+        // Rescaled from 6 to 8 decimals.
+        assert_eq!(packed[1][32..40], 20_000i64.to_be_bytes());
+        assert_eq!(packed[1][40..], 1_700_000_001i64.to_be_bytes());
+        assert!(PythAttestation::<Bn256, 3, 2>::packed_prices(&witness).is_err());
+
+        let circuit = AttestationCircuit::<Bn256, PythAttestation<Bn256, 2, 2>>::new(
+            vec![witness.clone()],
+            fixture.guardian_set(),
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // The network is fixed by the circuit, a mainnet update is rejected by a testnet circuit.
+        let circuit = AttestationCircuit::<Bn256, PythAttestation<Bn256, 2, 2, PythTestnet>>::new(
+            vec![witness],
+            fixture.guardian_set(),
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
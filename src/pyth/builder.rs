@@ -0,0 +1,542 @@
+//! Fluent configuration of [`PriceOracle`] circuits.
+//!
+//! ```ignore
+//! let (circuit, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+//!     .num_vaas(2)
+//!     .num_signatures(13)
+//!     .feeds(feed_ids)
+//!     .max_age(60)
+//!     .guardian_set(GuardianSetSource::File("guardians.toml".into()))
+//!     .build()?;
+//! // `circuit` has the shape used for key generation, `witness_builder` produces matching
+//! // circuits from hermes data.
+//! let oracle = witness_builder.build(accumulator_update_data)?;
+//! ```
+
+use std::{
+    marker::PhantomData,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use base64::Engine as _;
+use pythnet_sdk::{
+    messages::{Message, PriceFeedMessage},
+    wire::{
+        from_slice,
+        v1::{AccumulatorUpdateData, Proof},
+    },
+};
+
+use super::{
+    commitment::PriceCommitmentHash, timestamp, AccumulatorUpdateWitness, GuardianSetConfig,
+    PriceOracle, PythEnvironment, DEFAULT_ACCUMULATOR_UPDATE_DATA, DEFAULT_COMMITMENT_HASH,
+};
+use crate::witness::{PricesSummarize, PublicInputData};
+
+/// Where the guardian set of the circuit comes from.
+#[derive(Debug, Clone, Default)]
+pub enum GuardianSetSource {
+    #[default]
+    Mainnet,
+    Config(GuardianSetConfig),
+    /// JSON or TOML file, see [`GuardianSetConfig::load`].
+    File(PathBuf),
+}
+
+impl GuardianSetSource {
+    pub fn load(&self) -> Result<GuardianSetConfig, anyhow::Error> {
+        match self {
+            GuardianSetSource::Mainnet => Ok(GuardianSetConfig::mainnet()),
+            GuardianSetSource::Config(config) => config.clone().validate(),
+            GuardianSetSource::File(path) => GuardianSetConfig::load(path),
+        }
+    }
+}
+
+/// Builder of a [`PriceOracle`] circuit and its [`OracleWitnessBuilder`].
+///
+/// `NUM_PRICES` is the number of prices per accumulator update.
+#[derive(Debug, Clone)]
+pub struct OracleCircuitBuilder<E: Engine, const NUM_PRICES: usize> {
+    num_vaas: usize,
+    num_signatures: Option<usize>,
+    feeds: Option<Vec<[u8; 32]>>,
+    max_age: Option<u64>,
+    bind_slot: bool,
+    bind_guardian_set_index: bool,
+    bind_vaa_digest: bool,
+    enforce_sequence_order: bool,
+    environment: PythEnvironment,
+    commitment_hash: PriceCommitmentHash,
+    guardian_set: GuardianSetSource,
+    _marker: PhantomData<E>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize> Default for OracleCircuitBuilder<E, NUM_PRICES> {
+    fn default() -> Self {
+        Self {
+            num_vaas: 1,
+            num_signatures: None,
+            feeds: None,
+            max_age: None,
+            bind_slot: false,
+            bind_guardian_set_index: false,
+            bind_vaa_digest: false,
+            enforce_sequence_order: false,
+            environment: PythEnvironment::default(),
+            commitment_hash: DEFAULT_COMMITMENT_HASH,
+            guardian_set: GuardianSetSource::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> OracleCircuitBuilder<E, NUM_PRICES> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of accumulator updates verified by one proof, `1` by default.
+    pub fn num_vaas(mut self, num_vaas: usize) -> Self {
+        self.num_vaas = num_vaas;
+        self
+    }
+
+    /// Number of signatures verified per VAA, at least and by default the guardian set quorum.
+    pub fn num_signatures(mut self, num_signatures: usize) -> Self {
+        self.num_signatures = Some(num_signatures);
+        self
+    }
+
+    /// Feed ids every accumulator update must carry, in order. Any feeds are accepted by default.
+    pub fn feeds(mut self, feeds: Vec<[u8; 32]>) -> Self {
+        self.feeds = Some(feeds);
+        self
+    }
+
+    /// Maximum age in seconds of a price relative to the time the witness is built.
+    pub fn max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Inputize the slot shared by the updates, see [`PriceOracle::with_slot_binding`].
+    pub fn bind_slot(mut self, bind_slot: bool) -> Self {
+        self.bind_slot = bind_slot;
+        self
+    }
+
+    /// Inputize the guardian set index of the VAA headers, see
+    /// [`PriceOracle::with_guardian_set_index_binding`].
+    pub fn bind_guardian_set_index(mut self, bind_guardian_set_index: bool) -> Self {
+        self.bind_guardian_set_index = bind_guardian_set_index;
+        self
+    }
+
+    /// Inputize the digest of each VAA body, see [`PriceOracle::with_vaa_digest_binding`].
+    pub fn bind_vaa_digest(mut self, bind_vaa_digest: bool) -> Self {
+        self.bind_vaa_digest = bind_vaa_digest;
+        self
+    }
+
+    /// Enforce strictly increasing VAA sequences, see [`PriceOracle::with_sequence_ordering`].
+    pub fn enforce_sequence_order(mut self, enforce_sequence_order: bool) -> Self {
+        self.enforce_sequence_order = enforce_sequence_order;
+        self
+    }
+
+    /// Network whose accumulator VAAs are accepted, mainnet by default.
+    pub fn environment(mut self, environment: PythEnvironment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Hash of the commitments, see [`PriceOracle::with_commitment_hash`]. Poseidon by default.
+    pub fn commitment_hash(mut self, commitment_hash: PriceCommitmentHash) -> Self {
+        self.commitment_hash = commitment_hash;
+        self
+    }
+
+    pub fn guardian_set(mut self, guardian_set: GuardianSetSource) -> Self {
+        self.guardian_set = guardian_set;
+        self
+    }
+
+    /// Validate the configuration and build the circuit used for key generation along with the
+    /// builder of the witnesses it accepts.
+    pub fn build(
+        self,
+    ) -> Result<
+        (
+            PriceOracle<E, NUM_PRICES>,
+            OracleWitnessBuilder<E, NUM_PRICES>,
+        ),
+        anyhow::Error,
+    > {
+        anyhow::ensure!(NUM_PRICES > 0, "at least one price per update is required");
+        anyhow::ensure!(self.num_vaas > 0, "at least one VAA is required");
+        anyhow::ensure!(
+            self.commitment_hash.is_algebraic(),
+            "{:?} cannot commit field elements",
+            self.commitment_hash
+        );
+        let guardian_set = self.guardian_set.load()?;
+        let num_signatures = self.num_signatures.unwrap_or_else(|| guardian_set.quorum());
+        anyhow::ensure!(
+            num_signatures <= guardian_set.addresses.len(),
+            "cannot verify {} signatures with {} guardians",
+            num_signatures,
+            guardian_set.addresses.len()
+        );
+        anyhow::ensure!(
+            num_signatures >= guardian_set.quorum(),
+            "{} signatures are below the quorum {} of {} guardians",
+            num_signatures,
+            guardian_set.quorum(),
+            guardian_set.addresses.len()
+        );
+        if let Some(feeds) = &self.feeds {
+            anyhow::ensure!(
+                feeds.len() == NUM_PRICES,
+                "expected {} feeds, got {}",
+                NUM_PRICES,
+                feeds.len()
+            );
+            for (i, feed) in feeds.iter().enumerate() {
+                if let Some(j) = feeds[..i].iter().position(|f| f == feed) {
+                    anyhow::bail!("feed {} duplicates feed {}", i, j);
+                }
+            }
+        }
+
+        let witness_builder = OracleWitnessBuilder {
+            num_vaas: self.num_vaas,
+            num_signatures,
+            feeds: self.feeds,
+            max_age: self.max_age,
+            bind_slot: self.bind_slot,
+            bind_guardian_set_index: self.bind_guardian_set_index,
+            bind_vaa_digest: self.bind_vaa_digest,
+            enforce_sequence_order: self.enforce_sequence_order,
+            environment: self.environment,
+            commitment_hash: self.commitment_hash,
+            guardian_set,
+            _marker: PhantomData,
+        };
+        let circuit = witness_builder.placeholder()?;
+        Ok((circuit, witness_builder))
+    }
+}
+
+/// Builds [`PriceOracle`] circuits from accumulator updates, checking them against the
+/// configuration of the [`OracleCircuitBuilder`] it comes from.
+#[derive(Debug, Clone)]
+pub struct OracleWitnessBuilder<E: Engine, const NUM_PRICES: usize> {
+    num_vaas: usize,
+    num_signatures: usize,
+    feeds: Option<Vec<[u8; 32]>>,
+    max_age: Option<u64>,
+    bind_slot: bool,
+    bind_guardian_set_index: bool,
+    bind_vaa_digest: bool,
+    enforce_sequence_order: bool,
+    environment: PythEnvironment,
+    commitment_hash: PriceCommitmentHash,
+    guardian_set: GuardianSetConfig,
+    _marker: PhantomData<E>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize> OracleWitnessBuilder<E, NUM_PRICES> {
+    pub fn num_vaas(&self) -> usize {
+        self.num_vaas
+    }
+
+    pub fn num_signatures(&self) -> usize {
+        self.num_signatures
+    }
+
+    pub fn commitment_hash(&self) -> PriceCommitmentHash {
+        self.commitment_hash
+    }
+
+    pub fn environment(&self) -> PythEnvironment {
+        self.environment
+    }
+
+    pub fn guardian_set(&self) -> &GuardianSetConfig {
+        &self.guardian_set
+    }
+
+    /// Build the circuit for `accumulator_update_data`, checking staleness against the current time.
+    pub fn build(
+        &self,
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+    ) -> Result<PriceOracle<E, NUM_PRICES>, anyhow::Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.build_at(accumulator_update_data, now as i64)
+    }
+
+    /// Build the circuit for `accumulator_update_data`, checking staleness against `now`.
+    pub fn build_at(
+        &self,
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+        now: i64,
+    ) -> Result<PriceOracle<E, NUM_PRICES>, anyhow::Error> {
+        anyhow::ensure!(
+            accumulator_update_data.len() == self.num_vaas,
+            "expected {} accumulator updates, got {}",
+            self.num_vaas,
+            accumulator_update_data.len()
+        );
+        anyhow::ensure!(
+            !self.guardian_set.is_expired(now.max(0) as u64),
+            "guardian set {} is expired",
+            self.guardian_set.index
+        );
+        for (i, data) in accumulator_update_data.iter().enumerate() {
+            let (guardian_set_index, price_feeds) = decode(data)?;
+            anyhow::ensure!(
+                guardian_set_index == self.guardian_set.index,
+                "accumulator update {} is signed by guardian set {}, expect {}",
+                i,
+                guardian_set_index,
+                self.guardian_set.index
+            );
+            if let Some(feeds) = &self.feeds {
+                let ids = price_feeds.iter().map(|p| p.feed_id).collect::<Vec<_>>();
+                anyhow::ensure!(
+                    &ids == feeds,
+                    "accumulator update {} carries unexpected feeds",
+                    i
+                );
+            }
+            if let Some(max_age) = self.max_age {
+                for price_feed in price_feeds.iter() {
+                    anyhow::ensure!(
+                        timestamp::is_fresh(now, price_feed.publish_time, max_age),
+                        "price of {} published at {} is older than {} seconds",
+                        hex::encode(price_feed.feed_id),
+                        price_feed.publish_time,
+                        max_age
+                    );
+                }
+            }
+        }
+        let oracle = PriceOracle::new_in(
+            self.environment,
+            accumulator_update_data,
+            self.guardian_set.addresses.clone(),
+            self.num_signatures,
+        )?
+        .with_commitment_hash(self.commitment_hash)?;
+        let oracle = if self.bind_slot {
+            oracle.with_slot_binding()?
+        } else {
+            oracle
+        };
+        let oracle = if self.bind_guardian_set_index {
+            oracle.with_guardian_set_index_binding()?
+        } else {
+            oracle
+        };
+        let oracle = if self.bind_vaa_digest {
+            oracle.with_vaa_digest_binding()
+        } else {
+            oracle
+        };
+        if self.enforce_sequence_order {
+            oracle.with_sequence_ordering()
+        } else {
+            Ok(oracle)
+        }
+    }
+
+    /// A circuit of the configured shape, for key generation only as its witness is not satisfying.
+    fn placeholder(&self) -> Result<PriceOracle<E, NUM_PRICES>, anyhow::Error> {
+        let bytes =
+            base64::engine::general_purpose::STANDARD.decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let mut witness = AccumulatorUpdateWitness::from_accumulator_update_data(
+            &AccumulatorUpdateData::try_from_slice(&bytes)?,
+        );
+        witness.updates = witness
+            .updates
+            .iter()
+            .cycle()
+            .take(NUM_PRICES)
+            .cloned()
+            .collect();
+        let data = witness.to_accumulator_update_data()?;
+        Ok(PriceOracle {
+            accumulator_update_data: vec![data; self.num_vaas],
+            guardian_set: self.guardian_set.addresses.clone(),
+            public_input_data: PublicInputData {
+                guardian_set_hash: E::Fr::zero(),
+                earliest_publish_time: E::Fr::zero(),
+                prices_summarize: PricesSummarize {
+                    commitment: E::Fr::zero(),
+                    num: E::Fr::zero(),
+                    commitment_base_sum: E::Fr::zero(),
+                },
+            },
+            commitment: E::Fr::zero(),
+            num_signature_to_verify: self.num_signatures,
+            bind_slot: self.bind_slot,
+            bind_guardian_set_index: self.bind_guardian_set_index,
+            bind_vaa_digest: self.bind_vaa_digest,
+            enforce_sequence_order: self.enforce_sequence_order,
+            environment: self.environment,
+            commitment_hash: self.commitment_hash,
+        })
+    }
+}
+
+fn decode(data: &AccumulatorUpdateData) -> Result<(u32, Vec<PriceFeedMessage>), anyhow::Error> {
+    let Proof::WormholeMerkle { vaa, updates } = data.proof.clone();
+    let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+        serde_wormhole::from_slice(vaa.as_ref())?;
+    let price_feeds = updates
+        .into_iter()
+        .map(|update| {
+            let message: Vec<u8> = update.message.into();
+            match from_slice::<byteorder::BE, Message>(&message)? {
+                Message::PriceFeedMessage(price_feed) => Ok(price_feed),
+                _ => anyhow::bail!("invalid price feed message"),
+            }
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    Ok((vaa.guardian_set_index, price_feeds))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+    use base64::Engine as _;
+    use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+
+    use super::{GuardianSetSource, OracleCircuitBuilder};
+    use crate::pyth::{
+        commitment::PriceCommitmentHash, GuardianSetConfig, PriceOracle,
+        DEFAULT_ACCUMULATOR_UPDATE_DATA,
+    };
+
+    const FEED_ID: &str = "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43";
+    // Publish time of the prices in `DEFAULT_ACCUMULATOR_UPDATE_DATA`.
+    const PUBLISH_TIME: i64 = 1706588882;
+
+    fn default_data() -> AccumulatorUpdateData {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)
+            .unwrap();
+        AccumulatorUpdateData::try_from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_builder() -> anyhow::Result<()> {
+        let feed_id: [u8; 32] = hex::decode(FEED_ID)?.try_into().unwrap();
+        let (circuit, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_vaas(2)
+            .num_signatures(13)
+            .feeds(vec![feed_id, [1; 32], [2; 32]])
+            .build()?;
+        assert_eq!(circuit.verification_num(), 2);
+        assert_eq!(circuit.num_signature_to_verify, 13);
+        // The default data carries the same feed three times.
+        assert!(witness_builder
+            .build_at(vec![default_data(); 2], PUBLISH_TIME)
+            .is_err());
+
+        let (_, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_vaas(2)
+            .num_signatures(13)
+            .max_age(60)
+            .build()?;
+        let oracle = witness_builder.build_at(vec![default_data(); 2], PUBLISH_TIME + 60)?;
+        assert_eq!(oracle.verification_num(), 2);
+        assert!(witness_builder
+            .build_at(vec![default_data(); 2], PUBLISH_TIME + 61)
+            .is_err());
+        assert!(witness_builder
+            .build_at(vec![default_data()], PUBLISH_TIME)
+            .is_err());
+
+        let (circuit, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_vaas(2)
+            .bind_slot(true)
+            .bind_guardian_set_index(true)
+            .bind_vaa_digest(true)
+            .build()?;
+        assert!(circuit.bind_slot && circuit.bind_guardian_set_index && circuit.bind_vaa_digest);
+        let oracle = witness_builder.build_at(vec![default_data(); 2], PUBLISH_TIME)?;
+        assert!(oracle.bind_slot && oracle.bind_guardian_set_index && oracle.bind_vaa_digest);
+
+        let (circuit, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+            .commitment_hash(PriceCommitmentHash::Rescue)
+            .build()?;
+        assert_eq!(circuit.commitment_hash, PriceCommitmentHash::Rescue);
+        let oracle = witness_builder.build_at(vec![default_data()], PUBLISH_TIME)?;
+        assert_eq!(oracle.commitment_hash, PriceCommitmentHash::Rescue);
+        let poseidon = PriceOracle::<Bn256, 3>::new(
+            vec![default_data()],
+            GuardianSetConfig::mainnet().addresses,
+            13,
+        )?;
+        assert_ne!(oracle.commitment, poseidon.commitment);
+
+        let (_, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_vaas(2)
+            .enforce_sequence_order(true)
+            .build()?;
+        // Both updates carry the same VAA.
+        assert!(witness_builder
+            .build_at(vec![default_data(); 2], PUBLISH_TIME)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        // Quorum of the mainnet guardian set.
+        let (_, witness_builder) = OracleCircuitBuilder::<Bn256, 3>::new().build().unwrap();
+        assert_eq!(witness_builder.num_signatures(), 13);
+        // Placeholder circuits are padded to any number of prices.
+        let (circuit, _) = OracleCircuitBuilder::<Bn256, 5>::new().build().unwrap();
+        assert_eq!(circuit.verification_num(), 1);
+
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_signatures(20)
+            .build()
+            .is_err());
+        // One signature below the quorum of the mainnet guardian set.
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_signatures(12)
+            .build()
+            .is_err());
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .num_vaas(0)
+            .build()
+            .is_err());
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .commitment_hash(PriceCommitmentHash::Keccak256)
+            .build()
+            .is_err());
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .feeds(vec![[1; 32], [1; 32], [2; 32]])
+            .build()
+            .is_err());
+        assert!(OracleCircuitBuilder::<Bn256, 2>::new()
+            .feeds(vec![[1; 32]])
+            .build()
+            .is_err());
+        let empty = GuardianSetConfig {
+            index: 3,
+            expiration_time: None,
+            addresses: vec![],
+        };
+        assert!(OracleCircuitBuilder::<Bn256, 3>::new()
+            .guardian_set(GuardianSetSource::Config(empty))
+            .build()
+            .is_err());
+    }
+}
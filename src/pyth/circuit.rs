@@ -23,21 +23,24 @@ use base64::Engine as _;
 use num_bigint::BigUint;
 use pythnet_sdk::{
     messages::Message,
-    wire::{from_slice, v1::AccumulatorUpdateData},
+    wire::{
+        from_slice,
+        v1::{AccumulatorUpdateData, WormholeMessage, WormholePayload},
+    },
 };
-use secp256k1::{ecdsa::RecoveryId, Secp256k1};
+use secp256k1::{ecdsa::RecoveryId, SECP256K1};
 use serde::{Deserialize, Serialize};
 use serde_wormhole::RawMessage;
-use sha3::{Digest, Keccak256};
 use wormhole_sdk::vaa::{Body, Header};
 
 use crate::{
-    gadgets::{
-        ethereum::Address,
-        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    gadgets::{ecdsa::Secp256k1Params, ethereum::Address, keccak256::Keccak256Params},
+    pyth::{
+        commitment::PriceCommitmentHash, compute_guardian_set_commitment, is_sequence_increasing,
+        GuardianSet, PriceUpdates, PythEnvironment, GUARDIAN_SET,
     },
-    pyth::{PriceUpdate, PriceUpdates, Vaa},
-    utils::{fr_from_biguint, new_synthesis_error},
+    report::section,
+    utils::{fr_from_biguint, num_from_be_bytes},
     witness::{PricesSummarize, PublicInputData},
 };
 
@@ -49,6 +52,15 @@ use crate::gadgets::rescue::circuit_rescue_hash;
 pub use advanced_circuit_component::franklin_crypto;
 use crate::franklin_crypto::bellman::plonk::better_better_cs::gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext;
 
+/// Base64 encoded accumulator update data (3 prices, 13 signatures) from hermes API
+/// [`/api/latest_vaas`](https://hermes.pyth.network/docs/#/rest/latest_vaas), used by [`PriceOracle::circuit_default`].
+pub const DEFAULT_ACCUMULATOR_UPDATE_DATA: &str = "UE5BVQEAAAADuAEAAAADDQKR8EO5PyxuSK5T+gNQkaJreUwBZifEwzHpa9tpHugiM09aJtlNZ+QGacbggPbh74MLGekxLbW0L3nW0iWvpp9VAQP7Qvjz7AWngPgTQkXph4sWBNxZ//lLN1TmuddxZ85wFQqdpbC2mX8VAhRL7sER5oFsFWLzxQ1HBLWrHACe2ekWAQTz+pimoBD55XdYKhtbb4/0T01HYaHDJbL0yLgz5UTmy2DxgkEYW0AqiQeQq5kT7wwgaiS/1R2MqVHv4kKBBy4qAAZ4POFVBLBb7HktrrqZCazVQkXRX1h92E23BXK3Vjt+Sxf/ueIJJXK6PoQJKpNuGRPLJPu55O5CCeFga/4kihOZAAfmHbBMH2IiDqUxccAigMYDwFhuMN3Zjby/UiQwcccKnl1tyB6PZUjTBrz9huv+3Lb37TYZH3GLXvwPgGuy+oI2AQjiUQNmxfe/ns3lYELUcJmD0SjfC9O9t757mkWdMZyXzHULb4Z17xaBW9b0CDvKMf+gh6qqHmwBOokmNEP2Ln/WAAo+m7ccVx/M7EkPu5PXFnQt11+mixtm8/gzAXn8TR+/Ng9l/2Gx/T6iXYNgL2ErXIXiGDXxFjnUa08FcaKLgmuvAAvH0mXgEHynf85669H4swCIWlRucdhFxmMp/W9mihoeFQgXbypikATYOzLI0NV3oOCtj6ASvGecSfa4FngwkxqvAAzzY2hcw9bh2u/NU31oC9TRmon9QxkKWNLm3B6gyGVxJFurQ5kfLPHJ9JfAll/oVPlTe2PDzC9z0/Ea2vuPcB1IAQ2Xgc8lPA5CZYuY2U5rGAPUT2nov1d4aFZGDunWdte8uXISM5UEOYaENGKUkuCQn9CdXPL+nvD3nD/LPtDG+gjuAQ6+q5Uzyq307xHErRAcoVkYziIPSoGZf6Rgh0ted5pZokh5P1kzWBsJHM3ISzW3IX4slBfZweZQLMCIpcBTFR/BABD9FzYKBnUQrmi+yZIJpGNQZmxNXVQAybg8qTayhVPOGAFvQ8boVEysxiUlqLKTmI05FpmrB9ESrZMR/Fa1ULUJARL2cMOlIJ9lz4NuPdZAWyp5OONMXZtDI1nRLCMlqwXA7ApUrzUEX8vz6JTbkhEf3a0vh4EvTlv3vTRuYk3Lg6mwAGW4etIAAAAAABrhAfrtrFhR4yubI7X5QRqMK6xKrj7U3XuBHdGnLqSqcQAAAAACSzpwAUFVV1YAAAAAAAdTH/EAACcQjEIPxn/xQVV6+Fv/qiA+BGAg0v0DAFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7";
+
+/// End-to-end circuit of the oracle: it verifies `num_signature_to_verify` signatures of the VAA of
+/// every accumulator update against `guardian_set`, the merkle proofs of its `NUM_PRICES` prices
+/// against the VAA root, and exposes `commitment`, over the guardian set and the prices, as public
+/// input. Build one with [`PriceOracle::new`] or
+/// [`OracleCircuitBuilder`](super::OracleCircuitBuilder).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct PriceOracle<E: Engine, const NUM_PRICES: usize> {
@@ -57,6 +69,36 @@ pub struct PriceOracle<E: Engine, const NUM_PRICES: usize> {
     pub public_input_data: PublicInputData<E>,
     pub commitment: E::Fr,
     pub num_signature_to_verify: usize,
+    /// Whether the slot of the accumulator snapshot, shared by all updates, is inputized after the
+    /// commitment, see [`PriceOracle::with_slot_binding`].
+    #[serde(default)]
+    pub bind_slot: bool,
+    /// Whether the guardian set index of the VAA headers, shared by all updates, is inputized
+    /// last, see [`PriceOracle::with_guardian_set_index_binding`].
+    #[serde(default)]
+    pub bind_guardian_set_index: bool,
+    /// Whether the digest of each VAA body is inputized last, see
+    /// [`PriceOracle::with_vaa_digest_binding`].
+    #[serde(default)]
+    pub bind_vaa_digest: bool,
+    /// Whether the sequences of the VAAs are enforced to be strictly increasing, see
+    /// [`PriceOracle::with_sequence_ordering`].
+    #[serde(default)]
+    pub enforce_sequence_order: bool,
+    /// Network whose accumulator must have emitted every VAA.
+    #[serde(default)]
+    pub environment: PythEnvironment,
+    /// Hash of the prices commitments and of `commitment`, see
+    /// [`PriceOracle::with_commitment_hash`].
+    #[serde(default = "default_commitment_hash")]
+    pub commitment_hash: PriceCommitmentHash,
+}
+
+/// Hash of the commitments of [`PriceOracle`] unless set otherwise.
+pub const DEFAULT_COMMITMENT_HASH: PriceCommitmentHash = PriceCommitmentHash::Poseidon;
+
+fn default_commitment_hash() -> PriceCommitmentHash {
+    DEFAULT_COMMITMENT_HASH
 }
 
 impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
@@ -65,157 +107,172 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
         guardian_set: Vec<[u8; 20]>,
         num_signature_to_verify: usize,
     ) -> Result<Self, anyhow::Error> {
-        let mut last_publish_time = 0;
-        let mut earliest_publish_time = 0;
-        let mut prices_commitments = vec![];
-
-        let secp = Secp256k1::new();
-        for data in accumulator_update_data.clone().into_iter() {
-            let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } = data.proof;
-            if updates.len() != NUM_PRICES {
-                anyhow::bail!("expected {} prices, got {}", NUM_PRICES, updates.len())
-            }
-            let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
-                serde_wormhole::from_slice(vaa.as_ref())?;
-            // Check signatures in VAA
-            {
-                let (header, body): (Header, Body<&RawMessage>) = vaa.clone().into();
-                let digest = body.digest()?;
-                if header.signatures.len() < num_signature_to_verify {
-                    anyhow::bail!(
-                        "got {} signatures which is less than {}",
-                        header.signatures.len(),
-                        num_signature_to_verify
-                    )
-                }
-                for i in 0..num_signature_to_verify {
-                    let signature = header.signatures[i];
-                    let recid = RecoveryId::from_i32(signature.signature[64].into())?;
-                    let pubkey: &[u8; 65] = &secp
-                        .recover_ecdsa(
-                            &secp256k1::Message::from_digest_slice(&digest.secp256k_hash)?,
-                            &secp256k1::ecdsa::RecoverableSignature::from_compact(
-                                &signature.signature[..64],
-                                recid,
-                            )?,
-                        )?
-                        .serialize_uncompressed();
-                    let address: [u8; 32] =
-                        Keccak256::new_with_prefix(&pubkey[1..]).finalize().into();
-                    let address: [u8; 20] = address[address.len() - 20..].try_into()?;
-                    let found = guardian_set.iter().any(|g| g == &address);
-                    if !found {
-                        anyhow::bail!("invalid signature {}", hex::encode(signature.signature));
-                    }
-                }
-            }
-            // Compute price root
-            let mut price_feeds = vec![];
-            for price_update in updates {
-                let message: Vec<u8> = price_update.message.clone().into();
-                if let Message::PriceFeedMessage(price_feed) =
-                    from_slice::<byteorder::BE, Message>(&message)?
-                {
-                    price_feeds.push(price_feed);
-                } else {
-                    anyhow::bail!("invalid price feed message")
-                };
-            }
-            {
-                let mut prices_commitment_members = vec![];
-                for price_feed in price_feeds.iter() {
-                    let feed_id = {
-                        // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
-                        let mut bytes = [0u8; 16];
-                        bytes[1..].copy_from_slice(&price_feed.feed_id[0..15]);
-                        BigUint::from_bytes_be(&bytes)
-                    };
-                    // normalized_price = 10^(18-real_exponent) * price
-                    let price = {
-                        let exponent = (18 + price_feed.exponent) as u32;
-                        let coefficient = BigUint::from(10u32).pow(exponent);
-                        coefficient.mul(&BigUint::try_from(price_feed.price)?)
-                    };
-                    prices_commitment_members.push(fr_from_biguint::<E>(&feed_id)?);
-                    prices_commitment_members.push(fr_from_biguint::<E>(&price)?);
-                }
-                let prices_commitment = poseidon_hash::<E>(&prices_commitment_members);
-                prices_commitments.push(prices_commitment);
-            }
-            // Check publish time is increasing
-            {
-                if price_feeds[0].publish_time < last_publish_time {
-                    anyhow::bail!(
-                        "publish time is not increasing: {} <= {}",
-                        price_feeds[0].price,
-                        last_publish_time
-                    )
-                };
-                last_publish_time = price_feeds[0].publish_time;
-                if earliest_publish_time == 0 {
-                    earliest_publish_time = last_publish_time;
-                }
-            }
-        }
-
-        let guardian_set_hash = {
-            let input = guardian_set
-                .iter()
-                .map(|g| {
-                    let u = BigUint::from_bytes_be(g);
-                    fr_from_biguint::<E>(&u)
-                })
-                .collect::<Result<Vec<_>, _>>()?;
-            poseidon_hash::<E>(&input)
-        };
+        Self::new_in(
+            PythEnvironment::Mainnet,
+            accumulator_update_data,
+            guardian_set,
+            num_signature_to_verify,
+        )
+    }
 
-        let earliest_publish_time =
-            fr_from_biguint::<E>(&BigUint::from(earliest_publish_time as u64))?;
-
-        let mut prices_num = E::Fr::zero();
-        let mut prices_commitment_base_sum = E::Fr::zero();
-        let mut prices_commitment = E::Fr::zero();
-        for mut commitment in prices_commitments.into_iter() {
-            Field::add_assign(&mut prices_commitment_base_sum, &commitment);
-            Field::add_assign(&mut prices_num, &E::Fr::one());
-            Field::mul_assign(&mut commitment, &prices_num);
-            Field::add_assign(&mut prices_commitment, &commitment);
+    /// Like [`PriceOracle::new`], for the VAAs of `environment`.
+    pub fn new_in(
+        environment: PythEnvironment,
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+    ) -> Result<Self, anyhow::Error> {
+        for data in accumulator_update_data.iter() {
+            let vaa = accumulator_vaa(data)?;
+            environment.check_emitter(vaa.emitter_chain.into(), &vaa.emitter_address.0)?;
         }
-
-        let commitment = poseidon_hash::<E>(&[
-            guardian_set_hash,
-            earliest_publish_time,
-            prices_commitment,
-            prices_num,
-            prices_commitment_base_sum,
-        ]);
-
+        let (public_input_data, commitment) = compute_public_input::<E>(
+            &accumulator_update_data,
+            &guardian_set,
+            num_signature_to_verify,
+            NUM_PRICES,
+        )?;
         Ok(Self {
             accumulator_update_data,
             guardian_set,
+            public_input_data,
             commitment,
-            public_input_data: PublicInputData {
-                guardian_set_hash,
-                prices_summarize: PricesSummarize {
-                    commitment: prices_commitment,
-                    num: prices_num,
-                    commitment_base_sum: prices_commitment_base_sum,
-                },
-                earliest_publish_time,
-            },
             num_signature_to_verify,
+            bind_slot: false,
+            bind_guardian_set_index: false,
+            bind_vaa_digest: false,
+            enforce_sequence_order: false,
+            environment,
+            commitment_hash: DEFAULT_COMMITMENT_HASH,
         })
     }
 
+    /// Commit with `hash` instead of [`DEFAULT_COMMITMENT_HASH`], which must be algebraic as the
+    /// commitments are over field elements.
+    pub fn with_commitment_hash(
+        mut self,
+        hash: PriceCommitmentHash,
+    ) -> Result<Self, anyhow::Error> {
+        if hash == self.commitment_hash {
+            return Ok(self);
+        }
+        anyhow::ensure!(
+            hash.is_algebraic(),
+            "{:?} cannot commit field elements",
+            hash
+        );
+        let (public_input_data, commitment) = compute_public_input_with_hash::<E>(
+            &self.accumulator_update_data,
+            &self.guardian_set,
+            self.num_signature_to_verify,
+            NUM_PRICES,
+            hash,
+        )?;
+        self.public_input_data = public_input_data;
+        self.commitment = commitment;
+        self.commitment_hash = hash;
+        Ok(self)
+    }
+
+    /// Inputize the slot of the accumulator snapshot attested by the updates, after the commitment,
+    /// so that a verifier knows which snapshot the prices come from. All updates must share it.
+    pub fn with_slot_binding(mut self) -> Result<Self, anyhow::Error> {
+        let slots = self
+            .accumulator_update_data
+            .iter()
+            .map(accumulator_slot)
+            .collect::<Result<Vec<_>, _>>()?;
+        anyhow::ensure!(
+            slots.windows(2).all(|w| w[0] == w[1]),
+            "updates are from different slots: {:?}",
+            slots
+        );
+        self.bind_slot = true;
+        Ok(self)
+    }
+
+    /// Slot of the accumulator snapshot attested by the first update.
+    pub fn slot(&self) -> Result<u64, anyhow::Error> {
+        let data = self
+            .accumulator_update_data
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("no accumulator update"))?;
+        accumulator_slot(data)
+    }
+
+    /// Inputize the guardian set index claimed by the VAA headers, last, so that a verifier can
+    /// check that the guardian set committed by the proof is the one registered under that index
+    /// instead of trusting the configuration of the prover. All updates must share it.
+    pub fn with_guardian_set_index_binding(mut self) -> Result<Self, anyhow::Error> {
+        let indices = self
+            .accumulator_update_data
+            .iter()
+            .map(|data| Ok(accumulator_vaa(data)?.guardian_set_index))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        anyhow::ensure!(
+            indices.windows(2).all(|w| w[0] == w[1]),
+            "updates are signed by different guardian sets: {:?}",
+            indices
+        );
+        self.bind_guardian_set_index = true;
+        Ok(self)
+    }
+
+    /// Inputize the double keccak256 of the body of each VAA, in order and as two halves of 16
+    /// bytes, high first, after the other bindings, so that a verifier can cross-check the exact
+    /// VAAs against the `hash` recorded by `parseAndVerifyVM` of the wormhole core bridge. The
+    /// digests are computed again for the binding, two keccak permutations per VAA.
+    pub fn with_vaa_digest_binding(mut self) -> Self {
+        self.bind_vaa_digest = true;
+        self
+    }
+
+    /// Native digests inputized by [`PriceOracle::with_vaa_digest_binding`].
+    pub fn vaa_digests(&self) -> Result<Vec<[u8; 32]>, anyhow::Error> {
+        self.accumulator_update_data
+            .iter()
+            .map(|data| {
+                let (_, body): (Header, Body<&RawMessage>) = accumulator_vaa(data)?.into();
+                Ok(body.digest()?.secp256k_hash)
+            })
+            .collect()
+    }
+
+    /// Enforce that the sequences of the VAAs, all emitted by the pyth accumulator, are strictly
+    /// increasing, so that a batch can not include a VAA twice or out of order.
+    pub fn with_sequence_ordering(mut self) -> Result<Self, anyhow::Error> {
+        let sequences = self
+            .accumulator_update_data
+            .iter()
+            .map(|data| Ok(accumulator_vaa(data)?.sequence))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        anyhow::ensure!(
+            sequences.windows(2).all(|w| w[0] < w[1]),
+            "sequences are not strictly increasing: {:?}",
+            sequences
+        );
+        self.enforce_sequence_order = true;
+        Ok(self)
+    }
+
+    fn options(&self) -> OracleOptions {
+        OracleOptions {
+            bind_slot: self.bind_slot,
+            bind_guardian_set_index: self.bind_guardian_set_index,
+            bind_vaa_digest: self.bind_vaa_digest,
+            enforce_sequence_order: self.enforce_sequence_order,
+            environment: self.environment,
+            commitment_hash: self.commitment_hash,
+        }
+    }
+
     pub fn circuit_default(
         num_accumulator_update_dara: usize,
         num_signature_to_verify: usize,
     ) -> Self {
         let accumulator_update_data = {
-            // base64 encoded hex from hermes API /api/latest_vaas (https://hermes.pyth.network/docs/#/rest/latest_vaas)
-            let hex = "UE5BVQEAAAADuAEAAAADDQKR8EO5PyxuSK5T+gNQkaJreUwBZifEwzHpa9tpHugiM09aJtlNZ+QGacbggPbh74MLGekxLbW0L3nW0iWvpp9VAQP7Qvjz7AWngPgTQkXph4sWBNxZ//lLN1TmuddxZ85wFQqdpbC2mX8VAhRL7sER5oFsFWLzxQ1HBLWrHACe2ekWAQTz+pimoBD55XdYKhtbb4/0T01HYaHDJbL0yLgz5UTmy2DxgkEYW0AqiQeQq5kT7wwgaiS/1R2MqVHv4kKBBy4qAAZ4POFVBLBb7HktrrqZCazVQkXRX1h92E23BXK3Vjt+Sxf/ueIJJXK6PoQJKpNuGRPLJPu55O5CCeFga/4kihOZAAfmHbBMH2IiDqUxccAigMYDwFhuMN3Zjby/UiQwcccKnl1tyB6PZUjTBrz9huv+3Lb37TYZH3GLXvwPgGuy+oI2AQjiUQNmxfe/ns3lYELUcJmD0SjfC9O9t757mkWdMZyXzHULb4Z17xaBW9b0CDvKMf+gh6qqHmwBOokmNEP2Ln/WAAo+m7ccVx/M7EkPu5PXFnQt11+mixtm8/gzAXn8TR+/Ng9l/2Gx/T6iXYNgL2ErXIXiGDXxFjnUa08FcaKLgmuvAAvH0mXgEHynf85669H4swCIWlRucdhFxmMp/W9mihoeFQgXbypikATYOzLI0NV3oOCtj6ASvGecSfa4FngwkxqvAAzzY2hcw9bh2u/NU31oC9TRmon9QxkKWNLm3B6gyGVxJFurQ5kfLPHJ9JfAll/oVPlTe2PDzC9z0/Ea2vuPcB1IAQ2Xgc8lPA5CZYuY2U5rGAPUT2nov1d4aFZGDunWdte8uXISM5UEOYaENGKUkuCQn9CdXPL+nvD3nD/LPtDG+gjuAQ6+q5Uzyq307xHErRAcoVkYziIPSoGZf6Rgh0ted5pZokh5P1kzWBsJHM3ISzW3IX4slBfZweZQLMCIpcBTFR/BABD9FzYKBnUQrmi+yZIJpGNQZmxNXVQAybg8qTayhVPOGAFvQ8boVEysxiUlqLKTmI05FpmrB9ESrZMR/Fa1ULUJARL2cMOlIJ9lz4NuPdZAWyp5OONMXZtDI1nRLCMlqwXA7ApUrzUEX8vz6JTbkhEf3a0vh4EvTlv3vTRuYk3Lg6mwAGW4etIAAAAAABrhAfrtrFhR4yubI7X5QRqMK6xKrj7U3XuBHdGnLqSqcQAAAAACSzpwAUFVV1YAAAAAAAdTH/EAACcQjEIPxn/xQVV6+Fv/qiA+BGAg0v0DAFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7";
             let bytes = base64::engine::general_purpose::STANDARD
-                .decode(hex)
+                .decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)
                 .unwrap();
             AccumulatorUpdateData::try_from_slice(bytes.as_ref()).unwrap()
         };
@@ -223,30 +280,7 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
             .map(|_| accumulator_update_data.clone())
             .collect::<Vec<_>>();
 
-        let guardian_set = vec![
-            "58CC3AE5C097b213cE3c81979e1B9f9570746AA5",
-            "fF6CB952589BDE862c25Ef4392132fb9D4A42157",
-            "114De8460193bdf3A2fCf81f86a09765F4762fD1",
-            "107A0086b32d7A0977926A205131d8731D39cbEB",
-            "8C82B2fd82FaeD2711d59AF0F2499D16e726f6b2",
-            "11b39756C042441BE6D8650b69b54EbE715E2343",
-            "54Ce5B4D348fb74B958e8966e2ec3dBd4958a7cd",
-            "15e7cAF07C4e3DC8e7C469f92C8Cd88FB8005a20",
-            "74a3bf913953D695260D88BC1aA25A4eeE363ef0",
-            "000aC0076727b35FBea2dAc28fEE5cCB0fEA768e",
-            "AF45Ced136b9D9e24903464AE889F5C8a723FC14",
-            "f93124b7c738843CBB89E864c862c38cddCccF95",
-            "D2CC37A4dc036a8D232b48f62cDD4731412f4890",
-            "DA798F6896A3331F64b48c12D1D57Fd9cbe70811",
-            "71AA1BE1D36CaFE3867910F99C09e347899C19C3",
-            "8192b6E7387CCd768277c17DAb1b7a5027c0b3Cf",
-            "178e21ad2E77AE06711549CFBB1f9c7a9d8096e8",
-            "5E1487F35515d02A92753504a8D75471b9f49EdB",
-            "6FbEBc898F403E4773E95feB15E80C9A99c8348d",
-        ]
-        .iter()
-        .map(|guardian| hex::decode(guardian).unwrap().try_into().unwrap())
-        .collect();
+        let guardian_set = GUARDIAN_SET.to_vec();
         Self::new(
             accumulator_update_data,
             guardian_set,
@@ -264,53 +298,484 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
     }
 }
 
+/// Run the native checks of [`PriceOracle::new`] and compute the public input data along with the
+/// commitment inputized by the circuit, `num_prices` being the number of prices per update.
+pub fn compute_public_input<E: Engine>(
+    accumulator_update_data: &[AccumulatorUpdateData],
+    guardian_set: &[[u8; 20]],
+    num_signature_to_verify: usize,
+    num_prices: usize,
+) -> Result<(PublicInputData<E>, E::Fr), anyhow::Error> {
+    compute_public_input_with_hash::<E>(
+        accumulator_update_data,
+        guardian_set,
+        num_signature_to_verify,
+        num_prices,
+        DEFAULT_COMMITMENT_HASH,
+    )
+}
+
+/// [`compute_public_input`] of a [`PriceOracle::with_commitment_hash`].
+pub fn compute_public_input_with_hash<E: Engine>(
+    accumulator_update_data: &[AccumulatorUpdateData],
+    guardian_set: &[[u8; 20]],
+    num_signature_to_verify: usize,
+    num_prices: usize,
+    hash: PriceCommitmentHash,
+) -> Result<(PublicInputData<E>, E::Fr), anyhow::Error> {
+    anyhow::ensure!(num_prices > 0, "at least one price per update is required");
+    // Updates are checked independently, only their publish time ordering is sequential.
+    let checked = crate::utils::par_map(accumulator_update_data, |i, data| {
+        let _span = tracing::debug_span!("accumulator_update", index = i).entered();
+        check_accumulator_update::<E>(
+            data,
+            guardian_set,
+            num_signature_to_verify,
+            num_prices,
+            hash,
+        )
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let mut last_publish_time = 0;
+    let mut earliest_publish_time = 0;
+    let mut prices_commitments = vec![];
+    for (publish_time, prices_commitment) in checked {
+        prices_commitments.push(prices_commitment);
+        // Check publish time is increasing
+        {
+            if publish_time < last_publish_time {
+                anyhow::bail!(
+                    "publish time is not increasing: {} <= {}",
+                    publish_time,
+                    last_publish_time
+                )
+            };
+            last_publish_time = publish_time;
+            if earliest_publish_time == 0 {
+                earliest_publish_time = last_publish_time;
+            }
+        }
+    }
+
+    let guardian_set_hash = compute_guardian_set_commitment::<E>(guardian_set)?;
+
+    let earliest_publish_time = fr_from_biguint::<E>(&BigUint::from(earliest_publish_time as u64))?;
+
+    let mut prices_num = E::Fr::zero();
+    let mut prices_commitment_base_sum = E::Fr::zero();
+    let mut prices_commitment = E::Fr::zero();
+    for mut commitment in prices_commitments.into_iter() {
+        Field::add_assign(&mut prices_commitment_base_sum, &commitment);
+        Field::add_assign(&mut prices_num, &E::Fr::one());
+        Field::mul_assign(&mut commitment, &prices_num);
+        Field::add_assign(&mut prices_commitment, &commitment);
+    }
+
+    let commitment = hash.hash::<E>(&[
+        guardian_set_hash,
+        earliest_publish_time,
+        prices_commitment,
+        prices_num,
+        prices_commitment_base_sum,
+    ])?;
+
+    let public_input_data = PublicInputData {
+        guardian_set_hash,
+        prices_summarize: PricesSummarize {
+            commitment: prices_commitment,
+            num: prices_num,
+            commitment_base_sum: prices_commitment_base_sum,
+        },
+        earliest_publish_time,
+    };
+    Ok((public_input_data, commitment))
+}
+
+pub(super) fn accumulator_vaa(
+    data: &AccumulatorUpdateData,
+) -> Result<wormhole_sdk::Vaa<&RawMessage>, anyhow::Error> {
+    let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, .. } = &data.proof;
+    Ok(serde_wormhole::from_slice(vaa.as_ref())?)
+}
+
+fn accumulator_slot(data: &AccumulatorUpdateData) -> Result<u64, anyhow::Error> {
+    let vaa = accumulator_vaa(data)?;
+    let message = WormholeMessage::try_from_bytes(vaa.payload.as_ref())
+        .map_err(|e| anyhow::anyhow!("invalid wormhole message: {:?}", e))?;
+    let WormholePayload::Merkle(root) = message.payload;
+    Ok(root.slot)
+}
+
+/// Check that the first `num_signature_to_verify` signatures of one accumulator update are from the
+/// guardians at their strictly increasing indices and that its messages are price feeds, returning
+/// the publish time of its first price and its prices commitment. Merkle proofs are left to the
+/// circuit.
+fn check_accumulator_update<E: Engine>(
+    data: &AccumulatorUpdateData,
+    guardian_set: &[[u8; 20]],
+    num_signature_to_verify: usize,
+    num_prices: usize,
+    hash: PriceCommitmentHash,
+) -> Result<(i64, E::Fr), anyhow::Error> {
+    let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } = data.proof.clone();
+    if updates.len() != num_prices {
+        anyhow::bail!("expected {} prices, got {}", num_prices, updates.len())
+    }
+    let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+        serde_wormhole::from_slice(vaa.as_ref())?;
+    // Check signatures in VAA
+    {
+        let (header, body): (Header, Body<&RawMessage>) = vaa.clone().into();
+        let digest = body.digest()?;
+        if header.signatures.len() < num_signature_to_verify {
+            anyhow::bail!(
+                "got {} signatures which is less than {}",
+                header.signatures.len(),
+                num_signature_to_verify
+            )
+        }
+        let signatures = &header.signatures[..num_signature_to_verify];
+        if signatures
+            .windows(2)
+            .any(|pair| pair[0].index >= pair[1].index)
+        {
+            anyhow::bail!("guardian indices of the signatures are not strictly increasing")
+        }
+        let pubkeys = signatures
+            .iter()
+            .map(|signature| {
+                let recid = RecoveryId::from_i32(signature.signature[64].into())?;
+                let pubkey = SECP256K1
+                    .recover_ecdsa(
+                        &secp256k1::Message::from_digest_slice(&digest.secp256k_hash)?,
+                        &secp256k1::ecdsa::RecoverableSignature::from_compact(
+                            &signature.signature[..64],
+                            recid,
+                        )?,
+                    )?
+                    .serialize_uncompressed();
+                Ok(pubkey[1..].to_vec())
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        let hashes = crate::keccak::keccak256_batch(&pubkeys);
+        for (signature, hash) in signatures.iter().zip(hashes) {
+            let address: [u8; 20] = hash[hash.len() - 20..].try_into()?;
+            if guardian_set.get(signature.index as usize) != Some(&address) {
+                anyhow::bail!("invalid signature {}", hex::encode(signature.signature));
+            }
+        }
+    }
+    // Compute price root
+    let mut price_feeds = vec![];
+    for price_update in updates {
+        let message: Vec<u8> = price_update.message.clone().into();
+        if let Message::PriceFeedMessage(price_feed) =
+            from_slice::<byteorder::BE, Message>(&message)?
+        {
+            price_feeds.push(price_feed);
+        } else {
+            anyhow::bail!("invalid price feed message")
+        };
+    }
+    let prices_commitment = {
+        let mut prices_commitment_members = vec![];
+        for price_feed in price_feeds.iter() {
+            let feed_id = {
+                // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
+                let mut bytes = [0u8; 16];
+                bytes[1..].copy_from_slice(&price_feed.feed_id[0..15]);
+                BigUint::from_bytes_be(&bytes)
+            };
+            // normalized_price = 10^(18-real_exponent) * price
+            let price = {
+                let exponent = (18 + price_feed.exponent) as u32;
+                let coefficient = BigUint::from(10u32).pow(exponent);
+                coefficient.mul(&BigUint::try_from(price_feed.price)?)
+            };
+            prices_commitment_members.push(fr_from_biguint::<E>(&feed_id)?);
+            prices_commitment_members.push(fr_from_biguint::<E>(&price)?);
+        }
+        hash.hash::<E>(&prices_commitment_members)?
+    };
+    Ok((price_feeds[0].publish_time, prices_commitment))
+}
+
 impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICES> {
     type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
 
     fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span = tracing::info_span!(
+            "pyth_oracle_synthesize",
+            num_vaas = self.accumulator_update_data.len(),
+            num_signatures = self.num_signature_to_verify,
+            num_prices = NUM_PRICES,
+        )
+        .entered();
+        synthesize_price_oracle(
+            cs,
+            |cs| {
+                allocate_witness(
+                    cs,
+                    self.guardian_set.iter().map(Some),
+                    self.accumulator_update_data.iter().cloned().map(Some),
+                    self.num_signature_to_verify,
+                )
+            },
+            Some(self.public_input_data.prices_summarize.commitment),
+            Some(self.commitment),
+            self.options(),
+        )
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+/// [`PriceOracle`] verifying `NUM_SIGS` signatures of every VAA, fixed by the type like the number
+/// of prices so that circuits of different shapes cannot be mixed up.
+#[derive(Clone, Debug)]
+pub struct PythOracleCircuit<E: Engine, const NUM_SIGS: usize, const NUM_PRICES: usize> {
+    oracle: PriceOracle<E, NUM_PRICES>,
+}
+
+impl<E: Engine, const NUM_SIGS: usize, const NUM_PRICES: usize>
+    PythOracleCircuit<E, NUM_SIGS, NUM_PRICES>
+{
+    pub fn new(
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+        guardian_set: Vec<[u8; 20]>,
+    ) -> Result<Self, anyhow::Error> {
+        PriceOracle::new(accumulator_update_data, guardian_set, NUM_SIGS)?.try_into()
+    }
+
+    pub fn oracle(&self) -> &PriceOracle<E, NUM_PRICES> {
+        &self.oracle
+    }
+
+    pub fn into_oracle(self) -> PriceOracle<E, NUM_PRICES> {
+        self.oracle
+    }
+}
+
+impl<E: Engine, const NUM_SIGS: usize, const NUM_PRICES: usize> TryFrom<PriceOracle<E, NUM_PRICES>>
+    for PythOracleCircuit<E, NUM_SIGS, NUM_PRICES>
+{
+    type Error = anyhow::Error;
+
+    fn try_from(oracle: PriceOracle<E, NUM_PRICES>) -> Result<Self, Self::Error> {
+        anyhow::ensure!(
+            oracle.num_signature_to_verify == NUM_SIGS,
+            "oracle verifies {} signatures, expected {}",
+            oracle.num_signature_to_verify,
+            NUM_SIGS
+        );
+        Ok(Self { oracle })
+    }
+}
+
+impl<E: Engine, const NUM_SIGS: usize, const NUM_PRICES: usize> Circuit<E>
+    for PythOracleCircuit<E, NUM_SIGS, NUM_PRICES>
+{
+    type MainGate = <PriceOracle<E, NUM_PRICES> as Circuit<E>>::MainGate;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        self.oracle.synthesize(cs)
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        PriceOracle::<E, NUM_PRICES>::declare_used_gates()
+    }
+}
+
+/// Shape of a [`PriceOracle`] without any witness, synthesized into the same gates so that proving
+/// and verification keys can be generated before any VAA is available.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceOracleSetup<E: Engine, const NUM_PRICES: usize> {
+    pub num_vaas: usize,
+    pub num_signature_to_verify: usize,
+    pub guardian_set_size: usize,
+    pub bind_slot: bool,
+    pub bind_guardian_set_index: bool,
+    pub bind_vaa_digest: bool,
+    pub enforce_sequence_order: bool,
+    pub environment: PythEnvironment,
+    pub commitment_hash: PriceCommitmentHash,
+    _marker: std::marker::PhantomData<E>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize> PriceOracleSetup<E, NUM_PRICES> {
+    pub fn new(num_vaas: usize, num_signature_to_verify: usize, guardian_set_size: usize) -> Self {
+        Self {
+            num_vaas,
+            num_signature_to_verify,
+            guardian_set_size,
+            bind_slot: false,
+            bind_guardian_set_index: false,
+            bind_vaa_digest: false,
+            enforce_sequence_order: false,
+            environment: PythEnvironment::Mainnet,
+            commitment_hash: DEFAULT_COMMITMENT_HASH,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> From<&PriceOracle<E, NUM_PRICES>>
+    for PriceOracleSetup<E, NUM_PRICES>
+{
+    fn from(oracle: &PriceOracle<E, NUM_PRICES>) -> Self {
+        Self {
+            bind_slot: oracle.bind_slot,
+            bind_guardian_set_index: oracle.bind_guardian_set_index,
+            bind_vaa_digest: oracle.bind_vaa_digest,
+            enforce_sequence_order: oracle.enforce_sequence_order,
+            environment: oracle.environment,
+            commitment_hash: oracle.commitment_hash,
+            ..Self::new(
+                oracle.accumulator_update_data.len(),
+                oracle.num_signature_to_verify,
+                oracle.guardian_set.len(),
+            )
+        }
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracleSetup<E, NUM_PRICES> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span = tracing::info_span!(
+            "pyth_oracle_setup_synthesize",
+            num_vaas = self.num_vaas,
+            num_signatures = self.num_signature_to_verify,
+            num_prices = NUM_PRICES,
+        )
+        .entered();
+        synthesize_price_oracle(
+            cs,
+            |cs| {
+                allocate_witness(
+                    cs,
+                    (0..self.guardian_set_size).map(|_| None),
+                    (0..self.num_vaas).map(|_| None),
+                    self.num_signature_to_verify,
+                )
+            },
+            None,
+            None,
+            OracleOptions {
+                bind_slot: self.bind_slot,
+                bind_guardian_set_index: self.bind_guardian_set_index,
+                bind_vaa_digest: self.bind_vaa_digest,
+                enforce_sequence_order: self.enforce_sequence_order,
+                environment: self.environment,
+                commitment_hash: self.commitment_hash,
+            },
+        )
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        PriceOracle::<E, NUM_PRICES>::declare_used_gates()
+    }
+}
+
+/// Guardian set and price updates of each VAA.
+type Allocated<E, const NUM_PRICES: usize> =
+    (Vec<Address<E>>, Vec<PriceUpdates<E, NUM_PRICES, 10>>);
+
+/// Allocate the guardian set and the price updates of each VAA, shared by [`PriceOracle`] and
+/// [`PriceOracleSetup`] so that both synthesize the same gates. `None` witnesses are absent.
+fn allocate_witness<'a, E, CS, const NUM_PRICES: usize>(
+    cs: &mut CS,
+    guardian_set: impl Iterator<Item = Option<&'a [u8; 20]>>,
+    accumulator_update_data: impl Iterator<Item = Option<AccumulatorUpdateData>>,
+    num_signature_to_verify: usize,
+) -> Result<Allocated<E, NUM_PRICES>, SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+{
+    let guardian_set = guardian_set
+        .map(|w| Address::from_optional_witness(cs, w))
+        .collect::<Result<Vec<_>, _>>()?;
+    let price_updates_batch = accumulator_update_data
+        .map(|data| PriceUpdates::from_optional_witness(cs, data, num_signature_to_verify))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((guardian_set, price_updates_batch))
+}
+
+/// Options shared by [`PriceOracle`] and [`PriceOracleSetup`], which change the gates.
+#[derive(Debug, Clone, Copy)]
+struct OracleOptions {
+    bind_slot: bool,
+    bind_guardian_set_index: bool,
+    bind_vaa_digest: bool,
+    enforce_sequence_order: bool,
+    environment: PythEnvironment,
+    commitment_hash: PriceCommitmentHash,
+}
+
+/// Gates of [`PriceOracle`], with the guardian set and the updates allocated by `allocate`.
+fn synthesize_price_oracle<E, CS, F, const NUM_PRICES: usize>(
+    cs: &mut CS,
+    allocate: F,
+    expected_prices_commitment: Option<E::Fr>,
+    expected_commitment: Option<E::Fr>,
+    options: OracleOptions,
+) -> Result<(), SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+    F: FnOnce(&mut CS) -> Result<Allocated<E, NUM_PRICES>, SynthesisError>,
+{
+    // Curve parameters and keccak tables are set up once and shared by every VAA.
+    let (secp, keccak) = section(cs, "setup", |cs| {
         crate::utils::add_bitwise_logic_and_range_table(cs)?;
         let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
         circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+        Ok((Secp256k1Params::new(cs), Keccak256Params::new(cs)?))
+    })?;
 
-        let guardian_set = self
-            .guardian_set
-            .iter()
-            .map(|w| Address::from_address_witness(cs, w))
-            .collect::<Result<Vec<_>, _>>()?;
-        let mut price_updates_batch = vec![];
-        // Construct circuit variable from witness
-        for accumulator_update_data in self.accumulator_update_data.clone() {
-            let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } =
-                accumulator_update_data.proof;
-            let vaa = {
-                let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
-                    serde_wormhole::from_slice(vaa.as_ref()).unwrap();
-                Vaa::<_>::from_vaa_witness(cs, vaa, self.num_signature_to_verify)?
-            };
-            let price_updates: [_; NUM_PRICES] = {
-                let updates = updates
-                    .into_iter()
-                    .map(|u| PriceUpdate::<_>::from_price_update_witness(cs, u))
-                    .collect::<Result<Vec<_>, _>>()?;
-                let len = updates.len();
-                updates.try_into().map_err(|_| {
-                    new_synthesis_error(format!("expected {} prices, got {}", NUM_PRICES, len))
-                })?
-            };
-            price_updates_batch.push(PriceUpdates { vaa, price_updates });
-        }
+    // Construct circuit variable from witness
+    let allocate_span = tracing::debug_span!("allocate_witness").entered();
+    let (guardian_set, price_updates_batch) = section(cs, "allocate_witness", allocate)?;
+    drop(allocate_span);
 
-        let last_publish_time = UInt64::zero().into_num();
-        let mut is_publish_time_increasing = Boolean::constant(true);
-        let mut prices_commitments = vec![];
-        for price_updates in price_updates_batch.iter() {
-            // Check signatures in VAA
-            {
-                let is_valid = price_updates.check_by_address(cs, &guardian_set)?;
+    let last_publish_time = UInt64::zero().into_num();
+    let mut is_publish_time_increasing = Boolean::constant(true);
+    let mut prices_commitments = vec![];
+    for (i, price_updates) in price_updates_batch.iter().enumerate() {
+        let _span = tracing::debug_span!("accumulator_update", index = i).entered();
+        // Check signatures in VAA
+        {
+            let _span = tracing::debug_span!("verify_vaa").entered();
+            section(cs, "verify_vaa", |cs| {
+                let is_valid = price_updates.check_by_address_with_params(
+                    cs,
+                    &secp,
+                    &keccak,
+                    &guardian_set,
+                )?;
                 Boolean::enforce_equal(cs, &is_valid, &Boolean::Constant(true))?;
-            }
-            // Compute price root
-            {
+                let is_from_emitter = price_updates.vaa.check_emitter(cs, options.environment)?;
+                Boolean::enforce_equal(cs, &is_from_emitter, &Boolean::Constant(true))?;
+                let is_accumulator_message = price_updates.vaa.body.payload.check_format(cs)?;
+                Boolean::enforce_equal(cs, &is_accumulator_message, &Boolean::Constant(true))?;
+                let is_supported_version = price_updates.vaa.check_version(cs)?;
+                Boolean::enforce_equal(cs, &is_supported_version, &Boolean::Constant(true))?;
+                Ok(())
+            })?;
+        }
+        // Compute price root
+        {
+            let _span = tracing::debug_span!("commit_prices").entered();
+            section(cs, "commit_prices", |cs| {
                 let mut prices_commitment_members = vec![];
                 for price_update in price_updates.price_updates {
                     let price_feed = price_update.message;
@@ -368,12 +833,16 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
                     prices_commitment_members.push(feed_id);
                     prices_commitment_members.push(price);
                 }
-                let prices_commitment =
-                    circuit_poseidon_hash(cs, prices_commitment_members.as_slice())?;
+                let prices_commitment = options
+                    .commitment_hash
+                    .circuit_hash(cs, prices_commitment_members.as_slice())?;
                 prices_commitments.push(prices_commitment);
-            }
-            // Check publish time is increasing
-            {
+                Ok(())
+            })?;
+        }
+        // Check publish time is increasing
+        {
+            is_publish_time_increasing = section(cs, "check_publish_time", |cs| {
                 let publish_time = {
                     let mut publish_time = price_updates.price_updates[0].message.publish_time;
                     publish_time.reverse();
@@ -386,16 +855,31 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
                     &current_publish_time_is_equal,
                     &current_publish_time_is_greater,
                 )?;
-                is_publish_time_increasing = Boolean::and(
+                Boolean::and(
                     cs,
                     &is_publish_time_increasing,
                     &current_publish_time_is_equal_or_greater,
-                )?;
-            }
+                )
+            })?;
         }
+    }
 
-        Boolean::enforce_equal(cs, &is_publish_time_increasing, &Boolean::Constant(true))?;
+    Boolean::enforce_equal(cs, &is_publish_time_increasing, &Boolean::Constant(true))?;
 
+    if options.enforce_sequence_order {
+        section(cs, "check_sequence", |cs| {
+            let vaas = price_updates_batch
+                .iter()
+                .map(|price_updates| &price_updates.vaa)
+                .collect::<Vec<_>>();
+            let is_sequence_increasing = is_sequence_increasing(cs, &vaas)?;
+            Boolean::enforce_equal(cs, &is_sequence_increasing, &Boolean::Constant(true))?;
+            Ok(())
+        })?;
+    }
+
+    let _span = tracing::debug_span!("public_input").entered();
+    section(cs, "public_input", |cs| {
         let mut prices_commitment_base_sum = Num::zero();
         let mut prices_commitment = Num::zero();
         let mut prices_num = Num::zero();
@@ -407,17 +891,12 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
         }
 
         {
-            let expected_prices_commitment =
-                Num::alloc(cs, Some(self.public_input_data.prices_summarize.commitment))?;
+            let expected_prices_commitment = Num::alloc(cs, expected_prices_commitment)?;
             expected_prices_commitment.enforce_equal(cs, &prices_commitment)?;
         }
 
         // Compute guardian set hash
-        let guardian_set_num = guardian_set
-            .iter()
-            .map(|g| g.inner().to_num_unchecked(cs))
-            .collect::<Result<Vec<_>, _>>()?;
-        let guardian_set_hash = circuit_poseidon_hash(cs, &guardian_set_num)?;
+        let guardian_set_hash = GuardianSet::new(guardian_set).commitment(cs)?;
 
         let earliest_publish_time = {
             let mut earliest_publish_time = if let Some(batch) = price_updates_batch.first() {
@@ -428,7 +907,7 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
             earliest_publish_time.reverse();
             UInt64::from_bytes_le(cs, &earliest_publish_time)?.into_num()
         };
-        let commitment = circuit_poseidon_hash(
+        let commitment = options.commitment_hash.circuit_hash(
             cs,
             &[
                 guardian_set_hash,
@@ -439,19 +918,48 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
             ],
         )?;
 
-        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        let expected_commitment = Num::alloc(cs, expected_commitment)?;
         expected_commitment.enforce_equal(cs, &commitment)?;
         expected_commitment.get_variable().inputize(cs)?;
 
+        if options.bind_slot {
+            let mut slots = vec![];
+            for price_updates in price_updates_batch.iter() {
+                slots.push(price_updates.vaa.body.slot_u64(cs)?.into_num());
+            }
+            inputize_shared(cs, &slots)?;
+        }
+        if options.bind_guardian_set_index {
+            let mut indices = vec![];
+            for price_updates in price_updates_batch.iter() {
+                indices.push(price_updates.vaa.guardian_set_index(cs)?.into_num());
+            }
+            inputize_shared(cs, &indices)?;
+        }
+        if options.bind_vaa_digest {
+            for price_updates in price_updates_batch.iter() {
+                let digest = price_updates
+                    .vaa
+                    .body_digest_bytes_with_params(cs, &keccak)?;
+                for half in digest.chunks(16) {
+                    num_from_be_bytes(cs, half)?.get_variable().inputize(cs)?;
+                }
+            }
+        }
         Ok(())
-    }
+    })
+}
 
-    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
-        Ok(vec![
-            Self::MainGate::default().into_internal(),
-            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
-        ])
+/// Inputize the value shared by all `values`, enforcing that they are equal.
+fn inputize_shared<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    values: &[Num<E>],
+) -> Result<(), SynthesisError> {
+    let shared = Num::alloc(cs, values.first().and_then(|v| v.get_value()))?;
+    for value in values.iter() {
+        shared.enforce_equal(cs, value)?;
     }
+    shared.get_variable().inputize(cs)
 }
 
 // Gates of the circuit for the 13 signatures and 4 prices
@@ -481,4 +989,205 @@ mod tests {
         println!("circuit contains {} gates", cs.n());
         Ok(())
     }
+
+    #[test]
+    fn test_pyth_oracle_circuit() -> Result<(), anyhow::Error> {
+        use super::PythOracleCircuit;
+
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 2);
+        let circuit = PythOracleCircuit::<Bn256, 2, 3>::new(
+            oracle.accumulator_update_data.clone(),
+            oracle.guardian_set.clone(),
+        )?;
+        assert_eq!(circuit.oracle().commitment, oracle.commitment);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        assert!(PythOracleCircuit::<Bn256, 1, 3>::try_from(oracle).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_circuit() -> Result<(), anyhow::Error> {
+        use advanced_circuit_component::franklin_crypto::bellman::{
+            kate_commitment::{Crs, CrsForMonomialForm},
+            plonk::better_better_cs::{
+                cs::{PlonkCsWidth4WithNextStepAndCustomGatesParams, SetupAssembly},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+                setup::VerificationKey,
+            },
+            worker::Worker,
+        };
+
+        use super::PriceOracleSetup;
+        use crate::pyth::setup::generate_keys;
+
+        let price_oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        price_oracle.synthesize(&mut cs)?;
+
+        let shape = PriceOracleSetup::from(&price_oracle);
+        let mut setup = SetupAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        shape.synthesize(&mut setup)?;
+        assert_eq!(setup.n(), cs.n());
+        assert_eq!(setup.num_inputs, cs.num_inputs);
+
+        // Same gates and selectors, so the keys of the shape verify proofs of the circuit.
+        let worker = Worker::new();
+        let crs = Crs::<Bn256, CrsForMonomialForm>::crs_42(cs.n() + 1, &worker);
+        let mut assembly = SetupAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        price_oracle.synthesize(&mut assembly)?;
+        assembly.finalize();
+        let circuit_setup = assembly.create_setup::<PriceOracle<Bn256, 3>>(&worker)?;
+        let circuit_vk = VerificationKey::from_setup(&circuit_setup, &worker, &crs)?;
+        let (_, shape_vk) = generate_keys(&shape, &crs)?;
+        let (mut circuit_vk_bytes, mut shape_vk_bytes) = (vec![], vec![]);
+        circuit_vk.write(&mut circuit_vk_bytes)?;
+        shape_vk.write(&mut shape_vk_bytes)?;
+        assert_eq!(shape_vk_bytes, circuit_vk_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slot_binding() -> Result<(), anyhow::Error> {
+        use crate::testing::fixtures::AccumulatorFixture;
+
+        let fixture = AccumulatorFixture::default();
+        let data = |slot| {
+            AccumulatorFixture {
+                slot,
+                ..fixture.clone()
+            }
+            .build()
+        };
+        let oracle =
+            PriceOracle::<Bn256, 1>::new(vec![data(7), data(7)], fixture.guardian_set(), 1)?
+                .with_slot_binding()?;
+        assert_eq!(oracle.slot()?, 7);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        // The commitment, then the slot.
+        assert_eq!(cs.num_inputs, 2);
+
+        let mixed =
+            PriceOracle::<Bn256, 1>::new(vec![data(7), data(8)], fixture.guardian_set(), 1)?;
+        assert!(mixed.with_slot_binding().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guardian_set_index_binding() -> Result<(), anyhow::Error> {
+        use crate::testing::fixtures::AccumulatorFixture;
+
+        let fixture = AccumulatorFixture::default();
+        let data = |guardian_set_index| {
+            AccumulatorFixture {
+                guardian_set_index,
+                ..fixture.clone()
+            }
+            .build()
+        };
+        let oracle = PriceOracle::<Bn256, 1>::new(vec![data(3)], fixture.guardian_set(), 1)?
+            .with_slot_binding()?
+            .with_guardian_set_index_binding()?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        // The commitment, the slot, then the guardian set index.
+        assert_eq!(cs.num_inputs, 3);
+
+        let mixed =
+            PriceOracle::<Bn256, 1>::new(vec![data(3), data(4)], fixture.guardian_set(), 1)?;
+        assert!(mixed.with_guardian_set_index_binding().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vaa_digest_binding() -> Result<(), anyhow::Error> {
+        use crate::testing::fixtures::{keccak256, AccumulatorFixture};
+
+        let fixture = AccumulatorFixture::default();
+        let oracle =
+            PriceOracle::<Bn256, 1>::new(vec![fixture.build()], fixture.guardian_set(), 1)?
+                .with_vaa_digest_binding();
+        let digests = oracle.vaa_digests()?;
+        assert_eq!(digests, vec![keccak256(&keccak256(&fixture.vaa_body()))]);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        // The commitment, then the two halves of the digest.
+        assert_eq!(cs.num_inputs, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_emitter_binding() -> Result<(), anyhow::Error> {
+        use crate::{pyth::PythEnvironment, testing::fixtures::AccumulatorFixture};
+
+        let fixture = AccumulatorFixture::default();
+        let foreign = AccumulatorFixture {
+            emitter_address: [7u8; 32],
+            ..fixture.clone()
+        };
+        assert!(
+            PriceOracle::<Bn256, 1>::new(vec![foreign.build()], fixture.guardian_set(), 1).is_err()
+        );
+        assert!(PriceOracle::<Bn256, 1>::new_in(
+            PythEnvironment::Testnet,
+            vec![fixture.build()],
+            fixture.guardian_set(),
+            1
+        )
+        .is_err());
+
+        // Without the native check, a VAA of another emitter does not satisfy the circuit.
+        let mut oracle =
+            PriceOracle::<Bn256, 1>::new(vec![fixture.build()], fixture.guardian_set(), 1)?;
+        oracle.environment = PythEnvironment::Testnet;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sequence_ordering() -> Result<(), anyhow::Error> {
+        use crate::testing::fixtures::AccumulatorFixture;
+
+        let fixture = AccumulatorFixture::default();
+        let data = |sequence| {
+            AccumulatorFixture {
+                sequence,
+                ..fixture.clone()
+            }
+            .build()
+        };
+        let oracle =
+            PriceOracle::<Bn256, 1>::new(vec![data(7), data(8)], fixture.guardian_set(), 1)?
+                .with_sequence_ordering()?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        let replayed =
+            PriceOracle::<Bn256, 1>::new(vec![data(7), data(7)], fixture.guardian_set(), 1)?;
+        assert!(replayed.clone().with_sequence_ordering().is_err());
+        // Without the native check, the circuit is not satisfied.
+        let mut replayed = replayed;
+        replayed.enforce_sequence_order = true;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        replayed.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
 }
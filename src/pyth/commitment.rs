@@ -0,0 +1,350 @@
+//! Keccak commitment to verified prices, for verifiers where keccak is cheaper than poseidon.
+//!
+//! Each price is packed as `feed_id (32) || price (8) || publish_time (8)`, big-endian as in the
+//! price feed message, the price rescaled to [`COMMITTED_DECIMALS`] so that a committed integer means
+//! the same whatever the oracle and exponent. The packed prices are concatenated in order and hashed
+//! with keccak256. The digest is split into its high and low 16 bytes, each fitting a field
+//! element.
+//!
+//! For verifiers living inside another SNARK rather than the EVM, [`PriceCommitmentHash`] selects
+//! a Poseidon or Rescue sponge instead: each packed price is split into three 16-byte big-endian
+//! field elements, and the two elements squeezed from the sponge replace the digest halves.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use super::PriceFeed;
+use crate::{
+    error::OracleError,
+    gadgets::{
+        keccak256::{self, Keccak256Params},
+        poseidon::{circuit_poseidon_sponge, poseidon_sponge},
+        price_math::{compute_rescaled_i64, rescale_to_i64, ScaledPrice},
+        rescue::{circuit_rescue_sponge, rescue_sponge},
+    },
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+/// Bytes of a packed price.
+pub const LEN_PACKED_PRICE: usize = 32 + 8 + 8;
+/// Decimals of the prices committed by oracles of a fixed precision, as the `-8` exponent of most
+/// pyth feeds.
+pub const COMMITTED_DECIMALS: u32 = 8;
+/// Bytes of the digest in each of the two field elements.
+const LEN_HALF_DIGEST: usize = 16;
+/// Bytes of each field element a packed price is split into for an algebraic hash.
+const LEN_ALGEBRAIC_CHUNK: usize = 16;
+
+/// Hash of the price commitment, of attestation circuits over packed bytes and of
+/// [`PriceOracle`](super::PriceOracle) over field elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceCommitmentHash {
+    /// Cheapest to recompute on the EVM, over bytes only.
+    #[default]
+    Keccak256,
+    /// Cheapest in-circuit, for recursive verifiers.
+    Poseidon,
+    /// The sponge of sync_vm, with its custom gate.
+    Rescue,
+}
+
+impl PriceCommitmentHash {
+    /// Whether the hash takes field elements, as required by [`PriceOracle`](super::PriceOracle).
+    pub fn is_algebraic(self) -> bool {
+        match self {
+            PriceCommitmentHash::Keccak256 => false,
+            PriceCommitmentHash::Poseidon | PriceCommitmentHash::Rescue => true,
+        }
+    }
+
+    /// First element of [`PriceCommitmentHash::circuit_sponge`].
+    pub fn circuit_hash<E: Engine, CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS,
+        input: &[Num<E>],
+    ) -> Result<Num<E>, SynthesisError> {
+        Ok(self.circuit_sponge(cs, input)?[0])
+    }
+
+    /// Squeezed rate of the sponge over `input`, failing for a hash which is not algebraic.
+    pub fn circuit_sponge<E: Engine, CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS,
+        input: &[Num<E>],
+    ) -> Result<[Num<E>; 2], SynthesisError> {
+        match self {
+            PriceCommitmentHash::Keccak256 => Err(not_algebraic()),
+            PriceCommitmentHash::Poseidon => circuit_poseidon_sponge(cs, input),
+            PriceCommitmentHash::Rescue => circuit_rescue_sponge(cs, input),
+        }
+    }
+
+    /// Native [`PriceCommitmentHash::circuit_hash`].
+    pub fn hash<E: Engine>(self, input: &[E::Fr]) -> Result<E::Fr, SynthesisError> {
+        Ok(self.sponge::<E>(input)?[0])
+    }
+
+    /// Native [`PriceCommitmentHash::circuit_sponge`].
+    pub fn sponge<E: Engine>(self, input: &[E::Fr]) -> Result<[E::Fr; 2], SynthesisError> {
+        match self {
+            PriceCommitmentHash::Keccak256 => Err(not_algebraic()),
+            PriceCommitmentHash::Poseidon => Ok(poseidon_sponge::<E>(input)),
+            PriceCommitmentHash::Rescue => Ok(rescue_sponge::<E>(input)),
+        }
+    }
+}
+
+fn not_algebraic() -> SynthesisError {
+    new_synthesis_error("keccak256 does not hash field elements")
+}
+
+/// Packed bytes of `price_feed`, its price rescaled to [`COMMITTED_DECIMALS`] as the prices of
+/// other oracles, and whether it can be committed, see [`rescale_to_i64`].
+pub fn pack_price<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price_feed: &PriceFeed<E>,
+) -> Result<(Boolean, [Byte<E>; LEN_PACKED_PRICE]), SynthesisError> {
+    let (is_ok, price) = rescale_to_i64(cs, &ScaledPrice::from(price_feed), COMMITTED_DECIMALS)?;
+    let mut bytes = [Byte::zero(); LEN_PACKED_PRICE];
+    bytes[..32].copy_from_slice(&price_feed.feed_id);
+    bytes[32..40].copy_from_slice(&price);
+    bytes[40..].copy_from_slice(&price_feed.publish_time);
+    Ok((is_ok, bytes))
+}
+
+/// High and low halves of the keccak256 of the packed `price_feeds`, enforcing that all of them can
+/// be committed.
+pub fn price_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price_feeds: &[PriceFeed<E>],
+) -> Result<[Num<E>; 2], SynthesisError> {
+    let mut packed = vec![];
+    for price_feed in price_feeds {
+        let (is_ok, bytes) = pack_price(cs, price_feed)?;
+        Boolean::enforce_equal(cs, &is_ok, &Boolean::constant(true))?;
+        packed.push(bytes);
+    }
+    packed_price_commitment(cs, &packed)
+}
+
+/// [`price_commitment`] of prices already packed, by any oracle.
+pub fn packed_price_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    packed: &[[Byte<E>; LEN_PACKED_PRICE]],
+) -> Result<[Num<E>; 2], SynthesisError> {
+    packed_price_commitment_with_hash(cs, packed, PriceCommitmentHash::Keccak256)
+}
+
+/// Like [`packed_price_commitment`], with the keccak gadget shared with other gadgets.
+pub fn packed_price_commitment_with_params<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    keccak: &Keccak256Params<E>,
+    packed: &[[Byte<E>; LEN_PACKED_PRICE]],
+) -> Result<[Num<E>; 2], SynthesisError> {
+    let bytes = packed.iter().flatten().copied().collect::<Vec<_>>();
+    let digest = keccak256::digest_with_params(cs, keccak, &bytes)?;
+    Ok([
+        num_from_be_bytes(cs, &digest[..LEN_HALF_DIGEST])?,
+        num_from_be_bytes(cs, &digest[LEN_HALF_DIGEST..])?,
+    ])
+}
+
+/// [`packed_price_commitment`] with the given `hash`.
+pub fn packed_price_commitment_with_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    packed: &[[Byte<E>; LEN_PACKED_PRICE]],
+    hash: PriceCommitmentHash,
+) -> Result<[Num<E>; 2], SynthesisError> {
+    match hash {
+        PriceCommitmentHash::Keccak256 => {
+            let keccak = Keccak256Params::new(cs)?;
+            packed_price_commitment_with_params(cs, &keccak, packed)
+        }
+        PriceCommitmentHash::Poseidon | PriceCommitmentHash::Rescue => {
+            let bytes = packed.iter().flatten().copied().collect::<Vec<_>>();
+            let input = bytes
+                .chunks(LEN_ALGEBRAIC_CHUNK)
+                .map(|chunk| num_from_be_bytes(cs, chunk))
+                .collect::<Result<Vec<_>, _>>()?;
+            hash.circuit_sponge(cs, &input)
+        }
+    }
+}
+
+/// Compute [`price_commitment`] and inputize its halves, high first.
+pub fn inputize_price_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price_feeds: &[PriceFeed<E>],
+) -> Result<[Num<E>; 2], SynthesisError> {
+    let commitment = price_commitment(cs, price_feeds)?;
+    for half in commitment.iter() {
+        half.get_variable().inputize(cs)?;
+    }
+    Ok(commitment)
+}
+
+/// Native [`price_commitment`] of `prices`, the public inputs expected by the verifier.
+pub fn compute_price_commitment<E: Engine>(
+    prices: &[pythnet_sdk::messages::PriceFeedMessage],
+) -> Result<[E::Fr; 2], SynthesisError> {
+    let packed = prices
+        .iter()
+        .map(pack_price_message)
+        .collect::<Result<Vec<_>, _>>()?;
+    compute_packed_price_commitment::<E>(&packed)
+}
+
+/// Native [`pack_price`], failing if the price cannot be committed.
+pub fn pack_price_message(
+    price: &pythnet_sdk::messages::PriceFeedMessage,
+) -> Result<[u8; LEN_PACKED_PRICE], OracleError> {
+    let committed = compute_rescaled_i64(price.price, price.exponent, COMMITTED_DECIMALS).ok_or(
+        OracleError::UncommittablePrice {
+            price: price.price,
+            exponent: price.exponent,
+            decimals: COMMITTED_DECIMALS,
+        },
+    )?;
+    let mut bytes = [0u8; LEN_PACKED_PRICE];
+    bytes[..32].copy_from_slice(&price.feed_id);
+    bytes[32..40].copy_from_slice(&committed.to_be_bytes());
+    bytes[40..].copy_from_slice(&price.publish_time.to_be_bytes());
+    Ok(bytes)
+}
+
+/// Native [`packed_price_commitment`].
+pub fn compute_packed_price_commitment<E: Engine>(
+    packed: &[[u8; LEN_PACKED_PRICE]],
+) -> Result<[E::Fr; 2], SynthesisError> {
+    compute_packed_price_commitment_with_hash::<E>(packed, PriceCommitmentHash::Keccak256)
+}
+
+/// Native [`packed_price_commitment_with_hash`].
+pub fn compute_packed_price_commitment_with_hash<E: Engine>(
+    packed: &[[u8; LEN_PACKED_PRICE]],
+    hash: PriceCommitmentHash,
+) -> Result<[E::Fr; 2], SynthesisError> {
+    let bytes = packed.concat();
+    match hash {
+        PriceCommitmentHash::Keccak256 => {
+            let digest = crate::keccak::keccak256(&bytes);
+            Ok([
+                fr_from_biguint::<E>(&BigUint::from_bytes_be(&digest[..LEN_HALF_DIGEST]))?,
+                fr_from_biguint::<E>(&BigUint::from_bytes_be(&digest[LEN_HALF_DIGEST..]))?,
+            ])
+        }
+        PriceCommitmentHash::Poseidon | PriceCommitmentHash::Rescue => {
+            let input = bytes
+                .chunks(LEN_ALGEBRAIC_CHUNK)
+                .map(|chunk| fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk)))
+                .collect::<Result<Vec<_>, _>>()?;
+            hash.sponge::<E>(&input)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+    use pythnet_sdk::{messages::Message, wire::from_slice};
+
+    use super::{
+        compute_packed_price_commitment, compute_packed_price_commitment_with_hash,
+        compute_price_commitment, inputize_price_commitment, pack_price_message,
+        packed_price_commitment_with_hash, price_commitment, PriceCommitmentHash, LEN_PACKED_PRICE,
+    };
+    use crate::{
+        pyth::PriceFeed, testing::fixtures::FixturePrice,
+        utils::testing::create_test_constraint_system,
+    };
+
+    #[test]
+    fn test_price_commitment() -> Result<(), SynthesisError> {
+        let messages = [
+            FixturePrice::new([1u8; 32], 4_200_000_000, -8, 1_700_000_000),
+            FixturePrice::new([2u8; 32], 5, -2, 1_700_000_001),
+        ]
+        .iter()
+        .map(|price| price.to_bytes())
+        .collect::<Vec<_>>();
+        let prices = messages
+            .iter()
+            .map(|bytes| match from_slice::<byteorder::BE, Message>(bytes) {
+                Ok(Message::PriceFeedMessage(price)) => price,
+                _ => panic!("expect price feed message"),
+            })
+            .collect::<Vec<_>>();
+        let expected = compute_price_commitment::<Bn256>(&prices)?;
+        // Committed with 8 decimals whatever the exponent.
+        assert_eq!(
+            pack_price_message(&prices[1]).unwrap()[32..40],
+            5_000_000i64.to_be_bytes()
+        );
+        let mut negative = prices[1];
+        negative.price = -5;
+        assert!(pack_price_message(&negative).is_err());
+
+        let cs = &mut create_test_constraint_system()?;
+        let price_feeds = messages
+            .iter()
+            .map(|bytes| PriceFeed::new_from_slice(cs, bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitment = inputize_price_commitment(cs, &price_feeds)?;
+        assert_eq!(commitment.map(|half| half.get_value()), expected.map(Some));
+        assert!(cs.is_satisfied());
+
+        // The order of the prices is committed.
+        let mut reversed = price_feeds.clone();
+        reversed.reverse();
+        let commitment = price_commitment(cs, &reversed)?;
+        assert_ne!(commitment.map(|half| half.get_value()), expected.map(Some));
+        Ok(())
+    }
+
+    #[test]
+    fn test_algebraic_price_commitment() -> Result<(), SynthesisError> {
+        let packed = [
+            FixturePrice::new([1u8; 32], 4_200_000_000, -8, 1_700_000_000),
+            FixturePrice::new([2u8; 32], 5, -2, 1_700_000_001),
+        ]
+        .map(|price| {
+            let mut packed = [0u8; LEN_PACKED_PRICE];
+            packed[..32].copy_from_slice(&price.feed_id);
+            packed[32..40].copy_from_slice(&price.price.to_be_bytes());
+            packed[40..].copy_from_slice(&price.publish_time.to_be_bytes());
+            packed
+        });
+        let keccak = compute_packed_price_commitment::<Bn256>(&packed)?;
+
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = packed
+            .iter()
+            .map(|packed| {
+                let bytes = packed
+                    .iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(bytes.try_into().unwrap())
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        for hash in [PriceCommitmentHash::Poseidon, PriceCommitmentHash::Rescue] {
+            let expected = compute_packed_price_commitment_with_hash::<Bn256>(&packed, hash)?;
+            assert_ne!(expected, keccak);
+            let commitment = packed_price_commitment_with_hash(cs, &allocated, hash)?;
+            assert_eq!(commitment.map(|half| half.get_value()), expected.map(Some));
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
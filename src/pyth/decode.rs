@@ -0,0 +1,318 @@
+//! Human-readable decoding of VAAs and accumulator updates.
+//!
+//! Decoding goes through the same `wormhole_sdk`/`pythnet_sdk` parsers as witness generation, so
+//! anything that decodes here is what the circuit will be fed with.
+
+use std::fmt;
+
+use base64::Engine as _;
+use pythnet_sdk::{
+    messages::Message,
+    wire::{
+        from_slice,
+        v1::{AccumulatorUpdateData, Proof, WormholeMessage, WormholePayload},
+    },
+};
+use serde::Serialize;
+
+const ACCUMULATOR_MAGIC: &[u8; 4] = b"PNAU";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedSignature {
+    pub index: u8,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedWormholeMessage {
+    pub magic: String,
+    pub slot: u64,
+    pub ring_size: u32,
+    pub root: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedVaa {
+    pub version: u8,
+    pub guardian_set_index: u32,
+    pub signatures: Vec<DecodedSignature>,
+    pub timestamp: u32,
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    pub emitter_address: String,
+    pub sequence: u64,
+    pub consistency_level: u8,
+    pub payload: String,
+    /// `None` if the payload is not a pyth accumulator message.
+    pub wormhole_message: Option<DecodedWormholeMessage>,
+    /// keccak256(keccak256(body)), the hash signed by guardians.
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum DecodedMessage {
+    PriceFeed {
+        feed_id: String,
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: i64,
+        prev_publish_time: i64,
+        ema_price: i64,
+        ema_conf: u64,
+    },
+    Other {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedPriceUpdate {
+    pub message: DecodedMessage,
+    pub proof: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedAccumulatorUpdate {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub vaa: DecodedVaa,
+    pub updates: Vec<DecodedPriceUpdate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Decoded {
+    Vaa(DecodedVaa),
+    AccumulatorUpdate(DecodedAccumulatorUpdate),
+}
+
+/// Decode hex (with or without `0x`) or base64 encoded input into bytes.
+pub fn decode_input(input: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let input = input.trim();
+    let stripped = input.strip_prefix("0x").unwrap_or(input);
+    if stripped.len() % 2 == 0 && stripped.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(hex::decode(stripped)?);
+    }
+    Ok(base64::engine::general_purpose::STANDARD.decode(input)?)
+}
+
+/// Decode either a `PNAU` accumulator update or a bare VAA.
+pub fn decode(bytes: &[u8]) -> Result<Decoded, anyhow::Error> {
+    if bytes.starts_with(ACCUMULATOR_MAGIC) {
+        Ok(Decoded::AccumulatorUpdate(decode_accumulator_update(
+            bytes,
+        )?))
+    } else {
+        Ok(Decoded::Vaa(decode_vaa(bytes)?))
+    }
+}
+
+pub fn decode_vaa(bytes: &[u8]) -> Result<DecodedVaa, anyhow::Error> {
+    let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> = serde_wormhole::from_slice(bytes)?;
+    let (header, body): (wormhole_sdk::vaa::Header, wormhole_sdk::vaa::Body<_>) =
+        vaa.clone().into();
+    let digest = body.digest()?;
+    let payload: &[u8] = vaa.payload.as_ref();
+    let wormhole_message = WormholeMessage::try_from_bytes(payload)
+        .ok()
+        .map(|message| {
+            let WormholePayload::Merkle(root) = message.payload;
+            DecodedWormholeMessage {
+                magic: String::from_utf8_lossy(&message.magic).to_string(),
+                slot: root.slot,
+                ring_size: root.ring_size,
+                root: hex::encode(root.root),
+            }
+        });
+    Ok(DecodedVaa {
+        version: header.version,
+        guardian_set_index: header.guardian_set_index,
+        signatures: header
+            .signatures
+            .iter()
+            .map(|s| DecodedSignature {
+                index: s.index,
+                signature: hex::encode(s.signature),
+            })
+            .collect(),
+        timestamp: vaa.timestamp,
+        nonce: vaa.nonce,
+        emitter_chain: vaa.emitter_chain.into(),
+        emitter_address: hex::encode(vaa.emitter_address.0),
+        sequence: vaa.sequence,
+        consistency_level: vaa.consistency_level,
+        payload: hex::encode(payload),
+        wormhole_message,
+        digest: hex::encode(digest.secp256k_hash),
+    })
+}
+
+pub fn decode_accumulator_update(bytes: &[u8]) -> Result<DecodedAccumulatorUpdate, anyhow::Error> {
+    let data = AccumulatorUpdateData::try_from_slice(bytes)?;
+    let Proof::WormholeMerkle { vaa, updates } = data.proof;
+    let vaa = decode_vaa(vaa.as_ref())?;
+    let updates = updates
+        .into_iter()
+        .map(|update| {
+            let message: Vec<u8> = update.message.into();
+            let decoded = match from_slice::<byteorder::BE, Message>(&message)? {
+                Message::PriceFeedMessage(p) => DecodedMessage::PriceFeed {
+                    feed_id: hex::encode(p.feed_id),
+                    price: p.price,
+                    conf: p.conf,
+                    exponent: p.exponent,
+                    publish_time: p.publish_time,
+                    prev_publish_time: p.prev_publish_time,
+                    ema_price: p.ema_price,
+                    ema_conf: p.ema_conf,
+                },
+                _ => DecodedMessage::Other {
+                    message: hex::encode(&message),
+                },
+            };
+            let proof = update
+                .proof
+                .to_bytes()
+                .chunks(crate::gadgets::keccak160::WIDTH_HASH_BYTES)
+                .map(hex::encode)
+                .collect();
+            Ok(DecodedPriceUpdate {
+                message: decoded,
+                proof,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    Ok(DecodedAccumulatorUpdate {
+        major_version: data.major_version,
+        minor_version: data.minor_version,
+        vaa,
+        updates,
+    })
+}
+
+impl fmt::Display for DecodedVaa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "VAA")?;
+        writeln!(f, "  version:            {}", self.version)?;
+        writeln!(f, "  guardian set index: {}", self.guardian_set_index)?;
+        writeln!(f, "  signatures:         {}", self.signatures.len())?;
+        for s in self.signatures.iter() {
+            writeln!(f, "    [{:>2}] {}", s.index, s.signature)?;
+        }
+        writeln!(f, "  timestamp:          {}", self.timestamp)?;
+        writeln!(f, "  nonce:              {}", self.nonce)?;
+        writeln!(f, "  emitter chain:      {}", self.emitter_chain)?;
+        writeln!(f, "  emitter address:    {}", self.emitter_address)?;
+        writeln!(f, "  sequence:           {}", self.sequence)?;
+        writeln!(f, "  consistency level:  {}", self.consistency_level)?;
+        writeln!(f, "  digest:             {}", self.digest)?;
+        writeln!(f, "  payload:            {}", self.payload)?;
+        if let Some(message) = &self.wormhole_message {
+            writeln!(f, "    magic:            {}", message.magic)?;
+            writeln!(f, "    slot:             {}", message.slot)?;
+            writeln!(f, "    ring size:        {}", message.ring_size)?;
+            writeln!(f, "    root:             {}", message.root)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DecodedAccumulatorUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Accumulator update v{}.{}",
+            self.major_version, self.minor_version
+        )?;
+        write!(f, "{}", self.vaa)?;
+        writeln!(f, "Price updates: {}", self.updates.len())?;
+        for (i, update) in self.updates.iter().enumerate() {
+            match &update.message {
+                DecodedMessage::PriceFeed {
+                    feed_id,
+                    price,
+                    conf,
+                    exponent,
+                    publish_time,
+                    prev_publish_time,
+                    ema_price,
+                    ema_conf,
+                } => {
+                    writeln!(f, "  [{}] price feed {}", i, feed_id)?;
+                    writeln!(f, "    price:             {} (expo {})", price, exponent)?;
+                    writeln!(f, "    conf:              {}", conf)?;
+                    writeln!(f, "    publish time:      {}", publish_time)?;
+                    writeln!(f, "    prev publish time: {}", prev_publish_time)?;
+                    writeln!(f, "    ema price:         {}", ema_price)?;
+                    writeln!(f, "    ema conf:          {}", ema_conf)?;
+                }
+                DecodedMessage::Other { message } => {
+                    writeln!(f, "  [{}] unsupported message {}", i, message)?;
+                }
+            }
+            writeln!(f, "    proof depth:       {}", update.proof.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decoded::Vaa(vaa) => write!(f, "{}", vaa),
+            Decoded::AccumulatorUpdate(update) => write!(f, "{}", update),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_input, Decoded, DecodedMessage};
+    use crate::pyth::DEFAULT_ACCUMULATOR_UPDATE_DATA;
+
+    #[test]
+    fn test_decode_accumulator_update() -> anyhow::Result<()> {
+        let bytes = decode_input(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let Decoded::AccumulatorUpdate(update) = decode(&bytes)? else {
+            panic!("expect accumulator update");
+        };
+        assert_eq!(update.vaa.signatures.len(), 13);
+        assert_eq!(update.vaa.emitter_chain, 26);
+        assert_eq!(update.vaa.wormhole_message.as_ref().unwrap().magic, "AUWV");
+        assert_eq!(update.updates.len(), 3);
+        for price_update in update.updates.iter() {
+            assert_eq!(price_update.proof.len(), 10);
+            let DecodedMessage::PriceFeed { feed_id, .. } = &price_update.message else {
+                panic!("expect price feed message");
+            };
+            assert_eq!(
+                feed_id,
+                "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_vaa() -> anyhow::Result<()> {
+        let bytes = decode_input(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let Decoded::AccumulatorUpdate(update) = decode(&bytes)? else {
+            panic!("expect accumulator update");
+        };
+        // Hex input is also accepted.
+        let vaa = {
+            let data = pythnet_sdk::wire::v1::AccumulatorUpdateData::try_from_slice(&bytes)?;
+            let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, .. } = data.proof;
+            let vaa: &[u8] = vaa.as_ref();
+            format!("0x{}", hex::encode(vaa))
+        };
+        let Decoded::Vaa(vaa) = decode(&decode_input(&vaa)?)? else {
+            panic!("expect VAA");
+        };
+        assert_eq!(vaa.digest, update.vaa.digest);
+        assert_eq!(vaa.sequence, update.vaa.sequence);
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! Differential tests of the circuit witness parsers against `wormhole_sdk`/`pythnet_sdk`.
+//!
+//! Inputs are random bytes, optionally patched to carry valid magic numbers and discriminants so
+//! that most cases get past the first checks. For each input both sides must agree on whether it is
+//! valid, and if it is, on every field value.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte, franklin_crypto::bellman::pairing::Engine,
+};
+use proptest::prelude::*;
+use pythnet_sdk::{
+    messages::Message,
+    wire::{
+        from_slice,
+        v1::{WormholeMessage, WormholePayload},
+    },
+};
+
+use super::{
+    wormhole::{LEN_MESSAGE, LEN_WORMHOLE_BODY},
+    PriceFeed, VaaBody, VaaPayload, LEN_PRICE_FEED,
+};
+use crate::utils::testing::create_test_constraint_system;
+
+fn values<E: Engine>(bytes: &[Byte<E>]) -> Vec<u8> {
+    bytes.iter().map(|b| b.get_byte_value().unwrap()).collect()
+}
+
+/// Random bytes of length `len - 2..=len + 2`, with `prefix` written at `offset` half of the time.
+fn bytes_around(
+    len: usize,
+    offset: usize,
+    prefix: &'static [u8],
+) -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop::collection::vec(any::<u8>(), len - 2..=len + 2),
+        any::<bool>(),
+    )
+        .prop_map(move |(mut bytes, patch)| {
+            if patch && bytes.len() >= offset + prefix.len() {
+                bytes[offset..offset + prefix.len()].copy_from_slice(prefix);
+            }
+            bytes
+        })
+}
+
+fn check_payload(bytes: &[u8]) {
+    let cs = &mut create_test_constraint_system().unwrap();
+    let circuit = VaaPayload::new_from_slice(cs, bytes);
+    let native = WormholeMessage::try_from_bytes(bytes);
+    match (circuit, native) {
+        (Ok(circuit), Ok(native)) => {
+            let WormholePayload::Merkle(root) = native.payload;
+            assert_eq!(values(&circuit.magic), native.magic);
+            assert_eq!(values(&circuit.slot), root.slot.to_be_bytes());
+            assert_eq!(values(&circuit.ring_size), root.ring_size.to_be_bytes());
+            assert_eq!(values(&circuit.root.inner()), root.root);
+        }
+        // The circuit only accepts messages of the exact length it hashes.
+        (Err(_), Ok(_)) => assert_ne!(bytes.len(), LEN_MESSAGE),
+        (Ok(_), Err(e)) => panic!("accepted by circuit, rejected by pythnet_sdk: {:?}", e),
+        (Err(_), Err(_)) => {}
+    }
+}
+
+fn check_body(bytes: &[u8]) {
+    let cs = &mut create_test_constraint_system().unwrap();
+    let circuit = VaaBody::new_from_slice(cs, bytes);
+    // A VAA without signatures carrying the body.
+    let vaa = [&[1u8, 0, 0, 0, 0, 0][..], bytes].concat();
+    let native = serde_wormhole::from_slice::<wormhole_sdk::Vaa<&serde_wormhole::RawMessage>>(&vaa)
+        .ok()
+        .and_then(|vaa| {
+            let message = WormholeMessage::try_from_bytes(vaa.payload.as_ref()).ok()?;
+            Some((vaa, message))
+        });
+    match (circuit, native) {
+        (Ok(circuit), Some((vaa, message))) => {
+            let WormholePayload::Merkle(root) = message.payload;
+            assert_eq!(values(&circuit.timestamp), vaa.timestamp.to_be_bytes());
+            assert_eq!(values(&circuit.nonce), vaa.nonce.to_be_bytes());
+            assert_eq!(
+                values(&circuit.emitter_chain),
+                u16::from(vaa.emitter_chain).to_be_bytes()
+            );
+            assert_eq!(values(&circuit.emitter_address), vaa.emitter_address.0);
+            assert_eq!(values(&circuit.sequence), vaa.sequence.to_be_bytes());
+            assert_eq!(
+                values(&circuit.consistency_level),
+                vaa.consistency_level.to_be_bytes()
+            );
+            assert_eq!(values(&circuit.payload.magic), message.magic);
+            assert_eq!(values(&circuit.payload.slot), root.slot.to_be_bytes());
+            assert_eq!(values(&circuit.payload.root.inner()), root.root);
+            assert_eq!(values(&circuit.to_bytes()), bytes);
+        }
+        (Err(_), Some(_)) => assert_ne!(bytes.len(), LEN_WORMHOLE_BODY),
+        (Ok(_), None) => panic!("accepted by circuit, rejected by wormhole_sdk/pythnet_sdk"),
+        (Err(_), None) => {}
+    }
+}
+
+fn check_price_feed(bytes: &[u8]) {
+    let cs = &mut create_test_constraint_system().unwrap();
+    let circuit = PriceFeed::new_from_slice(cs, bytes);
+    let native = match from_slice::<byteorder::BE, Message>(bytes) {
+        Ok(Message::PriceFeedMessage(p)) => Some(p),
+        _ => None,
+    };
+    match (circuit, native) {
+        (Ok(circuit), Some(p)) => {
+            assert_eq!(values(&circuit.feed_id), p.feed_id);
+            assert_eq!(values(&circuit.price), p.price.to_be_bytes());
+            assert_eq!(values(&circuit.conf), p.conf.to_be_bytes());
+            assert_eq!(values(&circuit.exponent), p.exponent.to_be_bytes());
+            assert_eq!(values(&circuit.publish_time), p.publish_time.to_be_bytes());
+            assert_eq!(
+                values(&circuit.prev_publish_time),
+                p.prev_publish_time.to_be_bytes()
+            );
+            assert_eq!(values(&circuit.ema_price), p.ema_price.to_be_bytes());
+            assert_eq!(values(&circuit.ema_conf), p.ema_conf.to_be_bytes());
+            assert_eq!(values(&circuit.to_bytes(cs)), bytes);
+        }
+        (Err(_), Some(_)) => assert_ne!(bytes.len(), LEN_PRICE_FEED),
+        (Ok(_), None) => panic!("accepted by circuit, rejected by pythnet_sdk"),
+        (Err(_), None) => {}
+    }
+}
+
+proptest! {
+    // Each case builds a constraint system with lookup tables, keep the number of cases small.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_differential_payload(bytes in bytes_around(LEN_MESSAGE, 0, b"AUWV\x00")) {
+        check_payload(&bytes);
+    }
+
+    #[test]
+    fn test_differential_body(
+        bytes in bytes_around(LEN_WORMHOLE_BODY, LEN_WORMHOLE_BODY - LEN_MESSAGE, b"AUWV\x00")
+    ) {
+        check_body(&bytes);
+    }
+
+    #[test]
+    fn test_differential_price_feed(bytes in bytes_around(LEN_PRICE_FEED, 0, b"\x00")) {
+        check_price_feed(&bytes);
+    }
+}
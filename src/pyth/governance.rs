@@ -0,0 +1,309 @@
+//! Payloads of wormhole core governance VAAs.
+//!
+//! A governance payload starts with the module it is addressed to, the action and the chain it
+//! targets, `0` for all chains, followed by the arguments of the action. Only guardian set upgrades
+//! are represented, see [`super::rotation`].
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::UInt32},
+};
+
+use super::GuardianSet;
+use crate::{error::OracleError, gadgets::ethereum::Address};
+
+/// Wormhole chain id of the governance emitter.
+pub const GOVERNANCE_CHAIN_ID: u16 = 1;
+/// Emitter of wormhole governance VAAs.
+pub const GOVERNANCE_EMITTER: [u8; 32] = {
+    let mut emitter = [0u8; 32];
+    emitter[31] = 4;
+    emitter
+};
+/// `"Core"`, left-padded, the module of core bridge governance actions.
+pub const CORE_MODULE: [u8; 32] = {
+    let mut module = [0u8; 32];
+    module[28] = b'C';
+    module[29] = b'o';
+    module[30] = b'r';
+    module[31] = b'e';
+    module
+};
+pub const ACTION_GUARDIAN_SET_UPGRADE: u8 = 2;
+
+const LEN_MODULE: usize = 32;
+const LEN_ACTION: usize = 1;
+const LEN_CHAIN: usize = 2;
+const LEN_GOVERNANCE_HEADER: usize = LEN_MODULE + LEN_ACTION + LEN_CHAIN;
+const LEN_GUARDIAN_SET_INDEX: usize = 4;
+const LEN_NUM_GUARDIANS: usize = 1;
+const LEN_GUARDIAN_KEY: usize = 20;
+
+/// Length of the payload of a guardian set upgrade to `num_guardians` guardians.
+pub fn guardian_set_upgrade_len(num_guardians: usize) -> usize {
+    LEN_GOVERNANCE_HEADER
+        + LEN_GUARDIAN_SET_INDEX
+        + LEN_NUM_GUARDIANS
+        + LEN_GUARDIAN_KEY * num_guardians
+}
+
+/// Natively decoded guardian set upgrade payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardianSetUpgradeMessage {
+    /// Targeted chain, `0` for all chains.
+    pub chain: u16,
+    pub new_guardian_set_index: u32,
+    pub new_guardian_set: Vec<[u8; 20]>,
+}
+
+impl GuardianSetUpgradeMessage {
+    /// Decode a payload, which must be a guardian set upgrade of the core module.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        if bytes.len() < guardian_set_upgrade_len(0) {
+            return Err(OracleError::PayloadDecode(
+                "truncated governance payload".to_string(),
+            ));
+        }
+        if bytes[..LEN_MODULE] != CORE_MODULE || bytes[LEN_MODULE] != ACTION_GUARDIAN_SET_UPGRADE {
+            return Err(OracleError::PayloadDecode(
+                "not a guardian set upgrade".to_string(),
+            ));
+        }
+        let chain = u16::from_be_bytes(bytes[33..35].try_into().unwrap());
+        let new_guardian_set_index = u32::from_be_bytes(bytes[35..39].try_into().unwrap());
+        let num_guardians = bytes[39] as usize;
+        if bytes.len() != guardian_set_upgrade_len(num_guardians) {
+            return Err(OracleError::InvalidLength {
+                what: "guardian set upgrade",
+                got: bytes.len(),
+                expected: guardian_set_upgrade_len(num_guardians),
+            });
+        }
+        let new_guardian_set = bytes[guardian_set_upgrade_len(0)..]
+            .chunks_exact(LEN_GUARDIAN_KEY)
+            .map(|key| key.try_into().unwrap())
+            .collect();
+        Ok(Self {
+            chain,
+            new_guardian_set_index,
+            new_guardian_set,
+        })
+    }
+}
+
+/// Module, action and targeted chain of a governance payload.
+#[derive(Debug, Clone, Copy)]
+pub struct GovernanceHeader<E: Engine> {
+    pub module: [Byte<E>; LEN_MODULE],
+    pub action: [Byte<E>; LEN_ACTION],
+    pub chain: [Byte<E>; LEN_CHAIN],
+}
+
+impl<E: Engine> GovernanceHeader<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        module: [u8; 32],
+        action: u8,
+        chain: u16,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            module: CSAllocatable::alloc_from_witness(cs, Some(module))?,
+            action: CSAllocatable::alloc_from_witness(cs, Some([action]))?,
+            chain: CSAllocatable::alloc_from_witness(cs, Some(chain.to_be_bytes()))?,
+        })
+    }
+
+    /// Allocate a header with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            module: CSAllocatable::alloc_from_witness(cs, None)?,
+            action: CSAllocatable::alloc_from_witness(cs, None)?,
+            chain: CSAllocatable::alloc_from_witness(cs, None)?,
+        })
+    }
+
+    /// Whether the header is `action` of `module` targeting `chain`.
+    pub fn is_action<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        module: &[u8; 32],
+        action: u8,
+        chain: u16,
+    ) -> Result<Boolean, SynthesisError> {
+        let expected = module
+            .iter()
+            .copied()
+            .chain([action])
+            .chain(chain.to_be_bytes());
+        let mut is_ok = vec![];
+        for (byte, expected) in self.bytes_iter().zip(expected) {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.module
+            .iter()
+            .chain(self.action.iter())
+            .chain(self.chain.iter())
+            .copied()
+    }
+}
+
+/// Circuit representation of a guardian set upgrade payload of a fixed number of guardians.
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgradePayload<E: Engine> {
+    pub header: GovernanceHeader<E>,
+    pub new_guardian_set_index: [Byte<E>; LEN_GUARDIAN_SET_INDEX],
+    pub num_guardians: [Byte<E>; LEN_NUM_GUARDIANS],
+    /// Ethereum addresses of the new guardians.
+    pub new_guardian_set: Vec<[Byte<E>; LEN_GUARDIAN_KEY]>,
+}
+
+impl<E: Engine> GuardianSetUpgradePayload<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &GuardianSetUpgradeMessage,
+    ) -> Result<Self, OracleError> {
+        let header = GovernanceHeader::from_witness(
+            cs,
+            CORE_MODULE,
+            ACTION_GUARDIAN_SET_UPGRADE,
+            witness.chain,
+        )?;
+        let new_guardian_set_index = CSAllocatable::alloc_from_witness(
+            cs,
+            Some(witness.new_guardian_set_index.to_be_bytes()),
+        )?;
+        let num_guardians =
+            CSAllocatable::alloc_from_witness(cs, Some([witness.new_guardian_set.len() as u8]))?;
+        let new_guardian_set = witness
+            .new_guardian_set
+            .iter()
+            .map(|key| CSAllocatable::alloc_from_witness(cs, Some(*key)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            header,
+            new_guardian_set_index,
+            num_guardians,
+            new_guardian_set,
+        })
+    }
+
+    /// Allocate a payload of `num_guardians` guardians with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        num_guardians: usize,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            header: GovernanceHeader::alloc_absent(cs)?,
+            new_guardian_set_index: CSAllocatable::alloc_from_witness(cs, None)?,
+            num_guardians: CSAllocatable::alloc_from_witness(cs, None)?,
+            new_guardian_set: (0..num_guardians)
+                .map(|_| CSAllocatable::alloc_from_witness(cs, None))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    /// Whether the payload is a guardian set upgrade of the core module for all chains, of as many
+    /// guardians as allocated.
+    pub fn check_format<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let is_upgrade = self
+            .header
+            .is_action(cs, &CORE_MODULE, ACTION_GUARDIAN_SET_UPGRADE, 0)?;
+        let num_guardians = Num::Constant(u64_to_fe(self.new_guardian_set.len() as u64));
+        let is_complete = Num::equals(cs, &self.num_guardians[0].inner, &num_guardians)?;
+        Boolean::and(cs, &is_upgrade, &is_complete)
+    }
+
+    /// Index of the new guardian set, from its big-endian bytes.
+    pub fn new_guardian_set_index<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt32<E>, SynthesisError> {
+        let mut index = self.new_guardian_set_index;
+        index.reverse();
+        UInt32::from_bytes_le(cs, &index)
+    }
+
+    /// New guardian set, as a witness set.
+    pub fn new_guardian_set<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<GuardianSet<E>, SynthesisError> {
+        let addresses = self
+            .new_guardian_set
+            .iter()
+            .map(|key| Address::from_bytes(cs, key))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GuardianSet::new(addresses))
+    }
+
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.header
+            .bytes_iter()
+            .chain(self.new_guardian_set_index.iter().copied())
+            .chain(self.num_guardians.iter().copied())
+            .chain(self.new_guardian_set.iter().flatten().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::{GuardianSetUpgradeMessage, GuardianSetUpgradePayload, CORE_MODULE};
+    use crate::{
+        error::OracleError,
+        pyth::rotation::LEN_BODY_HEADER,
+        testing::fixtures::GuardianSetUpgradeFixture,
+        utils::testing::{bytes_assert_eq, create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_guardian_set_upgrade_payload() -> Result<(), SynthesisError> {
+        let fixture = GuardianSetUpgradeFixture::default();
+        let bytes = fixture.vaa_body()[LEN_BODY_HEADER..].to_vec();
+        let message = GuardianSetUpgradeMessage::try_from_bytes(&bytes)?;
+        assert_eq!(message.chain, 0);
+        assert_eq!(message.new_guardian_set_index, 4);
+        assert_eq!(message.new_guardian_set, fixture.new_guardian_set());
+
+        let cs = &mut create_test_constraint_system()?;
+        let mut payload = GuardianSetUpgradePayload::<Bn256>::from_witness(cs, &message)?;
+        bytes_assert_eq(
+            &payload.bytes_iter().collect::<Vec<_>>(),
+            hex::encode(&bytes),
+        );
+        assert_eq!(payload.check_format(cs)?.get_value(), Some(true));
+        assert_eq!(payload.new_guardian_set_index(cs)?.get_value(), Some(4));
+        payload.header.chain[1] = Byte::alloc_from_witness(cs, Some(2))?;
+        assert_eq!(payload.check_format(cs)?.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+
+        let mut other_module = bytes.clone();
+        other_module[..32].copy_from_slice(&[0u8; 32]);
+        assert!(GuardianSetUpgradeMessage::try_from_bytes(&other_module).is_err());
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(
+            GuardianSetUpgradeMessage::try_from_bytes(truncated),
+            Err(OracleError::InvalidLength { .. })
+        ));
+        assert_eq!(&bytes[..32], &CORE_MODULE);
+        Ok(())
+    }
+}
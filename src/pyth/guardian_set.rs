@@ -0,0 +1,159 @@
+//! Circuit representation of a wormhole guardian set.
+//!
+//! The addresses are either constants, fixing the set in the verification key, or witnesses, in
+//! which case the set is only meaningful through its [`GuardianSet::commitment`] exposed to the
+//! verifier.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_or,
+};
+
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    utils::fr_from_biguint,
+};
+
+#[derive(Debug, Clone)]
+pub struct GuardianSet<E: Engine> {
+    pub addresses: Vec<Address<E>>,
+}
+
+impl<E: Engine> GuardianSet<E> {
+    pub fn new(addresses: Vec<Address<E>>) -> Self {
+        Self { addresses }
+    }
+
+    /// Guardian set fixed in the circuit.
+    pub fn constant(addresses: &[[u8; 20]]) -> Self {
+        Self::new(addresses.iter().map(Address::constant).collect())
+    }
+
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        addresses: &[[u8; 20]],
+    ) -> Result<Self, SynthesisError> {
+        let addresses = addresses
+            .iter()
+            .map(|address| Address::from_address_witness(cs, address))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(addresses))
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Poseidon hash of the addresses, as committed by the [`PriceOracle`](super::PriceOracle)
+    /// public input.
+    pub fn commitment<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Num<E>, SynthesisError> {
+        let addresses = self
+            .addresses
+            .iter()
+            .map(|address| address.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        circuit_poseidon_hash(cs, &addresses)
+    }
+
+    /// Whether `address` is the guardian at `index`, false for an index past the set.
+    pub fn is_guardian_at<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        index: &Byte<E>,
+        address: &Address<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_matched = vec![];
+        for (i, guardian) in self.addresses.iter().enumerate() {
+            let is_index = Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
+            let is_equal = guardian.equals(cs, address)?;
+            is_matched.push(Boolean::and(cs, &is_index, &is_equal)?);
+        }
+        smart_or(cs, &is_matched)
+    }
+}
+
+/// Native [`GuardianSet::commitment`].
+pub fn compute_guardian_set_commitment<E: Engine>(
+    addresses: &[[u8; 20]],
+) -> Result<E::Fr, SynthesisError> {
+    let input = addresses
+        .iter()
+        .map(|address| fr_from_biguint::<E>(&BigUint::from_bytes_be(address)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(poseidon_hash::<E>(&input))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigUint;
+
+    use super::{compute_guardian_set_commitment, GuardianSet};
+    use crate::{
+        gadgets::poseidon::poseidon_hash,
+        pyth::Vaa,
+        testing::fixtures::AccumulatorFixture,
+        utils::{fr_from_biguint, new_synthesis_error, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_enforce_signed_by_guardians() -> Result<(), SynthesisError> {
+        let fixture = AccumulatorFixture {
+            num_signatures: 2,
+            ..Default::default()
+        };
+        let addresses = fixture.guardian_set();
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = GuardianSet::from_witness(cs, &addresses)?;
+        let expected = addresses
+            .iter()
+            .map(|a| fr_from_biguint(&BigUint::from_bytes_be(a)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitment = guardian_set.commitment(cs)?;
+        assert_eq!(commitment.get_value(), Some(poseidon_hash(&expected)));
+        assert_eq!(
+            commitment.get_value(),
+            Some(compute_guardian_set_commitment::<Bn256>(&addresses)?)
+        );
+
+        let vaa = Vaa::new_from_slice(cs, &fixture.vaa(), 2).map_err(new_synthesis_error)?;
+        vaa.enforce_signed_by_guardians(cs, &guardian_set)?;
+        assert!(cs.is_satisfied());
+
+        // Against a set missing the second signer, its index points past the set.
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = GuardianSet::constant(&addresses[..1]);
+        let vaa = Vaa::new_from_slice(cs, &fixture.vaa(), 2).map_err(new_synthesis_error)?;
+        vaa.enforce_signed_by_guardians(cs, &guardian_set)?;
+        assert!(!cs.is_satisfied());
+
+        // The second signature claims the index of the first guardian.
+        let mut misindexed = fixture.vaa();
+        misindexed[1 + 4 + 1 + 66] = 0;
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = GuardianSet::constant(&addresses);
+        let vaa = Vaa::new_from_slice(cs, &misindexed, 2).map_err(new_synthesis_error)?;
+        vaa.enforce_signed_by_guardians(cs, &guardian_set)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
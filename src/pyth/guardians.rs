@@ -0,0 +1,215 @@
+//! Guardian set configuration.
+//!
+//! A guardian set is configured as JSON or TOML:
+//!
+//! ```toml
+//! index = 3
+//! expiration_time = 0 # optional, unix timestamp after which the set is no longer valid
+//! addresses = [
+//!     "0x58CC3AE5C097b213cE3c81979e1B9f9570746AA5",
+//!     # ...
+//! ]
+//! ```
+//!
+//! Addresses may omit the `0x` prefix. Mixed-case addresses must carry a valid EIP-55 checksum.
+
+use std::path::Path;
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError,
+};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use super::GUARDIAN_SET;
+use crate::gadgets::ethereum::Address;
+
+/// Index of the wormhole guardian set in [`GUARDIAN_SET`].
+pub const MAINNET_GUARDIAN_SET_INDEX: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardianSetConfig {
+    pub index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_time: Option<u64>,
+    #[serde(with = "checksum_addresses")]
+    pub addresses: Vec<[u8; 20]>,
+}
+
+impl GuardianSetConfig {
+    pub fn mainnet() -> Self {
+        Self {
+            index: MAINNET_GUARDIAN_SET_INDEX,
+            expiration_time: None,
+            addresses: GUARDIAN_SET.to_vec(),
+        }
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, anyhow::Error> {
+        Ok(serde_json::from_str::<Self>(s)?.validate()?)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, anyhow::Error> {
+        Ok(toml::from_str::<Self>(s)?.validate()?)
+    }
+
+    /// Load from a `.json` or `.toml` file.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&content),
+            Some("toml") => Self::from_toml(&content),
+            _ => anyhow::bail!("unsupported guardian set file {}", path.display()),
+        }
+    }
+
+    pub(super) fn validate(self) -> Result<Self, anyhow::Error> {
+        anyhow::ensure!(!self.addresses.is_empty(), "empty guardian set");
+        for (i, address) in self.addresses.iter().enumerate() {
+            anyhow::ensure!(address != &[0u8; 20], "guardian {} is the zero address", i);
+            if let Some(j) = self.addresses[..i].iter().position(|a| a == address) {
+                anyhow::bail!("guardian {} duplicates guardian {}", i, j);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Number of signatures required by wormhole to accept a VAA.
+    pub fn quorum(&self) -> usize {
+        self.addresses.len() * 2 / 3 + 1
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiration_time.is_some_and(|t| t != 0 && t < now)
+    }
+
+    /// Allocate the guardian addresses as witnesses.
+    pub fn to_witness<E: Engine, CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Vec<Address<E>>, SynthesisError> {
+        self.addresses
+            .iter()
+            .map(|a| Address::from_address_witness(cs, a))
+            .collect()
+    }
+
+    /// Hardcode the guardian addresses into the circuit.
+    pub fn to_constants<E: Engine>(&self) -> Vec<Address<E>> {
+        self.addresses.iter().map(Address::constant).collect()
+    }
+}
+
+/// Parse a hex address, validating its EIP-55 checksum if it is mixed-case.
+pub fn parse_address(s: &str) -> Result<[u8; 20], anyhow::Error> {
+    let hex_str = s.strip_prefix("0x").unwrap_or(s);
+    let address: [u8; 20] = hex::decode(hex_str)
+        .with_context(|| format!("invalid address {}", s))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid address length {}", s))?;
+    let is_lower = hex_str.chars().all(|c| !c.is_ascii_uppercase());
+    let is_upper = hex_str.chars().all(|c| !c.is_ascii_lowercase());
+    if !is_lower && !is_upper {
+        let expected = to_checksum_address(&address);
+        anyhow::ensure!(
+            expected[2..] == *hex_str,
+            "invalid checksum of address {}, expect {}",
+            s,
+            expected
+        );
+    }
+    Ok(address)
+}
+
+/// Format an address with EIP-55 checksum.
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let checksummed = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    format!("0x{}", checksummed)
+}
+
+mod checksum_addresses {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        addresses: &[[u8; 20]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(addresses.iter().map(super::to_checksum_address))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; 20]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| super::parse_address(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_address, to_checksum_address, GuardianSetConfig};
+
+    #[test]
+    fn test_checksum_address() -> anyhow::Result<()> {
+        let address = parse_address("0x58CC3AE5C097b213cE3c81979e1B9f9570746AA5")?;
+        assert_eq!(
+            to_checksum_address(&address),
+            "0x58CC3AE5C097b213cE3c81979e1B9f9570746AA5"
+        );
+        assert_eq!(
+            parse_address("58cc3ae5c097b213ce3c81979e1b9f9570746aa5")?,
+            address
+        );
+        assert!(parse_address("0x58cC3AE5C097b213cE3c81979e1B9f9570746AA5").is_err());
+        assert!(parse_address("0x58CC3AE5C097b213cE3c81979e1B9f9570746A").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guardian_set_config() -> anyhow::Result<()> {
+        let mainnet = GuardianSetConfig::mainnet();
+        assert_eq!(mainnet.quorum(), 13);
+
+        let json = serde_json::to_string(&mainnet)?;
+        assert_eq!(GuardianSetConfig::from_json(&json)?, mainnet);
+        let toml = toml::to_string(&mainnet)?;
+        assert_eq!(GuardianSetConfig::from_toml(&toml)?, mainnet);
+
+        let duplicated = r#"
+            index = 1
+            expiration_time = 100
+            addresses = [
+                "0x58CC3AE5C097b213cE3c81979e1B9f9570746AA5",
+                "0x58cc3ae5c097b213ce3c81979e1b9f9570746aa5",
+            ]
+        "#;
+        assert!(GuardianSetConfig::from_toml(duplicated).is_err());
+
+        let expiring = r#"
+            index = 1
+            expiration_time = 100
+            addresses = ["0x58CC3AE5C097b213cE3c81979e1B9f9570746AA5"]
+        "#;
+        let expiring = GuardianSetConfig::from_toml(expiring)?;
+        assert!(!expiring.is_expired(100));
+        assert!(expiring.is_expired(101));
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+//! Pyth Lazer signed price updates.
+//!
+//! Lazer serves prices signed directly by a trusted signer instead of carried by a VAA. In the EVM
+//! format an update is `magic (4) || r (32) || s (32) || v (1) || payload_len (2) || payload`, the
+//! signature being over the keccak256 of the payload, which is
+//! `magic (4) || timestamp_us (8) || channel (1) || num_feeds (1)` followed by each feed as
+//! `feed_id (4) || num_properties (1)` and its properties, a property id and its value. Everything is
+//! big-endian. The Solana format is signed with ed25519, for which there is no gadget, and is not
+//! supported.
+//!
+//! [`LazerPriceUpdate`] verifies an update of feeds carrying their price then their exponent as an
+//! [`OracleAttestation`]: the `u32` feed id is committed right-aligned in the 32 bytes of a pyth feed
+//! id, the price rescaled with its exponent to [`COMMITTED_DECIMALS`] and the timestamp truncated to
+//! seconds, so that its prices are committed like those of the other pyth channels.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::{Byte, IntoBytes as _},
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
+};
+
+use super::commitment::{COMMITTED_DECIMALS, LEN_PACKED_PRICE};
+use crate::{
+    attestation::{AttestedPrice, OracleAttestation},
+    error::OracleError,
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+        price_math::{compute_rescaled_i64, rescale_to_i64, ScaledPrice},
+    },
+    utils::{div_to_i64, num_from_be_bytes, to_array},
+};
+
+/// Magic of updates in the EVM format.
+pub const LAZER_EVM_FORMAT_MAGIC: u32 = 0x2a22_999a;
+/// Magic of signed payloads.
+pub const LAZER_PAYLOAD_MAGIC: u32 = 0x93c7_d375;
+pub const PROPERTY_PRICE: u8 = 0;
+pub const PROPERTY_BEST_BID_PRICE: u8 = 1;
+pub const PROPERTY_BEST_ASK_PRICE: u8 = 2;
+pub const PROPERTY_PUBLISHER_COUNT: u8 = 3;
+pub const PROPERTY_EXPONENT: u8 = 4;
+/// Divisor of microsecond timestamps into the seconds of pyth publish times.
+pub const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+/// Magic, signature and payload length of an update in the EVM format.
+const LEN_EVM_HEADER: usize = 4 + 65 + 2;
+/// Magic, timestamp, channel and number of feeds.
+pub const LEN_PAYLOAD_HEADER: usize = 4 + 8 + 1 + 1;
+/// Feed id, number of properties, the price property and the exponent property of a feed carrying
+/// its price then its exponent.
+pub const LEN_PRICE_FEED: usize = 4 + 1 + (1 + 8) + (1 + 2);
+
+/// Native feed of a Lazer payload, with the properties it carries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LazerFeed {
+    pub feed_id: u32,
+    /// `None` if the feed has no price, encoded as zero.
+    pub price: Option<i64>,
+    pub best_bid_price: Option<i64>,
+    pub best_ask_price: Option<i64>,
+    pub publisher_count: Option<u16>,
+    pub exponent: Option<i16>,
+    /// Property ids in the order of the payload.
+    pub properties: Vec<u8>,
+}
+
+impl LazerFeed {
+    /// Feed id right-aligned in the 32 bytes of a pyth feed id.
+    pub fn padded_feed_id(&self) -> [u8; 32] {
+        let mut feed_id = [0u8; 32];
+        feed_id[28..].copy_from_slice(&self.feed_id.to_be_bytes());
+        feed_id
+    }
+}
+
+/// Native Lazer payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazerPayload {
+    /// Microseconds since the unix epoch.
+    pub timestamp_us: u64,
+    pub channel: u8,
+    pub feeds: Vec<LazerFeed>,
+}
+
+impl LazerPayload {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        let invalid = |reason: &str| OracleError::PayloadDecode(reason.to_string());
+        let mut offset = 0;
+        let mut next = |len: usize| -> Result<&[u8], OracleError> {
+            let chunk = bytes
+                .get(offset..offset + len)
+                .ok_or_else(|| invalid("truncated lazer payload"))?;
+            offset += len;
+            Ok(chunk)
+        };
+        if u32::from_be_bytes(next(4)?.try_into().unwrap()) != LAZER_PAYLOAD_MAGIC {
+            return Err(invalid("not a lazer payload"));
+        }
+        let timestamp_us = u64::from_be_bytes(next(8)?.try_into().unwrap());
+        let channel = next(1)?[0];
+        let num_feeds = next(1)?[0];
+        let mut feeds = vec![];
+        for _ in 0..num_feeds {
+            let mut feed = LazerFeed {
+                feed_id: u32::from_be_bytes(next(4)?.try_into().unwrap()),
+                ..Default::default()
+            };
+            let num_properties = next(1)?[0];
+            for _ in 0..num_properties {
+                let property = next(1)?[0];
+                let read_i64 = |value: &[u8]| i64::from_be_bytes(value.try_into().unwrap());
+                match property {
+                    PROPERTY_PRICE => {
+                        feed.price = Some(read_i64(next(8)?)).filter(|price| *price != 0)
+                    }
+                    PROPERTY_BEST_BID_PRICE => {
+                        feed.best_bid_price = Some(read_i64(next(8)?)).filter(|price| *price != 0)
+                    }
+                    PROPERTY_BEST_ASK_PRICE => {
+                        feed.best_ask_price = Some(read_i64(next(8)?)).filter(|price| *price != 0)
+                    }
+                    PROPERTY_PUBLISHER_COUNT => {
+                        feed.publisher_count =
+                            Some(u16::from_be_bytes(next(2)?.try_into().unwrap()))
+                    }
+                    PROPERTY_EXPONENT => {
+                        feed.exponent = Some(i16::from_be_bytes(next(2)?.try_into().unwrap()))
+                    }
+                    _ => return Err(invalid("unknown lazer property")),
+                }
+                feed.properties.push(property);
+            }
+            feeds.push(feed);
+        }
+        if offset != bytes.len() {
+            return Err(OracleError::InvalidLength {
+                what: "lazer payload",
+                got: bytes.len(),
+                expected: offset,
+            });
+        }
+        Ok(Self {
+            timestamp_us,
+            channel,
+            feeds,
+        })
+    }
+
+    /// Timestamp in seconds.
+    pub fn publish_time(&self) -> u64 {
+        self.timestamp_us / MICROSECONDS_PER_SECOND
+    }
+
+    /// Prices packed as [`crate::pyth::commitment::pack_price`] packs pyth prices, failing if a
+    /// feed has no price or exponent or its price cannot be committed.
+    pub fn packed_prices(&self) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        self.feeds
+            .iter()
+            .map(|feed| {
+                let (price, exponent) = feed.price.zip(feed.exponent).ok_or_else(|| {
+                    OracleError::PayloadDecode("lazer feed without a price or exponent".to_string())
+                })?;
+                let exponent = exponent as i32;
+                let committed = compute_rescaled_i64(price, exponent, COMMITTED_DECIMALS).ok_or(
+                    OracleError::UncommittablePrice {
+                        price,
+                        exponent,
+                        decimals: COMMITTED_DECIMALS,
+                    },
+                )?;
+                let mut bytes = [0u8; LEN_PACKED_PRICE];
+                bytes[..32].copy_from_slice(&feed.padded_feed_id());
+                bytes[32..40].copy_from_slice(&committed.to_be_bytes());
+                bytes[40..].copy_from_slice(&self.publish_time().to_be_bytes());
+                Ok(bytes)
+            })
+            .collect()
+    }
+}
+
+/// Lazer update in the EVM format, the witness of a [`LazerPriceUpdate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazerUpdate {
+    /// `r || s || v`, with `v` of 0 or 1.
+    pub signature: [u8; 65],
+    pub payload: Vec<u8>,
+}
+
+impl LazerUpdate {
+    pub fn from_evm_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        if bytes.len() < LEN_EVM_HEADER {
+            return Err(OracleError::InvalidLength {
+                what: "lazer update",
+                got: bytes.len(),
+                expected: LEN_EVM_HEADER,
+            });
+        }
+        if u32::from_be_bytes(bytes[..4].try_into().unwrap()) != LAZER_EVM_FORMAT_MAGIC {
+            return Err(OracleError::PayloadDecode(
+                "not a lazer update in the EVM format".to_string(),
+            ));
+        }
+        let payload_len = u16::from_be_bytes([bytes[69], bytes[70]]) as usize;
+        if bytes.len() != LEN_EVM_HEADER + payload_len {
+            return Err(OracleError::InvalidLength {
+                what: "lazer update",
+                got: bytes.len(),
+                expected: LEN_EVM_HEADER + payload_len,
+            });
+        }
+        Ok(Self {
+            signature: bytes[4..69].try_into().unwrap(),
+            payload: bytes[LEN_EVM_HEADER..].to_vec(),
+        })
+    }
+
+    pub fn to_evm_bytes(&self) -> Vec<u8> {
+        let mut bytes = LAZER_EVM_FORMAT_MAGIC.to_be_bytes().to_vec();
+        bytes.extend(self.signature);
+        bytes.extend((self.payload.len() as u16).to_be_bytes());
+        bytes.extend(&self.payload);
+        bytes
+    }
+
+    /// keccak256 of the payload, signed by the Lazer signer.
+    pub fn signed_hash(&self) -> [u8; 32] {
+        crate::keccak::keccak256(&self.payload)
+    }
+
+    pub fn decode_payload(&self) -> Result<LazerPayload, OracleError> {
+        LazerPayload::from_bytes(&self.payload)
+    }
+}
+
+/// Lazer update of `NUM_FEEDS` feeds carrying their price then their exponent, with the signature of
+/// its signer.
+#[derive(Debug, Clone)]
+pub struct LazerPriceUpdate<E: Engine, const NUM_FEEDS: usize> {
+    pub signature: Signature<E>,
+    pub payload_header: [Byte<E>; LEN_PAYLOAD_HEADER],
+    pub feeds: Vec<[Byte<E>; LEN_PRICE_FEED]>,
+}
+
+impl<E: Engine, const NUM_FEEDS: usize> LazerPriceUpdate<E, NUM_FEEDS> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &LazerUpdate,
+    ) -> Result<Self, OracleError> {
+        let expected = LEN_PAYLOAD_HEADER + NUM_FEEDS * LEN_PRICE_FEED;
+        if witness.payload.len() != expected {
+            return Err(OracleError::InvalidLength {
+                what: "lazer payload",
+                got: witness.payload.len(),
+                expected,
+            });
+        }
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        let (header, feeds) = witness.payload.split_at(LEN_PAYLOAD_HEADER);
+        let feeds = feeds
+            .chunks_exact(LEN_PRICE_FEED)
+            .map(|feed| {
+                let feed = to_array("lazer feed", feed)?;
+                Ok(CSAllocatable::alloc_from_witness(cs, Some(feed))?)
+            })
+            .collect::<Result<Vec<_>, OracleError>>()?;
+        Ok(Self {
+            signature,
+            payload_header: CSAllocatable::alloc_from_witness(
+                cs,
+                Some(to_array("lazer payload header", header)?),
+            )?,
+            feeds,
+        })
+    }
+
+    fn payload_bytes(&self) -> Vec<Byte<E>> {
+        self.payload_header
+            .iter()
+            .chain(self.feeds.iter().flatten())
+            .copied()
+            .collect()
+    }
+
+    /// Whether the payload has the magic of Lazer payloads and `NUM_FEEDS` feeds, each carrying its
+    /// price then its exponent.
+    pub fn check_format<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut expected = self.payload_header[..4]
+            .iter()
+            .copied()
+            .zip(LAZER_PAYLOAD_MAGIC.to_be_bytes())
+            .collect::<Vec<_>>();
+        expected.push((self.payload_header[13], NUM_FEEDS as u8));
+        for feed in self.feeds.iter() {
+            expected.push((feed[4], 2));
+            expected.push((feed[5], PROPERTY_PRICE));
+            expected.push((feed[14], PROPERTY_EXPONENT));
+        }
+        let mut is_ok = vec![];
+        for (byte, expected) in expected {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// Whether [`LazerPriceUpdate::check_format`] holds and the payload is signed by one of
+    /// `signers`.
+    pub fn check_by_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let is_formatted = self.check_format(cs)?;
+        let msg_hash = {
+            let hash = keccak256::digest_with_params(cs, keccak, &self.payload_bytes())?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let (successful, address) = self
+            .signature
+            .ecrecover_address_with_params(cs, params, keccak, &msg_hash)?;
+        let address = Address::from_bytes(cs, &address)?;
+        let mut is_signer = vec![];
+        for signer in signers.iter() {
+            is_signer.push(signer.equals(cs, &address)?);
+        }
+        let is_signer = smart_or(cs, &is_signer)?;
+        smart_and(cs, &[is_formatted, successful, is_signer])
+    }
+}
+
+impl<E: Engine, const NUM_FEEDS: usize> OracleAttestation<E> for LazerPriceUpdate<E, NUM_FEEDS> {
+    type Witness = LazerUpdate;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError> {
+        LazerPriceUpdate::from_witness(cs, witness)
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        self.check_by_signers(cs, &params, &keccak, signers)
+    }
+
+    /// Prices of the feeds rescaled to [`COMMITTED_DECIMALS`] at the timestamp truncated to seconds,
+    /// which can be committed if no price is zero, the encoding of a feed without a price, and all
+    /// can be rescaled, see [`rescale_to_i64`].
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError> {
+        let (is_time_ok, publish_time) =
+            div_to_i64(cs, &self.payload_header[4..12], MICROSECONDS_PER_SECOND)?;
+        let publish_time = publish_time.into_be_bytes(cs)?;
+        let mut is_ok = vec![is_time_ok];
+        let mut prices = vec![];
+        for feed in self.feeds.iter() {
+            let mut feed_id = [Byte::zero(); 32];
+            feed_id[28..].copy_from_slice(&feed[..4]);
+            let price = ScaledPrice {
+                price: to_array("price", &feed[6..14])?,
+                exponent: sign_extend(cs, &to_array("exponent", &feed[15..])?)?,
+            };
+            let is_zero = {
+                let price = num_from_be_bytes(cs, &price.price)?;
+                Num::equals(cs, &price, &Num::zero())?
+            };
+            let (is_committable, price) = rescale_to_i64(cs, &price, COMMITTED_DECIMALS)?;
+            is_ok.push(is_zero.not());
+            is_ok.push(is_committable);
+            prices.push(AttestedPrice {
+                feed_id,
+                price,
+                publish_time: to_array("publish time", &publish_time)?,
+            });
+        }
+        Ok((smart_and(cs, &is_ok)?, prices))
+    }
+
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        let payload = witness.decode_payload()?;
+        if payload.feeds.len() != NUM_FEEDS {
+            return Err(OracleError::InvalidPriceCount {
+                got: payload.feeds.len(),
+                expected: NUM_FEEDS,
+            });
+        }
+        payload.packed_prices()
+    }
+}
+
+/// Big-endian `i32` of the big-endian `i16` `exponent` of Lazer feeds, as the exponent of a
+/// [`ScaledPrice`].
+fn sign_extend<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    exponent: &[Byte<E>; 2],
+) -> Result<[Byte<E>; 4], SynthesisError> {
+    let bits = exponent[0].inner.into_bits_le(cs, Some(8))?;
+    let extension =
+        Num::conditionally_select(cs, &bits[7], &Num::Constant(u64_to_fe(0xff)), &Num::zero())?;
+    let extension = Byte::from_num_unconstrained(cs, extension);
+    Ok([extension, extension, exponent[0], exponent[1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, plonk::better_better_cs::cs::Circuit},
+        testing::create_test_artifacts_with_optimized_gate,
+    };
+
+    use super::{LazerPayload, LazerPriceUpdate, LazerUpdate, PROPERTY_EXPONENT, PROPERTY_PRICE};
+    use crate::{
+        attestation::{AttestationCircuit, OracleAttestation},
+        error::OracleError,
+        testing::fixtures::{guardian_address, guardian_key, LazerFixture},
+    };
+
+    type LazerCircuit = AttestationCircuit<Bn256, LazerPriceUpdate<Bn256, 2>>;
+
+    #[test]
+    fn test_lazer_price_update() -> anyhow::Result<()> {
+        let fixture = LazerFixture::default();
+        let update = LazerUpdate::from_evm_bytes(&fixture.update().to_evm_bytes())?;
+        assert_eq!(update, fixture.update());
+        let payload = update.decode_payload()?;
+        assert_eq!(payload.feeds[1].feed_id, 2);
+        assert_eq!(payload.feeds[1].price, Some(7));
+        assert_eq!(payload.feeds[1].exponent, Some(-2));
+        assert_eq!(payload.publish_time(), 1_700_000_000);
+        let packed = LazerPriceUpdate::<Bn256, 2>::packed_prices(&update)?;
+        assert_eq!(packed[1][..32], payload.feeds[1].padded_feed_id());
+        // Committed with 8 decimals whatever the exponent.
+        assert_eq!(packed[1][32..40], 7_000_000i64.to_be_bytes());
+        assert_eq!(packed[1][40..], 1_700_000_000i64.to_be_bytes());
+
+        let circuit = LazerCircuit::new(vec![update.clone()], vec![fixture.signer_address()])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Not signed by a trusted signer.
+        let foreign = LazerCircuit::new(
+            vec![update.clone()],
+            vec![guardian_address(&guardian_key(1))],
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        foreign.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Signed by a signer of the prover's choosing, which the verifier tells apart.
+        let foreign_fixture = LazerFixture {
+            signer: 1,
+            ..fixture.clone()
+        };
+        let foreign = LazerCircuit::new(
+            vec![foreign_fixture.update()],
+            vec![foreign_fixture.signer_address()],
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        foreign.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert_ne!(foreign.public_input()?, circuit.public_input()?);
+
+        // A feed without a price cannot be committed, nor a negative price.
+        for prices in [
+            vec![(1, 100, -8), (2, 0, -8)],
+            vec![(1, 100, -8), (2, -7, -8)],
+        ] {
+            let fixture = LazerFixture {
+                prices,
+                ..fixture.clone()
+            };
+            assert!(
+                LazerCircuit::new(vec![fixture.update()], vec![fixture.signer_address()]).is_err()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_payload() -> anyhow::Result<()> {
+        let mut bytes = super::LAZER_PAYLOAD_MAGIC.to_be_bytes().to_vec();
+        bytes.extend(1_700_000_000_000_000u64.to_be_bytes());
+        bytes.extend([1, 1]);
+        bytes.extend(42u32.to_be_bytes());
+        bytes.extend([2, PROPERTY_PRICE]);
+        bytes.extend(123i64.to_be_bytes());
+        bytes.push(PROPERTY_EXPONENT);
+        bytes.extend((-8i16).to_be_bytes());
+        let payload = LazerPayload::from_bytes(&bytes)?;
+        assert_eq!(payload.feeds[0].price, Some(123));
+        assert_eq!(payload.feeds[0].exponent, Some(-8));
+        assert_eq!(
+            payload.feeds[0].properties,
+            vec![PROPERTY_PRICE, PROPERTY_EXPONENT]
+        );
+
+        // Feeds without an exponent are not supported by the circuit.
+        let mut price_only = bytes[..bytes.len() - 3].to_vec();
+        price_only[super::LEN_PAYLOAD_HEADER + 4] = 1;
+        assert_eq!(
+            LazerPayload::from_bytes(&price_only)?.feeds[0].exponent,
+            None
+        );
+        let update = LazerUpdate {
+            signature: [0u8; 65],
+            payload: price_only,
+        };
+        assert!(matches!(
+            LazerPriceUpdate::<Bn256, 1>::from_witness(
+                &mut create_test_artifacts_with_optimized_gate().0,
+                &update
+            ),
+            Err(OracleError::InvalidLength { .. })
+        ));
+        bytes.pop();
+        assert!(LazerPayload::from_bytes(&bytes).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+//! Legacy pyth batch price attestations.
+//!
+//! Before the accumulator, the pyth attester published the prices themselves as the payload of a
+//! VAA, a `P2WH` batch of fixed-size price attestations, which historical data and some relays
+//! still serve. [`LegacyBatchAttestation`] verifies such a VAA as an [`OracleAttestation`], so that
+//! its prices are committed like those of an accumulator update: the format is selected by the
+//! attestation type of [`AttestationCircuit`](crate::attestation::AttestationCircuit).
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+
+use super::{
+    commitment::LEN_PACKED_PRICE, wormhole::LEN_WORMHOLE_BODY_HEADER, GuardianSet,
+    PYTHNET_ATTESTER_EMITTER, PYTHNET_CHAIN_ID, VAA_VERSION,
+};
+use crate::{
+    attestation::{AttestedPrice, OracleAttestation},
+    error::OracleError,
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+    },
+    utils::to_array,
+};
+
+/// Magic of pyth attester payloads.
+pub const P2W_MAGIC: [u8; 4] = *b"P2WH";
+pub const P2W_MAJOR_VERSION: u16 = 3;
+pub const PAYLOAD_ID_BATCH_PRICE_ATTESTATION: u8 = 2;
+/// Magic, major and minor versions, header size, payload id, number and size of attestations.
+const LEN_BATCH_HEADER: usize = 4 + 2 + 2 + 2 + 1 + 2 + 2;
+/// Size of the attestations of the first 3.x format, later minor versions append fields.
+pub const LEN_PRICE_ATTESTATION: usize =
+    32 + 32 + 8 + 8 + 4 + 8 + 8 + 1 + 4 + 4 + 8 + 8 + 8 + 8 + 8;
+const OFFSET_PRICE_ID: usize = 32;
+const OFFSET_PRICE: usize = 64;
+const OFFSET_PUBLISH_TIME: usize = 117;
+
+/// Native price of a legacy price attestation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyPriceAttestation {
+    pub price_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl LegacyPriceAttestation {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let read = |offset: usize| -> [u8; 8] { bytes[offset..offset + 8].try_into().unwrap() };
+        Self {
+            price_id: bytes[OFFSET_PRICE_ID..OFFSET_PRICE].try_into().unwrap(),
+            price: i64::from_be_bytes(read(OFFSET_PRICE)),
+            conf: u64::from_be_bytes(read(72)),
+            expo: i32::from_be_bytes(bytes[80..84].try_into().unwrap()),
+            publish_time: i64::from_be_bytes(read(OFFSET_PUBLISH_TIME)),
+        }
+    }
+
+    pub fn pack(&self) -> [u8; LEN_PACKED_PRICE] {
+        let mut bytes = [0u8; LEN_PACKED_PRICE];
+        bytes[..32].copy_from_slice(&self.price_id);
+        bytes[32..40].copy_from_slice(&self.price.to_be_bytes());
+        bytes[40..].copy_from_slice(&self.publish_time.to_be_bytes());
+        bytes
+    }
+}
+
+/// Decode a batch price attestation payload, returning the size of its attestations along with
+/// their prices.
+pub fn decode_batch_price_attestation(
+    payload: &[u8],
+) -> Result<(usize, Vec<LegacyPriceAttestation>), OracleError> {
+    let invalid = |reason: &str| OracleError::PayloadDecode(reason.to_string());
+    if payload.len() < LEN_BATCH_HEADER {
+        return Err(invalid("truncated batch price attestation"));
+    }
+    let read_u16 = |offset: usize| u16::from_be_bytes([payload[offset], payload[offset + 1]]);
+    if payload[..4] != P2W_MAGIC || read_u16(4) != P2W_MAJOR_VERSION {
+        return Err(invalid("not a pyth attester payload of version 3"));
+    }
+    if read_u16(8) != 1 || payload[10] != PAYLOAD_ID_BATCH_PRICE_ATTESTATION {
+        return Err(invalid("not a batch price attestation"));
+    }
+    let num_attestations = read_u16(11) as usize;
+    let attestation_size = read_u16(13) as usize;
+    if attestation_size < LEN_PRICE_ATTESTATION {
+        return Err(OracleError::InvalidLength {
+            what: "price attestation",
+            got: attestation_size,
+            expected: LEN_PRICE_ATTESTATION,
+        });
+    }
+    let expected = LEN_BATCH_HEADER + num_attestations * attestation_size;
+    if payload.len() != expected {
+        return Err(OracleError::InvalidLength {
+            what: "batch price attestation",
+            got: payload.len(),
+            expected,
+        });
+    }
+    let attestations = payload[LEN_BATCH_HEADER..]
+        .chunks_exact(attestation_size)
+        .map(LegacyPriceAttestation::from_bytes)
+        .collect();
+    Ok((attestation_size, attestations))
+}
+
+/// VAA of a batch price attestation, the witness of a [`LegacyBatchAttestation`].
+#[derive(Debug, Clone)]
+pub struct LegacyAttestationWitness {
+    pub vaa: Vec<u8>,
+}
+
+impl LegacyAttestationWitness {
+    pub fn new(vaa: Vec<u8>) -> Self {
+        Self { vaa }
+    }
+}
+
+/// Batch price attestation VAA of `NUM_PRICES` prices as an [`OracleAttestation`], with
+/// `NUM_SIGNATURES` signatures of the guardians verified and emitted by the pythnet attester. Both
+/// are fixed by the circuit rather than read from the witness.
+#[derive(Debug, Clone)]
+pub struct LegacyBatchAttestation<E: Engine, const NUM_PRICES: usize, const NUM_SIGNATURES: usize> {
+    /// Version of the VAA header, not covered by the signatures.
+    pub version: Byte<E>,
+    /// Index in the guardian set of the signer of each signature, as claimed by the VAA header.
+    pub guardian_indices: Vec<Byte<E>>,
+    pub signatures: Vec<Signature<E>>,
+    pub body_header: [Byte<E>; LEN_WORMHOLE_BODY_HEADER],
+    pub batch_header: [Byte<E>; LEN_BATCH_HEADER],
+    /// Attestations, of the size of the witness.
+    pub attestations: Vec<Vec<Byte<E>>>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize, const NUM_SIGNATURES: usize>
+    LegacyBatchAttestation<E, NUM_PRICES, NUM_SIGNATURES>
+{
+    fn body_bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.body_header
+            .iter()
+            .chain(self.batch_header.iter())
+            .chain(self.attestations.iter().flatten())
+            .copied()
+    }
+
+    /// Whether the VAA is of [`VAA_VERSION`], emitted by the attester and carries a batch of
+    /// `NUM_PRICES` attestations of the allocated size.
+    pub fn check_format<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let attestation_size = self.attestations.first().map_or(0, Vec::len) as u16;
+        let mut expected = vec![(self.version, VAA_VERSION)];
+        let emitter = PYTHNET_CHAIN_ID
+            .to_be_bytes()
+            .into_iter()
+            .chain(PYTHNET_ATTESTER_EMITTER);
+        expected.extend(self.body_header[8..42].iter().copied().zip(emitter));
+        let batch_header = P2W_MAGIC
+            .into_iter()
+            .chain(P2W_MAJOR_VERSION.to_be_bytes())
+            // Any minor version, header of the payload id only.
+            .chain([0, 0])
+            .chain(1u16.to_be_bytes())
+            .chain([PAYLOAD_ID_BATCH_PRICE_ATTESTATION])
+            .chain((NUM_PRICES as u16).to_be_bytes())
+            .chain(attestation_size.to_be_bytes());
+        for (i, (byte, expected_byte)) in self.batch_header.iter().zip(batch_header).enumerate() {
+            if i != 6 && i != 7 {
+                expected.push((*byte, expected_byte));
+            }
+        }
+        let mut is_ok = vec![];
+        for (byte, expected) in expected {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize, const NUM_SIGNATURES: usize> OracleAttestation<E>
+    for LegacyBatchAttestation<E, NUM_PRICES, NUM_SIGNATURES>
+{
+    type Witness = LegacyAttestationWitness;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError> {
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&witness.vaa)
+                .map_err(|e| OracleError::VaaDecode(e.to_string()))?;
+        let (header, body): (wormhole_sdk::vaa::Header, wormhole_sdk::vaa::Body<_>) = vaa.into();
+        let num_signatures = NUM_SIGNATURES;
+        if header.signatures.len() < num_signatures {
+            return Err(OracleError::InsufficientSignatures {
+                got: header.signatures.len(),
+                need: num_signatures,
+            });
+        }
+        let (attestation_size, prices) = decode_batch_price_attestation(body.payload.as_ref())?;
+        if prices.len() != NUM_PRICES {
+            return Err(OracleError::InvalidPriceCount {
+                got: prices.len(),
+                expected: NUM_PRICES,
+            });
+        }
+        let body =
+            serde_wormhole::to_vec(&body).map_err(|e| OracleError::VaaDecode(e.to_string()))?;
+
+        let version = Byte::alloc_from_witness(cs, Some(header.version))?;
+        let mut guardian_indices = vec![];
+        let mut signatures = vec![];
+        for signature in header.signatures[..num_signatures].iter() {
+            guardian_indices.push(Byte::alloc_from_witness(cs, Some(signature.index))?);
+            signatures.push(Signature::from_bytes_witness(cs, &signature.signature)?);
+        }
+        let (body_header, payload) = body.split_at(LEN_WORMHOLE_BODY_HEADER);
+        let (batch_header, payload) = payload.split_at(LEN_BATCH_HEADER);
+        let attestations = payload
+            .chunks_exact(attestation_size)
+            .map(|attestation| {
+                attestation
+                    .iter()
+                    .map(|byte| Byte::alloc_from_witness(cs, Some(*byte)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            version,
+            guardian_indices,
+            signatures,
+            body_header: CSAllocatable::alloc_from_witness(
+                cs,
+                Some(to_array("VAA body header", body_header)?),
+            )?,
+            batch_header: CSAllocatable::alloc_from_witness(
+                cs,
+                Some(to_array("batch header", batch_header)?),
+            )?,
+            attestations,
+        })
+    }
+
+    /// Whether [`LegacyBatchAttestation::check_format`] holds and every signature recovers to the
+    /// signer at the index the VAA header claims for it, the indices being strictly increasing.
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        if signers.is_empty() {
+            return Ok(Boolean::constant(false));
+        }
+        let mut is_ok = vec![self.check_format(cs)?];
+        for pair in self.guardian_indices.windows(2) {
+            let (_, is_greater) =
+                prepacked_long_comparison(cs, &[pair[1].inner], &[pair[0].inner], &[8])?;
+            is_ok.push(is_greater);
+        }
+        let keccak = Keccak256Params::new(cs)?;
+        let msg_hash = {
+            let hash = keccak256::double_digest_with_params(cs, &keccak, self.body_bytes_iter())?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let guardian_set = GuardianSet::new(signers.to_vec());
+        let params = Secp256k1Params::new(cs);
+        for (signature, index) in self.signatures.iter().zip(self.guardian_indices.iter()) {
+            let (successful, address) =
+                signature.ecrecover_address_with_params(cs, &params, &keccak, &msg_hash)?;
+            let address = Address::from_bytes(cs, &address)?;
+            is_ok.push(successful);
+            is_ok.push(guardian_set.is_guardian_at(cs, index, &address)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// Prices as attested, any `i64` price with any exponent can be committed.
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        _cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError> {
+        let prices = self
+            .attestations
+            .iter()
+            .map(|attestation| {
+                Ok(AttestedPrice {
+                    feed_id: to_array("price id", &attestation[OFFSET_PRICE_ID..OFFSET_PRICE])?,
+                    price: to_array("price", &attestation[OFFSET_PRICE..OFFSET_PRICE + 8])?,
+                    publish_time: to_array(
+                        "publish time",
+                        &attestation[OFFSET_PUBLISH_TIME..OFFSET_PUBLISH_TIME + 8],
+                    )?,
+                })
+            })
+            .collect::<Result<Vec<_>, OracleError>>()?;
+        Ok((Boolean::constant(true), prices))
+    }
+
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&witness.vaa)
+                .map_err(|e| OracleError::VaaDecode(e.to_string()))?;
+        let (_, prices) = decode_batch_price_attestation(vaa.payload.as_ref())?;
+        if prices.len() != NUM_PRICES {
+            return Err(OracleError::InvalidPriceCount {
+                got: prices.len(),
+                expected: NUM_PRICES,
+            });
+        }
+        Ok(prices.iter().map(LegacyPriceAttestation::pack).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, plonk::better_better_cs::cs::Circuit},
+        testing::create_test_artifacts_with_optimized_gate,
+    };
+
+    use super::{decode_batch_price_attestation, LegacyAttestationWitness, LegacyBatchAttestation};
+    use crate::{
+        attestation::{AttestationCircuit, OracleAttestation},
+        error::OracleError,
+        testing::fixtures::LegacyBatchFixture,
+    };
+
+    type LegacyCircuit = AttestationCircuit<Bn256, LegacyBatchAttestation<Bn256, 2, 3>>;
+
+    #[test]
+    fn test_legacy_batch_attestation() -> anyhow::Result<()> {
+        let fixture = LegacyBatchFixture::default();
+        let (size, prices) = decode_batch_price_attestation(&fixture.payload())?;
+        assert_eq!(size, super::LEN_PRICE_ATTESTATION);
+        assert_eq!(prices[1].price, -7);
+        assert_eq!(prices[1].expo, -2);
+        assert_eq!(prices[1].publish_time, 1_600_000_001);
+
+        let witness = LegacyAttestationWitness::new(fixture.vaa());
+        let packed = LegacyBatchAttestation::<Bn256, 2, 3>::packed_prices(&witness)?;
+        assert_eq!(packed[0][..32], [1u8; 32]);
+        assert_eq!(packed[1][32..40], (-7i64).to_be_bytes());
+        assert!(matches!(
+            LegacyBatchAttestation::<Bn256, 3, 3>::packed_prices(&witness),
+            Err(OracleError::InvalidPriceCount { .. })
+        ));
+
+        let circuit = LegacyCircuit::new(vec![witness.clone()], fixture.guardian_set())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Emitted by another attester than the pythnet one.
+        let foreign = LegacyBatchFixture {
+            emitter_address: [1u8; 32],
+            ..fixture.clone()
+        };
+        let circuit = LegacyCircuit::new(
+            vec![LegacyAttestationWitness::new(foreign.vaa())],
+            fixture.guardian_set(),
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Not signed by the signers.
+        let circuit = LegacyCircuit::new(vec![witness], fixture.guardian_set()[1..].to_vec())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        let mut truncated = fixture.payload();
+        truncated.pop();
+        assert!(decode_batch_price_attestation(&truncated).is_err());
+        Ok(())
+    }
+}
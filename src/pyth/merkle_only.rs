@@ -0,0 +1,303 @@
+//! Hybrid verification: the circuit proves the merkle inclusion of price messages only.
+//!
+//! On chains where the wormhole core bridge is already trusted, the VAA of an accumulator update
+//! can be verified on chain, by `parseAndVerifyVM`, which yields its merkle root. The circuit then
+//! only has to prove that the prices are leaves of that root, which removes the signature
+//! verification, by far the largest part of [`PriceOracle`](super::PriceOracle). The roots are its
+//! public input, one per update in order, each as the big-endian integer of its 20 bytes, followed
+//! by the two halves of the keccak
+//! [`packed_price_commitment`](super::commitment::packed_price_commitment) of the prices, high
+//! first. The verifier contract must check each root against the VAA it verified.
+
+use std::marker::PhantomData;
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            pairing::{ff::Field, Engine},
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    traits::CSAllocatable,
+    vm::partitioner::smart_and,
+};
+use num_bigint::BigUint;
+use pythnet_sdk::{
+    messages::Message,
+    wire::{
+        from_slice,
+        v1::{AccumulatorUpdateData, MerklePriceUpdate, WormholeMessage, WormholePayload},
+    },
+};
+
+use super::{
+    circuit::accumulator_vaa,
+    commitment::{compute_packed_price_commitment, pack_price, pack_price_message},
+    AccumulatorMessage, PriceUpdate,
+};
+use crate::{
+    gadgets::{
+        keccak160::{MerkleRoot, WIDTH_HASH_BYTES},
+        keccak256::Keccak256Params,
+        rescue::circuit_rescue_hash,
+    },
+    pyth::commitment::packed_price_commitment_with_params,
+    report::section,
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+/// Merkle root of the VAA of `data`, as output by the wormhole contract verifying it.
+pub fn accumulator_root(data: &AccumulatorUpdateData) -> Result<[u8; 20], anyhow::Error> {
+    let vaa = accumulator_vaa(data)?;
+    let message = WormholeMessage::try_from_bytes(vaa.payload.as_ref())
+        .map_err(|e| anyhow::anyhow!("invalid wormhole message: {:?}", e))?;
+    let WormholePayload::Merkle(root) = message.payload;
+    Ok(root.root)
+}
+
+/// Circuit proving that the `NUM_PRICES` price feed messages of each accumulator update are in the
+/// merkle tree of its root, the signatures of the VAAs being left to the verifier.
+#[derive(Debug, Clone)]
+pub struct MerkleOnlyOracle<E: Engine, const NUM_PRICES: usize> {
+    pub accumulator_update_data: Vec<AccumulatorUpdateData>,
+    pub roots: Vec<[u8; 20]>,
+    pub commitment: [E::Fr; 2],
+}
+
+impl<E: Engine, const NUM_PRICES: usize> MerkleOnlyOracle<E, NUM_PRICES> {
+    /// Check that every update carries `NUM_PRICES` price feed messages and compute the public
+    /// input. The merkle proofs are only checked by the circuit.
+    pub fn new(accumulator_update_data: Vec<AccumulatorUpdateData>) -> Result<Self, anyhow::Error> {
+        let mut roots = vec![];
+        let mut packed = vec![];
+        for data in accumulator_update_data.iter() {
+            roots.push(accumulator_root(data)?);
+            let pythnet_sdk::wire::v1::Proof::WormholeMerkle { updates, .. } = &data.proof;
+            anyhow::ensure!(
+                updates.len() == NUM_PRICES,
+                "expected {} prices, got {}",
+                NUM_PRICES,
+                updates.len()
+            );
+            for update in updates.iter() {
+                let message: Vec<u8> = update.message.clone().into();
+                match from_slice::<byteorder::BE, Message>(&message)? {
+                    Message::PriceFeedMessage(price_feed) => {
+                        packed.push(pack_price_message(&price_feed)?)
+                    }
+                    _ => anyhow::bail!("invalid price feed message"),
+                }
+            }
+        }
+        let commitment = compute_packed_price_commitment::<E>(&packed)?;
+        Ok(Self {
+            accumulator_update_data,
+            roots,
+            commitment,
+        })
+    }
+
+    /// Public input of the proof, the roots then the commitment.
+    pub fn public_input(&self) -> Result<Vec<E::Fr>, SynthesisError> {
+        let mut input = self
+            .roots
+            .iter()
+            .map(|root| fr_from_biguint::<E>(&BigUint::from_bytes_be(root)))
+            .collect::<Result<Vec<_>, _>>()?;
+        input.extend(self.commitment);
+        Ok(input)
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for MerkleOnlyOracle<E, NUM_PRICES> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span = tracing::info_span!(
+            "merkle_only_synthesize",
+            num_vaas = self.accumulator_update_data.len(),
+            num_prices = NUM_PRICES,
+        )
+        .entered();
+        let updates = self
+            .accumulator_update_data
+            .iter()
+            .zip(self.roots.iter())
+            .map(|(data, root)| {
+                let pythnet_sdk::wire::v1::Proof::WormholeMerkle { updates, .. } = &data.proof;
+                (Some(*root), updates.iter().cloned().map(Some).collect())
+            })
+            .collect();
+        synthesize_merkle_only(cs, updates, Some(self.commitment))
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(),
+        ])
+    }
+}
+
+/// Shape of a [`MerkleOnlyOracle`] of `num_vaas` updates without any witness, for key generation.
+#[derive(Debug, Clone, Copy)]
+pub struct MerkleOnlySetup<E: Engine, const NUM_PRICES: usize> {
+    pub num_vaas: usize,
+    _marker: PhantomData<E>,
+}
+
+impl<E: Engine, const NUM_PRICES: usize> MerkleOnlySetup<E, NUM_PRICES> {
+    pub fn new(num_vaas: usize) -> Self {
+        Self {
+            num_vaas,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> From<&MerkleOnlyOracle<E, NUM_PRICES>>
+    for MerkleOnlySetup<E, NUM_PRICES>
+{
+    fn from(oracle: &MerkleOnlyOracle<E, NUM_PRICES>) -> Self {
+        Self::new(oracle.accumulator_update_data.len())
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for MerkleOnlySetup<E, NUM_PRICES> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let updates = (0..self.num_vaas)
+            .map(|_| (None, vec![None; NUM_PRICES]))
+            .collect();
+        synthesize_merkle_only(cs, updates, None)
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        MerkleOnlyOracle::<E, NUM_PRICES>::declare_used_gates()
+    }
+}
+
+/// Root and price updates of an accumulator update, `None` when absent.
+type MerkleOnlyWitness = (Option<[u8; 20]>, Vec<Option<MerklePriceUpdate>>);
+
+/// Gates of [`MerkleOnlyOracle`], shared with [`MerkleOnlySetup`].
+fn synthesize_merkle_only<E, CS>(
+    cs: &mut CS,
+    updates: Vec<MerkleOnlyWitness>,
+    expected_commitment: Option<[E::Fr; 2]>,
+) -> Result<(), SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+{
+    let keccak = section(cs, "setup", |cs| {
+        crate::utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+        Keccak256Params::new(cs)
+    })?;
+
+    let mut roots = vec![];
+    let mut packed = vec![];
+    for (root, price_updates) in updates {
+        section(cs, "check_inclusion", |cs| {
+            let root: [Byte<E>; WIDTH_HASH_BYTES] = CSAllocatable::alloc_from_witness(cs, root)?;
+            roots.push(num_from_be_bytes(cs, &root)?);
+            let root = MerkleRoot::new(root);
+            for price_update in price_updates {
+                let price_update = PriceUpdate::<E, 10>::from_optional_witness(cs, price_update)?;
+                let is_price_feed =
+                    AccumulatorMessage::PriceFeed(price_update.message).check_type(cs)?;
+                let is_included = price_update.check(cs, &keccak, &root)?;
+                let (is_committable, price) = pack_price(cs, &price_update.message)?;
+                let is_valid = smart_and(cs, &[is_price_feed, is_included, is_committable])?;
+                Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+                packed.push(price);
+            }
+            Ok(())
+        })?;
+    }
+
+    section(cs, "public_input", |cs| {
+        for root in roots.iter() {
+            root.get_variable().inputize(cs)?;
+        }
+        let commitment = packed_price_commitment_with_params(cs, &keccak, &packed)?;
+        for (i, half) in commitment.iter().enumerate() {
+            let expected = Num::alloc(cs, expected_commitment.map(|commitment| commitment[i]))?;
+            expected.enforce_equal(cs, half)?;
+            expected.get_variable().inputize(cs)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{
+            pairing::bn256::Bn256,
+            plonk::better_better_cs::{
+                cs::{Circuit, PlonkCsWidth4WithNextStepAndCustomGatesParams, SetupAssembly},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+        },
+        testing::create_test_artifacts_with_optimized_gate,
+    };
+
+    use super::{accumulator_root, MerkleOnlyOracle, MerkleOnlySetup};
+    use crate::{
+        pyth::PriceOracle,
+        testing::fixtures::{AccumulatorFixture, FixturePrice},
+    };
+
+    #[test]
+    fn test_merkle_only_oracle() -> anyhow::Result<()> {
+        let fixture = AccumulatorFixture::with_prices(vec![
+            FixturePrice::new([1u8; 32], 100, -8, 1_700_000_000),
+            FixturePrice::new([2u8; 32], 200, -6, 1_700_000_000),
+        ]);
+        let data = vec![fixture.build(), fixture.build()];
+        assert_eq!(accumulator_root(&data[0])?, fixture.merkle_tree().root());
+
+        let oracle = MerkleOnlyOracle::<Bn256, 2>::new(data.clone())?;
+        assert_eq!(oracle.public_input()?.len(), 4);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs, 4);
+
+        let mut setup = SetupAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        MerkleOnlySetup::from(&oracle).synthesize(&mut setup)?;
+        assert_eq!(setup.n(), cs.n());
+
+        // Far smaller than the full circuit verifying the signatures.
+        let full = PriceOracle::<Bn256, 2>::new(data.clone(), fixture.guardian_set(), 13)?;
+        let (mut full_cs, _, _) = create_test_artifacts_with_optimized_gate();
+        full.synthesize(&mut full_cs)?;
+        assert!(cs.n() * 10 < full_cs.n());
+
+        // Prices against another root than the one verified on chain.
+        let mut forged = oracle;
+        forged.roots[1] = [0u8; 20];
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        forged.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        assert!(MerkleOnlyOracle::<Bn256, 3>::new(data).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,532 @@
+//! Accumulator messages other than price feeds.
+//!
+//! Besides [`PriceFeed`] leaves, the accumulator carries TWAP messages, whose cumulative prices give
+//! the time-weighted average price between two of them, and publisher stake caps.
+//! [`AccumulatorMessage`] is the tagged union of the three: it is allocated by the leading message
+//! type byte, so the circuit shape follows the [`MessageLayout`] a circuit is built for, and
+//! [`AccumulatorMessage::check_type`] binds the type byte of the leaf to that layout.
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+
+use super::{PriceFeed, LEN_PRICE_FEED};
+use crate::{
+    error::OracleError,
+    gadgets::{
+        keccak160::{self, MerklePath, MerkleRoot},
+        keccak256::Keccak256Params,
+    },
+    utils::to_array,
+};
+
+/// Discriminant of `Message::PriceFeedMessage`.
+pub const PRICE_FEED_MESSAGE_TYPE: u8 = 0;
+/// Discriminant of `Message::TwapMessage`.
+pub const TWAP_MESSAGE_TYPE: u8 = 1;
+/// Discriminant of `Message::PublisherStakeCapsMessage`.
+pub const PUBLISHER_STAKE_CAPS_MESSAGE_TYPE: u8 = 2;
+
+const LEN_MESSAGE_TYPE: usize = 1;
+const LEN_FEED_ID: usize = 32;
+const LEN_CUMULATIVE_PRICE: usize = 16;
+const LEN_CUMULATIVE_CONF: usize = 16;
+const LEN_NUM_DOWN_SLOTS: usize = 8;
+const LEN_EXPONENT: usize = 4;
+const LEN_PUBLISH_TIME: usize = 8;
+const LEN_PREV_PUBLISH_TIME: usize = 8;
+const LEN_PUBLISH_SLOT: usize = 8;
+pub const LEN_TWAP: usize = LEN_MESSAGE_TYPE
+    + LEN_FEED_ID
+    + LEN_CUMULATIVE_PRICE
+    + LEN_CUMULATIVE_CONF
+    + LEN_NUM_DOWN_SLOTS
+    + LEN_EXPONENT
+    + LEN_PUBLISH_TIME
+    + LEN_PREV_PUBLISH_TIME
+    + LEN_PUBLISH_SLOT;
+const LEN_NUM_CAPS: usize = 2;
+const LEN_PUBLISHER: usize = 32;
+const LEN_CAP: usize = 8;
+
+/// Shape of an [`AccumulatorMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageLayout {
+    PriceFeed,
+    Twap,
+    PublisherStakeCaps { num_caps: usize },
+}
+
+impl MessageLayout {
+    /// Layout of the wire format `bytes`, from its leading message type byte.
+    pub fn of_message(bytes: &[u8]) -> Result<Self, OracleError> {
+        match bytes.first() {
+            Some(&PRICE_FEED_MESSAGE_TYPE) => Ok(Self::PriceFeed),
+            Some(&TWAP_MESSAGE_TYPE) => Ok(Self::Twap),
+            Some(&PUBLISHER_STAKE_CAPS_MESSAGE_TYPE) => {
+                let header = LEN_MESSAGE_TYPE + LEN_PUBLISH_TIME + LEN_NUM_CAPS;
+                if bytes.len() < header {
+                    return Err(OracleError::MessageDecode(
+                        "truncated publisher stake caps".to_string(),
+                    ));
+                }
+                let num_caps = u16::from_be_bytes([bytes[header - 2], bytes[header - 1]]);
+                Ok(Self::PublisherStakeCaps {
+                    num_caps: num_caps as usize,
+                })
+            }
+            _ => Err(OracleError::UnsupportedMessage),
+        }
+    }
+
+    pub fn message_type(&self) -> u8 {
+        match self {
+            Self::PriceFeed => PRICE_FEED_MESSAGE_TYPE,
+            Self::Twap => TWAP_MESSAGE_TYPE,
+            Self::PublisherStakeCaps { .. } => PUBLISHER_STAKE_CAPS_MESSAGE_TYPE,
+        }
+    }
+
+    /// Length of the wire format.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::PriceFeed => LEN_PRICE_FEED,
+            Self::Twap => LEN_TWAP,
+            Self::PublisherStakeCaps { num_caps } => {
+                LEN_MESSAGE_TYPE
+                    + LEN_PUBLISH_TIME
+                    + LEN_NUM_CAPS
+                    + num_caps * (LEN_PUBLISHER + LEN_CAP)
+            }
+        }
+    }
+}
+
+/// Circuit representation of pyth `Message::TwapMessage`, cumulative sums of prices and
+/// confidences over the slots up to `publish_slot`.
+#[derive(Debug, Clone, Copy)]
+pub struct TwapFeed<E: Engine> {
+    pub message_type: [Byte<E>; LEN_MESSAGE_TYPE],
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    /// `i128` sum of the prices of the slots.
+    pub cumulative_price: [Byte<E>; LEN_CUMULATIVE_PRICE],
+    pub cumulative_conf: [Byte<E>; LEN_CUMULATIVE_CONF],
+    /// Number of slots without a price.
+    pub num_down_slots: [Byte<E>; LEN_NUM_DOWN_SLOTS],
+    pub exponent: [Byte<E>; LEN_EXPONENT],
+    pub publish_time: [Byte<E>; LEN_PUBLISH_TIME],
+    pub prev_publish_time: [Byte<E>; LEN_PREV_PUBLISH_TIME],
+    pub publish_slot: [Byte<E>; LEN_PUBLISH_SLOT],
+}
+
+impl<E: Engine> TwapFeed<E> {
+    /// Allocate a TWAP message from its wire format, `bytes` must be exactly one `TwapMessage`.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        if bytes.len() != LEN_TWAP {
+            return Err(OracleError::InvalidLength {
+                what: "TWAP message",
+                got: bytes.len(),
+                expected: LEN_TWAP,
+            });
+        }
+        if bytes[0] != TWAP_MESSAGE_TYPE {
+            return Err(OracleError::InvalidMessageType {
+                got: bytes[0],
+                expected: TWAP_MESSAGE_TYPE,
+            });
+        }
+        let mut offset = 0;
+        let mut next = |len: usize| {
+            let chunk = &bytes[offset..offset + len];
+            offset += len;
+            chunk
+        };
+        let message_type = next(LEN_MESSAGE_TYPE).try_into().unwrap();
+        let feed_id = next(LEN_FEED_ID).try_into().unwrap();
+        let cumulative_price = next(LEN_CUMULATIVE_PRICE).try_into().unwrap();
+        let cumulative_conf = next(LEN_CUMULATIVE_CONF).try_into().unwrap();
+        let num_down_slots = next(LEN_NUM_DOWN_SLOTS).try_into().unwrap();
+        let exponent = next(LEN_EXPONENT).try_into().unwrap();
+        let publish_time = next(LEN_PUBLISH_TIME).try_into().unwrap();
+        let prev_publish_time = next(LEN_PREV_PUBLISH_TIME).try_into().unwrap();
+        let publish_slot = next(LEN_PUBLISH_SLOT).try_into().unwrap();
+        Ok(Self {
+            message_type: CSAllocatable::alloc_from_witness(cs, Some(message_type))?,
+            feed_id: CSAllocatable::alloc_from_witness(cs, Some(feed_id))?,
+            cumulative_price: CSAllocatable::alloc_from_witness(cs, Some(cumulative_price))?,
+            cumulative_conf: CSAllocatable::alloc_from_witness(cs, Some(cumulative_conf))?,
+            num_down_slots: CSAllocatable::alloc_from_witness(cs, Some(num_down_slots))?,
+            exponent: CSAllocatable::alloc_from_witness(cs, Some(exponent))?,
+            publish_time: CSAllocatable::alloc_from_witness(cs, Some(publish_time))?,
+            prev_publish_time: CSAllocatable::alloc_from_witness(cs, Some(prev_publish_time))?,
+            publish_slot: CSAllocatable::alloc_from_witness(cs, Some(publish_slot))?,
+        })
+    }
+
+    /// Allocate a TWAP message with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            message_type: CSAllocatable::alloc_from_witness(cs, None)?,
+            feed_id: CSAllocatable::alloc_from_witness(cs, None)?,
+            cumulative_price: CSAllocatable::alloc_from_witness(cs, None)?,
+            cumulative_conf: CSAllocatable::alloc_from_witness(cs, None)?,
+            num_down_slots: CSAllocatable::alloc_from_witness(cs, None)?,
+            exponent: CSAllocatable::alloc_from_witness(cs, None)?,
+            publish_time: CSAllocatable::alloc_from_witness(cs, None)?,
+            prev_publish_time: CSAllocatable::alloc_from_witness(cs, None)?,
+            publish_slot: CSAllocatable::alloc_from_witness(cs, None)?,
+        })
+    }
+
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.message_type
+            .iter()
+            .chain(self.feed_id.iter())
+            .chain(self.cumulative_price.iter())
+            .chain(self.cumulative_conf.iter())
+            .chain(self.num_down_slots.iter())
+            .chain(self.exponent.iter())
+            .chain(self.publish_time.iter())
+            .chain(self.prev_publish_time.iter())
+            .chain(self.publish_slot.iter())
+            .copied()
+    }
+}
+
+/// Stake cap of a publisher.
+#[derive(Debug, Clone, Copy)]
+pub struct PublisherStakeCap<E: Engine> {
+    pub publisher: [Byte<E>; LEN_PUBLISHER],
+    /// `u64` cap.
+    pub cap: [Byte<E>; LEN_CAP],
+}
+
+/// Circuit representation of pyth `Message::PublisherStakeCapsMessage` of a fixed number of caps.
+#[derive(Debug, Clone)]
+pub struct PublisherStakeCaps<E: Engine> {
+    pub message_type: [Byte<E>; LEN_MESSAGE_TYPE],
+    pub publish_time: [Byte<E>; LEN_PUBLISH_TIME],
+    /// `u16` number of caps, prefixing them.
+    pub num_caps: [Byte<E>; LEN_NUM_CAPS],
+    pub caps: Vec<PublisherStakeCap<E>>,
+}
+
+impl<E: Engine> PublisherStakeCaps<E> {
+    /// Allocate publisher stake caps from their wire format.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        let layout = MessageLayout::of_message(bytes)?;
+        if !matches!(layout, MessageLayout::PublisherStakeCaps { .. }) {
+            return Err(OracleError::InvalidMessageType {
+                got: bytes[0],
+                expected: PUBLISHER_STAKE_CAPS_MESSAGE_TYPE,
+            });
+        }
+        if bytes.len() != layout.len() {
+            return Err(OracleError::InvalidLength {
+                what: "publisher stake caps",
+                got: bytes.len(),
+                expected: layout.len(),
+            });
+        }
+        let (header, caps) = bytes.split_at(LEN_MESSAGE_TYPE + LEN_PUBLISH_TIME + LEN_NUM_CAPS);
+        let caps = caps
+            .chunks_exact(LEN_PUBLISHER + LEN_CAP)
+            .map(|cap| {
+                Ok(PublisherStakeCap {
+                    publisher: CSAllocatable::alloc_from_witness(
+                        cs,
+                        Some(to_array("publisher", &cap[..LEN_PUBLISHER])?),
+                    )?,
+                    cap: CSAllocatable::alloc_from_witness(
+                        cs,
+                        Some(to_array("stake cap", &cap[LEN_PUBLISHER..])?),
+                    )?,
+                })
+            })
+            .collect::<Result<Vec<_>, OracleError>>()?;
+        Ok(Self {
+            message_type: CSAllocatable::alloc_from_witness(cs, Some([header[0]]))?,
+            publish_time: CSAllocatable::alloc_from_witness(
+                cs,
+                Some(to_array("publish time", &header[1..9])?),
+            )?,
+            num_caps: CSAllocatable::alloc_from_witness(cs, Some([header[9], header[10]]))?,
+            caps,
+        })
+    }
+
+    /// Allocate `num_caps` caps with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        num_caps: usize,
+    ) -> Result<Self, SynthesisError> {
+        let caps = (0..num_caps)
+            .map(|_| {
+                Ok(PublisherStakeCap {
+                    publisher: CSAllocatable::alloc_from_witness(cs, None)?,
+                    cap: CSAllocatable::alloc_from_witness(cs, None)?,
+                })
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        Ok(Self {
+            message_type: CSAllocatable::alloc_from_witness(cs, None)?,
+            publish_time: CSAllocatable::alloc_from_witness(cs, None)?,
+            num_caps: CSAllocatable::alloc_from_witness(cs, None)?,
+            caps,
+        })
+    }
+
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.message_type
+            .iter()
+            .chain(self.publish_time.iter())
+            .chain(self.num_caps.iter())
+            .copied()
+            .chain(
+                self.caps
+                    .iter()
+                    .flat_map(|cap| cap.publisher.into_iter().chain(cap.cap)),
+            )
+    }
+}
+
+/// Tagged union of the accumulator messages.
+#[derive(Debug, Clone)]
+pub enum AccumulatorMessage<E: Engine> {
+    PriceFeed(PriceFeed<E>),
+    Twap(TwapFeed<E>),
+    PublisherStakeCaps(PublisherStakeCaps<E>),
+}
+
+impl<E: Engine> AccumulatorMessage<E> {
+    /// Allocate the message of the wire format `bytes`, parsed as set by its leading type byte.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        match MessageLayout::of_message(bytes)? {
+            MessageLayout::PriceFeed => Ok(Self::PriceFeed(PriceFeed::new_from_slice(cs, bytes)?)),
+            MessageLayout::Twap => Ok(Self::Twap(TwapFeed::new_from_slice(cs, bytes)?)),
+            MessageLayout::PublisherStakeCaps { .. } => Ok(Self::PublisherStakeCaps(
+                PublisherStakeCaps::new_from_slice(cs, bytes)?,
+            )),
+        }
+    }
+
+    /// Allocate a message of `layout` with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        layout: MessageLayout,
+    ) -> Result<Self, SynthesisError> {
+        match layout {
+            MessageLayout::PriceFeed => Ok(Self::PriceFeed(PriceFeed::alloc_absent(cs)?)),
+            MessageLayout::Twap => Ok(Self::Twap(TwapFeed::alloc_absent(cs)?)),
+            MessageLayout::PublisherStakeCaps { num_caps } => Ok(Self::PublisherStakeCaps(
+                PublisherStakeCaps::alloc_absent(cs, num_caps)?,
+            )),
+        }
+    }
+
+    pub fn layout(&self) -> MessageLayout {
+        match self {
+            Self::PriceFeed(_) => MessageLayout::PriceFeed,
+            Self::Twap(_) => MessageLayout::Twap,
+            Self::PublisherStakeCaps(caps) => MessageLayout::PublisherStakeCaps {
+                num_caps: caps.caps.len(),
+            },
+        }
+    }
+
+    /// Bytes of the wire format, the merkle leaf of the message.
+    pub fn to_bytes<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Vec<Byte<E>> {
+        match self {
+            Self::PriceFeed(price_feed) => price_feed.to_bytes(cs).to_vec(),
+            Self::Twap(twap) => twap.bytes_iter().collect(),
+            Self::PublisherStakeCaps(caps) => caps.bytes_iter().collect(),
+        }
+    }
+
+    /// Whether the leading type byte is the one of the variant, and the number of publisher stake
+    /// caps the one allocated, so that the bytes parse as allocated.
+    pub fn check_type<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let layout = self.layout();
+        let mut expected = vec![];
+        match self {
+            Self::PriceFeed(price_feed) => {
+                expected.push((price_feed.price_feed_type[0], layout.message_type() as u64))
+            }
+            Self::Twap(twap) => expected.push((twap.message_type[0], layout.message_type() as u64)),
+            Self::PublisherStakeCaps(caps) => {
+                expected.push((caps.message_type[0], layout.message_type() as u64));
+                let num_caps = (caps.caps.len() as u16).to_be_bytes();
+                expected.push((caps.num_caps[0], num_caps[0] as u64));
+                expected.push((caps.num_caps[1], num_caps[1] as u64));
+            }
+        }
+        let mut is_ok = vec![];
+        for (byte, expected) in expected {
+            let expected = Num::Constant(u64_to_fe(expected));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+}
+
+/// Accumulator message with its merkle proof, the generalization of
+/// [`PriceUpdate`](super::PriceUpdate) to any message type.
+#[derive(Debug, Clone)]
+pub struct MessageUpdate<E: Engine, const N: usize = 10> {
+    pub message: AccumulatorMessage<E>,
+    pub proof: MerklePath<E, N>,
+}
+
+impl<E: Engine, const N: usize> MessageUpdate<E, N> {
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        message: &[u8],
+        proof: &[[u8; keccak160::WIDTH_HASH_BYTES]],
+    ) -> Result<Self, OracleError> {
+        let message = AccumulatorMessage::new_from_slice(cs, message)?;
+        let proof = proof
+            .iter()
+            .map(|hash| keccak160::Hash::alloc_from_witness(cs, Some(*hash)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            message,
+            proof: MerklePath::new(to_array("merkle proof", &proof)?),
+        })
+    }
+
+    /// Allocate an update of a message of `layout` with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        layout: MessageLayout,
+    ) -> Result<Self, SynthesisError> {
+        let message = AccumulatorMessage::alloc_absent(cs, layout)?;
+        let proof = (0..N)
+            .map(|_| keccak160::Hash::alloc_from_witness(cs, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            message,
+            proof: MerklePath::new(to_array("merkle proof", &proof)?),
+        })
+    }
+
+    /// Whether the message is of its allocated type and in the tree of `root`.
+    pub fn check<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        keccak: &Keccak256Params<E>,
+        root: &MerkleRoot<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let is_typed = self.message.check_type(cs)?;
+        let bytes = self.message.to_bytes(cs);
+        let is_included = root.check(cs, keccak, &self.proof, &bytes)?;
+        Boolean::and(cs, &is_typed, &is_included)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        traits::CSAllocatable,
+    };
+
+    use super::{AccumulatorMessage, MessageLayout, MessageUpdate, LEN_TWAP};
+    use crate::{
+        error::OracleError,
+        gadgets::{keccak160::MerkleRoot, keccak256::Keccak256Params},
+        testing::fixtures::{FixturePrice, MerkleTree},
+        utils::testing::{bytes_assert_eq, create_test_constraint_system},
+    };
+
+    fn twap_message() -> Vec<u8> {
+        let mut bytes = vec![1u8];
+        bytes.extend([3u8; 32]);
+        bytes.extend((-4_200_000_000_000i128).to_be_bytes());
+        bytes.extend(1_000_000u128.to_be_bytes());
+        bytes.extend(7u64.to_be_bytes());
+        bytes.extend((-8i32).to_be_bytes());
+        bytes.extend(1_700_000_000i64.to_be_bytes());
+        bytes.extend(1_699_999_999i64.to_be_bytes());
+        bytes.extend(123_456u64.to_be_bytes());
+        bytes
+    }
+
+    fn publisher_stake_caps(num_caps: u16) -> Vec<u8> {
+        let mut bytes = vec![2u8];
+        bytes.extend(1_700_000_000i64.to_be_bytes());
+        bytes.extend(num_caps.to_be_bytes());
+        for i in 0..num_caps {
+            bytes.extend([i as u8; 32]);
+            bytes.extend((i as u64 * 1_000).to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_accumulator_message() -> Result<(), SynthesisError> {
+        let messages = [
+            FixturePrice::new([1u8; 32], 100, -8, 1_700_000_000).to_bytes(),
+            twap_message(),
+            publisher_stake_caps(3),
+        ];
+        assert_eq!(messages[1].len(), LEN_TWAP);
+        assert_eq!(
+            MessageLayout::of_message(&messages[2])?,
+            MessageLayout::PublisherStakeCaps { num_caps: 3 }
+        );
+        let tree = MerkleTree::new(&messages, 10);
+
+        let cs = &mut create_test_constraint_system()?;
+        let root = MerkleRoot::new(CSAllocatable::alloc_from_witness(cs, Some(tree.root()))?);
+        let keccak = Keccak256Params::new(cs)?;
+        for (i, bytes) in messages.iter().enumerate() {
+            let update = MessageUpdate::<Bn256>::new_from_slice(cs, bytes, &tree.prove(i))?;
+            assert_eq!(update.message.layout(), MessageLayout::of_message(bytes)?);
+            assert_eq!(update.message.layout().len(), bytes.len());
+            bytes_assert_eq(&update.message.to_bytes(cs), hex::encode(bytes));
+            assert_eq!(update.check(cs, &keccak, &root)?.get_value(), Some(true));
+        }
+        assert!(matches!(
+            AccumulatorMessage::<Bn256>::new_from_slice(cs, &[3u8; 10]),
+            Err(OracleError::UnsupportedMessage)
+        ));
+        assert!(
+            AccumulatorMessage::<Bn256>::new_from_slice(cs, &publisher_stake_caps(3)[..50])
+                .is_err()
+        );
+
+        // A TWAP leaf proven as price feed bytes would not parse as allocated.
+        let mut message = AccumulatorMessage::<Bn256>::new_from_slice(cs, &messages[1])?;
+        if let AccumulatorMessage::Twap(twap) = &mut message {
+            twap.message_type = [Byte::alloc_from_witness(cs, Some(0))?];
+        }
+        assert_eq!(message.check_type(cs)?.get_value(), Some(false));
+        let mut caps = AccumulatorMessage::<Bn256>::new_from_slice(cs, &messages[2])?;
+        if let AccumulatorMessage::PublisherStakeCaps(caps) = &mut caps {
+            caps.num_caps[1] = Byte::alloc_from_witness(cs, Some(2))?;
+        }
+        assert_eq!(caps.check_type(cs)?.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
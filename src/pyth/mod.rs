@@ -1,10 +1,38 @@
+pub mod aggregation;
+mod attestation;
+mod builder;
 pub mod circuit;
+pub mod commitment;
+pub mod decode;
+pub mod governance;
+#[cfg(test)]
+mod differential;
+mod guardian_set;
+mod guardians;
+pub mod lazer;
+pub mod legacy;
+pub mod merkle_only;
+mod message;
+mod multi_vaa;
 mod params;
+mod prefilter;
 mod price;
+pub mod rotation;
+pub mod setup;
+pub mod timestamp;
+mod witness;
 mod wormhole;
 
 pub const WIDTH_PRICE_FEED_BYTES: usize = price::LEN_PRICE_FEED;
+pub use attestation::*;
+pub use builder::*;
 pub use circuit::*;
+pub use guardian_set::*;
+pub use guardians::*;
+pub use message::*;
+pub use multi_vaa::*;
 pub use params::*;
+pub use prefilter::*;
 pub use price::*;
+pub use witness::*;
 pub use wormhole::*;
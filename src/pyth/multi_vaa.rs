@@ -0,0 +1,164 @@
+//! Several VAAs verified against one guardian set.
+//!
+//! The guardian set is allocated once by the caller and the secp256k1 constants are built once for
+//! the signatures of all VAAs, instead of once per VAA as with [`Vaa::check_by_address`].
+
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    glue::prepacked_long_comparison,
+    vm::partitioner::smart_and,
+};
+
+use super::Vaa;
+use crate::{
+    error::OracleError,
+    gadgets::{ecdsa::Secp256k1Params, ethereum::Address, keccak256::Keccak256Params},
+};
+
+/// `NUM_VAAS` VAAs of `NUM_SIGS` signatures each, possibly of different wormhole sequences.
+#[derive(Debug, Clone)]
+pub struct MultiVaa<E: Engine, const NUM_VAAS: usize, const NUM_SIGS: usize> {
+    pub vaas: [Vaa<E>; NUM_VAAS],
+}
+
+impl<E: Engine, const NUM_VAAS: usize, const NUM_SIGS: usize> MultiVaa<E, NUM_VAAS, NUM_SIGS> {
+    pub fn new(vaas: [Vaa<E>; NUM_VAAS]) -> Self {
+        Self { vaas }
+    }
+
+    /// Allocate each VAA from its wire format, see [`Vaa::new_from_slice`].
+    pub fn new_from_slices<CS: ConstraintSystem<E>, T: AsRef<[u8]>>(
+        cs: &mut CS,
+        vaas: &[T],
+    ) -> Result<Self, OracleError> {
+        if vaas.len() != NUM_VAAS {
+            return Err(OracleError::InvalidLength {
+                what: "VAAs",
+                got: vaas.len(),
+                expected: NUM_VAAS,
+            });
+        }
+        let vaas = vaas
+            .iter()
+            .map(|vaa| Vaa::new_from_slice(cs, vaa.as_ref(), NUM_SIGS))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(vaas.try_into().unwrap()))
+    }
+
+    /// Allocate the VAAs with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        let vaas = (0..NUM_VAAS)
+            .map(|_| Vaa::from_optional_slice(cs, None, NUM_SIGS))
+            .collect::<Result<Vec<_>, OracleError>>()?;
+        Ok(Self::new(vaas.try_into().unwrap()))
+    }
+
+    /// Check every VAA as [`Vaa::check_by_address`] does.
+    pub fn check_by_address<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        let mut is_ok = vec![Boolean::constant(true)];
+        for vaa in self.vaas.iter() {
+            is_ok.push(vaa.check_by_address_with_params(cs, &params, &keccak, guardian_set)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// See [`is_sequence_increasing`].
+    pub fn is_sequence_increasing<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        is_sequence_increasing(cs, &self.vaas.iter().collect::<Vec<_>>())
+    }
+}
+
+/// Whether the sequences of `vaas` are strictly increasing, so that a batch of VAAs of one emitter
+/// includes none twice and none older than the ones before it.
+pub fn is_sequence_increasing<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaas: &[&Vaa<E>],
+) -> Result<Boolean, SynthesisError> {
+    let sequences = vaas
+        .iter()
+        .map(|vaa| Ok(vaa.body.sequence_u64(cs)?.inner))
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+    let mut is_increasing = vec![Boolean::constant(true)];
+    for pair in sequences.windows(2) {
+        let (_, is_greater) = prepacked_long_comparison(cs, &[pair[1]], &[pair[0]], &[64])?;
+        is_increasing.push(is_greater);
+    }
+    smart_and(cs, &is_increasing)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+
+    use super::MultiVaa;
+    use crate::{
+        error::OracleError, gadgets::ethereum::Address, testing::fixtures::AccumulatorFixture,
+        utils::testing::create_test_constraint_system,
+    };
+
+    #[test]
+    fn test_multi_vaa() -> Result<(), SynthesisError> {
+        let fixture = AccumulatorFixture {
+            num_signatures: 2,
+            ..Default::default()
+        };
+        let vaas = (0..2)
+            .map(|sequence| {
+                AccumulatorFixture {
+                    sequence,
+                    ..fixture.clone()
+                }
+                .vaa()
+            })
+            .collect::<Vec<_>>();
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = fixture
+            .guardian_set()
+            .iter()
+            .map(|address| Address::from_address_witness(cs, address))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let multi_vaa = MultiVaa::<Bn256, 2, 2>::new_from_slices(cs, &vaas)?;
+        let is_valid = multi_vaa.check_by_address(cs, &guardian_set)?;
+        assert_eq!(is_valid.get_value(), Some(true));
+
+        let mut tampered = vaas.clone();
+        *tampered[1].last_mut().unwrap() ^= 1;
+        let multi_vaa = MultiVaa::<Bn256, 2, 2>::new_from_slices(cs, &tampered)?;
+        let is_valid = multi_vaa.check_by_address(cs, &guardian_set)?;
+        assert_eq!(is_valid.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+
+        let multi_vaa = MultiVaa::<Bn256, 2, 2>::new_from_slices(cs, &vaas)?;
+        let is_increasing = multi_vaa.is_sequence_increasing(cs)?;
+        assert_eq!(is_increasing.get_value(), Some(true));
+        let replayed = [vaas[1].clone(), vaas[1].clone()];
+        let multi_vaa = MultiVaa::<Bn256, 2, 2>::new_from_slices(cs, &replayed)?;
+        let is_increasing = multi_vaa.is_sequence_increasing(cs)?;
+        assert_eq!(is_increasing.get_value(), Some(false));
+        let reordered = [vaas[1].clone(), vaas[0].clone()];
+        let multi_vaa = MultiVaa::<Bn256, 2, 2>::new_from_slices(cs, &reordered)?;
+        let is_increasing = multi_vaa.is_sequence_increasing(cs)?;
+        assert_eq!(is_increasing.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+
+        let err = MultiVaa::<Bn256, 3, 2>::new_from_slices(cs, &vaas).unwrap_err();
+        assert!(matches!(err, OracleError::InvalidLength { got: 2, .. }));
+        MultiVaa::<Bn256, 2, 2>::alloc_absent(cs)?;
+        Ok(())
+    }
+}
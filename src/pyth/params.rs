@@ -1,4 +1,5 @@
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 
 lazy_static! {
     pub static ref GUARDIAN_SET: [[u8; 20]; 19] = [
@@ -24,3 +25,60 @@ lazy_static! {
     ]
     .map(|w| hex::decode(w).unwrap().try_into().unwrap());
 }
+
+/// Wormhole chain id of pythnet, on mainnet and testnet alike.
+pub const PYTHNET_CHAIN_ID: u16 = 26;
+/// Emitter of the pythnet accumulator VAAs.
+pub const PYTHNET_ACCUMULATOR_EMITTER: [u8; 32] = [
+    0xe1, 0x01, 0xfa, 0xed, 0xac, 0x58, 0x51, 0xe3, 0x2b, 0x9b, 0x23, 0xb5, 0xf9, 0x41, 0x1a, 0x8c,
+    0x2b, 0xac, 0x4a, 0xae, 0x3e, 0xd4, 0xdd, 0x7b, 0x81, 0x1d, 0xd1, 0xa7, 0x2e, 0xa4, 0xaa, 0x71,
+];
+/// Emitter of the pythtest accumulator VAAs, signed by the Wormhole testnet guardians.
+pub const PYTHTEST_ACCUMULATOR_EMITTER: [u8; 32] = [
+    0xa2, 0x78, 0x39, 0xd6, 0x41, 0xb0, 0x77, 0x43, 0xc0, 0xcb, 0x5f, 0x68, 0xc5, 0x1f, 0x8c, 0xd3,
+    0x1d, 0x2c, 0x07, 0x62, 0xbe, 0xc0, 0x0d, 0xc6, 0xfc, 0xd2, 0x54, 0x33, 0xef, 0x1a, 0xb5, 0xb6,
+];
+
+/// Emitter of the legacy pythnet batch price attestation VAAs, see [`super::legacy`].
+pub const PYTHNET_ATTESTER_EMITTER: [u8; 32] = [
+    0xf8, 0xcd, 0x23, 0xc2, 0xab, 0x91, 0x23, 0x77, 0x30, 0x77, 0x0b, 0xbe, 0xa0, 0x8d, 0x61, 0x00,
+    0x5c, 0xdd, 0xa0, 0x98, 0x43, 0x48, 0xf3, 0xf6, 0xee, 0xcb, 0x55, 0x96, 0x38, 0xc0, 0xbb, 0xa0,
+];
+
+/// Pyth network whose accumulator VAAs a circuit accepts, enforced on the emitter of every VAA.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PythEnvironment {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl PythEnvironment {
+    pub fn emitter_chain(&self) -> u16 {
+        PYTHNET_CHAIN_ID
+    }
+
+    pub fn emitter_address(&self) -> [u8; 32] {
+        match self {
+            PythEnvironment::Mainnet => PYTHNET_ACCUMULATOR_EMITTER,
+            PythEnvironment::Testnet => PYTHTEST_ACCUMULATOR_EMITTER,
+        }
+    }
+
+    /// Check natively that a VAA of `emitter_chain` and `emitter_address` is from this environment.
+    pub fn check_emitter(
+        &self,
+        emitter_chain: u16,
+        emitter_address: &[u8; 32],
+    ) -> Result<(), anyhow::Error> {
+        anyhow::ensure!(
+            emitter_chain == self.emitter_chain() && emitter_address == &self.emitter_address(),
+            "VAA emitted by {} on chain {} is not from {:?}",
+            hex::encode(emitter_address),
+            emitter_chain,
+            self
+        );
+        Ok(())
+    }
+}
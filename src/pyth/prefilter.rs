@@ -0,0 +1,155 @@
+//! Native pre-filter of VAAs.
+//!
+//! Recovering a signature with `k256` takes tens of microseconds while synthesizing its check takes
+//! seconds, so services should run [`prefilter_vaa`] on untrusted input before building a circuit.
+//! The checks mirror [`Vaa::check_by_address`](super::Vaa::check_by_address), so any VAA the circuit
+//! accepts passes. An update passing the pre-filter may still fail on its merkle proofs or prices.
+
+use k256::{
+    ecdsa::{RecoveryId, Signature, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+};
+use sha3::{Digest, Keccak256};
+
+const VAA_VERSION: u8 = 1;
+const LEN_HEADER: usize = 6;
+const LEN_SIGNATURE: usize = 66;
+
+/// Why a VAA was rejected by [`prefilter_vaa`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VaaRejection {
+    #[error("truncated VAA of {0} bytes")]
+    Truncated(usize),
+    #[error("unsupported VAA version {0}")]
+    UnsupportedVersion(u8),
+    #[error("got {got} signatures, need at least {need}")]
+    InsufficientSignatures { got: usize, need: usize },
+    #[error("malformed signature {0}")]
+    MalformedSignature(usize),
+    #[error("signature {index} is signed by {address}, not a guardian")]
+    UnknownSigner { index: usize, address: String },
+    #[error("signature {index} reuses guardian {guardian}")]
+    DuplicateSigner { index: usize, guardian: usize },
+    #[error("guardian index of signature {0} is not above the previous one")]
+    UnorderedSignature(usize),
+}
+
+/// Check that the first `num_signatures` signatures of `vaa` are from distinct members of
+/// `guardian_set`, without any circuit work.
+pub fn prefilter_vaa(
+    vaa: &[u8],
+    guardian_set: &[[u8; 20]],
+    num_signatures: usize,
+) -> Result<(), VaaRejection> {
+    if vaa.len() < LEN_HEADER {
+        return Err(VaaRejection::Truncated(vaa.len()));
+    }
+    if vaa[0] != VAA_VERSION {
+        return Err(VaaRejection::UnsupportedVersion(vaa[0]));
+    }
+    let len_signatures = vaa[LEN_HEADER - 1] as usize;
+    if len_signatures < num_signatures {
+        return Err(VaaRejection::InsufficientSignatures {
+            got: len_signatures,
+            need: num_signatures,
+        });
+    }
+    let body_offset = LEN_HEADER + len_signatures * LEN_SIGNATURE;
+    if vaa.len() < body_offset {
+        return Err(VaaRejection::Truncated(vaa.len()));
+    }
+    let digest = Keccak256::digest(Keccak256::digest(&vaa[body_offset..]));
+
+    let mut used = vec![false; guardian_set.len()];
+    let mut previous_index = None;
+    for (index, signature) in vaa[LEN_HEADER..body_offset]
+        .chunks_exact(LEN_SIGNATURE)
+        .take(num_signatures)
+        .enumerate()
+    {
+        if previous_index.map_or(false, |previous| signature[0] <= previous) {
+            return Err(VaaRejection::UnorderedSignature(index));
+        }
+        previous_index = Some(signature[0]);
+        let address = recover_address(&digest, &signature[1..])
+            .ok_or(VaaRejection::MalformedSignature(index))?;
+        let guardian = guardian_set
+            .iter()
+            .position(|g| g == &address)
+            .ok_or_else(|| VaaRejection::UnknownSigner {
+                index,
+                address: hex::encode(address),
+            })?;
+        if std::mem::replace(&mut used[guardian], true) {
+            return Err(VaaRejection::DuplicateSigner { index, guardian });
+        }
+    }
+    Ok(())
+}
+
+/// Ethereum address of the signer of the 65 bytes `r || s || v` signature of `digest`.
+fn recover_address(digest: &[u8], signature: &[u8]) -> Option<[u8; 20]> {
+    let mut recovery_id = RecoveryId::from_byte(signature[64])?;
+    let mut signature = Signature::from_slice(&signature[..64]).ok()?;
+    // k256 only recovers low-s signatures, which the circuit does not require. Negating s mirrors
+    // the nonce point, so the parity of its y flips.
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+    let key = VerifyingKey::recover_from_prehash(digest, &signature, recovery_id).ok()?;
+    let hash = Keccak256::digest(&key.to_encoded_point(false).as_bytes()[1..]);
+    hash[12..].try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prefilter_vaa, VaaRejection, LEN_HEADER, LEN_SIGNATURE};
+    use crate::testing::fixtures::AccumulatorFixture;
+
+    #[test]
+    fn test_prefilter_vaa() {
+        let fixture = AccumulatorFixture::default();
+        let guardian_set = fixture.guardian_set();
+        let vaa = fixture.vaa();
+        prefilter_vaa(&vaa, &guardian_set, fixture.num_signatures).unwrap();
+
+        assert_eq!(
+            prefilter_vaa(&vaa, &guardian_set, fixture.num_signatures + 1),
+            Err(VaaRejection::InsufficientSignatures {
+                got: fixture.num_signatures,
+                need: fixture.num_signatures + 1
+            })
+        );
+        assert_eq!(
+            prefilter_vaa(&vaa[..LEN_HEADER + 1], &guardian_set, 1),
+            Err(VaaRejection::Truncated(LEN_HEADER + 1))
+        );
+
+        let mut tampered = vaa.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            prefilter_vaa(&tampered, &guardian_set, 1),
+            Err(VaaRejection::UnknownSigner { index: 0, .. })
+        ));
+
+        let mut duplicated = vaa.clone();
+        duplicated.copy_within(
+            LEN_HEADER..LEN_HEADER + LEN_SIGNATURE,
+            LEN_HEADER + LEN_SIGNATURE,
+        );
+        assert_eq!(
+            prefilter_vaa(&duplicated, &guardian_set, 2),
+            Err(VaaRejection::UnorderedSignature(1))
+        );
+        // Claiming another index does not hide the duplicate.
+        duplicated[LEN_HEADER + LEN_SIGNATURE] = 1;
+        assert_eq!(
+            prefilter_vaa(&duplicated, &guardian_set, 2),
+            Err(VaaRejection::DuplicateSigner {
+                index: 1,
+                guardian: 0
+            })
+        );
+    }
+}
@@ -6,15 +6,17 @@ use advanced_circuit_component::{
         plonk::circuit::boolean::Boolean,
     },
     traits::CSAllocatable,
-    vm::primitives::uint256::UInt256,
+    vm::primitives::{uint256::UInt256, UInt32, UInt64},
 };
 
 use crate::{
+    error::OracleError,
     gadgets::{
+        ecdsa::Secp256k1Params,
         ethereum::Address,
         keccak160::{self, MerklePath, MerkleRoot},
+        keccak256::Keccak256Params,
     },
-    utils::new_synthesis_error,
 };
 
 use super::wormhole::Vaa;
@@ -32,51 +34,64 @@ impl<E: Engine, const N: usize> PriceUpdate<E, N> {
     pub fn from_price_update_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: pythnet_sdk::wire::v1::MerklePriceUpdate,
-    ) -> Result<Self, SynthesisError> {
+    ) -> Result<Self, OracleError> {
+        Self::from_optional_witness(cs, Some(witness))
+    }
+
+    /// Allocate a price update, `None` for setup passes, which get the same layout.
+    pub fn from_optional_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<pythnet_sdk::wire::v1::MerklePriceUpdate>,
+    ) -> Result<Self, OracleError> {
         use pythnet_sdk::messages::Message;
-        let message = {
-            let message: Vec<u8> = witness.message.into();
-            let price_feed = pythnet_sdk::wire::from_slice::<byteorder::BE, Message>(&message)
-                .map_err(new_synthesis_error)?;
-            PriceFeed::from_message_witness(cs, price_feed)?
-        };
-        let proof = {
-            let proof = witness.proof.to_bytes();
-            if proof.len() != N * keccak160::WIDTH_HASH_BYTES {
-                return Err(new_synthesis_error(format!(
-                    "invalid proof length {}, expect {}",
-                    proof.len(),
-                    N * keccak160::WIDTH_HASH_BYTES
-                )));
+        let (message, proof) = match witness {
+            Some(witness) => {
+                let message: Vec<u8> = witness.message.into();
+                let message = pythnet_sdk::wire::from_slice::<byteorder::BE, Message>(&message)
+                    .map_err(|e| OracleError::MessageDecode(e.to_string()))?;
+                let proof = witness.proof.to_bytes();
+                if proof.len() != N * keccak160::WIDTH_HASH_BYTES {
+                    return Err(OracleError::InvalidLength {
+                        what: "merkle proof",
+                        got: proof.len(),
+                        expected: N * keccak160::WIDTH_HASH_BYTES,
+                    });
+                }
+                let proof = proof
+                    .chunks_exact(keccak160::WIDTH_HASH_BYTES)
+                    .map(|chunk| {
+                        Some(<[u8; keccak160::WIDTH_HASH_BYTES]>::try_from(chunk).unwrap())
+                    })
+                    .collect::<Vec<_>>();
+                (Some(message), proof)
             }
-
-            let merkle_paths: [[u8; keccak160::WIDTH_HASH_BYTES]; N] = proof
-                .chunks_exact(keccak160::WIDTH_HASH_BYTES)
-                .map(|chunk| chunk.try_into().unwrap())
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap();
-
-            let merkle_paths = merkle_paths
-                .into_iter()
-                .map(|hash| keccak160::Hash::alloc_from_witness(cs, Some(hash)))
-                .collect::<Result<Vec<_>, _>>()?
-                .try_into()
-                .unwrap();
-
-            MerklePath(merkle_paths)
+            None => (None, vec![None; N]),
         };
-        // let merkle_paths = MerklePath::new(merkle_paths.try_into().unwrap());
-        Ok(Self { message, proof })
+        let message = PriceFeed::from_optional_message_witness(cs, message)?;
+        let proof = proof
+            .into_iter()
+            .map(|hash| keccak160::Hash::alloc_from_witness(cs, hash))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = proof.len();
+        let proof = proof.try_into().map_err(|_| OracleError::InvalidLength {
+            what: "merkle proof",
+            got: len * keccak160::WIDTH_HASH_BYTES,
+            expected: N * keccak160::WIDTH_HASH_BYTES,
+        })?;
+        Ok(Self {
+            message,
+            proof: MerklePath::new(proof),
+        })
     }
 
     pub fn check<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
+        keccak: &Keccak256Params<E>,
         root: &MerkleRoot<E>,
     ) -> Result<Boolean, SynthesisError> {
         let bytes = self.message.to_bytes(cs);
-        root.check(cs, &self.proof, &bytes)
+        root.check(cs, keccak, &self.proof, &bytes)
     }
 }
 
@@ -92,6 +107,40 @@ pub struct PriceUpdates<E: Engine, const N1: usize, const N2: usize> {
 }
 
 impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
+    /// Allocate the VAA and the `N1` price updates of `accumulator_update_data`, `None` for setup
+    /// passes.
+    pub fn from_optional_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        accumulator_update_data: Option<pythnet_sdk::wire::v1::AccumulatorUpdateData>,
+        num_signature_to_verify: usize,
+    ) -> Result<Self, OracleError> {
+        let (vaa, updates) = match accumulator_update_data {
+            Some(data) => {
+                let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } = data.proof;
+                (Some(vaa), updates.into_iter().map(Some).collect::<Vec<_>>())
+            }
+            None => (None, (0..N1).map(|_| None).collect()),
+        };
+        let vaa = Vaa::from_optional_slice(
+            cs,
+            vaa.as_ref().map(|vaa| vaa.as_ref()),
+            num_signature_to_verify,
+        )?;
+        let price_updates = updates
+            .into_iter()
+            .map(|u| PriceUpdate::from_optional_witness(cs, u))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = price_updates.len();
+        let price_updates =
+            price_updates
+                .try_into()
+                .map_err(|_| OracleError::InvalidPriceCount {
+                    got: len,
+                    expected: N1,
+                })?;
+        Ok(Self { vaa, price_updates })
+    }
+
     /// Check if the price updates and VAA are valid.
     pub fn check_by_pubkey<CS: ConstraintSystem<E>>(
         &self,
@@ -99,7 +148,8 @@ impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
         guardian_set: &[(UInt256<E>, UInt256<E>)],
     ) -> Result<Boolean, SynthesisError> {
         let valid_signatures = self.check_vaa_by_pubkey(cs, guardian_set)?;
-        let valid_updates = self.check_price_updates(cs)?;
+        let keccak = Keccak256Params::new(cs)?;
+        let valid_updates = self.check_price_updates(cs, &keccak)?;
         Boolean::and(cs, &valid_signatures, &valid_updates)
     }
 
@@ -108,8 +158,24 @@ impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
         cs: &mut CS,
         guardian_set: &[Address<E>],
     ) -> Result<Boolean, SynthesisError> {
-        let valid_signatures = self.check_vaa_by_address(cs, guardian_set)?;
-        let valid_updates = self.check_price_updates(cs)?;
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        self.check_by_address_with_params(cs, &params, &keccak, guardian_set)
+    }
+
+    /// Like [`PriceUpdates::check_by_address`], with `params` and `keccak` shared with other
+    /// gadgets.
+    pub fn check_by_address_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        guardian_set: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let valid_signatures =
+            self.vaa
+                .check_by_address_with_params(cs, params, keccak, guardian_set)?;
+        let valid_updates = self.check_price_updates(cs, keccak)?;
         Boolean::and(cs, &valid_signatures, &valid_updates)
     }
 
@@ -134,11 +200,12 @@ impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
     pub fn check_price_updates<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
+        keccak: &Keccak256Params<E>,
     ) -> Result<Boolean, SynthesisError> {
         let root = self.vaa.merkle_root();
         let mut result = Boolean::constant(true);
         for price_update in self.price_updates.iter() {
-            let check = price_update.check(cs, root)?;
+            let check = price_update.check(cs, keccak, root)?;
             result = Boolean::and(cs, &result, &check)?;
         }
         Ok(result)
@@ -163,6 +230,7 @@ pub const LEN_PRICE_FEED: usize = LEN_PRICE_FEED_TYPE
     + LEN_PREV_PUBLISH_TIME
     + LEN_EMA_PRICE
     + LEN_EMA_CONF;
+const PRICE_FEED_TYPE: u8 = 0; // Discriminant of `Message::PriceFeedMessage`.
 /// Circuit representation of pyth
 /// [`Message::PriceFeedMessage`]((https://github.com/pyth-network/pyth-crosschain/blob/178ad4cb0edff38f43d8e26f23d1d9e83448093c/pythnet/pythnet_sdk/src/messages.rs#L36-L39))
 #[derive(Debug, Clone, Copy)]
@@ -182,54 +250,100 @@ impl<E: Engine> PriceFeed<E> {
     pub fn from_message_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: pythnet_sdk::messages::Message,
-    ) -> Result<Self, SynthesisError> {
+    ) -> Result<Self, OracleError> {
+        Self::from_optional_message_witness(cs, Some(witness))
+    }
+
+    /// Allocate a price feed with absent witnesses, see [`PriceUpdate::from_optional_witness`].
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        Ok(Self::from_optional_message_witness(cs, None)?)
+    }
+
+    /// Allocate a price feed, `None` for setup passes, which get the same layout.
+    pub fn from_optional_message_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<pythnet_sdk::messages::Message>,
+    ) -> Result<Self, OracleError> {
         let witness = match witness {
-            pythnet_sdk::messages::Message::PriceFeedMessage(p) => p,
-            _ => return Err(new_synthesis_error("invalid message type")),
-        };
-        let price_feed_type = [Byte::<E>::alloc_from_witness(cs, Some(0u8))?];
-        let feed_id = {
-            let bytes = witness.feed_id;
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+            Some(pythnet_sdk::messages::Message::PriceFeedMessage(p)) => Some(p),
+            Some(_) => return Err(OracleError::UnsupportedMessage),
+            None => None,
         };
-        let price = {
-            let bytes = witness.price.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let conf = {
-            let bytes = witness.conf.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let exponent = {
-            let bytes = witness.exponent.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let publish_time = {
-            let bytes = witness.publish_time.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let prev_publish_time = {
-            let bytes = witness.prev_publish_time.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let ema_price = {
-            let bytes = witness.ema_price.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let ema_conf = {
-            let bytes = witness.ema_conf.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+        let witness = witness.as_ref();
+        Ok(Self {
+            price_feed_type: [Byte::alloc_from_witness(
+                cs,
+                witness.map(|_| PRICE_FEED_TYPE),
+            )?],
+            feed_id: CSAllocatable::alloc_from_witness(cs, witness.map(|w| w.feed_id))?,
+            price: CSAllocatable::alloc_from_witness(cs, witness.map(|w| w.price.to_be_bytes()))?,
+            conf: CSAllocatable::alloc_from_witness(cs, witness.map(|w| w.conf.to_be_bytes()))?,
+            exponent: CSAllocatable::alloc_from_witness(
+                cs,
+                witness.map(|w| w.exponent.to_be_bytes()),
+            )?,
+            publish_time: CSAllocatable::alloc_from_witness(
+                cs,
+                witness.map(|w| w.publish_time.to_be_bytes()),
+            )?,
+            prev_publish_time: CSAllocatable::alloc_from_witness(
+                cs,
+                witness.map(|w| w.prev_publish_time.to_be_bytes()),
+            )?,
+            ema_price: CSAllocatable::alloc_from_witness(
+                cs,
+                witness.map(|w| w.ema_price.to_be_bytes()),
+            )?,
+            ema_conf: CSAllocatable::alloc_from_witness(
+                cs,
+                witness.map(|w| w.ema_conf.to_be_bytes()),
+            )?,
+        })
+    }
+
+    /// Allocate a price feed from its wire format, `bytes` must be exactly one `PriceFeedMessage`.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        if bytes.len() != LEN_PRICE_FEED {
+            return Err(OracleError::InvalidLength {
+                what: "price feed",
+                got: bytes.len(),
+                expected: LEN_PRICE_FEED,
+            });
+        }
+        if bytes[0] != PRICE_FEED_TYPE {
+            return Err(OracleError::InvalidMessageType {
+                got: bytes[0],
+                expected: PRICE_FEED_TYPE,
+            });
+        }
+        let mut offset = 0;
+        let mut next = |len: usize| {
+            let chunk = &bytes[offset..offset + len];
+            offset += len;
+            chunk
         };
+        let price_feed_type = next(LEN_PRICE_FEED_TYPE).try_into().unwrap();
+        let feed_id = next(LEN_FEED_ID).try_into().unwrap();
+        let price = next(LEN_PRICE).try_into().unwrap();
+        let conf = next(LEN_CONF).try_into().unwrap();
+        let exponent = next(LEN_EXPONENT).try_into().unwrap();
+        let publish_time = next(LEN_PUBLISH_TIME).try_into().unwrap();
+        let prev_publish_time = next(LEN_PREV_PUBLISH_TIME).try_into().unwrap();
+        let ema_price = next(LEN_EMA_PRICE).try_into().unwrap();
+        let ema_conf = next(LEN_EMA_CONF).try_into().unwrap();
         Ok(Self {
-            price_feed_type,
-            feed_id,
-            price,
-            conf,
-            exponent,
-            publish_time,
-            prev_publish_time,
-            ema_price,
-            ema_conf,
+            price_feed_type: CSAllocatable::alloc_from_witness(cs, Some(price_feed_type))?,
+            feed_id: CSAllocatable::alloc_from_witness(cs, Some(feed_id))?,
+            price: CSAllocatable::alloc_from_witness(cs, Some(price))?,
+            conf: CSAllocatable::alloc_from_witness(cs, Some(conf))?,
+            exponent: CSAllocatable::alloc_from_witness(cs, Some(exponent))?,
+            publish_time: CSAllocatable::alloc_from_witness(cs, Some(publish_time))?,
+            prev_publish_time: CSAllocatable::alloc_from_witness(cs, Some(prev_publish_time))?,
+            ema_price: CSAllocatable::alloc_from_witness(cs, Some(ema_price))?,
+            ema_conf: CSAllocatable::alloc_from_witness(cs, Some(ema_conf))?,
         })
     }
 
@@ -257,16 +371,76 @@ impl<E: Engine> PriceFeed<E> {
     }
 }
 
+/// Typed circuit representation of pyth
+/// [`PriceFeedMessage`](pythnet_sdk::messages::PriceFeedMessage), the integers read from the big
+/// endian bytes of a [`PriceFeed`].
+///
+/// Signed fields (`price`, `exponent`, `ema_price`, and the times) hold their two's complement
+/// bits, as in the wire format.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceFeedMessage<E: Engine> {
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    pub price: UInt64<E>,
+    pub conf: UInt64<E>,
+    pub exponent: UInt32<E>,
+    pub publish_time: UInt64<E>,
+    pub prev_publish_time: UInt64<E>,
+    pub ema_price: UInt64<E>,
+    pub ema_conf: UInt64<E>,
+}
+
+impl<E: Engine> PriceFeedMessage<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: pythnet_sdk::messages::PriceFeedMessage,
+    ) -> Result<Self, OracleError> {
+        let price_feed = PriceFeed::from_message_witness(
+            cs,
+            pythnet_sdk::messages::Message::PriceFeedMessage(witness),
+        )?;
+        Ok(Self::from_price_feed(cs, &price_feed)?)
+    }
+
+    /// Read the integers of `price_feed`, whose bytes are range checked by its allocation.
+    pub fn from_price_feed<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        price_feed: &PriceFeed<E>,
+    ) -> Result<Self, SynthesisError> {
+        let mut exponent = price_feed.exponent;
+        exponent.reverse();
+        Ok(Self {
+            feed_id: price_feed.feed_id,
+            price: uint64_from_be_bytes(cs, price_feed.price)?,
+            conf: uint64_from_be_bytes(cs, price_feed.conf)?,
+            exponent: UInt32::from_bytes_le(cs, &exponent)?,
+            publish_time: uint64_from_be_bytes(cs, price_feed.publish_time)?,
+            prev_publish_time: uint64_from_be_bytes(cs, price_feed.prev_publish_time)?,
+            ema_price: uint64_from_be_bytes(cs, price_feed.ema_price)?,
+            ema_conf: uint64_from_be_bytes(cs, price_feed.ema_conf)?,
+        })
+    }
+}
+
+fn uint64_from_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    mut bytes: [Byte<E>; 8],
+) -> Result<UInt64<E>, SynthesisError> {
+    bytes.reverse();
+    UInt64::from_bytes_le(cs, &bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        gadgets::keccak160,
+        gadgets::{keccak160, keccak256::Keccak256Params},
+        testing::fixtures::FixturePrice,
         utils::testing::{bytes_assert_eq, create_test_constraint_system},
     };
     use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
     use advanced_circuit_component::franklin_crypto::{
         bellman::SynthesisError, plonk::circuit::boolean::Boolean,
     };
+    use proptest::prelude::*;
     use pythnet_sdk::wire::from_slice;
 
     #[test]
@@ -297,6 +471,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_price_feed_message() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode("00e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b4300000352813ebdc00000000042eeb9f6fffffff800000000655ccff700000000655ccff700000356d0a75ce0000000005b0d7112").unwrap();
+        let pythnet_sdk::messages::Message::PriceFeedMessage(expected) =
+            from_slice::<byteorder::BE, pythnet_sdk::messages::Message>(&data).unwrap()
+        else {
+            panic!("expect price feed message");
+        };
+        let message = super::PriceFeedMessage::<Bn256>::from_witness(cs, expected)?;
+        bytes_assert_eq(&message.feed_id, hex::encode(expected.feed_id));
+        assert_eq!(message.price.get_value(), Some(expected.price as u64));
+        assert_eq!(message.conf.get_value(), Some(expected.conf));
+        assert_eq!(
+            message.exponent.get_value().map(|e| e as i32),
+            Some(expected.exponent)
+        );
+        assert_eq!(expected.exponent, -8);
+        assert_eq!(
+            message.publish_time.get_value(),
+            Some(expected.publish_time as u64)
+        );
+        assert_eq!(
+            message.prev_publish_time.get_value(),
+            Some(expected.prev_publish_time as u64)
+        );
+        assert_eq!(
+            message.ema_price.get_value(),
+            Some(expected.ema_price as u64)
+        );
+        assert_eq!(message.ema_conf.get_value(), Some(expected.ema_conf));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
     #[test]
     fn test_price_update() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
@@ -316,9 +525,65 @@ mod tests {
                 let hash = keccak160::Hash::alloc_from_witness(cs, Some(hash))?;
                 keccak160::MerkleRoot::new(hash)
             };
-            let check = update.check(cs, &root)?;
+            let keccak = Keccak256Params::new(cs)?;
+            let check = update.check(cs, &keccak, &root)?;
             Boolean::enforce_equal(cs, &check, &Boolean::constant(true))?;
         }
         Ok(())
     }
+
+    prop_compose! {
+        fn fixture_price()(
+            feed_id in any::<[u8; 32]>(),
+            price in any::<i64>(),
+            conf in any::<u64>(),
+            exponent in any::<i32>(),
+            publish_time in any::<i64>(),
+            prev_publish_time in any::<i64>(),
+            ema_price in any::<i64>(),
+            ema_conf in any::<u64>(),
+        ) -> FixturePrice {
+            FixturePrice {
+                feed_id,
+                price,
+                conf,
+                exponent,
+                publish_time,
+                prev_publish_time,
+                ema_price,
+                ema_conf,
+            }
+        }
+    }
+
+    fn check_price_feed_round_trip(expected: &FixturePrice) -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = expected.to_bytes();
+        let message = from_slice::<byteorder::BE, pythnet_sdk::messages::Message>(&data).unwrap();
+        let pythnet_sdk::messages::Message::PriceFeedMessage(p) = message else {
+            panic!("expect price feed message");
+        };
+        assert_eq!(p.feed_id, expected.feed_id);
+        assert_eq!(p.price, expected.price);
+        assert_eq!(p.conf, expected.conf);
+        assert_eq!(p.exponent, expected.exponent);
+        assert_eq!(p.publish_time, expected.publish_time);
+        assert_eq!(p.prev_publish_time, expected.prev_publish_time);
+        assert_eq!(p.ema_price, expected.ema_price);
+        assert_eq!(p.ema_conf, expected.ema_conf);
+
+        let price_feed = super::PriceFeed::<Bn256>::from_message_witness(cs, message)?;
+        bytes_assert_eq(&price_feed.to_bytes(cs), hex::encode(&data));
+        Ok(())
+    }
+
+    proptest! {
+        // Each case builds a constraint system with lookup tables, keep the number of cases small.
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_price_feed_round_trip(price in fixture_price()) {
+            check_price_feed_round_trip(&price).unwrap();
+        }
+    }
 }
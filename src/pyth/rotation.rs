@@ -0,0 +1,450 @@
+//! Guardian set rotations.
+//!
+//! Wormhole rotates its guardians with a governance VAA of the core bridge, signed by a quorum of
+//! the current set and carrying the next one. [`GuardianSetUpgradeCircuit`] verifies such a VAA
+//! against the commitment of the current set and outputs the commitment of the next one, so that a
+//! chain of proofs, e.g. folded by recursion, tracks every rotation from a hardcoded genesis set and
+//! the [`PriceOracle`](super::PriceOracle) guardian set commitment can be checked against its end.
+
+use std::marker::PhantomData;
+
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            pairing::{ff::Field, Engine},
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::smart_and,
+        primitives::{uint256::UInt256, UInt32},
+    },
+};
+
+use super::{
+    compute_guardian_set_commitment,
+    governance::{
+        GuardianSetUpgradeMessage, GuardianSetUpgradePayload, GOVERNANCE_CHAIN_ID,
+        GOVERNANCE_EMITTER,
+    },
+    GuardianSet, VAA_VERSION,
+};
+use crate::{
+    error::OracleError,
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{new_synthesis_error, to_array},
+};
+
+const LEN_GUARDIAN_SIGNATURE: usize = 1 + 65;
+/// Timestamp, nonce, emitter chain, emitter address, sequence and consistency level.
+pub(super) const LEN_BODY_HEADER: usize = 4 + 4 + 2 + 32 + 8 + 1;
+
+/// Natively parsed guardian set upgrade VAA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardianSetUpgradeVaa {
+    pub guardian_set_index: u32,
+    /// Guardian index and 65-byte signature of each signature.
+    pub signatures: Vec<(u8, [u8; 65])>,
+    /// Signed body.
+    pub body: Vec<u8>,
+    pub upgrade: GuardianSetUpgradeMessage,
+}
+
+impl GuardianSetUpgradeVaa {
+    /// Parse a VAA from its wire format, checking that it is a guardian set upgrade of the core
+    /// bridge emitted by the governance emitter. Signatures are not verified.
+    pub fn parse(bytes: &[u8]) -> Result<Self, OracleError> {
+        let invalid = |reason: String| OracleError::VaaDecode(reason);
+        let (&version, rest) = bytes
+            .split_first()
+            .ok_or_else(|| invalid("empty VAA".to_string()))?;
+        if version != VAA_VERSION {
+            return Err(invalid(format!("unsupported VAA version {}", version)));
+        }
+        if rest.len() < 5 {
+            return Err(invalid("truncated VAA header".to_string()));
+        }
+        let guardian_set_index = u32::from_be_bytes(rest[..4].try_into().unwrap());
+        let num_signatures = rest[4] as usize;
+        let body_start = 6 + num_signatures * LEN_GUARDIAN_SIGNATURE;
+        if bytes.len() < body_start + LEN_BODY_HEADER {
+            return Err(invalid("truncated guardian set upgrade".to_string()));
+        }
+        let signatures = bytes[6..body_start]
+            .chunks_exact(LEN_GUARDIAN_SIGNATURE)
+            .map(|signature| (signature[0], signature[1..].try_into().unwrap()))
+            .collect();
+        let body = bytes[body_start..].to_vec();
+
+        let emitter_chain = u16::from_be_bytes(body[8..10].try_into().unwrap());
+        if emitter_chain != GOVERNANCE_CHAIN_ID || body[10..42] != GOVERNANCE_EMITTER {
+            return Err(invalid(format!(
+                "VAA emitted by {} on chain {} is not a governance VAA",
+                hex::encode(&body[10..42]),
+                emitter_chain
+            )));
+        }
+        let upgrade = GuardianSetUpgradeMessage::try_from_bytes(&body[LEN_BODY_HEADER..])?;
+        if upgrade.chain != 0 {
+            return Err(invalid(
+                "guardian set upgrade targets a single chain".to_string(),
+            ));
+        }
+        Ok(Self {
+            guardian_set_index,
+            signatures,
+            body,
+            upgrade,
+        })
+    }
+}
+
+/// Circuit representation of a guardian set upgrade VAA of `num_guardians` new guardians.
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgrade<E: Engine> {
+    /// Version of the VAA header, not covered by the signatures.
+    pub version: Byte<E>,
+    /// Index of the guardian set claimed by the VAA header, not covered by the signatures.
+    pub guardian_set_index: [Byte<E>; 4],
+    /// Index in the guardian set of the signer of each signature.
+    pub guardian_indices: Vec<Byte<E>>,
+    pub signatures: Vec<Signature<E>>,
+    /// Signed body, up to the payload.
+    pub body_header: [Byte<E>; LEN_BODY_HEADER],
+    pub payload: GuardianSetUpgradePayload<E>,
+}
+
+impl<E: Engine> GuardianSetUpgrade<E> {
+    /// Allocate the first `num_signatures` signatures of `vaa`, which must upgrade to
+    /// `num_guardians` guardians so that the circuit shape does not depend on the witness.
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        vaa: &GuardianSetUpgradeVaa,
+        num_signatures: usize,
+        num_guardians: usize,
+    ) -> Result<Self, OracleError> {
+        if vaa.signatures.len() < num_signatures {
+            return Err(OracleError::InsufficientSignatures {
+                got: vaa.signatures.len(),
+                need: num_signatures,
+            });
+        }
+        if vaa.upgrade.new_guardian_set.len() != num_guardians {
+            return Err(OracleError::InvalidLength {
+                what: "new guardian set",
+                got: vaa.upgrade.new_guardian_set.len(),
+                expected: num_guardians,
+            });
+        }
+        let version = Byte::alloc_from_witness(cs, Some(VAA_VERSION))?;
+        let guardian_set_index =
+            CSAllocatable::alloc_from_witness(cs, Some(vaa.guardian_set_index.to_be_bytes()))?;
+        let mut guardian_indices = vec![];
+        let mut signatures = vec![];
+        for (index, signature) in vaa.signatures[..num_signatures].iter() {
+            guardian_indices.push(Byte::alloc_from_witness(cs, Some(*index))?);
+            signatures.push(Signature::from_bytes_witness(cs, signature)?);
+        }
+        let body_header = CSAllocatable::alloc_from_witness(
+            cs,
+            Some(to_array("VAA body header", &vaa.body[..LEN_BODY_HEADER])?),
+        )?;
+        let payload = GuardianSetUpgradePayload::from_witness(cs, &vaa.upgrade)?;
+        Ok(Self {
+            version,
+            guardian_set_index,
+            guardian_indices,
+            signatures,
+            body_header,
+            payload,
+        })
+    }
+
+    /// Allocate an upgrade with absent witnesses, for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        num_signatures: usize,
+        num_guardians: usize,
+    ) -> Result<Self, SynthesisError> {
+        let version = Byte::alloc_from_witness(cs, None)?;
+        let guardian_set_index = CSAllocatable::alloc_from_witness(cs, None)?;
+        let guardian_indices = (0..num_signatures)
+            .map(|_| Byte::alloc_from_witness(cs, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signatures = (0..num_signatures)
+            .map(|_| Signature::alloc_from_witness(cs, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            version,
+            guardian_set_index,
+            guardian_indices,
+            signatures,
+            body_header: CSAllocatable::alloc_from_witness(cs, None)?,
+            payload: GuardianSetUpgradePayload::alloc_absent(cs, num_guardians)?,
+        })
+    }
+
+    /// Bytes of the signed body.
+    pub fn body_bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.body_header
+            .iter()
+            .copied()
+            .chain(self.payload.bytes_iter())
+    }
+
+    /// Index of the guardian set signing the upgrade, from its big-endian bytes.
+    pub fn guardian_set_index<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt32<E>, SynthesisError> {
+        let mut index = self.guardian_set_index;
+        index.reverse();
+        UInt32::from_bytes_le(cs, &index)
+    }
+
+    /// Whether the VAA is emitted by the governance emitter and its payload is a guardian set
+    /// upgrade, see [`GuardianSetUpgradePayload::check_format`], to the set following the one of
+    /// the header.
+    pub fn check_format<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut expected = vec![(self.version, VAA_VERSION)];
+        let emitter = GOVERNANCE_CHAIN_ID
+            .to_be_bytes()
+            .into_iter()
+            .chain(GOVERNANCE_EMITTER);
+        expected.extend(self.body_header[8..42].iter().copied().zip(emitter));
+
+        let mut is_ok = vec![];
+        for (byte, expected) in expected {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        let is_next_index = {
+            let index = self.guardian_set_index(cs)?.inner;
+            let next = index.add(cs, &Num::one())?;
+            let new_index = self.payload.new_guardian_set_index(cs)?.inner;
+            Num::equals(cs, &next, &new_index)?
+        };
+        is_ok.push(is_next_index);
+        is_ok.push(self.payload.check_format(cs)?);
+        smart_and(cs, &is_ok)
+    }
+
+    /// Enforce that the upgrade is signed by a quorum of `guardian_set`, every signature recovering
+    /// to the guardian at the index the header claims for it, the indices being strictly increasing
+    /// so that every signer is distinct.
+    pub fn enforce_signed_by_quorum<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &GuardianSet<E>,
+    ) -> Result<(), SynthesisError> {
+        let quorum = guardian_set.len() * 2 / 3 + 1;
+        if self.signatures.len() < quorum {
+            return Err(new_synthesis_error(format!(
+                "{} signatures are below the quorum {} of {} guardians",
+                self.signatures.len(),
+                quorum,
+                guardian_set.len()
+            )));
+        }
+        let keccak = Keccak256Params::new(cs)?;
+        let msg_hash = {
+            let hash = keccak256::double_digest_with_params(cs, &keccak, self.body_bytes_iter())?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let params = Secp256k1Params::new(cs);
+        for pair in self.guardian_indices.windows(2) {
+            let (_, is_greater) =
+                prepacked_long_comparison(cs, &[pair[1].inner], &[pair[0].inner], &[8])?;
+            Boolean::enforce_equal(cs, &is_greater, &Boolean::constant(true))?;
+        }
+        for (signature, index) in self.signatures.iter().zip(self.guardian_indices.iter()) {
+            let (successful, address) =
+                signature.ecrecover_address_with_params(cs, &params, &keccak, &msg_hash)?;
+            let address = Address::from_bytes(cs, &address)?;
+            let is_guardian = guardian_set.is_guardian_at(cs, index, &address)?;
+            let is_signed = Boolean::and(cs, &successful, &is_guardian)?;
+            Boolean::enforce_equal(cs, &is_signed, &Boolean::constant(true))?;
+        }
+        Ok(())
+    }
+}
+
+/// Circuit proving one guardian set rotation. Its public input is the commitment and the index of
+/// the signing set, then those of the new set, see [`GuardianSet::commitment`].
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgradeCircuit<E: Engine> {
+    pub vaa: GuardianSetUpgradeVaa,
+    pub guardian_set: Vec<[u8; 20]>,
+    /// Number of verified signatures, the quorum of `guardian_set`.
+    pub num_signatures: usize,
+    _marker: PhantomData<E>,
+}
+
+impl<E: Engine> GuardianSetUpgradeCircuit<E> {
+    /// Circuit verifying the upgrade VAA `vaa` against `guardian_set`, the set it rotates from.
+    pub fn new(vaa: &[u8], guardian_set: Vec<[u8; 20]>) -> Result<Self, OracleError> {
+        let vaa = GuardianSetUpgradeVaa::parse(vaa)?;
+        let num_signatures = guardian_set.len() * 2 / 3 + 1;
+        if vaa.signatures.len() < num_signatures {
+            return Err(OracleError::InsufficientSignatures {
+                got: vaa.signatures.len(),
+                need: num_signatures,
+            });
+        }
+        Ok(Self {
+            vaa,
+            guardian_set,
+            num_signatures,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Public input of the circuit.
+    pub fn public_input(&self) -> Result<[E::Fr; 4], SynthesisError> {
+        Ok([
+            compute_guardian_set_commitment::<E>(&self.guardian_set)?,
+            u64_to_fe(self.vaa.guardian_set_index as u64),
+            compute_guardian_set_commitment::<E>(&self.vaa.upgrade.new_guardian_set)?,
+            u64_to_fe(self.vaa.upgrade.new_guardian_set_index as u64),
+        ])
+    }
+}
+
+impl<E: Engine> Circuit<E> for GuardianSetUpgradeCircuit<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span = tracing::info_span!(
+            "guardian_set_upgrade_synthesize",
+            num_signatures = self.num_signatures,
+            num_guardians = self.vaa.upgrade.new_guardian_set.len(),
+        )
+        .entered();
+        crate::utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let guardian_set = GuardianSet::from_witness(cs, &self.guardian_set)?;
+        let upgrade = GuardianSetUpgrade::from_witness(
+            cs,
+            &self.vaa,
+            self.num_signatures,
+            self.vaa.upgrade.new_guardian_set.len(),
+        )?;
+        let is_upgrade = upgrade.check_format(cs)?;
+        Boolean::enforce_equal(cs, &is_upgrade, &Boolean::constant(true))?;
+        upgrade.enforce_signed_by_quorum(cs, &guardian_set)?;
+
+        let new_guardian_set = upgrade.payload.new_guardian_set(cs)?;
+        let public_input = [
+            guardian_set.commitment(cs)?,
+            upgrade.guardian_set_index(cs)?.into_num(),
+            new_guardian_set.commitment(cs)?,
+            upgrade.payload.new_guardian_set_index(cs)?.into_num(),
+        ];
+        for input in public_input.iter() {
+            input.get_variable().inputize(cs)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, plonk::better_better_cs::cs::Circuit},
+        testing::create_test_artifacts_with_optimized_gate,
+        utils::u64_to_fe,
+    };
+
+    use super::{GuardianSetUpgradeCircuit, GuardianSetUpgradeVaa};
+    use crate::{
+        pyth::compute_guardian_set_commitment, testing::fixtures::GuardianSetUpgradeFixture,
+    };
+
+    #[test]
+    fn test_guardian_set_upgrade() -> anyhow::Result<()> {
+        let fixture = GuardianSetUpgradeFixture::default();
+        let vaa = GuardianSetUpgradeVaa::parse(&fixture.vaa())?;
+        assert_eq!(vaa.guardian_set_index, 3);
+        assert_eq!(vaa.upgrade.new_guardian_set_index, 4);
+        assert_eq!(vaa.upgrade.new_guardian_set, fixture.new_guardian_set());
+        assert_eq!(vaa.signatures.len(), 3);
+
+        let circuit =
+            GuardianSetUpgradeCircuit::<Bn256>::new(&fixture.vaa(), fixture.guardian_set())?;
+        assert_eq!(
+            circuit.public_input()?,
+            [
+                compute_guardian_set_commitment::<Bn256>(&fixture.guardian_set())?,
+                u64_to_fe(3),
+                compute_guardian_set_commitment::<Bn256>(&fixture.new_guardian_set())?,
+                u64_to_fe(4),
+            ]
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Not signed by the claimed guardian set.
+        let mut circuit = circuit;
+        circuit.guardian_set[0] = [0xffu8; 20];
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Skipping a guardian set index.
+        let skipping = GuardianSetUpgradeFixture {
+            new_guardian_set_index: 5,
+            ..fixture.clone()
+        };
+        let circuit =
+            GuardianSetUpgradeCircuit::<Bn256>::new(&skipping.vaa(), skipping.guardian_set())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Below the quorum of the guardian set, or not a governance VAA.
+        let below_quorum = GuardianSetUpgradeFixture {
+            num_signatures: 2,
+            ..fixture.clone()
+        };
+        assert!(GuardianSetUpgradeCircuit::<Bn256>::new(
+            &below_quorum.vaa(),
+            fixture.guardian_set()
+        )
+        .is_err());
+        let foreign = GuardianSetUpgradeFixture {
+            emitter_address: [1u8; 32],
+            ..fixture
+        };
+        assert!(GuardianSetUpgradeVaa::parse(&foreign.vaa()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Proving and verification keys of the [`PriceOracle`] circuit.
+//!
+//! Keys are generated from a [`PriceOracleSetup`], which synthesizes the circuit with absent
+//! witnesses, so a deployment can set up its keys before any VAA is available. Proofs of any
+//! [`PriceOracle`] of the same shape verify against them. The keys of the
+//! [`MerkleOnlyOracle`] circuit of the hybrid mode are generated the same way.
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    kate_commitment::{Crs, CrsForMonomialForm},
+    pairing::Engine,
+    plonk::better_better_cs::{
+        cs::{Circuit, PlonkCsWidth4WithNextStepAndCustomGatesParams, SetupAssembly},
+        gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        setup::{Setup, VerificationKey},
+    },
+    worker::Worker,
+    SynthesisError,
+};
+
+use super::{
+    merkle_only::{MerkleOnlyOracle, MerkleOnlySetup},
+    PriceOracle, PriceOracleSetup,
+};
+
+pub type OracleSetup<E, const NUM_PRICES: usize> = Setup<E, PriceOracle<E, NUM_PRICES>>;
+pub type OracleVerificationKey<E, const NUM_PRICES: usize> =
+    VerificationKey<E, PriceOracle<E, NUM_PRICES>>;
+/// Proving setup and verification key.
+pub type OracleKeys<E, const NUM_PRICES: usize> = (
+    OracleSetup<E, NUM_PRICES>,
+    OracleVerificationKey<E, NUM_PRICES>,
+);
+
+/// Generate the proving setup and the verification key of the circuits shaped as `shape`.
+pub fn generate_keys<E: Engine, const NUM_PRICES: usize>(
+    shape: &PriceOracleSetup<E, NUM_PRICES>,
+    crs: &Crs<E, CrsForMonomialForm>,
+) -> Result<OracleKeys<E, NUM_PRICES>, SynthesisError> {
+    let worker = Worker::new();
+    let mut assembly = SetupAssembly::<
+        E,
+        PlonkCsWidth4WithNextStepAndCustomGatesParams,
+        SelectorOptimizedWidth4MainGateWithDNext,
+    >::new();
+    shape.synthesize(&mut assembly)?;
+    assembly.finalize();
+    let setup = assembly.create_setup::<PriceOracle<E, NUM_PRICES>>(&worker)?;
+    let vk = VerificationKey::from_setup(&setup, &worker, crs)?;
+    Ok((setup, vk))
+}
+
+/// Proving setup and verification key of the [`MerkleOnlyOracle`] circuits shaped as `shape`.
+#[allow(clippy::type_complexity)]
+pub fn generate_merkle_only_keys<E: Engine, const NUM_PRICES: usize>(
+    shape: &MerkleOnlySetup<E, NUM_PRICES>,
+    crs: &Crs<E, CrsForMonomialForm>,
+) -> Result<
+    (
+        Setup<E, MerkleOnlyOracle<E, NUM_PRICES>>,
+        VerificationKey<E, MerkleOnlyOracle<E, NUM_PRICES>>,
+    ),
+    SynthesisError,
+> {
+    let worker = Worker::new();
+    let mut assembly = SetupAssembly::<
+        E,
+        PlonkCsWidth4WithNextStepAndCustomGatesParams,
+        SelectorOptimizedWidth4MainGateWithDNext,
+    >::new();
+    shape.synthesize(&mut assembly)?;
+    assembly.finalize();
+    let setup = assembly.create_setup::<MerkleOnlyOracle<E, NUM_PRICES>>(&worker)?;
+    let vk = VerificationKey::from_setup(&setup, &worker, crs)?;
+    Ok((setup, vk))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        kate_commitment::{Crs, CrsForMonomialForm},
+        pairing::bn256::{Bn256, Fr},
+        plonk::{
+            better_better_cs::{
+                cs::{Circuit, PlonkCsWidth4WithNextStepAndCustomGatesParams, ProvingAssembly},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+                verifier::verify,
+            },
+            commitments::transcript::keccak_transcript::RollingKeccakTranscript,
+        },
+        worker::Worker,
+    };
+
+    use super::generate_keys;
+    use crate::pyth::{PriceOracle, PriceOracleSetup};
+
+    #[test]
+    fn test_generate_keys() -> anyhow::Result<()> {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let shape = PriceOracleSetup::from(&oracle);
+        let worker = Worker::new();
+
+        let mut assembly = ProvingAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        oracle.synthesize(&mut assembly)?;
+        assembly.finalize();
+        let crs = Crs::<Bn256, CrsForMonomialForm>::crs_42(assembly.n() + 1, &worker);
+
+        let (setup, vk) = generate_keys(&shape, &crs)?;
+        assert_eq!(vk.num_inputs, 1);
+        let proof = assembly.create_proof::<PriceOracle<Bn256, 3>, RollingKeccakTranscript<Fr>>(
+            &worker, &setup, &crs, None,
+        )?;
+        assert!(verify::<_, _, RollingKeccakTranscript<Fr>>(
+            &vk, &proof, None
+        )?);
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Ages of VAA timestamps and publish times.
+//!
+//! Wormhole timestamps are `u32` seconds, which wrap on 2106-02-07T06:28:16Z, while publish times
+//! and the clock are `i64`. Ages are computed on integers wide enough for any of them, never with
+//! wrapping `u32` arithmetic: an old VAA can not look fresh by wrapping around, and a VAA signed
+//! after the rollover, whose timestamp has wrapped, looks older than it is and is rejected.
+
+/// First second a VAA timestamp can not represent, 2106-02-07T06:28:16Z.
+pub const VAA_TIMESTAMP_ROLLOVER: i64 = 1 << 32;
+
+/// A VAA timestamp as seconds comparable with publish times.
+pub fn widen_vaa_timestamp(timestamp: u32) -> u64 {
+    u64::from(timestamp)
+}
+
+/// Seconds from `timestamp` to `now`, saturating to zero for a timestamp in the future.
+pub fn age(now: i64, timestamp: i64) -> u64 {
+    // The difference of two i64 fits in i128 and, once positive, in u64.
+    (i128::from(now) - i128::from(timestamp)).max(0) as u64
+}
+
+/// Seconds from the VAA `timestamp` to `now`, see [`age`].
+pub fn vaa_age(now: i64, timestamp: u32) -> u64 {
+    age(now, widen_vaa_timestamp(timestamp) as i64)
+}
+
+/// Whether `timestamp` is at most `max_age` seconds before `now`.
+pub fn is_fresh(now: i64, timestamp: i64, max_age: u64) -> bool {
+    age(now, timestamp) <= max_age
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{age, is_fresh, vaa_age, widen_vaa_timestamp, VAA_TIMESTAMP_ROLLOVER};
+
+    #[test]
+    fn test_age() {
+        assert_eq!(age(100, 40), 60);
+        assert_eq!(age(40, 100), 0);
+        assert_eq!(age(i64::MAX, i64::MIN), u64::MAX);
+        assert_eq!(age(i64::MIN, i64::MAX), 0);
+        assert!(is_fresh(100, 40, 60));
+        assert!(!is_fresh(100, 39, 60));
+        assert!(is_fresh(100, 200, 0));
+        assert!(!is_fresh(i64::MAX, i64::MIN, u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_vaa_timestamp_rollover() {
+        assert_eq!(
+            widen_vaa_timestamp(u32::MAX) as i64,
+            VAA_TIMESTAMP_ROLLOVER - 1
+        );
+        // Last second before the rollover, seen just after it.
+        assert_eq!(vaa_age(VAA_TIMESTAMP_ROLLOVER + 10, u32::MAX), 11);
+        // A VAA of 1970 is not fresh after the rollover, as it would be with the clock truncated to
+        // u32 and a wrapping difference.
+        let now = VAA_TIMESTAMP_ROLLOVER + 10;
+        assert_eq!((now as u32).wrapping_sub(0), 10);
+        assert!(!is_fresh(now, widen_vaa_timestamp(0) as i64, 60));
+        // A VAA signed after the rollover has a wrapped timestamp and fails closed.
+        let wrapped = (VAA_TIMESTAMP_ROLLOVER + 5) as u32;
+        assert_eq!(vaa_age(now, wrapped), VAA_TIMESTAMP_ROLLOVER as u64 + 5);
+        // Up to the rollover, ages are exact.
+        assert_eq!(vaa_age(VAA_TIMESTAMP_ROLLOVER - 1, u32::MAX - 60), 60);
+    }
+}
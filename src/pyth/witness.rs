@@ -0,0 +1,309 @@
+//! Serializable witness of the pyth [`PriceOracle`] circuit.
+//!
+//! [`OracleWitness`] keeps the raw VAA bytes, price messages and merkle proofs instead of the parsed
+//! `pythnet_sdk` types, so a captured production input is stored exactly as it came over the wire and
+//! replays into the same circuit, bit for bit. Byte strings are hex encoded in the serialized form.
+
+use std::io::{Read, Write};
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use pythnet_sdk::wire::v1::{AccumulatorUpdateData, Proof};
+use serde::{Deserialize, Serialize};
+
+use super::PriceOracle;
+use crate::gadgets::keccak160::WIDTH_HASH_BYTES;
+
+const ACCUMULATOR_MAGIC: &[u8; 4] = b"PNAU";
+const PROOF_TYPE_WORMHOLE_MERKLE: u8 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleWitness {
+    pub accumulator_updates: Vec<AccumulatorUpdateWitness>,
+    #[serde(with = "hex_list")]
+    pub guardian_set: Vec<[u8; 20]>,
+    pub num_signature_to_verify: usize,
+}
+
+/// Raw content of one `PNAU` accumulator update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccumulatorUpdateWitness {
+    pub major_version: u8,
+    pub minor_version: u8,
+    #[serde(with = "hex_bytes")]
+    pub vaa: Vec<u8>,
+    pub updates: Vec<PriceUpdateWitness>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceUpdateWitness {
+    #[serde(with = "hex_bytes")]
+    pub message: Vec<u8>,
+    #[serde(with = "hex_list")]
+    pub proof: Vec<[u8; WIDTH_HASH_BYTES]>,
+}
+
+impl AccumulatorUpdateWitness {
+    pub fn new(vaa: Vec<u8>, updates: Vec<PriceUpdateWitness>) -> Self {
+        Self {
+            major_version: 1,
+            minor_version: 0,
+            vaa,
+            updates,
+        }
+    }
+
+    /// Split a binary `PNAU` blob, as returned by hermes, into the VAA and the price updates.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        let data = AccumulatorUpdateData::try_from_slice(bytes)?;
+        Ok(Self::from_accumulator_update_data(&data))
+    }
+
+    pub fn from_accumulator_update_data(data: &AccumulatorUpdateData) -> Self {
+        let Proof::WormholeMerkle { vaa, updates } = data.proof.clone();
+        let updates = updates
+            .into_iter()
+            .map(|update| PriceUpdateWitness {
+                message: update.message.into(),
+                proof: update
+                    .proof
+                    .to_bytes()
+                    .chunks_exact(WIDTH_HASH_BYTES)
+                    .map(|chunk| chunk.try_into().unwrap())
+                    .collect(),
+            })
+            .collect();
+        Self {
+            major_version: data.major_version,
+            minor_version: data.minor_version,
+            vaa: vaa.as_ref().to_vec(),
+            updates,
+        }
+    }
+
+    /// Encode in the `PNAU` wire format, failing if a length overflows its prefix.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let mut bytes = ACCUMULATOR_MAGIC.to_vec();
+        bytes.push(self.major_version);
+        bytes.push(self.minor_version);
+        bytes.push(0); // trailing header length
+        bytes.push(PROOF_TYPE_WORMHOLE_MERKLE);
+        bytes.extend(length_prefix::<u16>(self.vaa.len(), "VAA")?.to_be_bytes());
+        bytes.extend(&self.vaa);
+        bytes.push(length_prefix::<u8>(
+            self.updates.len(),
+            "number of updates",
+        )?);
+        for update in self.updates.iter() {
+            bytes.extend(length_prefix::<u16>(update.message.len(), "message")?.to_be_bytes());
+            bytes.extend(&update.message);
+            bytes.push(length_prefix::<u8>(update.proof.len(), "proof")?);
+            for node in update.proof.iter() {
+                bytes.extend(node);
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn to_accumulator_update_data(&self) -> Result<AccumulatorUpdateData, anyhow::Error> {
+        AccumulatorUpdateData::try_from_slice(&self.to_bytes()?)
+    }
+}
+
+fn length_prefix<T: TryFrom<usize>>(len: usize, what: &str) -> Result<T, anyhow::Error> {
+    T::try_from(len).map_err(|_| anyhow::anyhow!("{} length {} overflows its prefix", what, len))
+}
+
+impl OracleWitness {
+    pub fn new(
+        accumulator_update_data: &[AccumulatorUpdateData],
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+    ) -> Self {
+        Self {
+            accumulator_updates: crate::utils::par_map(accumulator_update_data, |_, data| {
+                AccumulatorUpdateWitness::from_accumulator_update_data(data)
+            }),
+            guardian_set,
+            num_signature_to_verify,
+        }
+    }
+
+    /// Witness of the binary `PNAU` blobs returned by hermes, one per VAA of the circuit.
+    pub fn from_hermes<T: AsRef<[u8]>>(
+        blobs: &[T],
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+    ) -> Result<Self, anyhow::Error> {
+        let accumulator_updates = blobs
+            .iter()
+            .map(|blob| AccumulatorUpdateWitness::from_bytes(blob.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            accumulator_updates,
+            guardian_set,
+            num_signature_to_verify,
+        })
+    }
+
+    pub fn accumulator_update_data(&self) -> Result<Vec<AccumulatorUpdateData>, anyhow::Error> {
+        crate::utils::par_map(&self.accumulator_updates, |_, update| {
+            update.to_accumulator_update_data()
+        })
+        .into_iter()
+        .collect()
+    }
+
+    /// Run [`prefilter_vaa`](super::prefilter_vaa) on every VAA, a cheap rejection of invalid
+    /// witnesses before [`OracleWitness::to_price_oracle`].
+    pub fn prefilter(&self) -> Result<(), super::VaaRejection> {
+        crate::utils::par_map(&self.accumulator_updates, |_, update| {
+            super::prefilter_vaa(
+                &update.vaa,
+                &self.guardian_set,
+                self.num_signature_to_verify,
+            )
+        })
+        .into_iter()
+        .collect()
+    }
+
+    /// Rebuild the circuit, running the same native checks as [`PriceOracle::new`].
+    pub fn to_price_oracle<E: Engine, const NUM_PRICES: usize>(
+        &self,
+    ) -> Result<PriceOracle<E, NUM_PRICES>, anyhow::Error> {
+        PriceOracle::new(
+            self.accumulator_update_data()?,
+            self.guardian_set.clone(),
+            self.num_signature_to_verify,
+        )
+    }
+
+    pub fn read_json<R: Read>(reader: R) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader)
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> From<&PriceOracle<E, NUM_PRICES>> for OracleWitness {
+    fn from(oracle: &PriceOracle<E, NUM_PRICES>) -> Self {
+        Self::new(
+            &oracle.accumulator_update_data,
+            oracle.guardian_set.clone(),
+            oracle.num_signature_to_verify,
+        )
+    }
+}
+
+mod hex_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s.strip_prefix("0x").unwrap_or(&s)).map_err(D::Error::custom)
+    }
+}
+
+mod hex_list {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        items: &[[u8; N]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; N]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| {
+                let bytes =
+                    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(D::Error::custom)?;
+                let len = bytes.len();
+                bytes
+                    .try_into()
+                    .map_err(|_| D::Error::custom(format!("invalid length {}, expect {}", len, N)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+    use base64::Engine as _;
+
+    use super::{AccumulatorUpdateWitness, OracleWitness};
+    use crate::pyth::{PriceOracle, DEFAULT_ACCUMULATOR_UPDATE_DATA};
+
+    #[test]
+    fn test_witness_round_trip() -> anyhow::Result<()> {
+        let bytes =
+            base64::engine::general_purpose::STANDARD.decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(2, 13);
+        let witness = OracleWitness::from(&oracle);
+        for update in witness.accumulator_updates.iter() {
+            assert_eq!(update.to_bytes()?, bytes);
+        }
+
+        let mut json = vec![];
+        witness.write_json(&mut json)?;
+        let replayed = OracleWitness::read_json(json.as_slice())?;
+        assert_eq!(replayed, witness);
+
+        let replayed = replayed.to_price_oracle::<Bn256, 3>()?;
+        assert_eq!(replayed.commitment, oracle.commitment);
+        assert_eq!(replayed.guardian_set, oracle.guardian_set);
+        Ok(())
+    }
+
+    #[test]
+    fn test_witness_from_hermes() -> anyhow::Result<()> {
+        let bytes =
+            base64::engine::general_purpose::STANDARD.decode(DEFAULT_ACCUMULATOR_UPDATE_DATA)?;
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(2, 13);
+        let witness = OracleWitness::from_hermes(
+            &[&bytes, &bytes],
+            oracle.guardian_set.clone(),
+            oracle.num_signature_to_verify,
+        )?;
+        assert_eq!(witness, OracleWitness::from(&oracle));
+        let update = &witness.accumulator_updates[0];
+        assert_eq!(update.updates.len(), 3);
+        assert!(update.updates.iter().all(|u| u.proof.len() == 10));
+
+        assert!(AccumulatorUpdateWitness::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(AccumulatorUpdateWitness::from_bytes(&bytes[4..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_witness_to_bytes_rejects_overflowing_lengths() {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 13);
+        let update = &OracleWitness::from(&oracle).accumulator_updates[0];
+
+        let mut long_proof = update.clone();
+        long_proof.updates[0].proof = vec![[0u8; 20]; 256];
+        assert!(long_proof.to_bytes().is_err());
+
+        let mut long_vaa = update.clone();
+        long_vaa.vaa = vec![0u8; u16::MAX as usize + 1];
+        assert!(long_vaa.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_witness_rejects_invalid_hash_length() {
+        let json =
+            r#"{"accumulator_updates":[],"guardian_set":["00"],"num_signature_to_verify":0}"#;
+        assert!(OracleWitness::read_json(json.as_bytes()).is_err());
+    }
+}
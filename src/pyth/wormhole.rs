@@ -1,24 +1,33 @@
+use std::cell::Cell;
+
 use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::{
-    circuit_structures::byte::{Byte, IntoBytes as _},
+    circuit_structures::byte::Byte,
     franklin_crypto::{
-        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
     },
+    glue::prepacked_long_comparison,
     traits::CSAllocatable,
+    utils::u64_to_fe,
     vm::{
         partitioner::{smart_and, smart_or},
-        primitives::uint256::UInt256,
+        primitives::{uint256::UInt256, UInt32, UInt64},
     },
 };
 
 use crate::{
+    error::OracleError,
     gadgets::{
-        ecdsa::Signature,
+        ecdsa::{Secp256k1Params, Signature},
         ethereum::Address,
-        keccak160::{self, MerkleRoot},
+        keccak160::{self, MerklePath, MerkleRoot},
+        keccak256::{self, Keccak256Params},
     },
-    utils::new_synthesis_error,
+    pyth::{GuardianSet, PythEnvironment},
+    utils::{new_synthesis_error, to_array},
 };
 
 /// Circuit (partial) representation of wormhole [`VAA<P>`](https://github.com/wormhole-foundation/wormhole/blob/bfd4ba40ef2d213ad69bac638c72009ba4a07878/sdk/rust/core/src/vaa.rs#L80-L100)
@@ -26,7 +35,13 @@ use crate::{
 /// Visit [VAAs documentation](https://docs.wormhole.com/wormhole/explore-wormhole/vaa) for more.
 #[derive(Debug, Clone)]
 pub struct Vaa<E: Engine> {
+    /// Version of the VAA header, not covered by the signatures.
+    pub version: Byte<E>,
     pub signatures: Vec<Signature<E>>,
+    /// Index in the guardian set of the signer of each signature, as claimed by the VAA header.
+    pub guardian_indices: Vec<Byte<E>>,
+    /// Index of the guardian set claimed by the VAA header, not covered by the signatures.
+    pub guardian_set_index: [Byte<E>; LEN_GUARDIAN_SET_INDEX],
     pub body: VaaBody<E>,
 }
 
@@ -35,16 +50,21 @@ impl<E: Engine> Vaa<E> {
         cs: &mut CS,
         message: wormhole_sdk::Vaa<&serde_wormhole::RawMessage>,
         num_signatures: usize,
-    ) -> Result<Self, SynthesisError> {
+    ) -> Result<Self, OracleError> {
         let (header, body): (wormhole_sdk::vaa::Header, wormhole_sdk::vaa::Body<_>) =
             message.into();
         let body = VaaBody::from_vaa_body_witness(cs, body)?;
+        tracing::debug!(
+            guardian_set_index = header.guardian_set_index,
+            available = header.signatures.len(),
+            used = num_signatures,
+            "allocating VAA signatures"
+        );
         if header.signatures.len() < num_signatures {
-            return Err(new_synthesis_error(format!(
-                "Only have {} signature. expect {} at least",
-                header.signatures.len(),
-                num_signatures
-            )));
+            return Err(OracleError::InsufficientSignatures {
+                got: header.signatures.len(),
+                need: num_signatures,
+            });
         }
 
         let signatures = (0..num_signatures)
@@ -53,13 +73,126 @@ impl<E: Engine> Vaa<E> {
                 Signature::from_bytes_witness(cs, &signature)
             })
             .collect::<Result<Vec<_>, _>>()?;
+        let guardian_indices = header.signatures[..num_signatures]
+            .iter()
+            .map(|signature| Byte::alloc_from_witness(cs, Some(signature.index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set_index =
+            CSAllocatable::alloc_from_witness(cs, Some(header.guardian_set_index.to_be_bytes()))?;
+        let version = Byte::alloc_from_witness(cs, Some(header.version))?;
 
         Ok(Self {
-            signatures: signatures.try_into().unwrap(),
+            version,
+            signatures,
+            guardian_indices,
+            guardian_set_index,
             body,
         })
     }
 
+    /// Allocate a VAA from its wire format as [`Vaa::new_from_slice`], `None` for setup passes,
+    /// which get the same layout.
+    pub fn from_optional_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: Option<&[u8]>,
+        num_signatures: usize,
+    ) -> Result<Self, OracleError> {
+        Self::new_from_slice_inner(cs, bytes, num_signatures, &VaaAllocation::default(), false)
+    }
+
+    /// Allocate a VAA from its wire format, slicing the fields out of `bytes` in a single pass
+    /// instead of decoding it into a [`wormhole_sdk::Vaa`] first.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+        num_signatures: usize,
+    ) -> Result<Self, OracleError> {
+        Self::from_optional_slice(cs, Some(bytes), num_signatures)
+    }
+
+    /// Like [`Vaa::new_from_slice`], allocating the body fields as set by `allocation`.
+    pub fn new_from_slice_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+        num_signatures: usize,
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        Self::new_from_slice_inner(cs, Some(bytes), num_signatures, allocation, false)
+    }
+
+    /// Like [`Vaa::new_from_slice`], allocating `max_signatures` signatures whatever the number
+    /// the VAA carries, for [`Vaa::check_quorum_by_address`]. Missing signatures are zero and never
+    /// recover a key.
+    pub fn new_from_slice_up_to<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+        max_signatures: usize,
+    ) -> Result<Self, OracleError> {
+        Self::new_from_slice_inner(
+            cs,
+            Some(bytes),
+            max_signatures,
+            &VaaAllocation::default(),
+            true,
+        )
+    }
+
+    fn new_from_slice_inner<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: Option<&[u8]>,
+        num_signatures: usize,
+        allocation: &VaaAllocation,
+        pad: bool,
+    ) -> Result<Self, OracleError> {
+        let header = bytes
+            .map(|bytes| RawHeader::parse(bytes, num_signatures, pad))
+            .transpose()?;
+        let header = header.as_ref();
+        let body = VaaBody::new_from_optional_slice_allocated(
+            cs,
+            header.map(|header| header.body),
+            allocation,
+        )?;
+        let signatures = (0..num_signatures)
+            .map(|i| match header {
+                // Skip the guardian index preceding each signature.
+                Some(header) => Signature::from_bytes_witness(cs, &header.signatures[i][1..]),
+                None => Ok(Signature::alloc_from_witness(cs, None)?),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_indices = (0..num_signatures)
+            .map(|i| Byte::alloc_from_witness(cs, header.map(|header| header.signatures[i][0])))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set_index = CSAllocatable::alloc_from_witness(
+            cs,
+            header.map(|header| header.guardian_set_index.to_be_bytes()),
+        )?;
+        let version = Byte::alloc_from_witness(cs, header.map(|header| header.version))?;
+
+        Ok(Self {
+            version,
+            signatures,
+            guardian_indices,
+            guardian_set_index,
+            body,
+        })
+    }
+
+    /// Enforce that the body equals the body of `bytes`, the VAA as received, see
+    /// [`VaaBody::enforce_raw_bytes`].
+    pub fn enforce_raw_body<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<(), OracleError> {
+        // Bodies carrying a pyth accumulator message have a fixed length and end the VAA.
+        let body_start = bytes
+            .len()
+            .checked_sub(LEN_WORMHOLE_BODY)
+            .ok_or_else(|| OracleError::VaaDecode("truncated VAA body".to_string()))?;
+        self.body.enforce_raw_bytes(cs, &bytes[body_start..])
+    }
+
     pub fn merkle_root(&self) -> &MerkleRoot<E> {
         &self.body.payload.root
     }
@@ -68,25 +201,181 @@ impl<E: Engine> Vaa<E> {
         &self.signatures
     }
 
+    /// Guardian set index of the header, from its big-endian bytes.
+    pub fn guardian_set_index<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt32<E>, SynthesisError> {
+        let mut index = self.guardian_set_index;
+        index.reverse();
+        UInt32::from_bytes_le(cs, &index)
+    }
+
+    /// Whether the header is of [`VAA_VERSION`], the only version whose layout is supported.
+    pub fn check_version<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let expected = Num::Constant(u64_to_fe(VAA_VERSION as u64));
+        Num::equals(cs, &self.version.inner, &expected)
+    }
+
+    /// Whether the header claims the guardian set of `index`, for callers pinning the guardian set
+    /// instead of inputizing its index.
+    pub fn check_guardian_set_index<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        index: u32,
+    ) -> Result<Boolean, SynthesisError> {
+        let actual = self.guardian_set_index(cs)?.inner;
+        Num::equals(cs, &actual, &Num::Constant(u64_to_fe(index as u64)))
+    }
+
+    /// Double keccak256 of the body, the hash signed by the guardians and the `hash` of the VM
+    /// returned by `parseAndVerifyVM` of the wormhole core bridge.
+    pub fn body_digest<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        let keccak = Keccak256Params::new(cs)?;
+        self.body_digest_with_params(cs, &keccak)
+    }
+
+    /// Like [`Vaa::body_digest`], with the keccak gadget shared by several digests.
+    pub fn body_digest_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        keccak: &Keccak256Params<E>,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        let hash = self.body_digest_bytes_with_params(cs, keccak)?;
+        UInt256::from_be_bytes_fixed(cs, &hash)
+    }
+
+    /// Big-endian bytes of [`Vaa::body_digest`].
+    pub fn body_digest_bytes<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let keccak = Keccak256Params::new(cs)?;
+        self.body_digest_bytes_with_params(cs, &keccak)
+    }
+
+    /// Like [`Vaa::body_digest_bytes`], with the keccak gadget shared by several digests.
+    pub fn body_digest_bytes_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        keccak: &Keccak256Params<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        keccak256::double_digest_with_params(cs, keccak, self.body.bytes_iter())
+    }
+
     /// Recover public keys from VAA signatures.
+    ///
+    /// The success flags are left to the caller, [`Vaa::enforce_valid`] enforces them along with the
+    /// signers instead.
+    ///
+    /// The signatures are synthesized one after the other: gates and variables of a bellman
+    /// constraint system are indexed globally as they are added and an assembly cannot be merged
+    /// into another, so the checks cannot be generated into separate regions on worker threads.
+    /// Parallelism is applied natively instead, see [`crate::pyth::compute_public_input`].
+    ///
+    /// The two non-native inversions of each signature, converting its projective points to affine,
+    /// are not batched with Montgomery's trick: in-circuit, an inverse is a witness checked by one
+    /// multiplication, while the trick spends three multiplications per inverted element and a
+    /// single zero denominator would fail every signature of the batch.
     pub fn ecrecover<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
     ) -> Result<Vec<crate::gadgets::ecdsa::EcRecoverRes<E>>, SynthesisError> {
-        let msg_hash = {
-            let bytes = self.body.to_bytes();
-            use crate::gadgets::keccak256::digest;
-            let hash1 = digest(cs, &bytes)?;
-            let hash2 = digest(cs, &hash1)?;
-            UInt256::from_be_bytes_fixed(cs, &hash2)?
-        };
+        let params = Secp256k1Params::new(cs);
+        self.ecrecover_with_params(cs, &params)
+    }
+
+    /// Like [`Vaa::ecrecover`], failing signatures with a high s as ethereum does.
+    pub fn ecrecover_low_s<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Vec<crate::gadgets::ecdsa::EcRecoverRes<E>>, SynthesisError> {
+        let params = Secp256k1Params::new(cs).with_low_s();
+        self.ecrecover_with_params(cs, &params)
+    }
 
+    /// Like [`Vaa::ecrecover`], with `params` shared with other signature gadgets.
+    ///
+    /// With [`Secp256k1Params::with_low_s`], the checks built on it reject high-s signatures too.
+    pub fn ecrecover_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+    ) -> Result<Vec<crate::gadgets::ecdsa::EcRecoverRes<E>>, SynthesisError> {
+        let msg_hash = self.body_digest(cs)?;
         self.signatures
             .iter()
-            .map(|signature| signature.ecrecover(cs, &msg_hash))
+            .map(|signature| signature.ecrecover_with_params(cs, params, &msg_hash))
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// Check that the guardian index of every signature is below `guardian_set_size`.
+    ///
+    /// A matched signer is already bound to the guardian at its index, so this only bounds the
+    /// indices of signatures the quorum check ignores.
+    pub fn check_guardian_indices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set_size: usize,
+    ) -> Result<Boolean, SynthesisError> {
+        if guardian_set_size > u8::MAX as usize {
+            return Ok(Boolean::constant(true));
+        }
+        let size = Num::Constant(u64_to_fe(guardian_set_size as u64));
+        let mut is_ok = vec![Boolean::constant(true)];
+        for index in self.guardian_indices.iter() {
+            let (is_equal, is_greater) =
+                prepacked_long_comparison(cs, &[index.inner], &[size], &[8])?;
+            is_ok.push(Boolean::or(cs, &is_equal, &is_greater)?.not());
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// Check that the guardian indices are strictly increasing, as wormhole requires, so that no
+    /// guardian is counted for two signatures.
+    pub fn check_guardian_indices_increasing<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_ok = vec![Boolean::constant(true)];
+        for pair in self.guardian_indices.windows(2) {
+            let (_, is_greater) =
+                prepacked_long_comparison(cs, &[pair[1].inner], &[pair[0].inner], &[8])?;
+            is_ok.push(is_greater);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// Check that the VAA is emitted by the accumulator of `environment`.
+    pub fn check_emitter<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        environment: PythEnvironment,
+    ) -> Result<Boolean, SynthesisError> {
+        let expected = environment
+            .emitter_chain()
+            .to_be_bytes()
+            .into_iter()
+            .chain(environment.emitter_address());
+        let actual = self
+            .body
+            .emitter_chain
+            .iter()
+            .chain(self.body.emitter_address.iter());
+        let mut is_ok = vec![];
+        for (byte, expected) in actual.zip(expected) {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
     /// Check if all VAA sigantures are signed by one from guardian set.
     /// There is not quorum check and you should make sure all signatures are valid.
     pub fn check_by_pubkey<CS: ConstraintSystem<E>>(
@@ -98,13 +387,18 @@ impl<E: Engine> Vaa<E> {
             return Ok(Boolean::Constant(false));
         }
         let recovered = self.ecrecover(cs)?;
-        let mut is_ok = vec![];
-        for (successful, (x, y)) in recovered {
+        let mut is_ok = vec![
+            self.check_guardian_indices(cs, guardian_set.len())?,
+            self.check_guardian_indices_increasing(cs)?,
+        ];
+        for ((successful, (x, y)), index) in recovered.into_iter().zip(self.guardian_indices.iter())
+        {
             let mut is_matched = vec![];
-            for pubkey in guardian_set {
+            for (i, pubkey) in guardian_set.iter().enumerate() {
+                let is_index = Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
                 let x_is_equal = UInt256::equals(cs, &x, &pubkey.0)?;
                 let y_is_equal = UInt256::equals(cs, &y, &pubkey.1)?;
-                let is_equal = Boolean::and(cs, &x_is_equal, &y_is_equal)?;
+                let is_equal = smart_and(cs, &[is_index, x_is_equal, y_is_equal])?;
                 is_matched.push(is_equal);
             }
             let is_matched = smart_or(cs, &is_matched)?;
@@ -118,46 +412,307 @@ impl<E: Engine> Vaa<E> {
         &self,
         cs: &mut CS,
         guardian_set: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        self.check_by_address_with_params(cs, &params, &keccak, guardian_set)
+    }
+
+    /// Enforce that every signature is valid and recovers to a distinct member of `guardian_set`,
+    /// at strictly increasing guardian indices within the set: the conjunction of
+    /// [`Vaa::check_by_address`], so that no flag of [`Vaa::ecrecover`] can be left unchecked.
+    pub fn enforce_valid<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &[Address<E>],
+    ) -> Result<(), SynthesisError> {
+        let is_valid = self.check_by_address(cs, guardian_set)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))
+    }
+
+    /// Like [`Vaa::check_by_address`], with `params` and `keccak` shared with other gadgets.
+    pub fn check_by_address_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        guardian_set: &[Address<E>],
     ) -> Result<Boolean, SynthesisError> {
         if guardian_set.is_empty() {
             return Ok(Boolean::Constant(false));
         }
-        let recovered = self.ecrecover(cs)?;
-        // Add a true bool to avoid panic if no signatures need to check
-        let mut is_ok = vec![Boolean::constant(true)];
+        let mut is_ok = vec![
+            self.check_guardian_indices(cs, guardian_set.len())?,
+            self.check_guardian_indices_increasing(cs)?,
+        ];
+        is_ok.extend(self.match_signers(cs, params, keccak, guardian_set)?);
+        smart_and(cs, &is_ok)
+    }
+
+    /// Check that a quorum of the signatures, as wormhole computes it from the size of
+    /// `guardian_set`, are from distinct members of `guardian_set`, the others being ignored, as
+    /// wormhole accepts a VAA. Fails if fewer signatures than the quorum are allocated, see
+    /// [`Vaa::new_from_slice_up_to`].
+    pub fn check_quorum_by_address<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        if guardian_set.is_empty() {
+            return Ok(Boolean::Constant(false));
+        }
+        let quorum = guardian_set.len() * 2 / 3 + 1;
+        if self.signatures.len() < quorum {
+            return Err(new_synthesis_error(format!(
+                "{} signatures are below the quorum {} of {} guardians",
+                self.signatures.len(),
+                quorum,
+                guardian_set.len()
+            )));
+        }
+        let indices_ok = self.check_guardian_indices(cs, guardian_set.len())?;
+        let mut num_signers = LinearCombination::zero();
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        for signed in self.match_signers(cs, &params, &keccak, guardian_set)? {
+            num_signers.add_assign_boolean_with_coeff(&signed, E::Fr::one());
+        }
+        let num_signers = num_signers.into_num(cs)?;
+        let quorum = Num::Constant(u64_to_fe(quorum as u64));
+        // Both are at most the number of signatures.
+        let bits = usize::BITS - self.signatures.len().leading_zeros();
+        let (is_equal, is_greater) =
+            prepacked_long_comparison(cs, &[num_signers], &[quorum], &[bits as usize])?;
+        let has_quorum = Boolean::or(cs, &is_equal, &is_greater)?;
+        Boolean::and(cs, &indices_ok, &has_quorum)
+    }
+
+    /// Enforce that every signature recovers to the guardian of `guardian_set` at the index the VAA
+    /// header claims for it, the indices being strictly increasing so that every signer is distinct.
+    pub fn enforce_signed_by_guardians<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &GuardianSet<E>,
+    ) -> Result<(), SynthesisError> {
+        let is_increasing = self.check_guardian_indices_increasing(cs)?;
+        Boolean::enforce_equal(cs, &is_increasing, &Boolean::constant(true))?;
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        let recovered = self.recover_addresses(cs, &params, &keccak)?;
+        for ((successful, address), index) in recovered.iter().zip(self.guardian_indices.iter()) {
+            let is_guardian = guardian_set.is_guardian_at(cs, index, address)?;
+            let is_signed = Boolean::and(cs, successful, &is_guardian)?;
+            Boolean::enforce_equal(cs, &is_signed, &Boolean::constant(true))?;
+        }
+        Ok(())
+    }
+
+    /// Whether each signature is valid, along with the address of its signer.
+    fn recover_addresses<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+    ) -> Result<Vec<(Boolean, Address<E>)>, SynthesisError> {
+        let msg_hash = self.body_digest_with_params(cs, keccak)?;
+        let mut addresses = vec![];
+        for signature in self.signatures.iter() {
+            let (successful, address) =
+                signature.ecrecover_address_with_params(cs, params, keccak, &msg_hash)?;
+            addresses.push((successful, Address::from_bytes(cs, &address)?));
+        }
+        Ok(addresses)
+    }
+
+    /// Whether each signature recovers to the member of `guardian_set` at its guardian index, not
+    /// matched by a previous one.
+    fn match_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        guardian_set: &[Address<E>],
+    ) -> Result<Vec<Boolean>, SynthesisError> {
+        let recovered = self.recover_addresses(cs, params, keccak)?;
+        let mut signed = vec![];
         let mut guardian_used = vec![];
         for _ in 0..guardian_set.len() {
             guardian_used.push(Boolean::alloc_from_witness(cs, Some(false))?);
         }
-        for (successful, (x, y)) in recovered {
-            let (x, y) = (
-                x.into_be_bytes(cs)?.try_into().unwrap(),
-                y.into_be_bytes(cs)?.try_into().unwrap(),
-            );
-            let address = Address::from_pubkey(cs, &x, &y)?;
+        for ((successful, address), index) in
+            recovered.into_iter().zip(self.guardian_indices.iter())
+        {
             let mut is_matched = vec![];
             for (i, guardian) in guardian_set.iter().enumerate() {
                 // Make sure we use each guardian only once.
                 let guardian = guardian.mask(cs, &guardian_used[i].not())?;
+                let is_index = Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
                 let is_equal = guardian.equals(cs, &address)?;
+                let is_equal = Boolean::and(cs, &is_index, &is_equal)?;
                 guardian_used[i] = Boolean::or(cs, &guardian_used[i], &is_equal)?;
                 is_matched.push(is_equal);
             }
             let is_matched = smart_or(cs, &is_matched)?;
-            is_ok.push(smart_and(cs, &[successful, is_matched])?)
+            signed.push(smart_and(cs, &[successful, is_matched])?)
         }
-        let is_ok = smart_and(cs, &is_ok)?;
-        Ok(is_ok)
+        Ok(signed)
+    }
+}
+
+/// How a field read by the circuit is allocated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AllocationMode {
+    /// Range checked witness bytes.
+    #[default]
+    Witness,
+    /// Constant bytes, taken from the witness the circuit is set up with, so that the keys only
+    /// accept VAAs carrying the same value.
+    Constant,
+    /// Range checked witness bytes, also inputized in big-endian chunks of
+    /// [`PUBLIC_INPUT_BYTES`] in allocation order.
+    PublicInput,
+}
+
+/// Bytes packed into each public input of [`AllocationMode::PublicInput`] fields.
+pub const PUBLIC_INPUT_BYTES: usize = 16;
+
+/// Native public inputs of `bytes` allocated with [`AllocationMode::PublicInput`].
+pub fn compute_public_input_chunks<E: Engine>(bytes: &[u8]) -> Vec<E::Fr> {
+    let shift = u64_to_fe::<E::Fr>(256);
+    bytes
+        .chunks(PUBLIC_INPUT_BYTES)
+        .map(|chunk| {
+            chunk.iter().fold(E::Fr::zero(), |mut acc, byte| {
+                acc.mul_assign(&shift);
+                acc.add_assign(&u64_to_fe(*byte as u64));
+                acc
+            })
+        })
+        .collect()
+}
+
+impl AllocationMode {
+    /// Allocate `bytes`, `None` for setup passes, which [`AllocationMode::Constant`] fields do not
+    /// support since the constants are part of the keys.
+    fn alloc<E: Engine, CS: ConstraintSystem<E>, const N: usize>(
+        self,
+        cs: &mut CS,
+        bytes: Option<[u8; N]>,
+    ) -> Result<[Byte<E>; N], SynthesisError> {
+        match self {
+            AllocationMode::Witness => alloc_read(cs, bytes),
+            AllocationMode::Constant => bytes
+                .map(|bytes| bytes.map(Byte::constant))
+                .ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::PublicInput => {
+                let allocated = alloc_read(cs, bytes)?;
+                let shift = u64_to_fe::<E::Fr>(256);
+                for chunk in allocated.chunks(PUBLIC_INPUT_BYTES) {
+                    let mut lc = LinearCombination::zero();
+                    let mut coeff = E::Fr::one();
+                    for byte in chunk.iter().rev() {
+                        lc.add_assign_number_with_coeff(&byte.inner, coeff);
+                        coeff.mul_assign(&shift);
+                    }
+                    lc.into_num(cs)?.get_variable().inputize(cs)?;
+                }
+                Ok(allocated)
+            }
+        }
+    }
+}
+
+/// Allocation of the VAA fields, [`AllocationMode::Witness`] for all of them by default.
+///
+/// `nonce` and `consistency_level` are never read and always allocated as range checked witness
+/// bytes, since every byte hashed into the signed digest must be one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VaaAllocation {
+    pub timestamp: AllocationMode,
+    pub emitter_chain: AllocationMode,
+    pub emitter_address: AllocationMode,
+    pub sequence: AllocationMode,
+    pub slot: AllocationMode,
+    pub ring_size: AllocationMode,
+    pub root: AllocationMode,
+}
+
+/// Allocate `bytes` as a field read by the circuit.
+fn alloc_read<E: Engine, CS: ConstraintSystem<E>, const N: usize>(
+    cs: &mut CS,
+    bytes: Option<[u8; N]>,
+) -> Result<[Byte<E>; N], SynthesisError> {
+    CSAllocatable::alloc_from_witness(cs, bytes)
+}
+
+/// Header fields of a VAA in wire format, sliced natively before allocation.
+struct RawHeader<'a> {
+    version: u8,
+    guardian_set_index: u32,
+    /// Guardian index and signature of the signatures to allocate, zero past the ones carried.
+    signatures: Vec<Vec<u8>>,
+    body: &'a [u8],
+}
+
+impl<'a> RawHeader<'a> {
+    /// Slice `num_signatures` signatures and the body out of `bytes`, padding missing signatures
+    /// if `pad`, else failing on them.
+    fn parse(bytes: &'a [u8], num_signatures: usize, pad: bool) -> Result<Self, OracleError> {
+        let invalid = |reason: &str| OracleError::VaaDecode(reason.to_string());
+        let (&version, rest) = bytes.split_first().ok_or_else(|| invalid("empty VAA"))?;
+        if version != VAA_VERSION {
+            return Err(invalid(&format!("unsupported VAA version {}", version)));
+        }
+        if rest.len() < LEN_GUARDIAN_SET_INDEX + 1 {
+            return Err(invalid("truncated VAA header"));
+        }
+        let guardian_set_index =
+            u32::from_be_bytes(rest[..LEN_GUARDIAN_SET_INDEX].try_into().unwrap());
+        let available = rest[LEN_GUARDIAN_SET_INDEX] as usize;
+        let signatures_start = 1 + LEN_GUARDIAN_SET_INDEX + 1;
+        let body_start = signatures_start + available * LEN_GUARDIAN_SIGNATURE;
+        if bytes.len() < body_start {
+            return Err(invalid("truncated VAA signatures"));
+        }
+        tracing::debug!(
+            guardian_set_index,
+            available,
+            used = num_signatures,
+            "allocating VAA signatures"
+        );
+        if available < num_signatures && !pad {
+            return Err(OracleError::InsufficientSignatures {
+                got: available,
+                need: num_signatures,
+            });
+        }
+        let mut signatures = bytes[signatures_start..body_start]
+            .chunks_exact(LEN_GUARDIAN_SIGNATURE)
+            .take(num_signatures)
+            .map(|signature| signature.to_vec())
+            .collect::<Vec<_>>();
+        signatures.resize(num_signatures, vec![0; LEN_GUARDIAN_SIGNATURE]);
+        Ok(Self {
+            version,
+            guardian_set_index,
+            signatures,
+            body: &bytes[body_start..],
+        })
     }
 }
 
+/// Version of the VAA headers, the only one defined by wormhole.
+pub const VAA_VERSION: u8 = 1;
+const LEN_GUARDIAN_SET_INDEX: usize = 4;
+/// Guardian index and 65-byte signature.
+const LEN_GUARDIAN_SIGNATURE: usize = 1 + 65;
 const LEN_WORMHOLE_BODY_TIMESTAMP: usize = 4;
 const LEN_WORMHOLE_BODY_NONCE: usize = 4;
 const LEN_WORMHOLE_BODY_EMITTER_CHAIN: usize = 2;
 const LEN_WORMHOLE_BODY_EMITTER_ADDRESS: usize = 32;
 const LEN_WORMHOLE_BODY_SEQUENCE: usize = 8;
 const LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL: usize = 1;
-const LEN_WORMHOLE_BODY: usize = LEN_WORMHOLE_BODY_TIMESTAMP
+pub(super) const LEN_WORMHOLE_BODY: usize = LEN_WORMHOLE_BODY_TIMESTAMP
     + LEN_WORMHOLE_BODY_NONCE
     + LEN_WORMHOLE_BODY_EMITTER_CHAIN
     + LEN_WORMHOLE_BODY_EMITTER_ADDRESS
@@ -176,9 +731,87 @@ pub struct VaaBody<E: Engine> {
     pub sequence: [Byte<E>; LEN_WORMHOLE_BODY_SEQUENCE],
     pub consistency_level: [Byte<E>; LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL],
     pub payload: VaaPayload<E>,
+    integers: BodyIntegers<E>,
+}
+
+/// Integer views of [`VaaBody`] fields, assembled from their bytes on first use.
+#[derive(Debug, Clone)]
+struct BodyIntegers<E: Engine> {
+    timestamp: Cell<Option<UInt32<E>>>,
+    sequence: Cell<Option<UInt64<E>>>,
+    slot: Cell<Option<UInt64<E>>>,
+}
+
+impl<E: Engine> Default for BodyIntegers<E> {
+    fn default() -> Self {
+        Self {
+            timestamp: Cell::new(None),
+            sequence: Cell::new(None),
+            slot: Cell::new(None),
+        }
+    }
+}
+
+/// The value of `cell`, initialized by `init` if absent.
+fn get_or_init<T: Copy>(
+    cell: &Cell<Option<T>>,
+    init: impl FnOnce() -> Result<T, SynthesisError>,
+) -> Result<T, SynthesisError> {
+    if let Some(value) = cell.get() {
+        return Ok(value);
+    }
+    let value = init()?;
+    cell.set(Some(value));
+    Ok(value)
 }
 
 impl<E: Engine> VaaBody<E> {
+    /// Timestamp of the observation, in seconds, from its big-endian bytes. Assembled once, later
+    /// calls return the same integer.
+    pub fn timestamp_u32<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt32<E>, SynthesisError> {
+        get_or_init(&self.integers.timestamp, || {
+            let mut timestamp = self.timestamp;
+            timestamp.reverse();
+            UInt32::from_bytes_le(cs, &timestamp)
+        })
+    }
+
+    /// Sequence of the message of the emitter, see [`VaaBody::timestamp_u32`].
+    pub fn sequence_u64<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt64<E>, SynthesisError> {
+        get_or_init(&self.integers.sequence, || {
+            let mut sequence = self.sequence;
+            sequence.reverse();
+            UInt64::from_bytes_le(cs, &sequence)
+        })
+    }
+
+    /// [`VaaPayload::slot`], see [`VaaBody::timestamp_u32`].
+    pub fn slot_u64<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt64<E>, SynthesisError> {
+        get_or_init(&self.integers.slot, || self.payload.slot(cs))
+    }
+
+    /// Bytes of [`VaaBody::to_bytes`], without building the array.
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.timestamp
+            .iter()
+            .chain(self.nonce.iter())
+            .chain(self.emitter_chain.iter())
+            .chain(self.emitter_address.iter())
+            .chain(self.sequence.iter())
+            .chain(self.consistency_level.iter())
+            .copied()
+            .chain(self.payload.bytes_iter())
+    }
+
     pub fn to_bytes(&self) -> [Byte<E>; LEN_WORMHOLE_BODY] {
         let mut bytes = [Byte::<E>::zero(); LEN_WORMHOLE_BODY];
         let mut offset = 0;
@@ -201,44 +834,117 @@ impl<E: Engine> VaaBody<E> {
         bytes
     }
 
+    /// Inputize `raw`, the body bytes that were signed, and enforce that it equals the body
+    /// re-serialized by [`VaaBody::to_bytes`], the bytes hashed by [`Vaa::body_digest_bytes`].
+    ///
+    /// Bodies allocated field by field from a natively parsed [`wormhole_sdk::vaa::Body`] trust the
+    /// parser to match the signed bytes, this closes that gap by having the verifier supply them as
+    /// one public input per [`PUBLIC_INPUT_BYTES`], computed by [`compute_public_input_chunks`].
+    pub fn enforce_raw_bytes<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        raw: &[u8],
+    ) -> Result<(), OracleError> {
+        let raw: [u8; LEN_WORMHOLE_BODY] =
+            raw.try_into().map_err(|_| OracleError::InvalidLength {
+                what: "VAA body",
+                got: raw.len(),
+                expected: LEN_WORMHOLE_BODY,
+            })?;
+        let raw = AllocationMode::PublicInput.alloc::<E, _, LEN_WORMHOLE_BODY>(cs, Some(raw))?;
+        for (byte, raw) in self.bytes_iter().zip(raw.iter()) {
+            byte.inner.enforce_equal(cs, &raw.inner)?;
+        }
+        Ok(())
+    }
+
+    /// Allocate a body from its wire format, `bytes` must be exactly one VAA body carrying a pyth
+    /// accumulator message.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        Self::new_from_slice_allocated(cs, bytes, &VaaAllocation::default())
+    }
+
+    /// Like [`VaaBody::new_from_slice`], allocating the fields as set by `allocation`.
+    pub fn new_from_slice_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        Self::new_from_optional_slice_allocated(cs, Some(bytes), allocation)
+    }
+
+    /// Like [`VaaBody::new_from_slice_allocated`], `None` for setup passes, which get the same
+    /// layout.
+    pub fn new_from_optional_slice_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: Option<&[u8]>,
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        if let Some(bytes) = bytes {
+            if bytes.len() != LEN_WORMHOLE_BODY {
+                return Err(OracleError::InvalidLength {
+                    what: "VAA body",
+                    got: bytes.len(),
+                    expected: LEN_WORMHOLE_BODY,
+                });
+            }
+        }
+        let mut offset = 0;
+        let mut next = |len: usize| {
+            let chunk = bytes.map(|bytes| &bytes[offset..offset + len]);
+            offset += len;
+            chunk
+        };
+        let timestamp = next(LEN_WORMHOLE_BODY_TIMESTAMP).map(|c| c.try_into().unwrap());
+        let nonce = next(LEN_WORMHOLE_BODY_NONCE).map(|c| c.try_into().unwrap());
+        let emitter_chain = next(LEN_WORMHOLE_BODY_EMITTER_CHAIN).map(|c| c.try_into().unwrap());
+        let emitter_address =
+            next(LEN_WORMHOLE_BODY_EMITTER_ADDRESS).map(|c| c.try_into().unwrap());
+        let sequence = next(LEN_WORMHOLE_BODY_SEQUENCE).map(|c| c.try_into().unwrap());
+        let consistency_level =
+            next(LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL).map(|c| c.try_into().unwrap());
+        let payload = next(LEN_MESSAGE);
+        Ok(Self {
+            timestamp: allocation.timestamp.alloc(cs, timestamp)?,
+            nonce: alloc_read(cs, nonce)?,
+            emitter_chain: allocation.emitter_chain.alloc(cs, emitter_chain)?,
+            emitter_address: allocation.emitter_address.alloc(cs, emitter_address)?,
+            sequence: allocation.sequence.alloc(cs, sequence)?,
+            consistency_level: alloc_read(cs, consistency_level)?,
+            payload: VaaPayload::new_from_optional_slice_allocated(cs, payload, allocation)?,
+            integers: BodyIntegers::default(),
+        })
+    }
+
     pub fn from_vaa_body_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: wormhole_sdk::vaa::Body<&serde_wormhole::RawMessage>,
-    ) -> Result<Self, SynthesisError> {
+    ) -> Result<Self, OracleError> {
         let timestamp = {
             let bytes = witness.timestamp.to_be_bytes();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
-        let nonce = {
-            let bytes = witness.nonce.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
+        let nonce = alloc_read(cs, Some(witness.nonce.to_be_bytes()))?;
         let emitter_chain = {
-            let bytes = serde_wormhole::to_vec(&witness.emitter_chain)
-                .unwrap()
-                .try_into()
-                .unwrap();
+            let bytes = u16::from(witness.emitter_chain).to_be_bytes();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
         let emitter_address = {
-            let bytes = serde_wormhole::to_vec(&witness.emitter_address)
-                .unwrap()
-                .try_into()
-                .unwrap();
+            let bytes = witness.emitter_address.0;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
         let sequence = {
             let bytes = witness.sequence.to_be_bytes();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
-        let consistency_level = {
-            let bytes = witness.consistency_level.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
+        let consistency_level = alloc_read(cs, Some(witness.consistency_level.to_be_bytes()))?;
         let payload = {
             let payload =
                 pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(witness.payload.as_ref())
-                    .map_err(new_synthesis_error)?;
+                    .map_err(|e| OracleError::PayloadDecode(e.to_string()))?;
             VaaPayload::from_wormhole_message_witness(cs, payload)?
         };
         Ok(Self {
@@ -249,8 +955,160 @@ impl<E: Engine> VaaBody<E> {
             sequence,
             consistency_level,
             payload,
+            integers: BodyIntegers::default(),
+        })
+    }
+}
+
+pub(super) const LEN_WORMHOLE_BODY_HEADER: usize = LEN_WORMHOLE_BODY - LEN_MESSAGE;
+
+/// [`VaaBody`] of any payload of at most `MAX_PAYLOAD` bytes, for VAAs of other emitters than the
+/// pyth accumulator, e.g. governance VAAs. The payload is zero-padded and its length is a witness,
+/// which [`VaaBodyVarLen::signed_digest`] respects so that the signatures are checked by the same
+/// [`Signature`] gadgets as for [`Vaa`].
+#[derive(Debug, Clone)]
+pub struct VaaBodyVarLen<E: Engine, const MAX_PAYLOAD: usize> {
+    pub timestamp: [Byte<E>; LEN_WORMHOLE_BODY_TIMESTAMP],
+    pub nonce: [Byte<E>; LEN_WORMHOLE_BODY_NONCE],
+    pub emitter_chain: [Byte<E>; LEN_WORMHOLE_BODY_EMITTER_CHAIN],
+    pub emitter_address: [Byte<E>; LEN_WORMHOLE_BODY_EMITTER_ADDRESS],
+    pub sequence: [Byte<E>; LEN_WORMHOLE_BODY_SEQUENCE],
+    pub consistency_level: [Byte<E>; LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL],
+    /// Length of the payload, in bytes.
+    pub payload_len: UInt32<E>,
+    /// Payload, zero-padded to `MAX_PAYLOAD` bytes.
+    pub payload: [Byte<E>; MAX_PAYLOAD],
+}
+
+impl<E: Engine, const MAX_PAYLOAD: usize> VaaBodyVarLen<E, MAX_PAYLOAD> {
+    /// Allocate a body from its wire format, of a payload of at most `MAX_PAYLOAD` bytes.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        let max_len = LEN_WORMHOLE_BODY_HEADER + MAX_PAYLOAD;
+        if bytes.len() < LEN_WORMHOLE_BODY_HEADER || bytes.len() > max_len {
+            return Err(OracleError::InvalidLength {
+                what: "VAA body",
+                got: bytes.len(),
+                expected: bytes.len().clamp(LEN_WORMHOLE_BODY_HEADER, max_len),
+            });
+        }
+        let (header, payload) = bytes.split_at(LEN_WORMHOLE_BODY_HEADER);
+        let mut offset = 0;
+        let mut next = |len: usize| {
+            let chunk = &header[offset..offset + len];
+            offset += len;
+            chunk
+        };
+        let timestamp: [u8; LEN_WORMHOLE_BODY_TIMESTAMP] =
+            next(LEN_WORMHOLE_BODY_TIMESTAMP).try_into().unwrap();
+        let nonce: [u8; LEN_WORMHOLE_BODY_NONCE] =
+            next(LEN_WORMHOLE_BODY_NONCE).try_into().unwrap();
+        let emitter_chain: [u8; LEN_WORMHOLE_BODY_EMITTER_CHAIN] =
+            next(LEN_WORMHOLE_BODY_EMITTER_CHAIN).try_into().unwrap();
+        let emitter_address: [u8; LEN_WORMHOLE_BODY_EMITTER_ADDRESS] =
+            next(LEN_WORMHOLE_BODY_EMITTER_ADDRESS).try_into().unwrap();
+        let sequence: [u8; LEN_WORMHOLE_BODY_SEQUENCE] =
+            next(LEN_WORMHOLE_BODY_SEQUENCE).try_into().unwrap();
+        let consistency_level: [u8; LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL] =
+            next(LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL)
+                .try_into()
+                .unwrap();
+        let padded = payload
+            .iter()
+            .copied()
+            .chain(std::iter::repeat(0))
+            .take(MAX_PAYLOAD)
+            .map(|byte| Byte::alloc_from_witness(cs, Some(byte)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            timestamp: CSAllocatable::alloc_from_witness(cs, Some(timestamp))?,
+            nonce: CSAllocatable::alloc_from_witness(cs, Some(nonce))?,
+            emitter_chain: CSAllocatable::alloc_from_witness(cs, Some(emitter_chain))?,
+            emitter_address: CSAllocatable::alloc_from_witness(cs, Some(emitter_address))?,
+            sequence: CSAllocatable::alloc_from_witness(cs, Some(sequence))?,
+            consistency_level: CSAllocatable::alloc_from_witness(cs, Some(consistency_level))?,
+            payload_len: UInt32::alloc_from_witness(cs, Some(payload.len() as u32))?,
+            payload: to_array("VAA payload", &padded)?,
+        })
+    }
+
+    /// Allocate a body with absent witnesses, in the same layout as
+    /// [`VaaBodyVarLen::new_from_slice`], for setup passes.
+    pub fn alloc_absent<CS: ConstraintSystem<E>>(cs: &mut CS) -> Result<Self, SynthesisError> {
+        let payload = (0..MAX_PAYLOAD)
+            .map(|_| Byte::alloc_from_witness(cs, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            timestamp: CSAllocatable::alloc_from_witness(cs, None)?,
+            nonce: CSAllocatable::alloc_from_witness(cs, None)?,
+            emitter_chain: CSAllocatable::alloc_from_witness(cs, None)?,
+            emitter_address: CSAllocatable::alloc_from_witness(cs, None)?,
+            sequence: CSAllocatable::alloc_from_witness(cs, None)?,
+            consistency_level: CSAllocatable::alloc_from_witness(cs, None)?,
+            payload_len: UInt32::alloc_from_witness(cs, None)?,
+            payload: to_array("VAA payload", &payload)?,
         })
     }
+
+    /// Header and padded payload bytes, the body being the header and the first `payload_len` bytes
+    /// of the payload.
+    pub fn padded_bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.timestamp
+            .iter()
+            .chain(self.nonce.iter())
+            .chain(self.emitter_chain.iter())
+            .chain(self.emitter_address.iter())
+            .chain(self.sequence.iter())
+            .chain(self.consistency_level.iter())
+            .chain(self.payload.iter())
+            .copied()
+    }
+
+    /// Whether the payload length is at most `MAX_PAYLOAD`.
+    pub fn check_payload_len<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let max_len = Num::Constant(u64_to_fe(MAX_PAYLOAD as u64));
+        let (_, is_too_long) =
+            prepacked_long_comparison(cs, &[self.payload_len.inner], &[max_len], &[32])?;
+        Ok(is_too_long.not())
+    }
+
+    /// Whether the bytes past the payload length are zero, so that a body has a single padded
+    /// representation.
+    pub fn check_padding<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_ok = vec![Boolean::constant(true)];
+        for (i, byte) in self.payload.iter().enumerate() {
+            let position = Num::Constant(u64_to_fe(i as u64));
+            let (_, is_payload) =
+                prepacked_long_comparison(cs, &[self.payload_len.inner], &[position], &[32])?;
+            let is_zero = Num::equals(cs, &byte.inner, &Num::zero())?;
+            is_ok.push(Boolean::or(cs, &is_payload, &is_zero)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// keccak256(keccak256(body)), the digest signed by the guardians, hashing the header and the
+    /// first `payload_len` bytes of the payload only. [`VaaBodyVarLen::check_payload_len`] is
+    /// enforced.
+    pub fn signed_digest<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        let is_len_ok = self.check_payload_len(cs)?;
+        Boolean::enforce_equal(cs, &is_len_ok, &Boolean::constant(true))?;
+        let bytes = self.padded_bytes_iter().collect::<Vec<_>>();
+        let header_len = Num::Constant(u64_to_fe(LEN_WORMHOLE_BODY_HEADER as u64));
+        let len = self.payload_len.inner.add(cs, &header_len)?;
+        let hash = keccak256::double_digest_var_len(cs, &bytes, &len)?;
+        UInt256::from_be_bytes_fixed(cs, &hash)
+    }
 }
 
 const LEN_MAGIC: usize = 4;
@@ -258,7 +1116,10 @@ const LEN_PAYLOAD_TYPE: usize = 1;
 const LEN_SLOT: usize = 8;
 const LEN_RING_SIZE: usize = 4;
 const LEN_ROOT: usize = keccak160::WIDTH_HASH_BYTES;
-const LEN_MESSAGE: usize = LEN_MAGIC + LEN_PAYLOAD_TYPE + LEN_SLOT + LEN_RING_SIZE + LEN_ROOT;
+pub(super) const LEN_MESSAGE: usize =
+    LEN_MAGIC + LEN_PAYLOAD_TYPE + LEN_SLOT + LEN_RING_SIZE + LEN_ROOT;
+/// Magic of pyth accumulator messages.
+const MAGIC: [u8; LEN_MAGIC] = *b"AUWV";
 const PAYLOAD_TYPE: u8 = 0; // Fixed payload type for now.
 /// Representation of pyth-defined wormhole payload [`WormholeMessage`](https://github.com/pyth-network/pyth-crosschain/blob/1d82f92d80598e689f4130983d06b12412b83427/pythnet/pythnet_sdk/src/wire.rs#L108-L112).
 #[derive(Debug, Clone)]
@@ -271,6 +1132,51 @@ pub struct VaaPayload<E: Engine> {
 }
 
 impl<E: Engine> VaaPayload<E> {
+    /// Enforce that `item`, a message of the accumulator, is in the tree of [`VaaPayload::root`].
+    pub fn enforce_inclusion<CS: ConstraintSystem<E>, const N: usize>(
+        &self,
+        cs: &mut CS,
+        keccak: &Keccak256Params<E>,
+        path: &MerklePath<E, N>,
+        item: &[Byte<E>],
+    ) -> Result<(), SynthesisError> {
+        path.enforce_inclusion(cs, keccak, item, &self.root)
+    }
+
+    /// Whether the payload starts with the magic and the payload type of pyth accumulator messages,
+    /// which the witness bytes are not constrained to on allocation.
+    pub fn check_format<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let expected = MAGIC.into_iter().chain([PAYLOAD_TYPE]);
+        let actual = self.magic.iter().chain(self.payload_type.iter());
+        let mut is_ok = vec![];
+        for (byte, expected) in actual.zip(expected) {
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_ok.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// Bytes of [`VaaPayload::to_bytes`], without building the array.
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Byte<E>> + '_ {
+        self.magic
+            .iter()
+            .chain(self.payload_type.iter())
+            .chain(self.slot.iter())
+            .chain(self.ring_size.iter())
+            .copied()
+            .chain(self.root.inner())
+    }
+
+    /// Slot of the accumulator snapshot, from its big-endian bytes.
+    pub fn slot<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<UInt64<E>, SynthesisError> {
+        let mut slot = self.slot;
+        slot.reverse();
+        UInt64::from_bytes_le(cs, &slot)
+    }
+
     pub fn to_bytes(&self) -> [Byte<E>; LEN_MESSAGE] {
         let mut bytes = [Byte::<E>::zero(); LEN_MESSAGE];
         let mut offset = 0;
@@ -286,25 +1192,83 @@ impl<E: Engine> VaaPayload<E> {
         bytes
     }
 
+    /// Allocate a payload from its wire format, `bytes` must be exactly one pyth accumulator message.
+    pub fn new_from_slice<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Self, OracleError> {
+        Self::new_from_slice_allocated(cs, bytes, &VaaAllocation::default())
+    }
+
+    /// Like [`VaaPayload::new_from_slice`], allocating the fields as set by `allocation`.
+    pub fn new_from_slice_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[u8],
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        Self::new_from_optional_slice_allocated(cs, Some(bytes), allocation)
+    }
+
+    /// Like [`VaaPayload::new_from_slice_allocated`], `None` for setup passes, which get the same
+    /// layout.
+    pub fn new_from_optional_slice_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: Option<&[u8]>,
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        let message = bytes
+            .map(|bytes| {
+                if bytes.len() != LEN_MESSAGE {
+                    return Err(OracleError::InvalidLength {
+                        what: "wormhole payload",
+                        got: bytes.len(),
+                        expected: LEN_MESSAGE,
+                    });
+                }
+                pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(bytes)
+                    .map_err(|e| OracleError::PayloadDecode(e.to_string()))
+            })
+            .transpose()?;
+        Self::from_optional_message_witness_allocated(cs, message, allocation)
+    }
+
     pub fn from_wormhole_message_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: pythnet_sdk::wire::v1::WormholeMessage,
-    ) -> Result<Self, SynthesisError> {
-        let magic = CSAllocatable::alloc_from_witness(cs, Some(witness.magic))?;
-        let payload_type = CSAllocatable::alloc_from_witness(cs, Some([PAYLOAD_TYPE]))?;
-        let pythnet_sdk::wire::v1::WormholePayload::Merkle(payload) = witness.payload;
-        let slot = {
-            let bytes = payload.slot.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let ring_size = {
-            let bytes = payload.ring_size.to_be_bytes();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
-        };
-        let root = {
-            let root = CSAllocatable::alloc_from_witness(cs, Some(payload.root))?;
-            MerkleRoot::new(root)
-        };
+    ) -> Result<Self, OracleError> {
+        Self::from_wormhole_message_witness_allocated(cs, witness, &VaaAllocation::default())
+    }
+
+    /// Like [`VaaPayload::from_wormhole_message_witness`], allocating the fields as set by
+    /// `allocation`.
+    pub fn from_wormhole_message_witness_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: pythnet_sdk::wire::v1::WormholeMessage,
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        Self::from_optional_message_witness_allocated(cs, Some(witness), allocation)
+    }
+
+    fn from_optional_message_witness_allocated<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<pythnet_sdk::wire::v1::WormholeMessage>,
+        allocation: &VaaAllocation,
+    ) -> Result<Self, OracleError> {
+        let magic = CSAllocatable::alloc_from_witness(cs, witness.as_ref().map(|w| w.magic))?;
+        let payload_type =
+            CSAllocatable::alloc_from_witness(cs, witness.as_ref().map(|_| [PAYLOAD_TYPE]))?;
+        let payload = witness.map(|witness| {
+            let pythnet_sdk::wire::v1::WormholePayload::Merkle(payload) = witness.payload;
+            payload
+        });
+        let payload = payload.as_ref();
+        let slot = allocation
+            .slot
+            .alloc(cs, payload.map(|p| p.slot.to_be_bytes()))?;
+        let ring_size = allocation
+            .ring_size
+            .alloc(cs, payload.map(|p| p.ring_size.to_be_bytes()))?;
+        let root = MerkleRoot::new(allocation.root.alloc(cs, payload.map(|p| p.root))?);
         Ok(Self {
             magic,
             payload_type,
@@ -317,15 +1281,18 @@ impl<E: Engine> VaaPayload<E> {
 
 #[cfg(test)]
 mod tests {
-    use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::{bn256::Bn256, Engine};
     use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
         franklin_crypto::{
             bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
             plonk::circuit::boolean::Boolean,
         },
         vm::primitives::uint256::UInt256,
     };
+    use proptest::prelude::*;
 
+    use crate::error::OracleError;
     use crate::utils::{
         new_synthesis_error,
         testing::{bytes_assert_eq, create_test_constraint_system},
@@ -338,8 +1305,16 @@ mod tests {
         let hex_str = "415557560000000000069b993c00002710095bb7e5fa374ea08603a6698123d99101547a50";
         let data = hex::decode(hex_str).unwrap();
         let payload = pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(data).unwrap();
-        let payload = super::VaaPayload::<_>::from_wormhole_message_witness(cs, payload)?;
+        let mut payload = super::VaaPayload::<_>::from_wormhole_message_witness(cs, payload)?;
         bytes_assert_eq(&payload.to_bytes(), hex_str);
+        assert_eq!(payload.check_format(cs)?.get_value(), Some(true));
+
+        payload.payload_type = [Byte::alloc_from_witness(cs, Some(1))?];
+        assert_eq!(payload.check_format(cs)?.get_value(), Some(false));
+        payload.payload_type = [Byte::alloc_from_witness(cs, Some(0))?];
+        payload.magic[0] = Byte::alloc_from_witness(cs, Some(b'P'))?;
+        assert_eq!(payload.check_format(cs)?.get_value(), Some(false));
+        assert!(cs.is_satisfied());
         Ok(())
     }
 
@@ -356,6 +1331,427 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_new_from_slice() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode(get_vaa()).unwrap();
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&data).unwrap();
+        let (_, body): (_, wormhole_sdk::vaa::Body<_>) = vaa.into();
+        let expected = serde_wormhole::to_vec(&body).unwrap();
+        let body = super::VaaBody::<_>::new_from_slice(cs, &expected)?;
+        bytes_assert_eq(&body.to_bytes(), hex::encode(&expected));
+        bytes_assert_eq(
+            &body.bytes_iter().collect::<Vec<_>>(),
+            hex::encode(&expected),
+        );
+
+        assert!(matches!(
+            super::VaaBody::<_>::new_from_slice(cs, &expected[1..]),
+            Err(OracleError::InvalidLength { got, .. }) if got == expected.len() - 1
+        ));
+        let mut invalid_magic = expected.clone();
+        invalid_magic[super::LEN_WORMHOLE_BODY - super::LEN_MESSAGE] = 0;
+        assert!(matches!(
+            super::VaaBody::<_>::new_from_slice(cs, &invalid_magic),
+            Err(OracleError::PayloadDecode(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_raw_body() -> Result<(), SynthesisError> {
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let bytes = fixture.vaa();
+        let cs = &mut create_test_constraint_system()?;
+        let vaa =
+            super::Vaa::<_>::from_vaa_witness(cs, serde_wormhole::from_slice(&bytes).unwrap(), 1)?;
+        let num_inputs = cs.num_inputs;
+        vaa.enforce_raw_body(cs, &bytes)?;
+        assert!(cs.is_satisfied());
+        // The signed bytes are supplied by the verifier.
+        let body = &bytes[bytes.len() - super::LEN_WORMHOLE_BODY..];
+        let expected = super::compute_public_input_chunks::<Bn256>(body);
+        assert_eq!(cs.num_inputs, num_inputs + expected.len());
+
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        vaa.enforce_raw_body(cs, &tampered)?;
+        assert!(!cs.is_satisfied());
+
+        assert!(matches!(
+            vaa.body.enforce_raw_bytes(cs, &fixture.vaa_body()[1..]),
+            Err(OracleError::InvalidLength { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vaa_header() -> Result<(), SynthesisError> {
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let cs = &mut create_test_constraint_system()?;
+        let bytes = fixture.vaa();
+        let mut vaa =
+            super::Vaa::<_>::new_from_slice(cs, &bytes, 1).map_err(new_synthesis_error)?;
+        assert_eq!(vaa.version.get_byte_value(), Some(super::VAA_VERSION));
+        assert_eq!(vaa.check_version(cs)?.get_value(), Some(true));
+        let pinned = vaa.check_guardian_set_index(cs, fixture.guardian_set_index)?;
+        assert_eq!(pinned.get_value(), Some(true));
+        let other = vaa.check_guardian_set_index(cs, fixture.guardian_set_index + 1)?;
+        assert_eq!(other.get_value(), Some(false));
+        let decoded =
+            super::Vaa::<_>::from_vaa_witness(cs, serde_wormhole::from_slice(&bytes).unwrap(), 1)?;
+        assert_eq!(decoded.check_version(cs)?.get_value(), Some(true));
+
+        // The version is not signed, a prover could claim any.
+        vaa.version = Byte::alloc_from_witness(cs, Some(2))?;
+        assert_eq!(vaa.check_version(cs)?.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guardian_indices() -> Result<(), SynthesisError> {
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let cs = &mut create_test_constraint_system()?;
+        let vaa = super::Vaa::<_>::new_from_slice(cs, &fixture.vaa(), fixture.num_signatures)
+            .map_err(new_synthesis_error)?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&vaa.guardian_indices).unwrap(),
+            (0..fixture.num_signatures as u8).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Byte::get_byte_value_multiple(&vaa.guardian_set_index).unwrap(),
+            fixture.guardian_set_index.to_be_bytes()
+        );
+        let guardian_set_index = vaa.guardian_set_index(cs)?;
+        assert_eq!(
+            guardian_set_index.get_value(),
+            Some(fixture.guardian_set_index)
+        );
+        let in_bounds = vaa.check_guardian_indices(cs, fixture.guardian_set_size)?;
+        assert_eq!(in_bounds.get_value(), Some(true));
+        let tight = vaa.check_guardian_indices(cs, fixture.num_signatures)?;
+        assert_eq!(tight.get_value(), Some(true));
+        let out_of_bounds = vaa.check_guardian_indices(cs, fixture.num_signatures - 1)?;
+        assert_eq!(out_of_bounds.get_value(), Some(false));
+        let increasing = vaa.check_guardian_indices_increasing(cs)?;
+        assert_eq!(increasing.get_value(), Some(true));
+
+        // The same signature twice, and so the same index, is rejected even when the signer would
+        // otherwise only be matched once.
+        let start = 1 + super::LEN_GUARDIAN_SET_INDEX + 1;
+        let mut repeated = fixture.vaa();
+        repeated.copy_within(
+            start..start + super::LEN_GUARDIAN_SIGNATURE,
+            start + super::LEN_GUARDIAN_SIGNATURE,
+        );
+        let vaa = super::Vaa::<_>::new_from_slice(cs, &repeated, 2).map_err(new_synthesis_error)?;
+        let increasing = vaa.check_guardian_indices_increasing(cs)?;
+        assert_eq!(increasing.get_value(), Some(false));
+        // Swapped signatures are valid but out of order.
+        let mut swapped = fixture.vaa();
+        let (first, second) = swapped[start..start + 2 * super::LEN_GUARDIAN_SIGNATURE]
+            .split_at_mut(super::LEN_GUARDIAN_SIGNATURE);
+        first.swap_with_slice(second);
+        let vaa = super::Vaa::<_>::new_from_slice(cs, &swapped, 2).map_err(new_synthesis_error)?;
+        let increasing = vaa.check_guardian_indices_increasing(cs)?;
+        assert_eq!(increasing.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guardian_index_bound_to_signer() -> Result<(), SynthesisError> {
+        use crate::gadgets::ethereum::Address;
+
+        let fixture = crate::testing::fixtures::AccumulatorFixture {
+            guardian_set_size: 4,
+            num_signatures: 2,
+            ..Default::default()
+        };
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = fixture
+            .guardian_set()
+            .iter()
+            .map(|address| Address::from_address_witness(cs, address))
+            .collect::<Result<Vec<_>, _>>()?;
+        let vaa =
+            super::Vaa::<_>::new_from_slice(cs, &fixture.vaa(), 2).map_err(new_synthesis_error)?;
+        let valid = vaa.check_by_address(cs, &guardian_set)?;
+        assert_eq!(valid.get_value(), Some(true));
+
+        // The second signer claims the index of a guardian that did not sign, still increasing and
+        // within the set.
+        let mut misindexed = fixture.vaa();
+        misindexed[1 + super::LEN_GUARDIAN_SET_INDEX + 1 + super::LEN_GUARDIAN_SIGNATURE] = 3;
+        let vaa =
+            super::Vaa::<_>::new_from_slice(cs, &misindexed, 2).map_err(new_synthesis_error)?;
+        assert_eq!(vaa.check_guardian_indices(cs, 4)?.get_value(), Some(true));
+        assert_eq!(
+            vaa.check_guardian_indices_increasing(cs)?.get_value(),
+            Some(true)
+        );
+        let misindexed = vaa.check_by_address(cs, &guardian_set)?;
+        assert_eq!(misindexed.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_quorum() -> Result<(), SynthesisError> {
+        use super::{LEN_GUARDIAN_SET_INDEX, LEN_GUARDIAN_SIGNATURE};
+        use crate::gadgets::ethereum::Address;
+
+        // A quorum of 3 for 4 guardians.
+        let fixture = crate::testing::fixtures::AccumulatorFixture {
+            guardian_set_size: 4,
+            num_signatures: 3,
+            ..Default::default()
+        };
+        let cs = &mut create_test_constraint_system()?;
+        let guardian_set = fixture
+            .guardian_set()
+            .iter()
+            .map(|address| Address::from_address_witness(cs, address))
+            .collect::<Result<Vec<_>, _>>()?;
+        // One missing signature is padded.
+        let vaa = super::Vaa::<_>::new_from_slice_up_to(cs, &fixture.vaa(), 4)
+            .map_err(new_synthesis_error)?;
+        assert_eq!(vaa.signatures.len(), 4);
+        let quorum = vaa.check_quorum_by_address(cs, &guardian_set)?;
+        assert_eq!(quorum.get_value(), Some(true));
+        let below_quorum = crate::testing::fixtures::AccumulatorFixture {
+            num_signatures: 2,
+            ..fixture.clone()
+        };
+        let vaa = super::Vaa::<_>::new_from_slice_up_to(cs, &below_quorum.vaa(), 4)
+            .map_err(new_synthesis_error)?;
+        let no_quorum = vaa.check_quorum_by_address(cs, &guardian_set)?;
+        assert_eq!(no_quorum.get_value(), Some(false));
+        // Too few signatures allocated to reach the quorum.
+        let vaa = super::Vaa::<_>::new_from_slice_up_to(cs, &fixture.vaa(), 2)
+            .map_err(new_synthesis_error)?;
+        assert!(vaa.check_quorum_by_address(cs, &guardian_set).is_err());
+
+        // A guardian signing twice counts once.
+        let start = 1 + LEN_GUARDIAN_SET_INDEX + 1;
+        let mut duplicated = fixture.vaa();
+        duplicated.copy_within(
+            start..start + LEN_GUARDIAN_SIGNATURE,
+            start + LEN_GUARDIAN_SIGNATURE,
+        );
+        let vaa = super::Vaa::<_>::new_from_slice_up_to(cs, &duplicated, 3)
+            .map_err(new_synthesis_error)?;
+        let duplicated = vaa.check_quorum_by_address(cs, &guardian_set)?;
+        assert_eq!(duplicated.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_valid() -> Result<(), SynthesisError> {
+        use crate::gadgets::ethereum::Address;
+
+        let fixture = crate::testing::fixtures::AccumulatorFixture {
+            num_signatures: 2,
+            ..Default::default()
+        };
+        let guardian_set = |cs: &mut _, addresses: &[[u8; 20]]| {
+            addresses
+                .iter()
+                .map(|address| Address::from_address_witness(cs, address))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let cs = &mut create_test_constraint_system()?;
+        let guardians = guardian_set(cs, &fixture.guardian_set())?;
+        let vaa =
+            super::Vaa::<_>::new_from_slice(cs, &fixture.vaa(), 2).map_err(new_synthesis_error)?;
+        vaa.enforce_valid(cs, &guardians)?;
+        assert!(cs.is_satisfied());
+
+        // The second signer is not in the set.
+        let cs = &mut create_test_constraint_system()?;
+        let guardians = guardian_set(cs, &fixture.guardian_set()[..1])?;
+        let vaa =
+            super::Vaa::<_>::new_from_slice(cs, &fixture.vaa(), 2).map_err(new_synthesis_error)?;
+        vaa.enforce_valid(cs, &guardians)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_emitter() -> Result<(), SynthesisError> {
+        use crate::pyth::PythEnvironment;
+
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let cs = &mut create_test_constraint_system()?;
+        let vaa =
+            super::Vaa::<_>::new_from_slice(cs, &fixture.vaa(), 1).map_err(new_synthesis_error)?;
+        let mainnet = vaa.check_emitter(cs, PythEnvironment::Mainnet)?;
+        assert_eq!(mainnet.get_value(), Some(true));
+        let testnet = vaa.check_emitter(cs, PythEnvironment::Testnet)?;
+        assert_eq!(testnet.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+
+        let (chain, address) = (fixture.emitter_chain, fixture.emitter_address);
+        assert!(PythEnvironment::Mainnet
+            .check_emitter(chain, &address)
+            .is_ok());
+        assert!(PythEnvironment::Testnet
+            .check_emitter(chain, &address)
+            .is_err());
+        assert!(PythEnvironment::Mainnet
+            .check_emitter(chain + 1, &address)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_body_integers() -> Result<(), SynthesisError> {
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let cs = &mut create_test_constraint_system()?;
+        let body = super::VaaBody::<_>::new_from_slice(cs, &fixture.vaa_body())
+            .map_err(new_synthesis_error)?;
+        let timestamp = body.timestamp_u32(cs)?;
+        let sequence = body.sequence_u64(cs)?;
+        let slot = body.slot_u64(cs)?;
+        assert_eq!(timestamp.get_value(), Some(fixture.timestamp));
+        assert_eq!(sequence.get_value(), Some(fixture.sequence));
+        assert_eq!(slot.get_value(), Some(fixture.slot));
+
+        // Cached, no gates are added.
+        let n = cs.n();
+        assert_eq!(body.timestamp_u32(cs)?.get_value(), Some(fixture.timestamp));
+        assert_eq!(body.sequence_u64(cs)?.get_value(), Some(fixture.sequence));
+        assert_eq!(body.slot_u64(cs)?.get_value(), Some(fixture.slot));
+        assert_eq!(cs.n(), n);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_body_var_len() -> Result<(), SynthesisError> {
+        use advanced_circuit_component::{traits::CSAllocatable, vm::primitives::UInt32};
+
+        use super::VaaBodyVarLen;
+        use crate::keccak::keccak256;
+
+        let cs = &mut create_test_constraint_system()?;
+        let accumulator = crate::testing::fixtures::AccumulatorFixture::default().vaa_body();
+        let governance = crate::testing::fixtures::GuardianSetUpgradeFixture::default().vaa_body();
+        for raw in [&accumulator, &governance] {
+            let body =
+                VaaBodyVarLen::<_, 160>::new_from_slice(cs, raw).map_err(new_synthesis_error)?;
+            assert_eq!(body.payload_len.get_value(), Some(raw.len() as u32 - 51));
+            assert_eq!(body.check_padding(cs)?.get_value(), Some(true));
+            let digest = body.signed_digest(cs)?;
+            let expected = uint256_from_bytes_witness(cs, &keccak256(&keccak256(raw)))?;
+            assert_eq!(
+                UInt256::equals(cs, &digest, &expected)?.get_value(),
+                Some(true)
+            );
+        }
+        assert!(cs.is_satisfied());
+
+        // Longer than the payload can be.
+        let too_long = [0u8; 51 + 161];
+        assert!(matches!(
+            VaaBodyVarLen::<_, 160>::new_from_slice(cs, &too_long),
+            Err(OracleError::InvalidLength {
+                got: 212,
+                expected: 211,
+                ..
+            })
+        ));
+        // Shorter than the header.
+        let too_short = [0u8; 50];
+        assert!(matches!(
+            VaaBodyVarLen::<_, 160>::new_from_slice(cs, &too_short),
+            Err(OracleError::InvalidLength {
+                got: 50,
+                expected: 51,
+                ..
+            })
+        ));
+        let mut body = VaaBodyVarLen::<_, 160>::new_from_slice(cs, &accumulator)
+            .map_err(new_synthesis_error)?;
+        body.payload[100] = Byte::alloc_from_witness(cs, Some(1))?;
+        assert_eq!(body.check_padding(cs)?.get_value(), Some(false));
+        body.payload_len = UInt32::alloc_from_witness(cs, Some(161))?;
+        assert_eq!(body.check_payload_len(cs)?.get_value(), Some(false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vaa_allocation() -> Result<(), SynthesisError> {
+        use super::{AllocationMode, VaaAllocation};
+
+        let fixture = crate::testing::fixtures::AccumulatorFixture::default();
+        let cs = &mut create_test_constraint_system()?;
+        let num_inputs = cs.num_inputs;
+        let allocation = VaaAllocation {
+            emitter_address: AllocationMode::Constant,
+            sequence: AllocationMode::PublicInput,
+            root: AllocationMode::PublicInput,
+            ..Default::default()
+        };
+        let body =
+            super::VaaBody::<_>::new_from_slice_allocated(cs, &fixture.vaa_body(), &allocation)
+                .map_err(new_synthesis_error)?;
+        bytes_assert_eq(&body.to_bytes(), hex::encode(fixture.vaa_body()));
+        assert!(body.emitter_address.iter().all(|b| b.inner.is_constant()));
+        assert!(!body.sequence[0].inner.is_constant());
+        // One input for the sequence, two for the 20-byte root.
+        assert_eq!(cs.num_inputs, num_inputs + 3);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vaa_new_from_slice() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode(get_vaa()).unwrap();
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&data).unwrap();
+        let expected = super::Vaa::<_>::from_vaa_witness(cs, vaa, 13)?;
+        let sliced = super::Vaa::<_>::new_from_slice(cs, &data, 13)?;
+        bytes_assert_eq(
+            &sliced.body.to_bytes(),
+            hex::encode(Byte::get_byte_value_multiple(&expected.body.to_bytes()).unwrap()),
+        );
+        for (sliced, expected) in sliced.signatures.iter().zip(expected.signatures.iter()) {
+            assert_eq!(sliced.r.get_value(), expected.r.get_value());
+            assert_eq!(sliced.s.get_value(), expected.s.get_value());
+            assert_eq!(sliced.recid.get_value(), expected.recid.get_value());
+        }
+
+        assert!(matches!(
+            super::Vaa::<_>::new_from_slice(cs, &data, 14),
+            Err(OracleError::InsufficientSignatures { got: 13, need: 14 })
+        ));
+        assert!(matches!(
+            super::Vaa::<_>::new_from_slice(cs, &data[..100], 1),
+            Err(OracleError::VaaDecode(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_insufficient_signatures() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode(get_vaa()).unwrap();
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&data).unwrap();
+        let got = vaa.signatures.len();
+        let err = super::Vaa::<_>::from_vaa_witness(cs, vaa, got + 1).unwrap_err();
+        assert!(matches!(
+            err,
+            OracleError::InsufficientSignatures { got: g, need } if g == got && need == got + 1
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_vaa() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
@@ -381,6 +1777,129 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Debug, Clone)]
+    struct BodyFields {
+        timestamp: u32,
+        nonce: u32,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        sequence: u64,
+        consistency_level: u8,
+        slot: u64,
+        ring_size: u32,
+        root: [u8; 20],
+    }
+
+    impl BodyFields {
+        fn payload(&self) -> Vec<u8> {
+            let mut bytes = b"AUWV".to_vec();
+            bytes.push(super::PAYLOAD_TYPE);
+            bytes.extend(self.slot.to_be_bytes());
+            bytes.extend(self.ring_size.to_be_bytes());
+            bytes.extend(self.root);
+            bytes
+        }
+
+        fn body(&self) -> Vec<u8> {
+            let mut bytes = vec![];
+            bytes.extend(self.timestamp.to_be_bytes());
+            bytes.extend(self.nonce.to_be_bytes());
+            bytes.extend(self.emitter_chain.to_be_bytes());
+            bytes.extend(self.emitter_address);
+            bytes.extend(self.sequence.to_be_bytes());
+            bytes.push(self.consistency_level);
+            bytes.extend(self.payload());
+            bytes
+        }
+
+        /// A VAA without signatures carrying this body.
+        fn vaa(&self) -> Vec<u8> {
+            let mut bytes = vec![1u8, 0, 0, 0, 0, 0];
+            bytes.extend(self.body());
+            bytes
+        }
+    }
+
+    prop_compose! {
+        fn body_fields()(
+            timestamp in any::<u32>(),
+            nonce in any::<u32>(),
+            emitter_chain in any::<u16>(),
+            emitter_address in any::<[u8; 32]>(),
+            sequence in any::<u64>(),
+            consistency_level in any::<u8>(),
+            slot in any::<u64>(),
+            ring_size in any::<u32>(),
+            root in any::<[u8; 20]>(),
+        ) -> BodyFields {
+            BodyFields {
+                timestamp,
+                nonce,
+                emitter_chain,
+                emitter_address,
+                sequence,
+                consistency_level,
+                slot,
+                ring_size,
+                root,
+            }
+        }
+    }
+
+    fn check_payload_round_trip(fields: &BodyFields) -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = fields.payload();
+        let message = pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(&data).unwrap();
+        assert_eq!(&message.magic, b"AUWV");
+        {
+            let pythnet_sdk::wire::v1::WormholePayload::Merkle(root) =
+                pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(&data)
+                    .unwrap()
+                    .payload;
+            assert_eq!(root.slot, fields.slot);
+            assert_eq!(root.ring_size, fields.ring_size);
+            assert_eq!(root.root, fields.root);
+        }
+
+        let payload = super::VaaPayload::<_>::from_wormhole_message_witness(cs, message)?;
+        bytes_assert_eq(&payload.to_bytes(), hex::encode(&data));
+        Ok(())
+    }
+
+    fn check_body_round_trip(fields: &BodyFields) -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = fields.vaa();
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&data).unwrap();
+        let (_, body): (_, wormhole_sdk::vaa::Body<_>) = vaa.into();
+        assert_eq!(body.timestamp, fields.timestamp);
+        assert_eq!(body.nonce, fields.nonce);
+        assert_eq!(u16::from(body.emitter_chain), fields.emitter_chain);
+        assert_eq!(body.emitter_address.0, fields.emitter_address);
+        assert_eq!(body.sequence, fields.sequence);
+        assert_eq!(body.consistency_level, fields.consistency_level);
+        assert_eq!(serde_wormhole::to_vec(&body).unwrap(), fields.body());
+
+        let body = super::VaaBody::<_>::from_vaa_body_witness(cs, body)?;
+        bytes_assert_eq(&body.to_bytes(), hex::encode(fields.body()));
+        Ok(())
+    }
+
+    proptest! {
+        // Each case builds a constraint system with lookup tables, keep the number of cases small.
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_wormhole_payload_round_trip(fields in body_fields()) {
+            check_payload_round_trip(&fields).unwrap();
+        }
+
+        #[test]
+        fn test_wormhole_body_round_trip(fields in body_fields()) {
+            check_body_round_trip(&fields).unwrap();
+        }
+    }
+
     fn uint256_pubkey<E: Engine, CS: ConstraintSystem<E>>(
         cs: &mut CS,
         hex_str: &str,
@@ -0,0 +1,149 @@
+//! Python bindings, built with the `python` feature by [maturin](https://github.com/PyO3/maturin):
+//!
+//! ```sh
+//! maturin develop --release
+//! ```
+//!
+//! Tests are under `tests/python`, run with `pytest tests/python` once the module is installed.
+//!
+//! ```python
+//! import zklink_oracle
+//!
+//! witness = zklink_oracle.build_witness([hermes_base64_update], num_signatures=13)
+//! zklink_oracle.public_input(witness, num_prices=3)["commitment"]
+//! zklink_oracle.estimate_gates(num_vaas=1, num_signatures=13, num_prices=3)
+//! zklink_oracle.verify_proof(open("vk.key", "rb").read(), open("proof.bin", "rb").read())
+//! ```
+//!
+//! Witnesses are exchanged as the JSON of [`OracleWitness`].
+
+use std::path::PathBuf;
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::bn256::Bn256,
+    plonk::{
+        better_better_cs::{proof::Proof, setup::VerificationKey, verifier::verify},
+        commitments::transcript::keccak_transcript::RollingKeccakTranscript,
+    },
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+
+use crate::{
+    estimate::{self, CircuitGeometry},
+    pyth::{compute_public_input, decode, GuardianSetConfig, OracleWitness, PriceOracle},
+    utils::fr_to_hex,
+};
+
+/// Verification only depends on the gates declared by the circuit, which are the same for any
+/// number of prices.
+type OracleCircuit = PriceOracle<Bn256, 1>;
+
+fn py_err<E: ToString>(err: E) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Decode a hex or base64 encoded VAA or `PNAU` accumulator update into JSON.
+#[pyfunction]
+fn decode_update(input: &str) -> PyResult<String> {
+    let bytes = decode::decode_input(input).map_err(py_err)?;
+    let decoded = decode::decode(&bytes).map_err(py_err)?;
+    serde_json::to_string(&decoded).map_err(py_err)
+}
+
+/// Build the JSON witness of hex or base64 encoded accumulator updates, running the native checks
+/// of the circuit. The mainnet guardian set is used unless a JSON/TOML guardian set file is given.
+#[pyfunction]
+#[pyo3(signature = (accumulator_updates, num_signatures, guardian_set=None))]
+fn build_witness(
+    accumulator_updates: Vec<String>,
+    num_signatures: usize,
+    guardian_set: Option<PathBuf>,
+) -> PyResult<String> {
+    let guardian_set = match guardian_set {
+        Some(path) => GuardianSetConfig::load(&path).map_err(py_err)?,
+        None => GuardianSetConfig::mainnet(),
+    };
+    let data = accumulator_updates
+        .iter()
+        .map(|update| {
+            let bytes = decode::decode_input(update)?;
+            Ok(AccumulatorUpdateData::try_from_slice(&bytes)?)
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()
+        .map_err(py_err)?;
+    let num_prices = data
+        .first()
+        .map(|data| {
+            let pythnet_sdk::wire::v1::Proof::WormholeMerkle { updates, .. } = &data.proof;
+            updates.len()
+        })
+        .unwrap_or_default();
+    compute_public_input::<Bn256>(&data, &guardian_set.addresses, num_signatures, num_prices)
+        .map_err(py_err)?;
+    let witness = OracleWitness::new(&data, guardian_set.addresses, num_signatures);
+    serde_json::to_string(&witness).map_err(py_err)
+}
+
+/// Public input of a JSON witness as a dict of big-endian hex field elements.
+#[pyfunction]
+fn public_input<'py>(py: Python<'py>, witness: &str, num_prices: usize) -> PyResult<&'py PyDict> {
+    let witness: OracleWitness = serde_json::from_str(witness).map_err(py_err)?;
+    let data = witness.accumulator_update_data().map_err(py_err)?;
+    let (public_input_data, commitment) = compute_public_input::<Bn256>(
+        &data,
+        &witness.guardian_set,
+        witness.num_signature_to_verify,
+        num_prices,
+    )
+    .map_err(py_err)?;
+    let prices_summarize = &public_input_data.prices_summarize;
+    let dict = PyDict::new(py);
+    dict.set_item(
+        "guardian_set_hash",
+        fr_to_hex(&public_input_data.guardian_set_hash),
+    )?;
+    dict.set_item(
+        "earliest_publish_time",
+        fr_to_hex(&public_input_data.earliest_publish_time),
+    )?;
+    dict.set_item("prices_commitment", fr_to_hex(&prices_summarize.commitment))?;
+    dict.set_item("prices_num", fr_to_hex(&prices_summarize.num))?;
+    dict.set_item(
+        "prices_commitment_base_sum",
+        fr_to_hex(&prices_summarize.commitment_base_sum),
+    )?;
+    dict.set_item("commitment", fr_to_hex(&commitment))?;
+    Ok(dict)
+}
+
+/// Estimated number of gates of a circuit, see [`estimate::estimate_gates`].
+#[pyfunction]
+fn estimate_gates(num_vaas: usize, num_signatures: usize, num_prices: usize) -> usize {
+    estimate::estimate_gates(&CircuitGeometry::new(num_vaas, num_signatures, num_prices))
+}
+
+/// Smallest power of tau of a CRS fitting the circuit, see [`estimate::required_power_of_tau`].
+#[pyfunction]
+fn required_power_of_tau(num_vaas: usize, num_signatures: usize, num_prices: usize) -> u32 {
+    estimate::required_power_of_tau(&CircuitGeometry::new(num_vaas, num_signatures, num_prices))
+}
+
+/// Verify a serialized proof against a serialized verification key.
+#[pyfunction]
+fn verify_proof(verification_key: &[u8], proof: &[u8]) -> PyResult<bool> {
+    let vk = VerificationKey::<Bn256, OracleCircuit>::read(verification_key).map_err(py_err)?;
+    let proof = Proof::<Bn256, OracleCircuit>::read(proof).map_err(py_err)?;
+    verify::<_, _, RollingKeccakTranscript<_>>(&vk, &proof, None).map_err(py_err)
+}
+
+#[pymodule]
+fn zklink_oracle(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(decode_update, m)?)?;
+    m.add_function(wrap_pyfunction!(build_witness, m)?)?;
+    m.add_function(wrap_pyfunction!(public_input, m)?)?;
+    m.add_function(wrap_pyfunction!(estimate_gates, m)?)?;
+    m.add_function(wrap_pyfunction!(required_power_of_tau, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_proof, m)?)?;
+    Ok(())
+}
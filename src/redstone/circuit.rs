@@ -3,13 +3,24 @@ use advanced_circuit_component::{
     circuit_structures::byte::{Byte, IntoBytes},
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
     },
     traits::CSAllocatable,
-    vm::primitives::uint256::UInt256,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
 };
 
-use crate::gadgets::{ecdsa::Signature, ethereum::Address};
+use crate::{
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+    },
+    pyth::commitment::LEN_PACKED_PRICE,
+    utils::{div_to_i64, to_array},
+};
 use std::convert::TryInto;
 
 use super::witness::{DataPackage, DataPoint};
@@ -26,11 +37,11 @@ impl<E: Engine> AllocatedDataPoint<E> {
         witness: DataPoint,
     ) -> Result<Self, SynthesisError> {
         let data_feed_id = {
-            let bytes = witness.serialize_feed_id().try_into().unwrap();
+            let bytes = to_array("data feed id", &witness.serialize_feed_id())?;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
         let value = {
-            let bytes = witness.serialize_value().try_into().unwrap();
+            let bytes = to_array("data point value", &witness.serialize_value())?;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
 
@@ -84,6 +95,52 @@ impl<E: Engine, const NUM_SIGNATURES: usize> AllocatedSignedPrice<E, NUM_SIGNATU
         Ok(is_valid)
     }
 
+    /// Whether the data packages are signed by distinct members of `signers`, in any order.
+    pub fn check_by_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        let mut is_ok = vec![Boolean::constant(true)];
+        let mut recovered: Vec<Address<E>> = vec![];
+        for signed_data_package in self.signed_data_packages.iter() {
+            let (is_signer, address) =
+                signed_data_package.check_by_signers(cs, &params, &keccak, signers)?;
+            is_ok.push(is_signer);
+            for previous in recovered.iter() {
+                is_ok.push(address.equals(cs, previous)?.not());
+            }
+            recovered.push(address);
+        }
+        smart_and(cs, &is_ok)
+    }
+
+    /// The price packed as [`crate::pyth::commitment::pack_price`] packs pyth prices, and whether
+    /// it can be: the value keeps its 8 decimals and must fit an `i64`, the timestamp is truncated
+    /// to seconds.
+    pub fn packed_price<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, [Byte<E>; LEN_PACKED_PRICE]), SynthesisError> {
+        let value = self.price();
+        let mut is_ok = vec![];
+        for byte in value[..super::DEFAULT_NUM_VALUE_BS - 8].iter() {
+            is_ok.push(Num::equals(cs, &byte.inner, &Num::zero())?);
+        }
+        let (price_fits, price) = div_to_i64(cs, &value[super::DEFAULT_NUM_VALUE_BS - 8..], 1)?;
+        let (timestamp_fits, publish_time) =
+            div_to_i64(cs, &self.timestamp(), super::MILLISECONDS_PER_SECOND)?;
+        is_ok.extend([price_fits, timestamp_fits]);
+
+        let mut packed = [Byte::zero(); LEN_PACKED_PRICE];
+        packed[..32].copy_from_slice(&self.feed_id());
+        packed[32..40].copy_from_slice(&price.into_be_bytes(cs)?);
+        packed[40..].copy_from_slice(&publish_time.into_be_bytes(cs)?);
+        Ok((smart_and(cs, &is_ok)?, packed))
+    }
+
     pub fn timestamp(&self) -> [Byte<E>; super::TIMESTAMP_BS] {
         self.signed_data_packages[0].data_package.timestamp
     }
@@ -136,6 +193,30 @@ impl<E: Engine> AllocatedSignedDataPackage<E> {
         Ok(pubkey)
     }
 
+    /// Whether the package is signed by one of `signers`, with the recovered signer.
+    pub fn check_by_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        signers: &[Address<E>],
+    ) -> Result<(Boolean, Address<E>), SynthesisError> {
+        let msg_hash = {
+            let hash = self.data_package.keccak256_hash_with_params(cs, keccak)?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let (successful, address) = self
+            .signature
+            .ecrecover_address_with_params(cs, params, keccak, &msg_hash)?;
+        let address = Address::from_bytes(cs, &address)?;
+        let mut is_signer = vec![];
+        for signer in signers.iter() {
+            is_signer.push(signer.equals(cs, &address)?);
+        }
+        let is_signer = smart_or(cs, &is_signer)?;
+        Ok((Boolean::and(cs, &successful, &is_signer)?, address))
+    }
+
     pub fn check_by_address<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
@@ -171,18 +252,18 @@ impl<E: Engine> AllocatedDataPackage<E> {
         witness: DataPackage,
     ) -> Result<Self, SynthesisError> {
         let timestamp = {
-            let bytes = witness.serialize_timestamp().try_into().unwrap();
+            let bytes = to_array("timestamp", &witness.serialize_timestamp())?;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
         let data_points_count = {
-            let bytes = witness.serialize_data_points_count().try_into().unwrap();
+            let bytes = to_array("data points count", &witness.serialize_data_points_count())?;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
         let default_data_point_value_byte_size = {
-            let bytes = witness
-                .serialize_default_data_point_byte_size()
-                .try_into()
-                .unwrap();
+            let bytes = to_array(
+                "data point value byte size",
+                &witness.serialize_default_data_point_byte_size(),
+            )?;
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
 
@@ -213,10 +294,19 @@ impl<E: Engine> AllocatedDataPackage<E> {
     pub fn keccak256_hash<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let params = Keccak256Params::new(cs)?;
+        self.keccak256_hash_with_params(cs, &params)
+    }
+
+    /// See [`Self::keccak256_hash`].
+    pub fn keccak256_hash_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
     ) -> Result<[Byte<E>; 32], SynthesisError> {
         let bytes = self.serialize()?;
-        use crate::gadgets::keccak256::digest;
-        digest(cs, &bytes)
+        keccak256::digest_with_params(cs, params, &bytes)
     }
 }
 
@@ -224,13 +314,17 @@ impl<E: Engine> AllocatedDataPackage<E> {
 mod tests {
     use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
 
+    use advanced_circuit_component::circuit_structures::byte::Byte;
+
     use crate::{
         gadgets::ethereum::Address,
-        redstone::witness::{DataPackage, DataPoint},
+        redstone::witness::{
+            convert_string_to_bytes32, parse_payload, to_payload, DataPackage, DataPoint,
+        },
         utils::testing::{bytes_assert_eq, create_test_constraint_system},
     };
 
-    use super::AllocatedDataPackage;
+    use super::{AllocatedDataPackage, AllocatedSignedPrice};
 
     #[test]
     fn test_serialize_and_hash() -> Result<(), SynthesisError> {
@@ -281,4 +375,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_check_by_signers_and_pack() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let signer: [u8; 20] = hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signers = [[1u8; 20], signer]
+            .iter()
+            .map(|signer| Address::from_address_witness(cs, signer))
+            .collect::<Result<Vec<_>, _>>()?;
+        let payload = to_payload(&[(
+            DataPackage::new(
+                vec![DataPoint::new("AVAX", "36.2488073814028")],
+                1705311690000,
+            ),
+            hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap(),
+        )]);
+        let packages = parse_payload(&payload).map_err(SynthesisError::from)?;
+        let signed_price =
+            AllocatedSignedPrice::<_, 1>::from_witness(cs, packages.try_into().unwrap())?;
+        let is_valid = signed_price.check_by_signers(cs, &signers)?;
+        assert_eq!(is_valid.get_value(), Some(true));
+        let is_valid = signed_price.check_by_signers(cs, &signers[..1])?;
+        assert_eq!(is_valid.get_value(), Some(false));
+
+        let (is_ok, packed) = signed_price.packed_price(cs)?;
+        assert_eq!(is_ok.get_value(), Some(true));
+        let mut expected = convert_string_to_bytes32("AVAX").to_vec();
+        expected.extend(3_624_880_738i64.to_be_bytes());
+        expected.extend(1_705_311_690u64.to_be_bytes());
+        assert_eq!(Byte::get_byte_value_multiple(&packed), Some(expected));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 }
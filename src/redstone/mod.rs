@@ -51,6 +51,18 @@ pub const DATA_POINT_VALUE_BYTE_SIZE_BS: usize = 4;
 pub const DEFAULT_NUM_VALUE_BS: usize = 32;
 // Default precision for numeric values
 pub const DEFAULT_NUM_VALUE_DECIMALS: usize = 8;
+// Number of bytes reserved to store the data feed id
+pub const DATA_FEED_ID_BS: usize = 32;
+// Number of bytes of a signature, `r || s || v`
+pub const SIGNATURE_BS: usize = 65;
+// Number of bytes reserved to store the number of data packages in a payload
+pub const DATA_PACKAGES_COUNT_BS: usize = 2;
+// Number of bytes reserved to store the unsigned metadata byte size in a payload
+pub const UNSIGNED_METADATA_BYTE_SIZE_BS: usize = 3;
+// Marker ending a payload appended to calldata
+pub const REDSTONE_MARKER: [u8; 9] = [0x00, 0x00, 0x02, 0xed, 0x57, 0x01, 0x1e, 0x00, 0x00];
+// Divisor of millisecond timestamps into the seconds of pyth publish times
+pub const MILLISECONDS_PER_SECOND: u64 = 1000;
 
 pub struct PriceOracle<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICE: usize> {
     pub signed_prices_batch: Vec<[[(DataPackage, [u8; 65]); NUM_SIGNATURES_TO_VERIFY]; NUM_PRICE]>,
@@ -174,6 +186,13 @@ impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICES: usize>
     type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
 
     fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let _span = tracing::info_span!(
+            "redstone_oracle_synthesize",
+            num_batches = self.signed_prices_batch.len(),
+            num_signatures = NUM_SIGNATURES_TO_VERIFY,
+            num_prices = NUM_PRICES,
+        )
+        .entered();
         utils::add_bitwise_logic_and_range_table(cs)?;
         let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
         circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
@@ -1,12 +1,17 @@
 use std::{str::FromStr, usize};
 
 use bigdecimal::{BigDecimal, RoundingMode};
-use num_bigint::ToBigInt;
+use num_bigint::{BigUint, ToBigInt};
 
 use super::{
-    DATA_POINTS_COUNT_BS, DATA_POINT_VALUE_BYTE_SIZE_BS, DEFAULT_NUM_VALUE_BS,
-    DEFAULT_NUM_VALUE_DECIMALS, TIMESTAMP_BS,
+    DATA_FEED_ID_BS, DATA_PACKAGES_COUNT_BS, DATA_POINTS_COUNT_BS, DATA_POINT_VALUE_BYTE_SIZE_BS,
+    DEFAULT_NUM_VALUE_BS, DEFAULT_NUM_VALUE_DECIMALS, REDSTONE_MARKER, SIGNATURE_BS, TIMESTAMP_BS,
+    UNSIGNED_METADATA_BYTE_SIZE_BS,
 };
+use crate::error::OracleError;
+
+/// Data package with the signature of its signer, `r || s || v`.
+pub type SignedDataPackage = (DataPackage, [u8; SIGNATURE_BS]);
 
 #[derive(Clone, Debug)]
 pub struct DataPoint {
@@ -34,6 +39,33 @@ impl DataPoint {
             .to_vec()
     }
 
+    /// Parse a serialized data point with a value of the default byte size.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OracleError> {
+        if bytes.len() != DATA_FEED_ID_BS + DEFAULT_NUM_VALUE_BS {
+            return Err(OracleError::InvalidLength {
+                what: "data point",
+                got: bytes.len(),
+                expected: DATA_FEED_ID_BS + DEFAULT_NUM_VALUE_BS,
+            });
+        }
+        let (feed_id, value) = bytes.split_at(DATA_FEED_ID_BS);
+        let len = feed_id
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(feed_id.len());
+        let feed_id = String::from_utf8(feed_id[..len].to_vec())
+            .map_err(|e| OracleError::ReportDecode(format!("invalid data feed id: {}", e)))?;
+        let value = BigUint::from_bytes_be(value);
+        let unit = BigUint::from(10u32).pow(DEFAULT_NUM_VALUE_DECIMALS as u32);
+        let value = format!(
+            "{}.{:0>width$}",
+            &value / &unit,
+            (&value % &unit).to_string(),
+            width = DEFAULT_NUM_VALUE_DECIMALS
+        );
+        Ok(Self::new(feed_id, value))
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let mut bytes = vec![];
         let bytes_data_feed_id = self.serialize_feed_id();
@@ -105,6 +137,102 @@ impl DataPackage {
     }
 }
 
+/// Parse the signed data packages of a payload appended to calldata, in their order.
+///
+/// The payload is read from the end: the RedStone marker, the unsigned metadata and its byte size,
+/// the number of data packages, then each data package ending with its signature. Whatever
+/// precedes the payload is ignored.
+pub fn parse_payload(payload: &[u8]) -> Result<Vec<SignedDataPackage>, OracleError> {
+    let mut rest = payload;
+    if take_back(&mut rest, REDSTONE_MARKER.len(), "redstone marker")? != REDSTONE_MARKER {
+        return Err(OracleError::ReportDecode(
+            "payload does not end with the redstone marker".to_string(),
+        ));
+    }
+    let unsigned_metadata_byte_size = be_number(take_back(
+        &mut rest,
+        UNSIGNED_METADATA_BYTE_SIZE_BS,
+        "unsigned metadata byte size",
+    )?);
+    take_back(&mut rest, unsigned_metadata_byte_size, "unsigned metadata")?;
+    let data_packages_count = be_number(take_back(
+        &mut rest,
+        DATA_PACKAGES_COUNT_BS,
+        "data packages count",
+    )?);
+
+    let mut packages = vec![];
+    for _ in 0..data_packages_count {
+        let signature = take_back(&mut rest, SIGNATURE_BS, "signature")?
+            .try_into()
+            .unwrap();
+        let data_points_count = be_number(take_back(
+            &mut rest,
+            DATA_POINTS_COUNT_BS,
+            "data points count",
+        )?);
+        let value_byte_size = be_number(take_back(
+            &mut rest,
+            DATA_POINT_VALUE_BYTE_SIZE_BS,
+            "data point value byte size",
+        )?);
+        if value_byte_size != DEFAULT_NUM_VALUE_BS {
+            return Err(OracleError::InvalidLength {
+                what: "data point value",
+                got: value_byte_size,
+                expected: DEFAULT_NUM_VALUE_BS,
+            });
+        }
+        let timestamp = be_number(take_back(&mut rest, TIMESTAMP_BS, "timestamp")?) as u64;
+        let data_point_bs = DATA_FEED_ID_BS + value_byte_size;
+        let data_points = take_back(&mut rest, data_points_count * data_point_bs, "data points")?
+            .chunks(data_point_bs)
+            .map(DataPoint::from_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        packages.push((DataPackage::new(data_points, timestamp), signature));
+    }
+    packages.reverse();
+    Ok(packages)
+}
+
+/// Payload of `packages` without unsigned metadata, as parsed by [`parse_payload`].
+pub fn to_payload(packages: &[SignedDataPackage]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (data_package, signature) in packages.iter() {
+        bytes.extend(data_package.serialize());
+        bytes.extend(signature);
+    }
+    bytes.extend(convert_number_to_bytes::<_, DATA_PACKAGES_COUNT_BS>(
+        packages.len(),
+        0,
+    ));
+    bytes.extend([0u8; UNSIGNED_METADATA_BYTE_SIZE_BS]);
+    bytes.extend(REDSTONE_MARKER);
+    bytes
+}
+
+/// Split the last `len` bytes off `bytes`.
+fn take_back<'a>(
+    bytes: &mut &'a [u8],
+    len: usize,
+    what: &'static str,
+) -> Result<&'a [u8], OracleError> {
+    if bytes.len() < len {
+        return Err(OracleError::InvalidLength {
+            what,
+            got: bytes.len(),
+            expected: len,
+        });
+    }
+    let (rest, taken) = bytes.split_at(bytes.len() - len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+fn be_number(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, b| (acc << 8) | *b as usize)
+}
+
 type Bytes32 = [u8; 32];
 
 pub fn convert_string_to_bytes32<T: ToString>(str: T) -> Bytes32 {
@@ -211,6 +339,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_payload() -> anyhow::Result<()> {
+        use crate::{
+            error::OracleError,
+            redstone::witness::{parse_payload, to_payload},
+        };
+
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
+        let packages = vec![
+            (
+                DataPackage::new(
+                    vec![DataPoint::new("AVAX", "36.2488073814028")],
+                    1705311690000,
+                ),
+                signature,
+            ),
+            (
+                DataPackage::new(
+                    vec![
+                        DataPoint::new("BTC", "20000"),
+                        DataPoint::new("ETH", "1000"),
+                    ],
+                    1654353400000,
+                ),
+                [1u8; 65],
+            ),
+        ];
+        let mut calldata = hex::decode("a9059cbb").unwrap();
+        calldata.extend(to_payload(&packages));
+
+        let parsed = parse_payload(&calldata)?;
+        assert_eq!(parsed.len(), packages.len());
+        for ((parsed, parsed_signature), (package, signature)) in parsed.iter().zip(packages.iter())
+        {
+            assert_eq!(parsed.serialize(), package.serialize());
+            assert_eq!(parsed.timestamp, package.timestamp);
+            assert_eq!(parsed_signature, signature);
+        }
+        assert_eq!(parsed[0].0.data_points[0].data_feed_id, "AVAX");
+
+        let mut unmarked = calldata.clone();
+        *unmarked.last_mut().unwrap() = 1;
+        assert!(matches!(
+            parse_payload(&unmarked),
+            Err(OracleError::ReportDecode(_))
+        ));
+        assert!(matches!(
+            parse_payload(&calldata[calldata.len() - 20..]),
+            Err(OracleError::InvalidLength { .. })
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_decimal_value() -> anyhow::Result<()> {
         use secp256k1::{
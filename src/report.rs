@@ -0,0 +1,317 @@
+//! Machine-readable constraint budget of the pyth [`PriceOracle`] circuit.
+//!
+//! Unlike [`crate::estimate`], the report synthesizes the circuit, so it is exact but takes as long
+//! as a synthesis. The JSON layout (format `zklink-oracle-budget/v1`) is:
+//!
+//! ```text
+//! {
+//!   "format": "zklink-oracle-budget/v1",
+//!   "num_vaas": 1, "num_signatures": 13, "num_prices": 3,
+//!   "total_gates": 12345678,        // rows of the trace, public input rows included
+//!   "num_inputs": 1,
+//!   "num_aux": 2345678,             // witness variables
+//!   "power_of_tau": 24,             // smallest CRS holding the circuit
+//!   "estimated_gates": 12000000,    // model of `crate::estimate`, to spot a stale model
+//!   "sections": [                   // gates per part of the circuit, summed over all VAAs
+//!     { "name": "verify_vaa", "gates": 11000000 }
+//!   ],
+//!   "lookups": [                    // rows using each lookup table
+//!     { "name": "Range check table", "width": 3, "size": 65536, "rows": 4321 }
+//!   ]
+//! }
+//! ```
+
+use std::{cell::RefCell, io::Write};
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::Engine,
+    plonk::better_better_cs::{
+        cs::{
+            Circuit, ConstraintSystem, PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            TrivialAssembly,
+        },
+        gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+    },
+    SynthesisError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::OracleConfig,
+    estimate,
+    gadgets::ethereum::Address,
+    pyth::{PriceOracle, Vaa},
+};
+
+pub const FORMAT: &str = "zklink-oracle-budget/v1";
+
+thread_local! {
+    /// Gates per section of the synthesis running on this thread, `None` unless reporting.
+    static SECTIONS: RefCell<Option<Vec<SectionCost>>> = RefCell::new(None);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintReport {
+    pub format: String,
+    pub num_vaas: usize,
+    pub num_signatures: usize,
+    pub num_prices: usize,
+    pub total_gates: usize,
+    pub num_inputs: usize,
+    pub num_aux: usize,
+    pub power_of_tau: u32,
+    pub estimated_gates: usize,
+    pub sections: Vec<SectionCost>,
+    pub lookups: Vec<LookupCost>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionCost {
+    pub name: String,
+    pub gates: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupCost {
+    pub name: String,
+    pub width: usize,
+    pub size: usize,
+    pub rows: usize,
+}
+
+/// Measured cost of verifying the signatures of one VAA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaaCost {
+    pub num_signatures: usize,
+    pub gates: usize,
+    pub num_aux: usize,
+}
+
+/// Run `f` and report the gates it adds under `name`. Sections must not nest.
+pub(crate) fn section<E, CS, T, F>(
+    cs: &mut CS,
+    name: &'static str,
+    f: F,
+) -> Result<T, SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+    F: FnOnce(&mut CS) -> Result<T, SynthesisError>,
+{
+    let start = cs.get_current_aux_gate_number();
+    let result = f(cs)?;
+    let gates = cs.get_current_aux_gate_number() - start;
+    SECTIONS.with(|sections| {
+        if let Some(sections) = sections.borrow_mut().as_mut() {
+            match sections.iter_mut().find(|s| s.name == name) {
+                Some(section) => section.gates += gates,
+                None => sections.push(SectionCost {
+                    name: name.to_string(),
+                    gates,
+                }),
+            }
+        }
+    });
+    Ok(result)
+}
+
+/// Records sections on this thread while alive, restoring the previous state when dropped.
+struct Recording {
+    previous: Option<Vec<SectionCost>>,
+}
+
+impl Recording {
+    fn start() -> Self {
+        Self {
+            previous: SECTIONS.with(|sections| sections.replace(Some(vec![]))),
+        }
+    }
+
+    fn finish(self) -> Vec<SectionCost> {
+        SECTIONS.with(|sections| sections.borrow_mut().take().unwrap_or_default())
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        SECTIONS.with(|sections| *sections.borrow_mut() = self.previous.take());
+    }
+}
+
+impl ConstraintReport {
+    /// Synthesize `circuit` and report its cost.
+    pub fn new<E: Engine, const NUM_PRICES: usize>(
+        circuit: &PriceOracle<E, NUM_PRICES>,
+    ) -> Result<Self, SynthesisError> {
+        let mut cs = TrivialAssembly::<
+            E,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        let recording = Recording::start();
+        circuit.synthesize(&mut cs)?;
+        let sections = recording.finish();
+
+        let geometry = estimate::CircuitGeometry {
+            guardian_set_size: circuit.guardian_set.len(),
+            ..estimate::CircuitGeometry::new(
+                circuit.accumulator_update_data.len(),
+                circuit.num_signature_to_verify,
+                NUM_PRICES,
+            )
+        };
+        let total_gates = cs.n();
+        let lookups = cs
+            .tables
+            .iter()
+            .map(|table| {
+                let name = table.functional_name();
+                let rows = cs
+                    .table_selectors
+                    .get(&name)
+                    .map_or(0, |selector| selector.iter().filter(|used| *used).count());
+                LookupCost {
+                    name,
+                    width: table.width(),
+                    size: table.size(),
+                    rows,
+                }
+            })
+            .collect();
+        Ok(Self {
+            format: FORMAT.to_string(),
+            num_vaas: geometry.num_vaas,
+            num_signatures: geometry.num_signatures,
+            num_prices: NUM_PRICES,
+            total_gates,
+            num_inputs: cs.num_inputs,
+            num_aux: cs.num_aux,
+            // The domain must be strictly larger than the number of gates.
+            power_of_tau: (total_gates + 1).next_power_of_two().trailing_zeros(),
+            estimated_gates: estimate::estimate_gates(&geometry),
+            sections,
+            lookups,
+        })
+    }
+
+    /// Report the circuit of `config`, whose number of prices must be `NUM_PRICES`.
+    pub fn from_config<E: Engine, const NUM_PRICES: usize>(
+        config: &OracleConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let (circuit, _) = config.circuit_builder::<E, NUM_PRICES>()?.build()?;
+        Ok(Self::new(&circuit)?)
+    }
+
+    /// Fail if the circuit exceeds `max_gates`, naming its most expensive sections.
+    pub fn check_budget(&self, max_gates: usize) -> Result<(), anyhow::Error> {
+        if self.total_gates <= max_gates {
+            return Ok(());
+        }
+        let mut sections = self.sections.clone();
+        sections.sort_by(|a, b| b.gates.cmp(&a.gates));
+        anyhow::bail!(
+            "{} gates over budget {}, by section: {}",
+            self.total_gates,
+            max_gates,
+            sections
+                .iter()
+                .map(|s| format!("{} {}", s.name, s.gates))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+/// Synthesize the check of `vaa` against `guardian_set` for each of `signature_counts` and report
+/// the cost of each, so that a deployment can pick the cheapest number of signatures meeting its
+/// quorum. The gates of the lookup tables, shared with the rest of the circuit, are excluded.
+pub fn measure_vaa_costs<E: Engine>(
+    vaa: &[u8],
+    guardian_set: &[[u8; 20]],
+    signature_counts: &[usize],
+) -> Result<Vec<VaaCost>, anyhow::Error> {
+    signature_counts
+        .iter()
+        .map(|&num_signatures| {
+            let mut cs = TrivialAssembly::<
+                E,
+                PlonkCsWidth4WithNextStepAndCustomGatesParams,
+                SelectorOptimizedWidth4MainGateWithDNext,
+            >::new();
+            crate::utils::add_bitwise_logic_and_range_table(&mut cs)?;
+            let (gates, num_aux) = (cs.n(), cs.num_aux);
+            let guardian_set = guardian_set
+                .iter()
+                .map(|address| Address::from_address_witness(&mut cs, address))
+                .collect::<Result<Vec<_>, _>>()?;
+            let vaa = Vaa::new_from_slice(&mut cs, vaa, num_signatures)?;
+            vaa.check_by_address(&mut cs, &guardian_set)?;
+            tracing::debug!(num_signatures, gates = cs.n() - gates, "measured VAA cost");
+            Ok(VaaCost {
+                num_signatures,
+                gates: cs.n() - gates,
+                num_aux: cs.num_aux - num_aux,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::{measure_vaa_costs, ConstraintReport};
+    use crate::{pyth::PriceOracle, testing::fixtures::AccumulatorFixture};
+
+    #[test]
+    fn test_measure_vaa_costs() -> anyhow::Result<()> {
+        let fixture = AccumulatorFixture::default();
+        let costs = measure_vaa_costs::<Bn256>(&fixture.vaa(), &fixture.guardian_set(), &[1, 2])?;
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].num_signatures, 1);
+        assert!(costs[0].gates < costs[1].gates);
+        assert!(costs[0].num_aux < costs[1].num_aux);
+
+        let err = measure_vaa_costs::<Bn256>(&fixture.vaa(), &fixture.guardian_set(), &[20]);
+        assert!(err.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_report() -> anyhow::Result<()> {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let report = ConstraintReport::new(&oracle)?;
+        assert_eq!(report.num_prices, 3);
+        let names = report
+            .sections
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "setup",
+                "allocate_witness",
+                "verify_vaa",
+                "commit_prices",
+                "check_publish_time",
+                "public_input"
+            ]
+        );
+        let sections_gates = report.sections.iter().map(|s| s.gates).sum::<usize>();
+        assert!(sections_gates <= report.total_gates);
+        assert!(report.lookups.iter().any(|l| l.rows > 0));
+
+        report.check_budget(report.total_gates)?;
+        let err = report.check_budget(report.total_gates - 1).unwrap_err();
+        assert!(err.to_string().contains("verify_vaa"));
+
+        let json = serde_json::to_string(&report)?;
+        assert_eq!(serde_json::from_str::<ConstraintReport>(&json)?, report);
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Local persistent cache of fetched accumulator updates and proofs.
+//!
+//! Accumulator updates are stored by the digest of their VAA body (the hash signed by guardians) and
+//! indexed by `(emitter chain, emitter address, sequence)`, so a restarted service does not refetch
+//! them. Updates of the same VAA carrying different price messages are merged, their proofs being
+//! against the same signed root. Proofs are stored by the digest of their witness and number of prices, so repeated proof
+//! requests are served from disk. The storage backend is any [`KvStore`], [`SledStore`] with the
+//! `sled` feature.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use sha3::{Digest, Keccak256};
+
+use crate::pyth::{decode::decode_vaa, AccumulatorUpdateWitness, OracleWitness};
+
+const UPDATE_PREFIX: &[u8] = b"update/";
+const SEQUENCE_PREFIX: &[u8] = b"sequence/";
+const PROOF_PREFIX: &[u8] = b"proof/";
+
+/// Byte key-value storage backend.
+pub trait KvStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, anyhow::Error>;
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), anyhow::Error>;
+}
+
+/// Non-persistent [`KvStore`], for tests and one-shot runs.
+#[derive(Debug, Default)]
+pub struct MemoryStore(Mutex<BTreeMap<Vec<u8>, Vec<u8>>>);
+
+impl KvStore for MemoryStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), anyhow::Error> {
+        self.0.lock().unwrap().insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+#[derive(Debug, Clone)]
+pub struct SledStore(sled::Db);
+
+#[cfg(feature = "sled")]
+impl SledStore {
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Ok(Self(sled::open(path)?))
+    }
+}
+
+#[cfg(feature = "sled")]
+impl KvStore for SledStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        Ok(self.0.get(key)?.map(|v| v.to_vec()))
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), anyhow::Error> {
+        self.0.insert(key, value)?;
+        self.0.flush()?;
+        Ok(())
+    }
+}
+
+/// Accumulator updates and proofs on top of a [`KvStore`].
+#[derive(Debug)]
+pub struct UpdateCache<S> {
+    store: S,
+}
+
+impl<S: KvStore> UpdateCache<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Store `update`, merged with the price messages already stored for its VAA body, returning
+    /// the digest of the body.
+    pub fn insert_update(
+        &self,
+        update: &AccumulatorUpdateWitness,
+    ) -> Result<[u8; 32], anyhow::Error> {
+        let vaa = decode_vaa(&update.vaa)?;
+        let digest: [u8; 32] = hex::decode(&vaa.digest)?.try_into().unwrap();
+        let emitter: [u8; 32] = hex::decode(&vaa.emitter_address)?.try_into().unwrap();
+        let merged = match self.update_by_digest(&digest)? {
+            Some(mut stored) => {
+                for price_update in update.updates.iter() {
+                    if !stored
+                        .updates
+                        .iter()
+                        .any(|stored| stored.message == price_update.message)
+                    {
+                        stored.updates.push(price_update.clone());
+                    }
+                }
+                stored
+            }
+            None => update.clone(),
+        };
+        self.store
+            .insert(&update_key(&digest), &merged.to_bytes()?)?;
+        self.store.insert(
+            &sequence_key(vaa.emitter_chain, &emitter, vaa.sequence),
+            &digest,
+        )?;
+        Ok(digest)
+    }
+
+    /// Accumulator update whose VAA body has `digest`.
+    pub fn update_by_digest(
+        &self,
+        digest: &[u8; 32],
+    ) -> Result<Option<AccumulatorUpdateWitness>, anyhow::Error> {
+        self.store
+            .get(&update_key(digest))?
+            .map(|bytes| {
+                let data = pythnet_sdk::wire::v1::AccumulatorUpdateData::try_from_slice(&bytes)?;
+                Ok(AccumulatorUpdateWitness::from_accumulator_update_data(
+                    &data,
+                ))
+            })
+            .transpose()
+    }
+
+    /// Accumulator update of the VAA `sequence` of `emitter` on `emitter_chain`.
+    pub fn update_by_sequence(
+        &self,
+        emitter_chain: u16,
+        emitter: &[u8; 32],
+        sequence: u64,
+    ) -> Result<Option<AccumulatorUpdateWitness>, anyhow::Error> {
+        match self
+            .store
+            .get(&sequence_key(emitter_chain, emitter, sequence))?
+        {
+            Some(digest) => {
+                let digest = digest
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("corrupted sequence index"))?;
+                self.update_by_digest(&digest)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert_proof(
+        &self,
+        witness: &OracleWitness,
+        num_prices: usize,
+        proof: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        self.store.insert(&proof_key(witness, num_prices)?, proof)
+    }
+
+    /// Proof of `witness` in a circuit of `num_prices` prices, as given to [`Self::insert_proof`].
+    pub fn proof(
+        &self,
+        witness: &OracleWitness,
+        num_prices: usize,
+    ) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        self.store.get(&proof_key(witness, num_prices)?)
+    }
+}
+
+fn update_key(digest: &[u8; 32]) -> Vec<u8> {
+    [UPDATE_PREFIX, digest].concat()
+}
+
+fn sequence_key(emitter_chain: u16, emitter: &[u8; 32], sequence: u64) -> Vec<u8> {
+    [
+        SEQUENCE_PREFIX,
+        &emitter_chain.to_be_bytes(),
+        emitter,
+        &sequence.to_be_bytes(),
+    ]
+    .concat()
+}
+
+fn proof_key(witness: &OracleWitness, num_prices: usize) -> Result<Vec<u8>, anyhow::Error> {
+    let mut hasher = Keccak256::new();
+    hasher.update((num_prices as u64).to_be_bytes());
+    hasher.update((witness.num_signature_to_verify as u64).to_be_bytes());
+    for address in witness.guardian_set.iter() {
+        hasher.update(address);
+    }
+    for update in witness.accumulator_updates.iter() {
+        let bytes = update.to_bytes()?;
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    }
+    Ok([PROOF_PREFIX, &hasher.finalize()[..]].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::{MemoryStore, UpdateCache};
+    use crate::pyth::{decode::decode_vaa, AccumulatorUpdateWitness, OracleWitness, PriceOracle};
+
+    #[test]
+    fn test_update_cache() -> anyhow::Result<()> {
+        let cache = UpdateCache::new(MemoryStore::default());
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let witness = OracleWitness::from(&oracle);
+        let update = &witness.accumulator_updates[0];
+
+        let digest = cache.insert_update(update)?;
+        assert_eq!(cache.update_by_digest(&digest)?.as_ref(), Some(update));
+        let vaa = decode_vaa(&update.vaa)?;
+        let emitter = hex::decode(&vaa.emitter_address)?.try_into().unwrap();
+        assert_eq!(
+            cache
+                .update_by_sequence(vaa.emitter_chain, &emitter, vaa.sequence)?
+                .as_ref(),
+            Some(update)
+        );
+        assert!(cache
+            .update_by_sequence(vaa.emitter_chain, &emitter, vaa.sequence + 1)?
+            .is_none());
+
+        // Updates of the same VAA with other price messages are merged, not overwritten.
+        let cache = UpdateCache::new(MemoryStore::default());
+        let mut first = update.clone();
+        let second = first.updates.split_off(1);
+        cache.insert_update(&first)?;
+        cache.insert_update(&AccumulatorUpdateWitness::new(update.vaa.clone(), second))?;
+        cache.insert_update(&first)?;
+        assert_eq!(cache.update_by_digest(&digest)?.as_ref(), Some(update));
+
+        assert!(cache.proof(&witness, 3)?.is_none());
+        cache.insert_proof(&witness, 3, &[1, 2, 3])?;
+        assert_eq!(cache.proof(&witness, 3)?, Some(vec![1, 2, 3]));
+        assert!(cache.proof(&witness, 2)?.is_none());
+        Ok(())
+    }
+}
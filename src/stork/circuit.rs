@@ -0,0 +1,283 @@
+use advanced_circuit_component::{
+    circuit_structures::byte::{Byte, IntoBytes as _},
+    franklin_crypto::{
+        bellman::{pairing::Engine, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    traits::CSAllocatable,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
+};
+
+use super::{
+    witness::SignedPriceUpdate, ETHEREUM_SIGNED_MESSAGE_PREFIX, LEN_MESSAGE,
+    NANOSECONDS_PER_SECOND, QUANTIZED_DECIMALS,
+};
+use crate::{
+    attestation::{AttestedPrice, OracleAttestation},
+    error::OracleError,
+    gadgets::{
+        ecdsa::{Secp256k1Params, Signature},
+        ethereum::Address,
+        keccak256::{self, Keccak256Params},
+    },
+    pyth::commitment::{COMMITTED_DECIMALS, LEN_PACKED_PRICE},
+    utils::{div_to_i64, num_from_be_bytes},
+};
+
+/// Stork price update with the signature of its publisher.
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedPriceUpdate<E: Engine> {
+    pub message: [Byte<E>; LEN_MESSAGE],
+    pub signature: Signature<E>,
+}
+
+impl<E: Engine> AllocatedSignedPriceUpdate<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedPriceUpdate,
+    ) -> Result<Self, OracleError> {
+        let message = CSAllocatable::alloc_from_witness(cs, Some(witness.message()?))?;
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self { message, signature })
+    }
+
+    fn word(&self, index: usize) -> &[Byte<E>] {
+        &self.message[20 + index * 32..52 + index * 32]
+    }
+
+    pub fn stork_pub_key(&self) -> [Byte<E>; 20] {
+        self.message[..20].try_into().unwrap()
+    }
+
+    pub fn asset_id(&self) -> [Byte<E>; 32] {
+        self.word(0).try_into().unwrap()
+    }
+
+    /// Timestamp in nanoseconds.
+    pub fn timestamp(&self) -> [Byte<E>; 32] {
+        self.word(1).try_into().unwrap()
+    }
+
+    pub fn quantized_value(&self) -> [Byte<E>; 32] {
+        self.word(2).try_into().unwrap()
+    }
+
+    /// keccak256 of the ethereum signed message of the keccak256 of the message.
+    pub fn signed_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let params = Keccak256Params::new(cs)?;
+        self.signed_hash_with_params(cs, &params)
+    }
+
+    /// See [`Self::signed_hash`].
+    pub fn signed_hash_with_params<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Keccak256Params<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = ETHEREUM_SIGNED_MESSAGE_PREFIX
+            .iter()
+            .map(|b| Byte::constant(*b))
+            .collect::<Vec<_>>();
+        bytes.extend(keccak256::digest_with_params(cs, params, &self.message)?);
+        keccak256::digest_with_params(cs, params, &bytes)
+    }
+
+    /// Whether the update is signed by the publisher key it names, and that key is one of
+    /// `publishers`.
+    pub fn check_by_publishers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        params: &Secp256k1Params<E>,
+        keccak: &Keccak256Params<E>,
+        publishers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let msg_hash = {
+            let hash = self.signed_hash_with_params(cs, keccak)?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+        let (successful, address) = self
+            .signature
+            .ecrecover_address_with_params(cs, params, keccak, &msg_hash)?;
+        let address = Address::from_bytes(cs, &address)?;
+        let stork_pub_key = Address::from_bytes(cs, &self.stork_pub_key())?;
+        let mut is_publisher = vec![];
+        for publisher in publishers.iter() {
+            is_publisher.push(publisher.equals(cs, &address)?);
+        }
+        let is_publisher = smart_or(cs, &is_publisher)?;
+        let is_named = address.equals(cs, &stork_pub_key)?;
+        smart_and(cs, &[successful, is_publisher, is_named])
+    }
+
+    /// The price packed as [`crate::pyth::commitment::pack_price`] packs pyth prices, and whether it
+    /// can be: the quantized value is truncated to [`COMMITTED_DECIMALS`] and must be non-negative
+    /// and fit an `i64`, the timestamp is truncated to seconds.
+    pub fn packed_price<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, [Byte<E>; LEN_PACKED_PRICE]), SynthesisError> {
+        // A negative value has its high bytes set.
+        let value = self.quantized_value();
+        let is_value_short = {
+            let high = num_from_be_bytes(cs, &value[..8])?;
+            Num::equals(cs, &high, &Num::zero())?
+        };
+        let (is_price_ok, price) = div_to_i64(
+            cs,
+            &value[8..],
+            10u64.pow(QUANTIZED_DECIMALS - COMMITTED_DECIMALS),
+        )?;
+        let timestamp = self.timestamp();
+        let is_timestamp_short = {
+            let high = num_from_be_bytes(cs, &timestamp[..24])?;
+            Num::equals(cs, &high, &Num::zero())?
+        };
+        let (is_timestamp_ok, publish_time) =
+            div_to_i64(cs, &timestamp[24..], NANOSECONDS_PER_SECOND)?;
+        let is_ok = smart_and(
+            cs,
+            &[
+                is_value_short,
+                is_price_ok,
+                is_timestamp_short,
+                is_timestamp_ok,
+            ],
+        )?;
+
+        let mut packed = [Byte::zero(); LEN_PACKED_PRICE];
+        packed[..32].copy_from_slice(&self.asset_id());
+        packed[32..40].copy_from_slice(&price.into_be_bytes(cs)?);
+        packed[40..].copy_from_slice(&publish_time.into_be_bytes(cs)?);
+        Ok((is_ok, packed))
+    }
+}
+
+impl<E: Engine> OracleAttestation<E> for AllocatedSignedPriceUpdate<E> {
+    type Witness = SignedPriceUpdate;
+
+    fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Self::Witness,
+    ) -> Result<Self, OracleError> {
+        AllocatedSignedPriceUpdate::from_witness(cs, witness)
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        self.check_by_publishers(cs, &params, &keccak, signers)
+    }
+
+    fn prices<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<AttestedPrice<E>>), SynthesisError> {
+        let (is_ok, packed) = self.packed_price(cs)?;
+        Ok((is_ok, vec![AttestedPrice::from_packed(&packed)]))
+    }
+
+    fn packed_prices(witness: &Self::Witness) -> Result<Vec<[u8; LEN_PACKED_PRICE]>, OracleError> {
+        let packed = witness.packed_price().ok_or_else(|| {
+            OracleError::ReportDecode("stork price cannot be committed".to_string())
+        })?;
+        Ok(vec![packed])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::AllocatedSignedPriceUpdate;
+    use crate::{
+        gadgets::{ecdsa::Secp256k1Params, ethereum::Address, keccak256::Keccak256Params},
+        pyth::commitment::{compute_packed_price_commitment, packed_price_commitment},
+        testing::fixtures::{guardian_address, guardian_key, StorkFixture},
+        utils::testing::create_test_constraint_system,
+    };
+
+    #[test]
+    fn test_signed_price_update() -> Result<(), SynthesisError> {
+        let fixture = StorkFixture::default();
+        let witness = fixture.update();
+        let cs = &mut create_test_constraint_system()?;
+        let params = Secp256k1Params::new(cs);
+        let keccak = Keccak256Params::new(cs)?;
+        let publisher = Address::from_address_witness(cs, &fixture.publisher_address())?;
+        let other = Address::from_address_witness(cs, &guardian_address(&guardian_key(1)))?;
+        let update = AllocatedSignedPriceUpdate::from_witness(cs, &witness)?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&update.signed_hash(cs)?),
+            Some(witness.signed_hash()?.to_vec())
+        );
+        assert_eq!(
+            update
+                .check_by_publishers(cs, &params, &keccak, &[other.clone(), publisher.clone()])?
+                .get_value(),
+            Some(true)
+        );
+        assert_eq!(
+            update
+                .check_by_publishers(cs, &params, &keccak, &[other.clone()])?
+                .get_value(),
+            Some(false)
+        );
+
+        let (is_ok, packed) = update.packed_price(cs)?;
+        assert_eq!(is_ok.get_value(), Some(true));
+        let expected = witness.packed_price().unwrap();
+        assert_eq!(
+            Byte::get_byte_value_multiple(&packed),
+            Some(expected.to_vec())
+        );
+        let commitment = packed_price_commitment(cs, &[packed])?;
+        assert_eq!(
+            commitment.map(|half| half.get_value()),
+            compute_packed_price_commitment::<Bn256>(&[expected])?.map(Some)
+        );
+        assert!(cs.is_satisfied());
+
+        // Signed by the publisher but naming another key.
+        let mut renamed = witness.clone();
+        renamed.stork_pub_key = guardian_address(&guardian_key(1));
+        let update = AllocatedSignedPriceUpdate::from_witness(cs, &renamed)?;
+        assert_eq!(
+            update
+                .check_by_publishers(cs, &params, &keccak, &[other, publisher])?
+                .get_value(),
+            Some(false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative_value_is_not_packed() -> Result<(), SynthesisError> {
+        let fixture = StorkFixture {
+            quantized_value: -1,
+            ..Default::default()
+        };
+        let cs = &mut create_test_constraint_system()?;
+        let update = AllocatedSignedPriceUpdate::<Bn256>::from_witness(cs, &fixture.update())?;
+        let (is_ok, _) = update.packed_price(cs)?;
+        assert_eq!(is_ok.get_value(), Some(false));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
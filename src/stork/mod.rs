@@ -0,0 +1,22 @@
+//! Stork signed price updates.
+//!
+//! An update is signed with the EVM scheme of the Stork contract: the publisher key signs, as an
+//! ethereum signed message, the keccak256 of
+//! `stork_pub_key || asset_id || timestamp || quantized_value || publisher_merkle_root ||
+//! value_compute_alg_hash` packed as with `abi.encodePacked`. The circuit checks the signature
+//! against the authorized publishers and packs the price as the pyth prices of
+//! [`crate::pyth::commitment`]: quantized values have 18 decimals and are committed with
+//! [`COMMITTED_DECIMALS`](crate::pyth::commitment::COMMITTED_DECIMALS), timestamps are in
+//! nanoseconds and committed in seconds.
+
+pub mod circuit;
+pub mod witness;
+
+/// Bytes of the signed message: a 20-byte address and five 32-byte words.
+pub const LEN_MESSAGE: usize = 20 + 5 * 32;
+/// Prefix of the ethereum signed message of a 32-byte hash.
+pub const ETHEREUM_SIGNED_MESSAGE_PREFIX: &[u8; 28] = b"\x19Ethereum Signed Message:\n32";
+/// Decimals of quantized values.
+pub const QUANTIZED_DECIMALS: u32 = 18;
+/// Divisor of nanosecond timestamps into the seconds of pyth publish times.
+pub const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
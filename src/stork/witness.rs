@@ -0,0 +1,152 @@
+use num_bigint::{BigInt, Sign};
+
+use super::{
+    ETHEREUM_SIGNED_MESSAGE_PREFIX, LEN_MESSAGE, NANOSECONDS_PER_SECOND, QUANTIZED_DECIMALS,
+};
+use crate::{
+    error::OracleError,
+    keccak::keccak256,
+    pyth::commitment::{COMMITTED_DECIMALS, LEN_PACKED_PRICE},
+};
+
+/// Price update signed by a Stork publisher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPriceUpdate {
+    /// Address of the publisher key.
+    pub stork_pub_key: [u8; 20],
+    pub asset_id: [u8; 32],
+    /// Nanoseconds since the unix epoch.
+    pub timestamp: u64,
+    pub quantized_value: BigInt,
+    pub publisher_merkle_root: [u8; 32],
+    pub value_compute_alg_hash: [u8; 32],
+    /// `r || s || v`, with `v` of 27 or 28.
+    pub signature: [u8; 65],
+}
+
+impl SignedPriceUpdate {
+    /// Parse the packed signed message.
+    pub fn from_message(message: &[u8], signature: [u8; 65]) -> Result<Self, OracleError> {
+        if message.len() != LEN_MESSAGE {
+            return Err(OracleError::InvalidLength {
+                what: "stork message",
+                got: message.len(),
+                expected: LEN_MESSAGE,
+            });
+        }
+        let word = |i: usize| -> [u8; 32] { message[20 + i * 32..52 + i * 32].try_into().unwrap() };
+        let timestamp = word(1);
+        if timestamp[..24].iter().any(|b| *b != 0) {
+            return Err(OracleError::ReportDecode(
+                "stork timestamp overflows a uint64".to_string(),
+            ));
+        }
+        Ok(Self {
+            stork_pub_key: message[..20].try_into().unwrap(),
+            asset_id: word(0),
+            timestamp: u64::from_be_bytes(timestamp[24..].try_into().unwrap()),
+            quantized_value: BigInt::from_signed_bytes_be(&word(2)),
+            publisher_merkle_root: word(3),
+            value_compute_alg_hash: word(4),
+            signature,
+        })
+    }
+
+    /// The message packed as with `abi.encodePacked`, if the quantized value fits an `int256`.
+    pub fn message(&self) -> Result<[u8; LEN_MESSAGE], OracleError> {
+        let value = self.quantized_value.to_signed_bytes_be();
+        if value.len() > 32 {
+            return Err(OracleError::InvalidLength {
+                what: "stork quantized value",
+                got: value.len(),
+                expected: 32,
+            });
+        }
+        let mut bytes = self.stork_pub_key.to_vec();
+        bytes.extend(self.asset_id);
+        bytes.extend([0u8; 24]);
+        bytes.extend(self.timestamp.to_be_bytes());
+        let padding = if self.quantized_value.sign() == Sign::Minus {
+            0xff
+        } else {
+            0
+        };
+        bytes.extend(vec![padding; 32 - value.len()]);
+        bytes.extend(value);
+        bytes.extend(self.publisher_merkle_root);
+        bytes.extend(self.value_compute_alg_hash);
+        Ok(bytes.try_into().unwrap())
+    }
+
+    /// Hash of the ethereum signed message of the keccak256 of [`SignedPriceUpdate::message`].
+    pub fn signed_hash(&self) -> Result<[u8; 32], OracleError> {
+        let mut bytes = ETHEREUM_SIGNED_MESSAGE_PREFIX.to_vec();
+        bytes.extend(keccak256(&self.message()?));
+        Ok(keccak256(&bytes))
+    }
+
+    /// Quantized value with [`COMMITTED_DECIMALS`], rounded down, if it is not negative and fits an
+    /// `i64`.
+    pub fn committed_price(&self) -> Option<i64> {
+        let divisor = BigInt::from(10u64.pow(QUANTIZED_DECIMALS - COMMITTED_DECIMALS));
+        if self.quantized_value.sign() == Sign::Minus {
+            return None;
+        }
+        i64::try_from(&self.quantized_value / divisor).ok()
+    }
+
+    /// Timestamp in seconds.
+    pub fn publish_time(&self) -> u64 {
+        self.timestamp / NANOSECONDS_PER_SECOND
+    }
+
+    /// `asset_id || price || publish_time` as packed by [`crate::pyth::commitment::pack_price`], if
+    /// the price can be committed.
+    pub fn packed_price(&self) -> Option<[u8; LEN_PACKED_PRICE]> {
+        let price = self.committed_price()?;
+        let mut bytes = [0u8; LEN_PACKED_PRICE];
+        bytes[..32].copy_from_slice(&self.asset_id);
+        bytes[32..40].copy_from_slice(&price.to_be_bytes());
+        bytes[40..].copy_from_slice(&self.publish_time().to_be_bytes());
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignedPriceUpdate;
+    use crate::{error::OracleError, testing::fixtures::StorkFixture};
+
+    #[test]
+    fn test_message_round_trip() -> anyhow::Result<()> {
+        let update = StorkFixture::default().update();
+        let parsed = SignedPriceUpdate::from_message(&update.message()?, update.signature)?;
+        assert_eq!(parsed, update);
+        assert_eq!(update.committed_price(), Some(6_543_210_987_654));
+        assert_eq!(update.publish_time(), 1_700_000_000);
+
+        let negative = StorkFixture {
+            quantized_value: -1,
+            ..Default::default()
+        }
+        .update();
+        let parsed = SignedPriceUpdate::from_message(&negative.message()?, negative.signature)?;
+        assert_eq!(parsed, negative);
+        assert_eq!(negative.committed_price(), None);
+
+        assert!(matches!(
+            SignedPriceUpdate::from_message(&update.message()?[1..], update.signature),
+            Err(OracleError::InvalidLength { .. })
+        ));
+
+        // Wider than an int256.
+        let mut overflowing = update.clone();
+        overflowing.quantized_value = num_bigint::BigInt::from(1) << 255;
+        assert!(matches!(
+            overflowing.message(),
+            Err(OracleError::InvalidLength { got: 33, .. })
+        ));
+        assert!(overflowing.signed_hash().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Regression corpus of captured accumulator updates.
+//!
+//! Every entry goes through witness generation and the VAA and merkle checks of the circuit, and the
+//! resulting constraint system must be satisfied. See `tests/corpus/README.md` for the file layout.
+
+use std::path::{Path, PathBuf};
+
+use advanced_circuit_component::franklin_crypto::plonk::circuit::boolean::Boolean;
+use anyhow::Context as _;
+use pythnet_sdk::wire::v1::Proof;
+
+use super::create_constraint_system;
+use crate::{
+    gadgets::ethereum::Address,
+    pyth::{
+        decode::decode_input, AccumulatorUpdateWitness, OracleWitness, PriceUpdate, Vaa,
+        GUARDIAN_SET,
+    },
+};
+
+/// Environment variable overriding the corpus directory.
+pub const CORPUS_DIR_ENV: &str = "ORACLE_CORPUS_DIR";
+
+#[derive(Debug, Clone)]
+pub struct CorpusEntry {
+    pub name: String,
+    pub witness: OracleWitness,
+}
+
+/// Corpus directory, `tests/corpus` unless overridden by [`CORPUS_DIR_ENV`].
+pub fn corpus_dir() -> PathBuf {
+    std::env::var_os(CORPUS_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus"))
+}
+
+/// Load all entries of `dir` sorted by file name, files with other extensions are ignored.
+pub fn load_corpus(dir: &Path) -> Result<Vec<CorpusEntry>, anyhow::Error> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read corpus directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut entries = vec![];
+    for path in paths {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        if !["json", "hex", "b64"].contains(&extension) {
+            continue;
+        }
+        let entry =
+            load_entry(&path).with_context(|| format!("invalid entry {}", path.display()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn load_entry(path: &Path) -> Result<CorpusEntry, anyhow::Error> {
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let content = std::fs::read_to_string(path)?;
+    let witness = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&content)?
+    } else {
+        let update = AccumulatorUpdateWitness::from_accumulator_update_data(
+            &pythnet_sdk::wire::v1::AccumulatorUpdateData::try_from_slice(&decode_input(
+                &content,
+            )?)?,
+        );
+        OracleWitness {
+            accumulator_updates: vec![update],
+            guardian_set: GUARDIAN_SET.to_vec(),
+            num_signature_to_verify: 1,
+        }
+    };
+    Ok(CorpusEntry { name, witness })
+}
+
+/// Synthesize the VAA and merkle checks of every accumulator update in `entry`, verifying
+/// `num_signatures` signatures each, and return the number of gates.
+pub fn verify_entry(entry: &CorpusEntry, num_signatures: usize) -> Result<usize, anyhow::Error> {
+    let cs = &mut create_constraint_system()?;
+    let guardian_set = entry
+        .witness
+        .guardian_set
+        .iter()
+        .map(|g| Address::from_address_witness(cs, g))
+        .collect::<Result<Vec<_>, _>>()?;
+    for data in entry.witness.accumulator_update_data()? {
+        let Proof::WormholeMerkle { vaa, updates } = data.proof;
+        let vaa = serde_wormhole::from_slice(vaa.as_ref())?;
+        let vaa = Vaa::from_vaa_witness(cs, vaa, num_signatures)?;
+        let valid = vaa.check_by_address(cs, &guardian_set)?;
+        Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
+        for update in updates {
+            let update = PriceUpdate::<_>::from_price_update_witness(cs, update)?;
+            let valid = update.check(cs, vaa.merkle_root())?;
+            Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
+        }
+    }
+    anyhow::ensure!(cs.is_satisfied(), "constraint system is not satisfied");
+    Ok(cs.n())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{corpus_dir, load_corpus, verify_entry};
+
+    #[test]
+    fn test_corpus() -> anyhow::Result<()> {
+        let entries = load_corpus(&corpus_dir())?;
+        assert!(!entries.is_empty());
+        for entry in entries.iter() {
+            verify_entry(entry, 1).map_err(|e| e.context(entry.name.clone()))?;
+        }
+        Ok(())
+    }
+}
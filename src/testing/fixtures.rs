@@ -0,0 +1,718 @@
+//! Synthetic accumulator updates signed by known guardian keys.
+//!
+//! Everything here is computed natively: guardian keys are derived deterministically, price messages
+//! are put into a pyth-compatible keccak160 merkle tree and the root is wrapped into a VAA signed by
+//! the guardians. The result is encoded in the `PNAU` wire format and parsed back with `pythnet_sdk`,
+//! so it goes through exactly the same code paths as data fetched from Hermes.
+
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+use secp256k1::{Message, Secp256k1, SecretKey};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::keccak160::WIDTH_HASH_BYTES,
+    keccak::keccak256_batch,
+    pyth::{AccumulatorUpdateWitness, PriceUpdateWitness},
+};
+
+pub use crate::pyth::PYTHNET_CHAIN_ID;
+pub const PYTHNET_EMITTER_ADDRESS: [u8; 32] = crate::pyth::PYTHNET_ACCUMULATOR_EMITTER;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const NULL_PREFIX: u8 = 2;
+
+pub type Hash160 = [u8; WIDTH_HASH_BYTES];
+
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::new_with_prefix(bytes).finalize().into()
+}
+
+pub fn keccak160(bytes: &[u8]) -> Hash160 {
+    keccak256(bytes)[..WIDTH_HASH_BYTES].try_into().unwrap()
+}
+
+fn truncate(hashes: Vec<[u8; 32]>) -> Vec<Hash160> {
+    hashes
+        .iter()
+        .map(|hash| hash[..WIDTH_HASH_BYTES].try_into().unwrap())
+        .collect()
+}
+
+/// Deterministic secret key of the `i`-th fixture guardian.
+pub fn guardian_key(i: usize) -> SecretKey {
+    let seed = keccak256(format!("zklink-oracle fixture guardian {}", i).as_bytes());
+    SecretKey::from_slice(&seed).expect("keccak output is a valid secret key")
+}
+
+/// Ethereum address of a guardian key.
+pub fn guardian_address(key: &SecretKey) -> [u8; 20] {
+    let secp = Secp256k1::new();
+    let pubkey = key.public_key(&secp).serialize_uncompressed();
+    keccak256(&pubkey[1..])[12..].try_into().unwrap()
+}
+
+/// VAA of `body` signed by `keys`, the guardians at the first indices of the set.
+pub fn sign_vaa(guardian_set_index: u32, keys: &[SecretKey], body: Vec<u8>) -> Vec<u8> {
+    let digest = keccak256(&keccak256(&body));
+    let message = Message::from_digest_slice(&digest).unwrap();
+    let secp = Secp256k1::new();
+
+    let mut bytes = vec![1u8]; // version
+    bytes.extend(guardian_set_index.to_be_bytes());
+    bytes.push(keys.len() as u8);
+    for (i, key) in keys.iter().enumerate() {
+        let (recid, signature) = secp
+            .sign_ecdsa_recoverable(&message, key)
+            .serialize_compact();
+        bytes.push(i as u8);
+        bytes.extend(signature);
+        bytes.push(recid.to_i32() as u8);
+    }
+    bytes.extend(body);
+    bytes
+}
+
+/// Native pyth [`PriceFeedMessage`](pythnet_sdk::messages::PriceFeedMessage) used as merkle leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixturePrice {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+impl FixturePrice {
+    pub fn new(feed_id: [u8; 32], price: i64, exponent: i32, publish_time: i64) -> Self {
+        Self {
+            feed_id,
+            price,
+            conf: 1,
+            exponent,
+            publish_time,
+            prev_publish_time: publish_time,
+            ema_price: price,
+            ema_conf: 1,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8]; // PriceFeedMessage discriminant
+        bytes.extend(self.feed_id);
+        bytes.extend(self.price.to_be_bytes());
+        bytes.extend(self.conf.to_be_bytes());
+        bytes.extend(self.exponent.to_be_bytes());
+        bytes.extend(self.publish_time.to_be_bytes());
+        bytes.extend(self.prev_publish_time.to_be_bytes());
+        bytes.extend(self.ema_price.to_be_bytes());
+        bytes.extend(self.ema_conf.to_be_bytes());
+        bytes
+    }
+}
+
+/// Native pyth merkle tree padded with null leaves up to `2^depth` leaves.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    // levels[0] are the leaves, levels[depth] is the root.
+    levels: Vec<Vec<Hash160>>,
+}
+
+impl MerkleTree {
+    pub fn hash_leaf(item: &[u8]) -> Hash160 {
+        let mut bytes = vec![LEAF_PREFIX];
+        bytes.extend_from_slice(item);
+        keccak160(&bytes)
+    }
+
+    pub fn hash_node(l: &Hash160, r: &Hash160) -> Hash160 {
+        let (l, r) = if l <= r { (l, r) } else { (r, l) };
+        let mut bytes = vec![NODE_PREFIX];
+        bytes.extend_from_slice(l);
+        bytes.extend_from_slice(r);
+        keccak160(&bytes)
+    }
+
+    pub fn hash_null() -> Hash160 {
+        keccak160(&[NULL_PREFIX])
+    }
+
+    pub fn new(items: &[Vec<u8>], depth: usize) -> Self {
+        assert!(
+            items.len() <= 1 << depth,
+            "too many items for depth {}",
+            depth
+        );
+        let leaves = (0..1usize << depth)
+            .map(|i| match items.get(i) {
+                Some(item) => [&[LEAF_PREFIX], item.as_slice()].concat(),
+                None => vec![NULL_PREFIX],
+            })
+            .collect::<Vec<_>>();
+        let mut levels = vec![truncate(keccak256_batch(&leaves))];
+        for _ in 0..depth {
+            let nodes = levels
+                .last()
+                .unwrap()
+                .chunks_exact(2)
+                .map(|pair| {
+                    let (l, r) = if pair[0] <= pair[1] {
+                        (&pair[0], &pair[1])
+                    } else {
+                        (&pair[1], &pair[0])
+                    };
+                    [&[NODE_PREFIX], l.as_slice(), r.as_slice()].concat()
+                })
+                .collect::<Vec<_>>();
+            levels.push(truncate(keccak256_batch(&nodes)));
+        }
+        Self { levels }
+    }
+
+    pub fn root(&self) -> Hash160 {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Sibling hashes from the leaf up to the root.
+    pub fn prove(&self, index: usize) -> Vec<Hash160> {
+        let mut index = index;
+        let mut path = vec![];
+        for level in self.levels.iter().take(self.levels.len() - 1) {
+            path.push(level[index ^ 1]);
+            index >>= 1;
+        }
+        path
+    }
+}
+
+/// Description of a synthetic accumulator update.
+#[derive(Debug, Clone)]
+pub struct AccumulatorFixture {
+    pub guardian_set_size: usize,
+    pub guardian_set_index: u32,
+    /// The first `num_signatures` guardians sign the VAA.
+    pub num_signatures: usize,
+    pub prices: Vec<FixturePrice>,
+    pub merkle_depth: usize,
+    pub timestamp: u32,
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub sequence: u64,
+    pub consistency_level: u8,
+    pub slot: u64,
+    pub ring_size: u32,
+}
+
+impl Default for AccumulatorFixture {
+    fn default() -> Self {
+        Self {
+            guardian_set_size: 19,
+            guardian_set_index: 3,
+            num_signatures: 13,
+            prices: vec![FixturePrice::new(
+                [1u8; 32],
+                4_200_000_000,
+                -8,
+                1_700_000_000,
+            )],
+            merkle_depth: 10,
+            timestamp: 1_700_000_000,
+            nonce: 0,
+            emitter_chain: PYTHNET_CHAIN_ID,
+            emitter_address: PYTHNET_EMITTER_ADDRESS,
+            sequence: 1,
+            consistency_level: 1,
+            slot: 1,
+            ring_size: 10_000,
+        }
+    }
+}
+
+impl AccumulatorFixture {
+    pub fn with_prices(prices: Vec<FixturePrice>) -> Self {
+        Self {
+            prices,
+            ..Default::default()
+        }
+    }
+
+    pub fn guardian_keys(&self) -> Vec<SecretKey> {
+        (0..self.guardian_set_size).map(guardian_key).collect()
+    }
+
+    pub fn guardian_set(&self) -> Vec<[u8; 20]> {
+        self.guardian_keys().iter().map(guardian_address).collect()
+    }
+
+    pub fn merkle_tree(&self) -> MerkleTree {
+        let items = self.prices.iter().map(|p| p.to_bytes()).collect::<Vec<_>>();
+        MerkleTree::new(&items, self.merkle_depth)
+    }
+
+    /// Pyth `WormholeMessage` carried as VAA payload.
+    pub fn payload(&self) -> Vec<u8> {
+        let mut bytes = b"AUWV".to_vec();
+        bytes.push(0); // payload type
+        bytes.extend(self.slot.to_be_bytes());
+        bytes.extend(self.ring_size.to_be_bytes());
+        bytes.extend(self.merkle_tree().root());
+        bytes
+    }
+
+    pub fn vaa_body(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(self.timestamp.to_be_bytes());
+        bytes.extend(self.nonce.to_be_bytes());
+        bytes.extend(self.emitter_chain.to_be_bytes());
+        bytes.extend(self.emitter_address);
+        bytes.extend(self.sequence.to_be_bytes());
+        bytes.push(self.consistency_level);
+        bytes.extend(self.payload());
+        bytes
+    }
+
+    pub fn vaa(&self) -> Vec<u8> {
+        let keys = self.guardian_keys();
+        sign_vaa(
+            self.guardian_set_index,
+            &keys[..self.num_signatures],
+            self.vaa_body(),
+        )
+    }
+
+    pub fn witness(&self) -> AccumulatorUpdateWitness {
+        let tree = self.merkle_tree();
+        let updates = self
+            .prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| PriceUpdateWitness {
+                message: price.to_bytes(),
+                proof: tree.prove(i),
+            })
+            .collect();
+        AccumulatorUpdateWitness::new(self.vaa(), updates)
+    }
+
+    /// Encode as `PNAU` accumulator update data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.witness()
+            .to_bytes()
+            .expect("fixture lengths must fit the wire format")
+    }
+
+    pub fn build(&self) -> AccumulatorUpdateData {
+        AccumulatorUpdateData::try_from_slice(&self.to_bytes())
+            .expect("fixture must be a valid accumulator update")
+    }
+}
+
+/// Legacy pyth batch price attestation of `prices`, signed by the first `num_signatures` of
+/// `guardian_set_size` fixture guardians.
+#[derive(Debug, Clone)]
+pub struct LegacyBatchFixture {
+    pub guardian_set_size: usize,
+    pub num_signatures: usize,
+    pub prices: Vec<FixturePrice>,
+    pub emitter_address: [u8; 32],
+}
+
+impl Default for LegacyBatchFixture {
+    fn default() -> Self {
+        Self {
+            guardian_set_size: 4,
+            num_signatures: 3,
+            prices: vec![
+                FixturePrice::new([1u8; 32], 4_200_000_000, -8, 1_600_000_000),
+                FixturePrice::new([2u8; 32], -7, -2, 1_600_000_001),
+            ],
+            emitter_address: crate::pyth::PYTHNET_ATTESTER_EMITTER,
+        }
+    }
+}
+
+impl LegacyBatchFixture {
+    pub fn guardian_set(&self) -> Vec<[u8; 20]> {
+        (0..self.guardian_set_size)
+            .map(|i| guardian_address(&guardian_key(i)))
+            .collect()
+    }
+
+    /// `P2WH` batch of version 3.0.
+    pub fn payload(&self) -> Vec<u8> {
+        let mut bytes = crate::pyth::legacy::P2W_MAGIC.to_vec();
+        bytes.extend(3u16.to_be_bytes()); // major version
+        bytes.extend(0u16.to_be_bytes()); // minor version
+        bytes.extend(1u16.to_be_bytes()); // header size
+        bytes.push(crate::pyth::legacy::PAYLOAD_ID_BATCH_PRICE_ATTESTATION);
+        bytes.extend((self.prices.len() as u16).to_be_bytes());
+        bytes.extend((crate::pyth::legacy::LEN_PRICE_ATTESTATION as u16).to_be_bytes());
+        for price in self.prices.iter() {
+            bytes.extend([9u8; 32]); // product id
+            bytes.extend(price.feed_id);
+            bytes.extend(price.price.to_be_bytes());
+            bytes.extend(price.conf.to_be_bytes());
+            bytes.extend(price.exponent.to_be_bytes());
+            bytes.extend(price.ema_price.to_be_bytes());
+            bytes.extend(price.ema_conf.to_be_bytes());
+            bytes.push(1); // trading
+            bytes.extend(10u32.to_be_bytes()); // publishers
+            bytes.extend(32u32.to_be_bytes()); // max publishers
+            bytes.extend(price.publish_time.to_be_bytes()); // attestation time
+            bytes.extend(price.publish_time.to_be_bytes());
+            bytes.extend(price.prev_publish_time.to_be_bytes());
+            bytes.extend(price.price.to_be_bytes()); // previous price
+            bytes.extend(price.conf.to_be_bytes()); // previous confidence
+        }
+        bytes
+    }
+
+    pub fn vaa_body(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(1_600_000_000u32.to_be_bytes()); // timestamp
+        bytes.extend(0u32.to_be_bytes()); // nonce
+        bytes.extend(PYTHNET_CHAIN_ID.to_be_bytes());
+        bytes.extend(self.emitter_address);
+        bytes.extend(1u64.to_be_bytes()); // sequence
+        bytes.push(1); // consistency level
+        bytes.extend(self.payload());
+        bytes
+    }
+
+    pub fn vaa(&self) -> Vec<u8> {
+        let keys = (0..self.num_signatures)
+            .map(guardian_key)
+            .collect::<Vec<_>>();
+        sign_vaa(0, &keys, self.vaa_body())
+    }
+}
+
+/// Chainlink Data Streams v3 report signed by the first `num_signatures` fixture guardians, standing
+/// in for the DON signers.
+#[derive(Debug, Clone)]
+pub struct ChainlinkFixture {
+    pub num_signatures: usize,
+    pub feed_id: [u8; 32],
+    /// Benchmark price with 18 decimals.
+    pub benchmark_price: i128,
+    pub observations_timestamp: u32,
+}
+
+impl Default for ChainlinkFixture {
+    fn default() -> Self {
+        let mut feed_id = [7u8; 32];
+        feed_id[..2].copy_from_slice(&crate::chainlink::REPORT_SCHEMA_V3.to_be_bytes());
+        Self {
+            num_signatures: 4,
+            feed_id,
+            benchmark_price: 3_012_345_678_901_234_567_890,
+            observations_timestamp: 1_700_000_000,
+        }
+    }
+}
+
+impl ChainlinkFixture {
+    pub fn signer_keys(&self) -> Vec<SecretKey> {
+        (0..self.num_signatures).map(guardian_key).collect()
+    }
+
+    pub fn signers(&self) -> Vec<[u8; 20]> {
+        self.signer_keys().iter().map(guardian_address).collect()
+    }
+
+    pub fn report(&self) -> crate::chainlink::witness::ReportV3 {
+        use num_bigint::{BigInt, BigUint};
+        let price = BigInt::from(self.benchmark_price);
+        crate::chainlink::witness::ReportV3 {
+            feed_id: self.feed_id,
+            valid_from_timestamp: self.observations_timestamp - 1,
+            observations_timestamp: self.observations_timestamp,
+            native_fee: BigUint::from(10u32).pow(15),
+            link_fee: BigUint::from(10u32).pow(16),
+            expires_at: self.observations_timestamp + 86_400,
+            bid: &price - 1,
+            ask: &price + 1,
+            benchmark_price: price,
+        }
+    }
+
+    pub fn full_report(&self) -> crate::chainlink::witness::FullReport {
+        let mut full_report = crate::chainlink::witness::FullReport {
+            report_context: [keccak256(b"config digest"), [0u8; 32], [0u8; 32]],
+            report_blob: self.report().to_bytes(),
+            raw_rs: vec![],
+            raw_ss: vec![],
+            raw_vs: [0u8; 32],
+        };
+        full_report.report_context[1][31] = 1; // epoch and round
+        let message = Message::from_digest_slice(&full_report.signed_hash()).unwrap();
+        let secp = Secp256k1::new();
+        for (i, key) in self.signer_keys().iter().enumerate() {
+            let (recid, signature) = secp
+                .sign_ecdsa_recoverable(&message, key)
+                .serialize_compact();
+            full_report.raw_rs.push(signature[..32].try_into().unwrap());
+            full_report.raw_ss.push(signature[32..].try_into().unwrap());
+            full_report.raw_vs[i] = recid.to_i32() as u8;
+        }
+        full_report
+    }
+}
+
+/// Stork price update signed by the fixture guardian `publisher`, standing in for a Stork
+/// publisher.
+#[derive(Debug, Clone)]
+pub struct StorkFixture {
+    pub publisher: usize,
+    pub asset_id: [u8; 32],
+    /// Quantized value with 18 decimals.
+    pub quantized_value: i128,
+    /// Nanoseconds since the unix epoch.
+    pub timestamp: u64,
+}
+
+impl Default for StorkFixture {
+    fn default() -> Self {
+        Self {
+            publisher: 0,
+            asset_id: keccak256(b"BTCUSD"),
+            quantized_value: 65_432_109_876_543_210_987_654,
+            timestamp: 1_700_000_000_123_456_789,
+        }
+    }
+}
+
+impl StorkFixture {
+    pub fn publisher_address(&self) -> [u8; 20] {
+        guardian_address(&guardian_key(self.publisher))
+    }
+
+    pub fn update(&self) -> crate::stork::witness::SignedPriceUpdate {
+        let mut update = crate::stork::witness::SignedPriceUpdate {
+            stork_pub_key: self.publisher_address(),
+            asset_id: self.asset_id,
+            timestamp: self.timestamp,
+            quantized_value: num_bigint::BigInt::from(self.quantized_value),
+            publisher_merkle_root: keccak256(b"publisher merkle root"),
+            value_compute_alg_hash: keccak256(b"median"),
+            signature: [0u8; 65],
+        };
+        let message = Message::from_digest_slice(&update.signed_hash().unwrap()).unwrap();
+        let (recid, signature) = Secp256k1::new()
+            .sign_ecdsa_recoverable(&message, &guardian_key(self.publisher))
+            .serialize_compact();
+        update.signature[..64].copy_from_slice(&signature);
+        update.signature[64] = recid.to_i32() as u8 + 27;
+        update
+    }
+}
+
+/// Pyth Lazer update of feeds carrying their price and exponent, signed by the fixture guardian `signer`
+/// standing in for the Lazer signer.
+#[derive(Debug, Clone)]
+pub struct LazerFixture {
+    pub signer: usize,
+    /// Microseconds since the unix epoch.
+    pub timestamp_us: u64,
+    /// Feed id, price and exponent of each feed.
+    pub prices: Vec<(u32, i64, i16)>,
+}
+
+impl Default for LazerFixture {
+    fn default() -> Self {
+        Self {
+            signer: 0,
+            timestamp_us: 1_700_000_000_123_456,
+            prices: vec![(1, 6_543_210_987_654, -8), (2, 7, -2)],
+        }
+    }
+}
+
+impl LazerFixture {
+    pub fn signer_address(&self) -> [u8; 20] {
+        guardian_address(&guardian_key(self.signer))
+    }
+
+    pub fn payload(&self) -> Vec<u8> {
+        let mut bytes = crate::pyth::lazer::LAZER_PAYLOAD_MAGIC
+            .to_be_bytes()
+            .to_vec();
+        bytes.extend(self.timestamp_us.to_be_bytes());
+        bytes.push(1); // real-time channel
+        bytes.push(self.prices.len() as u8);
+        for (feed_id, price, exponent) in self.prices.iter() {
+            bytes.extend(feed_id.to_be_bytes());
+            bytes.push(2);
+            bytes.push(crate::pyth::lazer::PROPERTY_PRICE);
+            bytes.extend(price.to_be_bytes());
+            bytes.push(crate::pyth::lazer::PROPERTY_EXPONENT);
+            bytes.extend(exponent.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn update(&self) -> crate::pyth::lazer::LazerUpdate {
+        let payload = self.payload();
+        let message = Message::from_digest_slice(&keccak256(&payload)).unwrap();
+        let (recid, signature) = Secp256k1::new()
+            .sign_ecdsa_recoverable(&message, &guardian_key(self.signer))
+            .serialize_compact();
+        let mut update = crate::pyth::lazer::LazerUpdate {
+            signature: [0u8; 65],
+            payload,
+        };
+        update.signature[..64].copy_from_slice(&signature);
+        update.signature[64] = recid.to_i32() as u8;
+        update
+    }
+}
+
+/// Wormhole governance VAA rotating the fixture guardians from the first `guardian_set_size` keys
+/// to the keys at `new_guardians`.
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgradeFixture {
+    pub guardian_set_size: usize,
+    pub guardian_set_index: u32,
+    pub num_signatures: usize,
+    /// Indices of the keys of the new guardians.
+    pub new_guardians: Vec<usize>,
+    pub new_guardian_set_index: u32,
+    pub emitter_address: [u8; 32],
+    pub sequence: u64,
+}
+
+impl Default for GuardianSetUpgradeFixture {
+    fn default() -> Self {
+        Self {
+            guardian_set_size: 4,
+            guardian_set_index: 3,
+            num_signatures: 3,
+            new_guardians: (2..7).collect(),
+            new_guardian_set_index: 4,
+            emitter_address: crate::pyth::governance::GOVERNANCE_EMITTER,
+            sequence: 0,
+        }
+    }
+}
+
+impl GuardianSetUpgradeFixture {
+    pub fn guardian_set(&self) -> Vec<[u8; 20]> {
+        (0..self.guardian_set_size)
+            .map(|i| guardian_address(&guardian_key(i)))
+            .collect()
+    }
+
+    pub fn new_guardian_set(&self) -> Vec<[u8; 20]> {
+        self.new_guardians
+            .iter()
+            .map(|&i| guardian_address(&guardian_key(i)))
+            .collect()
+    }
+
+    pub fn vaa_body(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(1_700_000_000u32.to_be_bytes()); // timestamp
+        bytes.extend(0u32.to_be_bytes()); // nonce
+        bytes.extend(crate::pyth::governance::GOVERNANCE_CHAIN_ID.to_be_bytes());
+        bytes.extend(self.emitter_address);
+        bytes.extend(self.sequence.to_be_bytes());
+        bytes.push(32); // consistency level
+        bytes.extend(crate::pyth::governance::CORE_MODULE);
+        bytes.push(crate::pyth::governance::ACTION_GUARDIAN_SET_UPGRADE);
+        bytes.extend(0u16.to_be_bytes()); // all chains
+        bytes.extend(self.new_guardian_set_index.to_be_bytes());
+        bytes.push(self.new_guardians.len() as u8);
+        for address in self.new_guardian_set() {
+            bytes.extend(address);
+        }
+        bytes
+    }
+
+    pub fn vaa(&self) -> Vec<u8> {
+        let keys = (0..self.num_signatures)
+            .map(guardian_key)
+            .collect::<Vec<_>>();
+        sign_vaa(self.guardian_set_index, &keys, self.vaa_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::{
+            bellman::{pairing::bn256::Bn256, SynthesisError},
+            plonk::circuit::boolean::Boolean,
+        },
+        traits::CSAllocatable,
+    };
+    use pythnet_sdk::wire::v1::Proof;
+
+    use super::{AccumulatorFixture, FixturePrice};
+    use crate::{
+        gadgets::{keccak160, keccak256::Keccak256Params},
+        pyth::{PriceOracle, PriceUpdate},
+        utils::testing::create_test_constraint_system,
+    };
+
+    #[test]
+    fn test_fixture_is_accepted_by_price_oracle() -> anyhow::Result<()> {
+        let fixture = AccumulatorFixture::with_prices(vec![
+            FixturePrice::new([1u8; 32], 100, -8, 1_700_000_000),
+            FixturePrice::new([2u8; 32], 200, -6, 1_700_000_000),
+        ]);
+        let oracle =
+            PriceOracle::<Bn256, 2>::new(vec![fixture.build()], fixture.guardian_set(), 13)?;
+        assert_eq!(oracle.verification_num(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixture_rejects_negative_price() {
+        let fixture =
+            AccumulatorFixture::with_prices(vec![FixturePrice::new([1u8; 32], -1, -8, 1)]);
+        let oracle = PriceOracle::<Bn256, 1>::new(vec![fixture.build()], fixture.guardian_set(), 1);
+        assert!(oracle.is_err());
+    }
+
+    #[test]
+    fn test_fixture_rejects_decreasing_publish_time() {
+        let later =
+            AccumulatorFixture::with_prices(vec![FixturePrice::new([1u8; 32], 1, -8, i64::MAX)]);
+        let earlier = AccumulatorFixture::with_prices(vec![FixturePrice::new([1u8; 32], 1, -8, 0)]);
+        let oracle = PriceOracle::<Bn256, 1>::new(
+            vec![later.build(), earlier.build()],
+            later.guardian_set(),
+            1,
+        );
+        assert!(oracle.is_err());
+    }
+
+    #[test]
+    fn test_fixture_merkle_path_in_circuit() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let prices = (0..4u8)
+            .map(|i| FixturePrice::new([i; 32], i as i64, -8, 1))
+            .collect();
+        let fixture = AccumulatorFixture {
+            merkle_depth: 2,
+            ..AccumulatorFixture::with_prices(prices)
+        };
+        let root = {
+            let hash = keccak160::Hash::alloc_from_witness(cs, Some(fixture.merkle_tree().root()))?;
+            keccak160::MerkleRoot::new(hash)
+        };
+        let Proof::WormholeMerkle { updates, .. } = fixture.build().proof;
+        let keccak = Keccak256Params::new(cs)?;
+        for update in updates {
+            let update = PriceUpdate::<_, 2>::from_price_update_witness(cs, update)?;
+            let valid = update.check(cs, &keccak, &root)?;
+            Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+//! In-process mock of the Hermes, Benchmarks and Wormholescan HTTP APIs, serving canned accumulator
+//! updates and VAAs so that the fetch, witness and prove path runs without network access.
+//!
+//! Served routes, `ids[]` query parameters select the updates carrying any of the given feeds:
+//!
+//! - `/api/latest_vaas`, `/v2/updates/price/latest`: the last added matching update.
+//! - `/v2/updates/price/{t}`, `/v1/updates/price/{t}`: the last added matching update published at
+//!   or before `t`.
+//! - `/v2/updates/price/stream`: all matching updates as server-sent events, then the stream ends.
+//! - `/api/v1/vaas/{chain}/{emitter}/{sequence}`: a VAA added with [`MockServer::add_vaa`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use base64::Engine as _;
+use pythnet_sdk::{messages::Message, wire::from_slice};
+use serde_json::json;
+
+use crate::pyth::AccumulatorUpdateWitness;
+
+#[derive(Debug, Default)]
+struct MockState {
+    updates: Vec<AccumulatorUpdateWitness>,
+    vaas: HashMap<(u16, [u8; 32], u64), Vec<u8>>,
+    failures: VecDeque<u16>,
+    requests: Vec<String>,
+}
+
+/// HTTP server on a local port, stopped when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // A broken connection only fails the request of the client.
+                        let _ = handle_connection(stream, &state);
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// Base URL, to be used as endpoint of the clients.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn add_update(&self, update: AccumulatorUpdateWitness) {
+        self.state.lock().unwrap().updates.push(update);
+    }
+
+    pub fn add_vaa(&self, emitter_chain: u16, emitter: [u8; 32], sequence: u64, vaa: Vec<u8>) {
+        self.state
+            .lock()
+            .unwrap()
+            .vaas
+            .insert((emitter_chain, emitter, sequence), vaa);
+    }
+
+    /// Answer the next request with `status` and an empty body, e.g. to exercise retries.
+    pub fn fail_next(&self, status: u16) {
+        self.state.lock().unwrap().failures.push_back(status);
+    }
+
+    /// Paths of the requests received so far, query included.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the accept loop.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, requests of the clients have no body.
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+            break;
+        }
+    }
+    let target = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let mut state = state.lock().unwrap();
+    state.requests.push(target.clone());
+    let (status, content_type, body) = match state.failures.pop_front() {
+        Some(status) => (status, "text/plain", String::new()),
+        None => route(&state, &target),
+    };
+    drop(state);
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn route(state: &MockState, target: &str) -> (u16, &'static str, String) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let ids = query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .filter(|(key, _)| matches!(*key, "ids[]" | "ids%5B%5D" | "ids"))
+        .map(|(_, id)| id.trim_start_matches("0x").to_lowercase())
+        .collect::<Vec<_>>();
+    let matching = state
+        .updates
+        .iter()
+        .filter(|update| {
+            ids.is_empty()
+                || feed_prices(update)
+                    .iter()
+                    .any(|(id, _)| ids.contains(&hex::encode(id)))
+        })
+        .collect::<Vec<_>>();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let not_found = (404, "text/plain", format!("no data for {}", target));
+    match segments.as_slice() {
+        ["api", "latest_vaas"] => match matching.last() {
+            Some(update) => json_response(encode(update).map(|update| json!([update]))),
+            None => not_found,
+        },
+        ["v2", "updates", "price", "stream"] => {
+            let events = matching
+                .iter()
+                .map(|update| Ok(format!("data: {}\n\n", price_update_response(update)?)))
+                .collect::<Result<String, anyhow::Error>>();
+            match events {
+                Ok(events) => (200, "text/event-stream", events),
+                Err(err) => (500, "text/plain", err.to_string()),
+            }
+        }
+        ["v2", "updates", "price", "latest"] => match matching.last() {
+            Some(update) => json_response(price_update_response(update)),
+            None => not_found,
+        },
+        ["v1" | "v2", "updates", "price", timestamp] => {
+            let Ok(timestamp) = timestamp.parse::<i64>() else {
+                return (
+                    400,
+                    "text/plain",
+                    format!("invalid timestamp {}", timestamp),
+                );
+            };
+            let update = matching.iter().rev().find(|update| {
+                feed_prices(update)
+                    .iter()
+                    .all(|(_, publish_time)| *publish_time <= timestamp)
+            });
+            match update {
+                Some(update) => json_response(price_update_response(update)),
+                None => not_found,
+            }
+        }
+        ["api", "v1", "vaas", chain, emitter, sequence] => {
+            let key = (|| {
+                let emitter: [u8; 32] = hex::decode(emitter).ok()?.try_into().ok()?;
+                Some((chain.parse().ok()?, emitter, sequence.parse().ok()?))
+            })();
+            match key.and_then(|key| state.vaas.get(&key)) {
+                Some(vaa) => (
+                    200,
+                    "application/json",
+                    json!({ "data": { "vaa": base64::engine::general_purpose::STANDARD.encode(vaa) } })
+                        .to_string(),
+                ),
+                None => not_found,
+            }
+        }
+        _ => not_found,
+    }
+}
+
+fn json_response(body: Result<serde_json::Value, anyhow::Error>) -> (u16, &'static str, String) {
+    match body {
+        Ok(body) => (200, "application/json", body.to_string()),
+        Err(err) => (500, "text/plain", err.to_string()),
+    }
+}
+
+fn encode(update: &AccumulatorUpdateWitness) -> Result<String, anyhow::Error> {
+    Ok(base64::engine::general_purpose::STANDARD.encode(update.to_bytes()?))
+}
+
+fn price_update_response(
+    update: &AccumulatorUpdateWitness,
+) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(json!({ "binary": { "encoding": "base64", "data": [encode(update)?] } }))
+}
+
+/// Feed id and publish time of the price feed messages of `update`.
+fn feed_prices(update: &AccumulatorUpdateWitness) -> Vec<([u8; 32], i64)> {
+    update
+        .updates
+        .iter()
+        .filter_map(|price_update| {
+            match from_slice::<byteorder::BE, Message>(&price_update.message) {
+                Ok(Message::PriceFeedMessage(p)) => Some((p.feed_id, p.publish_time)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::MockServer;
+    use crate::testing::fixtures::{AccumulatorFixture, FixturePrice};
+
+    fn get(server: &MockServer, target: &str) -> String {
+        let mut stream = std::net::TcpStream::connect(server.addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: mock\r\n\r\n", target).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_routes() {
+        let server = MockServer::start().unwrap();
+        for (i, publish_time) in [100, 200].into_iter().enumerate() {
+            server.add_update(
+                AccumulatorFixture {
+                    sequence: i as u64,
+                    ..AccumulatorFixture::with_prices(vec![FixturePrice::new(
+                        [1; 32],
+                        1,
+                        -8,
+                        publish_time,
+                    )])
+                }
+                .witness(),
+            );
+        }
+        let id = hex::encode([1u8; 32]);
+
+        assert!(
+            get(&server, &format!("/api/latest_vaas?ids%5B%5D={}", id)).starts_with("HTTP/1.1 200")
+        );
+        assert!(get(&server, &format!("/v1/updates/price/150?ids[]={}", id))
+            .starts_with("HTTP/1.1 200"));
+        assert!(
+            get(&server, &format!("/v1/updates/price/50?ids[]={}", id)).starts_with("HTTP/1.1 404")
+        );
+        assert!(get(
+            &server,
+            &format!("/api/latest_vaas?ids[]={}", hex::encode([2u8; 32]))
+        )
+        .starts_with("HTTP/1.1 404"));
+        let stream = get(&server, "/v2/updates/price/stream");
+        assert_eq!(stream.matches("data: ").count(), 2);
+
+        server.add_vaa(26, [3; 32], 7, vec![1, 2, 3]);
+        assert!(get(
+            &server,
+            &format!("/api/v1/vaas/26/{}/7", hex::encode([3u8; 32]))
+        )
+        .contains("AQID"));
+
+        server.fail_next(503);
+        assert!(get(&server, "/api/latest_vaas").starts_with("HTTP/1.1 503"));
+        assert!(get(&server, "/api/latest_vaas").starts_with("HTTP/1.1 200"));
+        assert_eq!(server.requests().len(), 8);
+    }
+
+    #[cfg(feature = "hermes-client")]
+    #[tokio::test]
+    async fn test_hermes_client() -> anyhow::Result<()> {
+        use std::time::Duration;
+
+        use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+        use crate::{
+            estimate::CircuitGeometry,
+            hermes::{HermesClient, RetryPolicy, Scheduler},
+        };
+
+        let server = MockServer::start()?;
+        let fixture = AccumulatorFixture::default();
+        server.add_update(fixture.witness());
+        server.fail_next(503);
+
+        let client = HermesClient::new(
+            vec![server.url()],
+            RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )?;
+        let mut scheduler = Scheduler::new(
+            client,
+            CircuitGeometry::new(1, fixture.num_signatures, 1),
+            fixture.guardian_set(),
+        );
+        scheduler.request([hex::encode(fixture.prices[0].feed_id)]);
+        let witnesses = scheduler.run().await?;
+        assert_eq!(witnesses.len(), 1);
+        witnesses[0].to_price_oracle::<Bn256, 1>()?;
+        // The first request failed and was retried.
+        assert_eq!(server.requests().len(), 2);
+        Ok(())
+    }
+
+    #[cfg(feature = "hermes-client")]
+    #[tokio::test]
+    async fn test_scheduler_vaa_fallback() -> anyhow::Result<()> {
+        use std::time::Duration;
+
+        use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+        use crate::{
+            estimate::CircuitGeometry,
+            hermes::{HermesClient, RetryPolicy, Scheduler, WormholescanClient},
+            pyth::decode::decode_vaa,
+        };
+
+        let server = MockServer::start()?;
+        let fixture = AccumulatorFixture::default();
+        // Hermes serves the VAA with a single signature.
+        let trimmed = AccumulatorFixture {
+            num_signatures: 1,
+            ..fixture.clone()
+        };
+        server.add_update(trimmed.witness());
+        let decoded = decode_vaa(&fixture.vaa())?;
+        let emitter: [u8; 32] = hex::decode(&decoded.emitter_address)?.try_into().unwrap();
+        server.add_vaa(
+            decoded.emitter_chain,
+            emitter,
+            decoded.sequence,
+            fixture.vaa(),
+        );
+
+        let geometry = CircuitGeometry::new(1, fixture.num_signatures, 1);
+        let client = HermesClient::new(vec![server.url()], RetryPolicy::none())?;
+        let mut scheduler = Scheduler::new(client.clone(), geometry, fixture.guardian_set());
+        scheduler.request([hex::encode(fixture.prices[0].feed_id)]);
+        let witnesses = scheduler.run().await?;
+        assert!(witnesses[0].to_price_oracle::<Bn256, 1>().is_err());
+
+        let fallback = WormholescanClient::new(server.url(), RetryPolicy::none())?
+            .with_min_interval(Duration::ZERO);
+        let mut scheduler =
+            Scheduler::new(client, geometry, fixture.guardian_set()).with_vaa_fallback(fallback);
+        scheduler.request([hex::encode(fixture.prices[0].feed_id)]);
+        let witnesses = scheduler.run().await?;
+        assert_eq!(witnesses[0].accumulator_updates[0].vaa, fixture.vaa());
+        witnesses[0].to_price_oracle::<Bn256, 1>()?;
+        Ok(())
+    }
+}
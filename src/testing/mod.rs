@@ -0,0 +1,27 @@
+//! Test support shared by unit tests and downstream crates (enable the `testing` feature).
+pub mod corpus;
+pub mod fixtures;
+pub mod mock_server;
+
+use advanced_circuit_component::franklin_crypto::bellman::{
+    pairing::bn256::Bn256,
+    plonk::better_better_cs::{
+        cs::{PlonkCsWidth4WithNextStepAndCustomGatesParams, TrivialAssembly},
+        gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+    },
+    SynthesisError,
+};
+
+pub type TestAssembly = TrivialAssembly<
+    Bn256,
+    PlonkCsWidth4WithNextStepAndCustomGatesParams,
+    SelectorOptimizedWidth4MainGateWithDNext,
+>;
+
+/// Constraint system with the lookup tables required by the gadgets of this crate.
+pub fn create_constraint_system() -> Result<TestAssembly, SynthesisError> {
+    let (mut cs, _, _) =
+        advanced_circuit_component::testing::create_test_artifacts_with_optimized_gate();
+    crate::utils::add_bitwise_logic_and_range_table(&mut cs)?;
+    Ok(cs)
+}
@@ -3,7 +3,9 @@ use crate::franklin_crypto::bellman::plonk::better_better_cs::cs::{
 };
 use crate::franklin_crypto::plonk::circuit::tables::inscribe_default_range_table_for_bit_width_over_first_three_columns;
 use advanced_circuit_component::circuit_structures::byte::Byte;
-use advanced_circuit_component::franklin_crypto::bellman::pairing::ff::{PrimeField, ScalarEngine};
+use advanced_circuit_component::franklin_crypto::bellman::pairing::ff::{
+    PrimeField, PrimeFieldRepr, ScalarEngine,
+};
 use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
 use advanced_circuit_component::franklin_crypto::plonk::circuit::boolean::Boolean;
@@ -14,35 +16,136 @@ use advanced_circuit_component::{
     franklin_crypto::{
         bellman::plonk::better_better_cs::cs::ConstraintSystem, plonk::circuit::allocated_num::Num,
     },
-    vm::primitives::uint256::UInt256,
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::smart_and,
+        primitives::{uint256::UInt256, UInt64},
+    },
 };
 use num_bigint::BigUint;
 use std::str::FromStr;
 
+use crate::error::OracleError;
+
+/// Map `items` with `f(index, item)`, across the rayon thread pool with the `parallel` feature.
+pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &T) -> R + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items
+            .par_iter()
+            .enumerate()
+            .map(|(i, item)| f(i, item))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().enumerate().map(|(i, item)| f(i, item)).collect()
+    }
+}
+
 pub fn new_synthesis_error<T: ToString>(msg: T) -> SynthesisError {
     let err = std::io::Error::new(std::io::ErrorKind::Other, msg.to_string());
     SynthesisError::from(err)
 }
 
+/// Copy `items` into an array, failing with [`OracleError::InvalidLength`] of `what` unless there are
+/// exactly `N` of them.
+pub fn to_array<T: Copy, const N: usize>(
+    what: &'static str,
+    items: &[T],
+) -> Result<[T; N], OracleError> {
+    items.try_into().map_err(|_| OracleError::InvalidLength {
+        what,
+        got: items.len(),
+        expected: N,
+    })
+}
+
 pub fn num_from_be_bytes<CS: ConstraintSystem<E>, E: Engine>(
     cs: &mut CS,
     hash: &[Byte<E>],
 ) -> Result<Num<E>, SynthesisError> {
     let mut bytes = [Byte::zero(); 32];
     let len = hash.len();
-    assert!(len <= <<E as ScalarEngine>::Fr as PrimeField>::CAPACITY as usize);
+    let capacity = <<E as ScalarEngine>::Fr as PrimeField>::CAPACITY as usize / 8;
+    if len > capacity {
+        return Err(OracleError::InvalidLength {
+            what: "field element bytes",
+            got: len,
+            expected: capacity,
+        }
+        .into());
+    }
     bytes[(32 - len)..].copy_from_slice(hash);
     let uint = UInt256::from_be_bytes_fixed(cs, &bytes)?;
     uint.to_num_unchecked(cs)
 }
 
+/// Divide the unsigned big-endian `bytes` by `divisor`, with whether the quotient fits an `i64`.
+///
+/// The quotient and the remainder are witnessed and checked by `bytes = quotient * divisor +
+/// remainder` and `remainder < divisor`. When the quotient does not fit, the flag is false and the
+/// quotient zero.
+pub fn div_to_i64<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    divisor: u64,
+) -> Result<(Boolean, UInt64<E>), SynthesisError> {
+    let witness = Byte::get_byte_value_multiple(bytes).map(|bytes| {
+        let value = BigUint::from_bytes_be(&bytes);
+        match u64::try_from(&value / divisor) {
+            Ok(quotient) if quotient <= i64::MAX as u64 => {
+                (quotient, u64::try_from(value % divisor).unwrap())
+            }
+            _ => (0, 0),
+        }
+    });
+    let quotient = UInt64::alloc_from_witness(cs, witness.map(|(q, _)| q))?;
+    let remainder = UInt64::alloc_from_witness(cs, witness.map(|(_, r)| r))?;
+
+    let (_, is_above_max) = prepacked_long_comparison(
+        cs,
+        &[quotient.inner],
+        &[Num::Constant(u64_to_fe(i64::MAX as u64))],
+        &[64],
+    )?;
+    let (is_divisor, is_above_divisor) = prepacked_long_comparison(
+        cs,
+        &[remainder.inner],
+        &[Num::Constant(u64_to_fe(divisor))],
+        &[64],
+    )?;
+    let recomposed = quotient
+        .inner
+        .mul(cs, &Num::Constant(u64_to_fe(divisor)))?
+        .add(cs, &remainder.inner)?;
+    let value = num_from_be_bytes(cs, bytes)?;
+    let is_recomposed = Num::equals(cs, &recomposed, &value)?;
+    let is_ok = smart_and(
+        cs,
+        &[
+            is_above_max.not(),
+            is_divisor.not(),
+            is_above_divisor.not(),
+            is_recomposed,
+        ],
+    )?;
+    Ok((is_ok, quotient))
+}
+
 pub fn uint256_from_bytes_with_mask<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     bytes: &[Byte<E>],
     mask: &Boolean,
 ) -> Result<UInt256<E>, SynthesisError> {
-    let mut chunks_be_arr = [Byte::empty(); 32];
-    chunks_be_arr.copy_from_slice(bytes);
+    let chunks_be_arr = to_array("uint256 bytes", bytes)?;
     let uint256 = UInt256::from_be_bytes_fixed(cs, &chunks_be_arr)?;
     let uint256 = uint256.mask(cs, mask)?;
     Ok(uint256)
@@ -52,8 +155,7 @@ pub fn uint256_from_bytes<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     bytes: &[Byte<E>],
 ) -> Result<UInt256<E>, SynthesisError> {
-    let mut chunks_be_arr = [Byte::empty(); 32];
-    chunks_be_arr.copy_from_slice(bytes);
+    let chunks_be_arr = to_array("uint256 bytes", bytes)?;
     let uint256 = UInt256::from_be_bytes_fixed(cs, &chunks_be_arr)?;
     Ok(uint256)
 }
@@ -94,6 +196,17 @@ pub fn fr_from_biguint<E: Engine>(biguint: &BigUint) -> Result<E::Fr, SynthesisE
     })
 }
 
+/// `0x` prefixed big-endian hex of `fr`, as the bindings expose field elements.
+pub fn fr_to_hex<F: PrimeField>(fr: &F) -> String {
+    format!("{}", fr.into_repr())
+}
+
+pub fn fr_to_be_bytes<F: PrimeField>(fr: &F) -> Vec<u8> {
+    let mut bytes = vec![];
+    fr.into_repr().write_be(&mut bytes).unwrap();
+    bytes
+}
+
 pub fn add_bitwise_logic_and_range_table<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
 ) -> Result<(), SynthesisError> {
@@ -120,18 +233,15 @@ pub fn add_bitwise_logic_and_range_table<E: Engine, CS: ConstraintSystem<E>>(
 
 #[cfg(test)]
 pub mod testing {
-    use advanced_circuit_component::franklin_crypto::bellman::pairing::{bn256::Bn256, Engine};
+    use std::ops::{Deref, DerefMut};
+
     use advanced_circuit_component::{
         circuit_structures::byte::Byte,
-        franklin_crypto::bellman::{
-            plonk::better_better_cs::{
-                cs::{PlonkCsWidth4WithNextStepAndCustomGatesParams, TrivialAssembly},
-                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
-            },
-            SynthesisError,
-        },
+        franklin_crypto::bellman::{pairing::Engine, SynthesisError},
     };
 
+    use crate::testing::TestAssembly;
+
     pub fn bytes_assert_eq<E: Engine, T: ToString>(bytes: &[Byte<E>], expected_hex: T) {
         let bytes = bytes
             .into_iter()
@@ -140,17 +250,77 @@ pub mod testing {
         assert_eq!(hex::encode(&bytes), expected_hex.to_string());
     }
 
-    pub fn create_test_constraint_system() -> Result<
-        TrivialAssembly<
-            Bn256,
-            PlonkCsWidth4WithNextStepAndCustomGatesParams,
-            SelectorOptimizedWidth4MainGateWithDNext,
-        >,
-        SynthesisError,
-    > {
-        let (mut cs, _, _) =
-            advanced_circuit_component::testing::create_test_artifacts_with_optimized_gate();
-        super::add_bitwise_logic_and_range_table(&mut cs).unwrap();
-        Ok(cs)
+    pub fn create_test_constraint_system() -> Result<TestAssembly, SynthesisError> {
+        crate::testing::create_constraint_system()
+    }
+
+    /// Test constraint system recording the gates added by labeled sections, used to lock the cost
+    /// of gadgets so that regressions show up as test failures.
+    pub struct CountingConstraintSystem {
+        cs: TestAssembly,
+        measurements: Vec<(String, usize)>,
+    }
+
+    impl CountingConstraintSystem {
+        pub fn new() -> Result<Self, SynthesisError> {
+            Ok(Self {
+                cs: create_test_constraint_system()?,
+                measurements: vec![],
+            })
+        }
+
+        /// Run `f` and record the number of gates it adds under `label`.
+        pub fn measure<T, F>(&mut self, label: &str, f: F) -> Result<T, SynthesisError>
+        where
+            F: FnOnce(&mut TestAssembly) -> Result<T, SynthesisError>,
+        {
+            let n = self.cs.n();
+            let result = f(&mut self.cs)?;
+            self.measurements.push((label.to_string(), self.cs.n() - n));
+            Ok(result)
+        }
+
+        /// Total gates recorded under `label`.
+        pub fn gates(&self, label: &str) -> usize {
+            self.measurements
+                .iter()
+                .filter(|(l, _)| l == label)
+                .map(|(_, n)| n)
+                .sum()
+        }
+
+        pub fn assert_within_budget(&self, label: &str, budget: usize) {
+            let gates = self.gates(label);
+            assert!(
+                gates <= budget,
+                "`{}` costs {} gates, over budget {}\n{}",
+                label,
+                gates,
+                budget,
+                self.report()
+            );
+        }
+
+        pub fn report(&self) -> String {
+            self.measurements
+                .iter()
+                .map(|(label, n)| format!("{}: {} gates", label, n))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+
+    impl Deref for CountingConstraintSystem {
+        type Target = TestAssembly;
+
+        fn deref(&self) -> &Self::Target {
+            &self.cs
+        }
+    }
+
+    impl DerefMut for CountingConstraintSystem {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.cs
+        }
     }
 }
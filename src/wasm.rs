@@ -0,0 +1,97 @@
+//! JavaScript bindings for witness preparation, built with the `wasm` feature:
+//!
+//! ```sh
+//! cargo rustc --release --lib --crate-type cdylib --target wasm32-unknown-unknown --no-default-features --features wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/zklink_oracle.wasm
+//! ```
+//!
+//! Everything here is native, so front-ends can check data before sending it to a prover.
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    pyth::{compute_public_input, decode, OracleWitness},
+    utils::fr_to_hex,
+};
+
+/// Public input of a pyth oracle proof, field elements are big-endian hex.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicInput {
+    pub guardian_set_hash: String,
+    pub earliest_publish_time: String,
+    pub prices_commitment: String,
+    pub prices_num: String,
+    pub prices_commitment_base_sum: String,
+    /// The only value inputized by the circuit.
+    pub commitment: String,
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+fn public_input(witness: &str, num_prices: usize) -> Result<PublicInput, anyhow::Error> {
+    let witness: OracleWitness = serde_json::from_str(witness)?;
+    let (public_input_data, commitment) = compute_public_input::<Bn256>(
+        &witness.accumulator_update_data()?,
+        &witness.guardian_set,
+        witness.num_signature_to_verify,
+        num_prices,
+    )?;
+    let prices_summarize = &public_input_data.prices_summarize;
+    Ok(PublicInput {
+        guardian_set_hash: fr_to_hex(&public_input_data.guardian_set_hash),
+        earliest_publish_time: fr_to_hex(&public_input_data.earliest_publish_time),
+        prices_commitment: fr_to_hex(&prices_summarize.commitment),
+        prices_num: fr_to_hex(&prices_summarize.num),
+        prices_commitment_base_sum: fr_to_hex(&prices_summarize.commitment_base_sum),
+        commitment: fr_to_hex(&commitment),
+    })
+}
+
+/// Decode a hex or base64 encoded VAA or `PNAU` accumulator update.
+#[wasm_bindgen(js_name = decode)]
+pub fn decode_js(input: &str) -> Result<JsValue, JsError> {
+    let bytes = decode::decode_input(input).map_err(|e| JsError::new(&e.to_string()))?;
+    let decoded = decode::decode(&bytes).map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&decoded)
+}
+
+/// Run the native checks of the circuit on a JSON [`OracleWitness`], throwing on invalid witness.
+#[wasm_bindgen(js_name = validateWitness)]
+pub fn validate_witness(witness: &str, num_prices: usize) -> Result<(), JsError> {
+    public_input(witness, num_prices).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(())
+}
+
+/// Reconstruct the public input a proof of a JSON [`OracleWitness`] is verified against.
+#[wasm_bindgen(js_name = publicInput)]
+pub fn public_input_js(witness: &str, num_prices: usize) -> Result<JsValue, JsError> {
+    let public_input =
+        public_input(witness, num_prices).map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&public_input)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::{fr_to_hex, public_input};
+    use crate::pyth::{OracleWitness, PriceOracle};
+
+    #[test]
+    fn test_public_input() -> anyhow::Result<()> {
+        let oracle = PriceOracle::<Bn256, 3>::circuit_default(2, 1);
+        let mut json = vec![];
+        OracleWitness::from(&oracle).write_json(&mut json)?;
+        let json = String::from_utf8(json)?;
+
+        let input = public_input(&json, 3)?;
+        assert_eq!(input.commitment, fr_to_hex(&oracle.commitment));
+        assert!(public_input(&json, 4).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+"""Tests of the `python` feature bindings, run with `maturin develop && pytest tests/python`."""
+
+import json
+from pathlib import Path
+
+import pytest
+
+import zklink_oracle
+
+UPDATE = (Path(__file__).parent.parent / "corpus" / "mainnet-3-prices.b64").read_text().strip()
+
+
+def test_decode_update():
+    decoded = json.loads(zklink_oracle.decode_update(UPDATE))
+    assert len(decoded["updates"]) == 3
+    assert all(update["message"]["type"] == "PriceFeed" for update in decoded["updates"])
+    assert decoded["vaa"]["emitter_chain"] == 26
+
+    with pytest.raises(ValueError):
+        zklink_oracle.decode_update("not an update")
+
+
+def test_witness_and_public_input():
+    witness = zklink_oracle.build_witness([UPDATE], num_signatures=1)
+    assert json.loads(witness)["num_signature_to_verify"] == 1
+
+    public_input = zklink_oracle.public_input(witness, num_prices=3)
+    assert set(public_input) == {
+        "guardian_set_hash",
+        "earliest_publish_time",
+        "prices_commitment",
+        "prices_num",
+        "prices_commitment_base_sum",
+        "commitment",
+    }
+    assert all(value.startswith("0x") for value in public_input.values())
+    assert int(public_input["prices_num"], 16) == 1
+
+    with pytest.raises(ValueError):
+        zklink_oracle.public_input(witness, num_prices=2)
+
+
+def test_build_witness_rejects_invalid_updates():
+    with pytest.raises(ValueError):
+        zklink_oracle.build_witness([UPDATE[:-8]], num_signatures=1)
+    with pytest.raises(ValueError):
+        zklink_oracle.build_witness([UPDATE], num_signatures=100)
+
+
+def test_estimates():
+    one = zklink_oracle.estimate_gates(num_vaas=1, num_signatures=1, num_prices=3)
+    two = zklink_oracle.estimate_gates(num_vaas=1, num_signatures=2, num_prices=3)
+    assert 0 < one < two
+    power = zklink_oracle.required_power_of_tau(num_vaas=1, num_signatures=2, num_prices=3)
+    assert 2**power > two
+
+
+def test_verify_proof_rejects_garbage():
+    with pytest.raises(ValueError):
+        zklink_oracle.verify_proof(b"", b"")